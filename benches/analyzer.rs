@@ -0,0 +1,70 @@
+//! Keyword scoring throughput over synthetic corpora with the keywords of the starting configuration, and
+//! the cost of compiling the automata that runs reuse.
+//! `cargo bench` keeps the previous results under target/criterion and reports the change against them.
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use news_aggregator::analyzer::Analyzer;
+use news_aggregator::bench::{DESCRIPTION_WORDS, synthetic_corpus};
+use news_aggregator::config::{self, Config};
+use std::hint::black_box;
+use tokio_util::sync::CancellationToken;
+
+const SEED: u64 = 42;
+const PAGE_ARTICLES: usize = 2_000;
+/// Words of a long article's page text
+const PAGE_WORDS: usize = 2_000;
+
+fn scoring(c: &mut Criterion) {
+	let loaded = config::starter().and_then(|config| Analyzer::new(&config).map(|analyzer| (config, analyzer)));
+	let (config, analyzer) = match loaded {
+		Ok(loaded) => loaded,
+		Err(e) => {
+			eprintln!("Starting configuration does not load: {e}");
+			return;
+		}
+	};
+	c.bench_function("compile", |b| b.iter(|| black_box(Analyzer::new(&config))));
+
+	let cancel = CancellationToken::new();
+	score_corpora(c, &config, &analyzer, &cancel);
+	score_page_text(c, &config, &analyzer, &cancel);
+}
+
+fn score_corpora(c: &mut Criterion, config: &Config, analyzer: &Analyzer, cancel: &CancellationToken) {
+	let mut group = c.benchmark_group("score");
+	for count in [1_000u64, 10_000, 50_000] {
+		let corpus = synthetic_corpus(
+			&config.keywords.values,
+			usize::try_from(count).unwrap_or(usize::MAX),
+			DESCRIPTION_WORDS,
+			SEED,
+		);
+		group.throughput(Throughput::Elements(count));
+		group.bench_with_input(BenchmarkId::from_parameter(count), &corpus, |b, corpus| {
+			b.iter_batched(
+				|| corpus.clone(),
+				|articles| black_box(analyzer.score(articles, config, cancel)),
+				criterion::BatchSize::LargeInput,
+			);
+		});
+	}
+	group.finish();
+}
+
+/// Articles whose description is the text of their page, as scrape sources extract it without
+/// `analyzer.max_description_chars`; scoring these is dominated by scanning the text
+fn score_page_text(c: &mut Criterion, config: &Config, analyzer: &Analyzer, cancel: &CancellationToken) {
+	let mut group = c.benchmark_group("score_page_text");
+	let corpus = synthetic_corpus(&config.keywords.values, PAGE_ARTICLES, PAGE_WORDS, SEED);
+	group.throughput(Throughput::Elements(u64::try_from(PAGE_ARTICLES).unwrap_or(u64::MAX)));
+	group.bench_function(BenchmarkId::from_parameter(PAGE_ARTICLES), |b| {
+		b.iter_batched(
+			|| corpus.clone(),
+			|articles| black_box(analyzer.score(articles, config, cancel)),
+			criterion::BatchSize::LargeInput,
+		);
+	});
+	group.finish();
+}
+
+criterion_group!(benches, scoring);
+criterion_main!(benches);
@@ -0,0 +1,17 @@
+// Generates the gRPC service from proto/news_aggregator.proto when the `grpc` feature is on, with a
+// vendored protoc so that building needs no system-wide protobuf install
+#[cfg_attr(not(feature = "grpc"), allow(clippy::unnecessary_wraps))]
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+	#[cfg(feature = "grpc")]
+	{
+		let mut config = tonic_prost_build::Config::new();
+		config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+		let include = protoc_bin_vendored::include_path()?;
+		tonic_prost_build::configure().build_client(false).compile_with_config(
+			config,
+			&[std::path::PathBuf::from("proto/news_aggregator.proto")],
+			&[std::path::PathBuf::from("proto"), include],
+		)?;
+	}
+	Ok(())
+}
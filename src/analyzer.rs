@@ -1,92 +1,674 @@
+use crate::config::Config;
 use crate::error::{AppError, Result};
-use crate::model::Article;
+use crate::formula::{ScoreFormula, ScoreInputs};
+use crate::history::RankChange;
+use crate::language;
+use crate::model::{Article, Discussion};
 use aho_corasick::AhoCorasick;
 use rayon::{ThreadPoolBuildError, prelude::*};
-use std::sync::Arc;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt::Write as _;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
 
 const MAX_KEY_WORD_COUNT: usize = 20;
+/// Articles per rayon thread in each scoring chunk
+const ARTICLES_PER_THREAD: usize = 64;
+/// Fewest articles rayon hands to one task, so small chunks are not split into tasks costlier than the scoring
+const MIN_ARTICLES_PER_TASK: usize = 16;
 
-#[derive(Debug, Clone)]
+/// A relevance score that is always finite, so scores are totally ordered and can be filtered by threshold
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(transparent)]
+pub struct Score(f64);
+
+impl Score {
+	/// NaN, e.g. from a custom formula or a script, counts as no relevance at all, and infinities are clamped to
+	/// the largest finite scores
+	#[must_use]
+	pub const fn new(value: f64) -> Self {
+		if value.is_nan() {
+			Self(0.0)
+		} else {
+			Self(value.clamp(f64::MIN, f64::MAX))
+		}
+	}
+
+	#[must_use]
+	pub const fn get(self) -> f64 {
+		self.0
+	}
+}
+
+impl Ord for Score {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.0.total_cmp(&other.0)
+	}
+}
+
+impl PartialOrd for Score {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl PartialEq for Score {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl Eq for Score {}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct ScoredArticle {
 	article: Article,
-	relevance_score: f64,
+	relevance_score: Score,
 	matched_keywords: Vec<String>,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	categories: Vec<String>,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	entities: Vec<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	semantic_similarity: Option<f64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	summary: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	explanation: Option<ScoreExplanation>,
+	/// Extra text attached for display and delivery, e.g. by a script's `decorate` hook
+	#[serde(skip_serializing_if = "Option::is_none")]
+	note: Option<String>,
+	/// Wayback Machine snapshot of the article
+	#[serde(skip_serializing_if = "Option::is_none")]
+	archive_url: Option<String>,
+	/// Length of the article's page text, when it was fetched
+	#[serde(skip_serializing_if = "Option::is_none")]
+	reading: Option<ReadingTime>,
+	/// Hacker News thread about an article from another source
+	#[serde(skip_serializing_if = "Option::is_none")]
+	discussion: Option<Discussion>,
+	/// Movement since the previous run's ranking, when runs are compared through the store
+	#[serde(skip_serializing_if = "Option::is_none")]
+	change: Option<RankChange>,
+}
+
+/// Length of an article's readable text
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ReadingTime {
+	pub words: usize,
+	/// Whole minutes at `content.words_per_minute`, at least one
+	pub minutes: u32,
+}
+
+/// How much a single keyword contributed to the keyword score
+#[derive(Debug, Clone, Serialize)]
+pub struct KeywordContribution {
+	pub keyword: String,
+	pub count: u32,
+	/// `1 + ln(count)`, so repeated mentions have diminishing returns
+	pub contribution: f64,
+}
+
+/// Semantic similarity blended into the score
+#[derive(Debug, Clone, Serialize)]
+pub struct SemanticContribution {
+	pub similarity: f64,
+	pub weight: f64,
+	pub contribution: f64,
+}
+
+/// Learned interest blended into the score
+#[derive(Debug, Clone, Serialize)]
+pub struct LearnedContribution {
+	/// From -1 (unlike anything starred) to 1 (like the starred articles)
+	pub signal: f64,
+	pub weight: f64,
+	pub contribution: f64,
+}
+
+/// Breakdown of how a relevance score was computed, attached in explain mode
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoreExplanation {
+	pub keywords: Vec<KeywordContribution>,
+	pub keyword_score: f64,
+	pub source_weight: f64,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub semantic: Option<SemanticContribution>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub learned: Option<LearnedContribution>,
+	/// Custom `analyzer.score_formula` that produced the score, if any
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub score_formula: Option<String>,
+	/// Score before a script's `adjust_score` hook replaced it
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub scripted_from: Option<f64>,
+	pub final_score: f64,
+}
+
+impl ScoreExplanation {
+	/// Human-readable formula, e.g. `(rust 1+ln(3)=2.10 + async 1.00) * 2.00 = 6.20`
+	#[must_use]
+	pub fn formula(&self) -> String {
+		let terms: Vec<String> = self
+			.keywords
+			.iter()
+			.map(|k| {
+				if k.count > 1 {
+					format!("{} 1+ln({})={:.2}", k.keyword, k.count, k.contribution)
+				} else {
+					format!("{} {:.2}", k.keyword, k.contribution)
+				}
+			})
+			.collect();
+		let keyword_part = if terms.is_empty() {
+			"0".to_string()
+		} else {
+			terms.join(" + ")
+		};
+		let mut formula = self.score_formula.as_ref().map_or_else(
+			|| format!("({keyword_part}) * {:.2}", self.source_weight),
+			|custom| {
+				format!(
+					"{custom} with keyword_score = {keyword_part} = {:.2}, source_weight = {:.2}",
+					self.keyword_score, self.source_weight
+				)
+			},
+		);
+		if let Some(semantic) = &self.semantic {
+			let _ = write!(formula, " + {:.2} * sim {:.3}", semantic.weight, semantic.similarity);
+		}
+		if let Some(learned) = &self.learned {
+			let _ = write!(formula, " + {:.2} * learned {:.3}", learned.weight, learned.signal);
+		}
+		if let Some(from) = self.scripted_from {
+			let _ = write!(formula, " = {from:.2}, adjusted by script");
+		}
+		let _ = write!(formula, " = {:.2}", self.final_score);
+		formula
+	}
 }
 
 impl ScoredArticle {
+	#[must_use]
 	pub const fn article(&self) -> &Article {
 		&self.article
 	}
 
+	#[must_use]
 	pub const fn relevance_score(&self) -> f64 {
-		self.relevance_score
+		self.relevance_score.get()
 	}
 
+	#[must_use]
 	pub fn matched_keywords(&self) -> &[String] {
 		&self.matched_keywords
 	}
+
+	#[must_use]
+	pub fn categories(&self) -> &[String] {
+		&self.categories
+	}
+
+	pub fn set_categories(&mut self, categories: Vec<String>) {
+		self.categories = categories;
+	}
+
+	/// Add tags to the article, e.g. from a model that read it
+	#[cfg(feature = "ollama")]
+	pub fn add_tags(&mut self, tags: &[String]) {
+		self.article.add_tags(tags);
+	}
+
+	/// Projects and companies mentioned by the article
+	#[must_use]
+	pub fn entities(&self) -> &[String] {
+		&self.entities
+	}
+
+	pub fn set_entities(&mut self, entities: Vec<String>) {
+		self.entities = entities;
+	}
+
+	#[must_use]
+	pub const fn semantic_similarity(&self) -> Option<f64> {
+		self.semantic_similarity
+	}
+
+	/// Add `weight * similarity` to the keyword score when the similarity clears `threshold`
+	#[cfg(feature = "semantic")]
+	pub fn blend_semantic(&mut self, similarity: f64, threshold: f64, weight: f64) {
+		self.semantic_similarity = Some(similarity);
+		if similarity.is_finite() && similarity >= threshold {
+			self.relevance_score = Score::new(weight.mul_add(similarity, self.relevance_score.get()));
+			if let Some(explanation) = &mut self.explanation {
+				explanation.semantic = Some(SemanticContribution {
+					similarity,
+					weight,
+					contribution: weight * similarity,
+				});
+				explanation.final_score = self.relevance_score.get();
+			}
+		}
+	}
+
+	/// Add `weight * signal` from the learned interest model
+	#[cfg(feature = "learning")]
+	pub fn blend_learned(&mut self, signal: f64, weight: f64) {
+		if !signal.is_finite() {
+			return;
+		}
+		self.relevance_score = Score::new(weight.mul_add(signal, self.relevance_score.get()));
+		if let Some(explanation) = &mut self.explanation {
+			explanation.learned = Some(LearnedContribution {
+				signal,
+				weight,
+				contribution: weight * signal,
+			});
+			explanation.final_score = self.relevance_score.get();
+		}
+	}
+
+	#[must_use]
+	pub const fn explanation(&self) -> Option<&ScoreExplanation> {
+		self.explanation.as_ref()
+	}
+
+	#[must_use]
+	pub fn summary(&self) -> Option<&str> {
+		self.summary.as_deref()
+	}
+
+	pub fn set_summary(&mut self, summary: String) {
+		self.summary = Some(summary);
+	}
+
+	/// Replace the score after the fact, keeping the computed one in the explanation
+	#[cfg(feature = "scripting")]
+	pub const fn set_relevance_score(&mut self, score: f64) {
+		if let Some(explanation) = &mut self.explanation {
+			explanation.scripted_from = Some(explanation.final_score);
+			explanation.final_score = score;
+		}
+		self.relevance_score = Score::new(score);
+	}
+
+	#[must_use]
+	pub fn note(&self) -> Option<&str> {
+		self.note.as_deref()
+	}
+
+	#[cfg(feature = "scripting")]
+	pub fn set_note(&mut self, note: String) {
+		self.note = Some(note);
+	}
+
+	#[must_use]
+	pub fn archive_url(&self) -> Option<&str> {
+		self.archive_url.as_deref()
+	}
+
+	pub fn set_archive_url(&mut self, url: String) {
+		self.archive_url = Some(url);
+	}
+
+	pub fn set_image_url(&mut self, url: String) {
+		self.article.set_image_url(url);
+	}
+
+	pub fn set_dead_link(&mut self, reason: String) {
+		self.article.set_dead_link(reason);
+	}
+
+	pub fn set_click_url(&mut self, url: String) {
+		self.article.set_click_url(url);
+	}
+
+	#[must_use]
+	pub const fn reading(&self) -> Option<ReadingTime> {
+		self.reading
+	}
+
+	pub const fn set_reading(&mut self, reading: ReadingTime) {
+		self.reading = Some(reading);
+	}
+
+	#[must_use]
+	pub const fn discussion(&self) -> Option<&Discussion> {
+		self.discussion.as_ref()
+	}
+
+	pub fn set_discussion(&mut self, discussion: Discussion) {
+		self.discussion = Some(discussion);
+	}
+
+	#[must_use]
+	pub const fn change(&self) -> Option<RankChange> {
+		self.change
+	}
+
+	pub const fn set_change(&mut self, change: RankChange) {
+		self.change = Some(change);
+	}
+}
+
+/// Ranking order, where greater ranks higher: by score, then the more recently published article
+/// (undated ones last), then by URL, so articles with equal scores come out the same way every run
+impl Ord for ScoredArticle {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.relevance_score
+			.cmp(&other.relevance_score)
+			.then_with(|| self.article.published_at().cmp(&other.article.published_at()))
+			.then_with(|| other.article.url().cmp(self.article.url()))
+	}
 }
 
+impl PartialOrd for ScoredArticle {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+/// Equal in ranking terms: same score, publication time and URL
+impl PartialEq for ScoredArticle {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl Eq for ScoredArticle {}
+
+/// # Errors
+///
+/// When the global rayon pool was already built
 pub fn init_rayon_pool(num_threads: usize) -> std::result::Result<(), ThreadPoolBuildError> {
 	rayon::ThreadPoolBuilder::new().num_threads(num_threads).build_global()
 }
 
-pub fn score_articles(articles: Vec<Article>, keywords: &[String]) -> Result<Vec<ScoredArticle>> {
-	if keywords.is_empty() {
-		return Err(AppError::AnalyzerError("no keywords configured".into()));
+/// Keyword list together with its compiled automaton
+struct KeywordMatcher {
+	keywords: Vec<String>,
+	ac: AhoCorasick,
+	/// Some keyword has non-ASCII characters, which the automaton only matches in lowercased text, since it
+	/// ignores the case of ASCII letters alone
+	unicode: bool,
+}
+
+impl KeywordMatcher {
+	fn new(keywords: &[String]) -> Result<Self> {
+		let ac = AhoCorasick::builder()
+			.ascii_case_insensitive(true)
+			.build(keywords)
+			.map_err(|e| AppError::AnalyzerError(format!("failed to build AC: {e}")))?;
+		Ok(Self {
+			keywords: keywords.to_vec(),
+			ac,
+			unicode: !keywords.iter().all(|keyword| keyword.is_ascii()),
+		})
 	}
+}
 
-	let patterns: Vec<&str> = keywords.iter().map(String::as_str).collect();
-	let ac = AhoCorasick::builder()
-		.ascii_case_insensitive(true)
-		.build(&patterns)
-		.map_err(|e| AppError::AnalyzerError(format!("failed to build AC: {e}")))?;
+/// The keyword automata and score formula of a configuration, compiled once and used for every chunk
+/// of a run, and across daemon cycles while the keywords stay the same
+pub struct Analyzer {
+	default_matcher: KeywordMatcher,
+	/// One automaton per distinct language-specific keyword set
+	language_matchers: Vec<(String, KeywordMatcher)>,
+	formula: Option<ScoreFormula>,
+}
 
-	let ac = Arc::new(ac);
+impl Analyzer {
+	/// # Errors
+	///
+	/// `AnalyzerError` when no keywords are configured or their automata cannot be built
+	pub fn new(config: &Config) -> Result<Self> {
+		let keywords = &config.keywords.values;
+		if keywords.is_empty() {
+			return Err(AppError::AnalyzerError("no keywords configured".into()));
+		}
+		let mut language_matchers = Vec::new();
+		for code in config.keywords.languages.keys() {
+			let code = language::normalize_code(code);
+			let matcher = KeywordMatcher::new(config.keywords.for_language(Some(&code)))?;
+			language_matchers.push((code, matcher));
+		}
+		Ok(Self {
+			default_matcher: KeywordMatcher::new(keywords)?,
+			language_matchers,
+			formula: config
+				.analyzer
+				.score_formula
+				.as_deref()
+				.map(ScoreFormula::parse)
+				.transpose()?,
+		})
+	}
 
-	let scored = articles
-		.into_par_iter()
-		.map(|article| {
-			let (score, matched) = calculate_relevance(&article, &ac, keywords);
-			ScoredArticle {
-				article,
-				relevance_score: score,
-				matched_keywords: matched,
+	/// Score `articles` in parallel, in chunks that give every thread of the rayon pool a share, so a
+	/// shutdown request is noticed between chunks instead of after the whole batch
+	///
+	/// # Errors
+	///
+	/// `ShutdownError` when `cancel_token` is cancelled between chunks
+	pub fn score(
+		&self,
+		articles: Vec<Article>,
+		config: &Config,
+		cancel_token: &CancellationToken,
+	) -> Result<Vec<ScoredArticle>> {
+		let chunk_size = rayon::current_num_threads().saturating_mul(ARTICLES_PER_THREAD);
+		let mut scored = Vec::with_capacity(articles.len());
+		let mut remaining = articles.into_iter();
+		loop {
+			let chunk: Vec<Article> = remaining.by_ref().take(chunk_size).collect();
+			if chunk.is_empty() {
+				break;
 			}
-		})
-		.collect();
+			if cancel_token.is_cancelled() {
+				return Err(AppError::ShutdownError);
+			}
+			scored.par_extend(
+				chunk
+					.into_par_iter()
+					.with_min_len(MIN_ARTICLES_PER_TASK)
+					.map(|article| self.score_one(article, config)),
+			);
+		}
+		Ok(scored)
+	}
 
-	Ok(scored)
+	fn score_one(&self, article: Article, config: &Config) -> ScoredArticle {
+		let matcher = article
+			.language()
+			.and_then(|lang| self.language_matchers.iter().find(|(code, _)| code == lang))
+			.map_or(&self.default_matcher, |(_, matcher)| matcher);
+		let (score, contributions) = calculate_relevance(&article, matcher);
+		let weight = config.source_weight(article.source());
+		let relevance_score = self.formula.as_ref().map_or(score * weight, |formula| {
+			let inputs = score_inputs(&article, score, weight, &contributions);
+			formula.evaluate(&inputs).map_or_else(
+				|e| {
+					debug!(url = article.url(), error = %e, "Score formula failed, using default scoring");
+					score * weight
+				},
+				|value| if value.is_finite() { value } else { 0.0 },
+			)
+		});
+		let matched_keywords = contributions.iter().map(|c| c.keyword.clone()).collect();
+		let explanation = config.analyzer.explain.then_some(ScoreExplanation {
+			keywords: contributions,
+			keyword_score: score,
+			source_weight: weight,
+			semantic: None,
+			learned: None,
+			score_formula: self.formula.as_ref().map(|f| f.source().to_string()),
+			scripted_from: None,
+			final_score: relevance_score,
+		});
+		ScoredArticle {
+			article,
+			relevance_score: Score::new(relevance_score),
+			matched_keywords,
+			categories: Vec::new(),
+			entities: Vec::new(),
+			semantic_similarity: None,
+			summary: None,
+			explanation,
+			note: None,
+			archive_url: None,
+			reading: None,
+			discussion: None,
+			change: None,
+		}
+	}
 }
 
-fn calculate_relevance(article: &Article, ac: &AhoCorasick, keywords: &[String]) -> (f64, Vec<String>) {
-	let mut matched_keywords = Vec::new();
+/// Score `articles` with automata compiled for this call only
+///
+/// # Errors
+///
+/// As `Analyzer::new` and `Analyzer::score`
+pub fn score_articles(
+	articles: Vec<Article>,
+	config: &Config,
+	cancel_token: &CancellationToken,
+) -> Result<Vec<ScoredArticle>> {
+	Analyzer::new(config)?.score(articles, config, cancel_token)
+}
+
+fn score_inputs(
+	article: &Article,
+	keyword_score: f64,
+	source_weight: f64,
+	keywords: &[KeywordContribution],
+) -> ScoreInputs {
+	ScoreInputs {
+		keyword_score,
+		source_weight,
+		keyword_count: u32::try_from(keywords.len()).unwrap_or(u32::MAX),
+		keyword_hits: keywords.iter().fold(0u32, |hits, k| hits.saturating_add(k.count)),
+		hn_points: article.points().unwrap_or(0),
+		comments: article.comments().unwrap_or(0),
+		title_length: u32::try_from(article.title().chars().count()).unwrap_or(u32::MAX),
+	}
+}
+
+/// Count the keywords in the article's title and description where they are, without copying the text,
+/// except lowercasing non-ASCII text for non-ASCII keywords
+fn calculate_relevance(article: &Article, matcher: &KeywordMatcher) -> (f64, Vec<KeywordContribution>) {
+	let mut contributions = Vec::new();
 	let mut total_score = 0.0;
+	let keywords = matcher.keywords.as_slice();
 	if keywords.is_empty() {
-		return (total_score, matched_keywords);
+		return (total_score, contributions);
 	}
 	let max_allowed_count = MAX_KEY_WORD_COUNT.min(keywords.len());
 
-	let keywords = &keywords[..max_allowed_count];
+	let keywords = keywords.get(..max_allowed_count).unwrap_or(keywords);
 
-	let text = article.searchable_text().to_lowercase();
+	let mut keyword_counts: Vec<u32> = vec![0; keywords.len()];
 
-	let mut keyword_counts: Vec<usize> = vec![0; keywords.len()];
-
-	for mat in ac.find_iter(&text) {
-		keyword_counts[mat.pattern().as_usize()] += 1;
+	for text in [Some(article.title()), article.description()].into_iter().flatten() {
+		let text = if matcher.unicode && !text.is_ascii() {
+			Cow::Owned(text.to_lowercase())
+		} else {
+			Cow::Borrowed(text)
+		};
+		for mat in matcher.ac.find_iter(text.as_ref()) {
+			if let Some(count) = keyword_counts.get_mut(mat.pattern().as_usize()) {
+				*count = count.saturating_add(1);
+			}
+		}
 	}
 
-	for (idx, &count) in keyword_counts.iter().enumerate() {
+	for (keyword, &count) in keywords.iter().zip(&keyword_counts) {
 		if count > 0 {
-			matched_keywords.push(keywords[idx].clone());
 			// Logarithmic scoring to prevent single keyword dominance
-			total_score += 1.0 + (count as f64).ln();
+			let contribution = 1.0 + f64::from(count).ln();
+			total_score += contribution;
+			contributions.push(KeywordContribution {
+				keyword: keyword.clone(),
+				count,
+				contribution,
+			});
 		}
 	}
 
 	// Ensure score is finite
 	let final_score = if total_score.is_finite() { total_score } else { 0.0 };
 
-	(final_score, matched_keywords)
+	(final_score, contributions)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn scored(url: &str, score: f64, published_at: Option<&str>) -> ScoredArticle {
+		let published_at = published_at.and_then(|date| date.parse().ok());
+		ScoredArticle {
+			article: Article::new("Title".into(), url.into(), "Source".into()).with_published_at(published_at),
+			relevance_score: Score::new(score),
+			matched_keywords: Vec::new(),
+			categories: Vec::new(),
+			entities: Vec::new(),
+			semantic_similarity: None,
+			summary: None,
+			explanation: None,
+			note: None,
+			archive_url: None,
+			reading: None,
+			discussion: None,
+			change: None,
+		}
+	}
+
+	#[test]
+	fn scores_are_always_finite() {
+		assert_eq!(Score::new(1.5).get(), 1.5);
+		assert_eq!(Score::new(-2.0).get(), -2.0);
+		assert_eq!(Score::new(f64::NAN).get(), 0.0);
+		assert_eq!(Score::new(-f64::NAN).get(), 0.0);
+		assert_eq!(Score::new(f64::INFINITY).get(), f64::MAX);
+		assert_eq!(Score::new(f64::NEG_INFINITY).get(), f64::MIN);
+	}
+
+	#[test]
+	fn scores_order_like_numbers() {
+		assert!(Score::new(2.0) > Score::new(1.0));
+		assert!(Score::new(f64::INFINITY) > Score::new(1e300));
+		assert!(Score::new(f64::NAN) < Score::new(0.1));
+		assert_eq!(Score::new(f64::NAN), Score::new(0.0));
+	}
+
+	#[test]
+	fn higher_scores_rank_first() {
+		let low = scored("https://a.example/", 1.0, Some("2025-01-02T00:00:00Z"));
+		let high = scored("https://b.example/", 2.0, Some("2025-01-01T00:00:00Z"));
+		assert!(high > low);
+	}
+
+	#[test]
+	fn equal_scores_rank_newer_articles_first() {
+		let older = scored("https://a.example/", 1.0, Some("2025-01-01T00:00:00Z"));
+		let newer = scored("https://b.example/", 1.0, Some("2025-01-02T00:00:00Z"));
+		let undated = scored("https://c.example/", 1.0, None);
+		assert!(newer > older);
+		assert!(older > undated);
+	}
+
+	#[test]
+	fn equal_scores_and_dates_rank_by_url() {
+		let date = Some("2025-01-01T00:00:00Z");
+		let first = scored("https://a.example/", 1.0, date);
+		let second = scored("https://b.example/", 1.0, date);
+		assert!(first > second);
+		assert_eq!(first.cmp(&scored("https://a.example/", 1.0, date)), Ordering::Equal);
+
+		let mut ranking = [second, first];
+		ranking.sort_by(|a, b| b.cmp(a));
+		let urls: Vec<&str> = ranking.iter().map(|scored| scored.article().url()).collect();
+		assert_eq!(urls, ["https://a.example/", "https://b.example/"]);
+	}
 }
@@ -0,0 +1,72 @@
+use crate::config::{AuthConfig, SourceConfig};
+use reqwest::RequestBuilder;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT};
+use std::collections::BTreeMap;
+use tracing::warn;
+
+/// Credentials, extra headers and User-Agent override attached to every request to one source.
+///
+/// Secrets are resolved once when the fetcher is built; a credential that cannot be resolved is
+/// logged and the source is fetched unauthenticated, so `config validate` is the place to catch it.
+#[derive(Debug, Default)]
+pub struct SourceAuth {
+	headers: HeaderMap,
+	credentials: Option<Credentials>,
+}
+
+#[derive(Debug)]
+enum Credentials {
+	Bearer(String),
+	Basic { username: String, password: String },
+}
+
+impl SourceAuth {
+	#[must_use]
+	pub fn new(source: &str, config: &SourceConfig) -> Self {
+		let headers = header_map(&config.headers, config.user_agent.as_deref());
+		let credentials = config.auth.as_ref().and_then(|auth| {
+			let (_, secret) = auth.secret();
+			let value = secret
+				.resolve()
+				.map_err(|e| {
+					warn!(source, error = %e, "Source credentials unavailable, sending unauthenticated requests");
+				})
+				.ok()?;
+			Some(match auth {
+				AuthConfig::Bearer { .. } => Credentials::Bearer(value),
+				AuthConfig::Basic { username, .. } => Credentials::Basic {
+					username: username.clone(),
+					password: value,
+				},
+			})
+		});
+		Self { headers, credentials }
+	}
+
+	pub fn apply(&self, request: RequestBuilder) -> RequestBuilder {
+		let request = request.headers(self.headers.clone());
+		match &self.credentials {
+			Some(Credentials::Bearer(token)) => request.bearer_auth(token),
+			Some(Credentials::Basic { username, password }) => request.basic_auth(username, Some(password)),
+			None => request,
+		}
+	}
+}
+
+/// Configured headers plus an optional User-Agent as a `HeaderMap`.
+/// Names and values are checked when the configuration is validated, so invalid entries are skipped here.
+pub fn header_map(headers: &BTreeMap<String, String>, user_agent: Option<&str>) -> HeaderMap {
+	let mut map: HeaderMap = headers
+		.iter()
+		.filter_map(|(name, value)| {
+			Some((
+				HeaderName::from_bytes(name.as_bytes()).ok()?,
+				HeaderValue::from_str(value).ok()?,
+			))
+		})
+		.collect();
+	if let Some(agent) = user_agent.and_then(|agent| HeaderValue::from_str(agent).ok()) {
+		map.insert(USER_AGENT, agent);
+	}
+	map
+}
@@ -0,0 +1,129 @@
+use crate::analyzer::Analyzer;
+use crate::config::Config;
+use crate::error::Result;
+use crate::model::Article;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// Words the synthetic titles and descriptions are made of, besides the keywords
+const FILLER: &str = "release notes compiler database startup kernel browser update security design open source \
+	library framework cloud latency memory network review guide team launch version bug patch benchmark service \
+	storage protocol editor language runtime server client hardware model data study report interview";
+
+const SOURCES: &[&str] = &["hackernews", "reddit", "lobsters", "devto", "rss"];
+
+/// Chance that a word of a synthetic article is one of the keywords
+const KEYWORD_RATE: f64 = 0.02;
+const TITLE_WORDS: usize = 8;
+/// Words in a feed's description of an article; the text of an extracted page runs to thousands
+pub const DESCRIPTION_WORDS: usize = 40;
+
+/// `count` made-up articles with descriptions of `description_words` words, whose text mixes `keywords` into
+/// filler words at a realistic rate, the same for the same `seed` so runs compare
+#[must_use]
+pub fn synthetic_corpus(keywords: &[String], count: usize, description_words: usize, seed: u64) -> Vec<Article> {
+	let mut rng = fastrand::Rng::with_seed(seed);
+	let filler: Vec<&str> = FILLER.split_whitespace().collect();
+	let mut text = |words: usize| -> String {
+		let picked: Vec<&str> = (0..words)
+			.map(|_| {
+				let keyword = (rng.f64() < KEYWORD_RATE)
+					.then(|| keywords.get(rng.usize(..keywords.len().max(1))))
+					.flatten();
+				keyword.map_or_else(
+					|| filler.get(rng.usize(..filler.len())).copied().unwrap_or("news"),
+					String::as_str,
+				)
+			})
+			.collect();
+		picked.join(" ")
+	};
+	(0..count)
+		.zip(SOURCES.iter().cycle())
+		.map(|(index, source)| {
+			Article::new(
+				text(TITLE_WORDS),
+				format!("https://bench.example/{source}/{index}"),
+				source.to_string(),
+			)
+			.with_description(text(description_words))
+		})
+		.collect()
+}
+
+/// `corpus` repeated or cut to exactly `count` articles; empty when the corpus is
+#[must_use]
+pub fn resize_corpus(corpus: Vec<Article>, count: usize) -> Vec<Article> {
+	if corpus.len() >= count {
+		let mut corpus = corpus;
+		corpus.truncate(count);
+		return corpus;
+	}
+	corpus.iter().cycle().take(count).cloned().collect()
+}
+
+/// Timings of scoring one corpus several times
+#[derive(Debug)]
+pub struct BenchReport {
+	pub articles: usize,
+	/// Articles that matched at least one keyword, from the last iteration
+	pub matched: usize,
+	pub timings: Vec<Duration>,
+}
+
+impl BenchReport {
+	#[must_use]
+	pub fn best(&self) -> Duration {
+		self.timings.iter().min().copied().unwrap_or_default()
+	}
+
+	#[must_use]
+	pub fn median(&self) -> Duration {
+		let mut sorted = self.timings.clone();
+		sorted.sort_unstable();
+		sorted
+			.len()
+			.checked_div(2)
+			.and_then(|middle| sorted.get(middle))
+			.copied()
+			.unwrap_or_default()
+	}
+
+	/// Articles scored per second in `elapsed`
+	#[allow(clippy::cast_precision_loss)]
+	#[must_use]
+	pub fn throughput(&self, elapsed: Duration) -> f64 {
+		let seconds = elapsed.as_secs_f64();
+		if seconds > 0.0 {
+			self.articles as f64 / seconds
+		} else {
+			0.0
+		}
+	}
+}
+
+/// Score `corpus` with the analyzer `iterations` times after one warm-up pass, timing each pass; only the
+/// scoring is timed, not compiling the automata, which runs reuse, or copying the corpus
+///
+/// # Errors
+///
+/// When the analyzer cannot be built from `config`
+pub fn run(config: &Config, corpus: &[Article], iterations: usize) -> Result<BenchReport> {
+	let cancel = CancellationToken::new();
+	let analyzer = Analyzer::new(config)?;
+	analyzer.score(corpus.to_vec(), config, &cancel)?;
+	let mut timings = Vec::with_capacity(iterations);
+	let mut matched = 0;
+	for _ in 0..iterations {
+		let articles = corpus.to_vec();
+		let started = Instant::now();
+		let scored = analyzer.score(articles, config, &cancel)?;
+		timings.push(started.elapsed());
+		matched = scored.iter().filter(|item| !item.matched_keywords().is_empty()).count();
+	}
+	Ok(BenchReport {
+		articles: corpus.len(),
+		matched,
+		timings,
+	})
+}
@@ -0,0 +1,67 @@
+use crate::config::CacheConfig;
+use crate::error::{AppError, Result};
+use bytes::Bytes;
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// Response bodies on disk, one file per URL named by the URL's SHA-256.
+///
+/// A body's age is the modification time of its file, so entries are refreshed by overwriting them
+/// and can be expired by hand simply by deleting files from `cache.dir`.
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+	dir: PathBuf,
+}
+
+impl ResponseCache {
+	#[must_use]
+	pub fn new(config: &CacheConfig) -> Self {
+		Self::in_dir(PathBuf::from(&config.dir))
+	}
+
+	#[must_use]
+	pub const fn in_dir(dir: PathBuf) -> Self {
+		Self { dir }
+	}
+
+	/// Cached body for `url` if it is younger than `max_age`; `None` accepts any age
+	pub fn get(&self, url: &str, max_age: Option<Duration>) -> Option<Bytes> {
+		let path = self.path(url);
+		if let Some(max_age) = max_age {
+			let age = fs::metadata(&path)
+				.and_then(|metadata| metadata.modified())
+				.ok()
+				.and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+			if age > max_age {
+				return None;
+			}
+		}
+		fs::read(path).ok().map(Bytes::from)
+	}
+
+	/// Store the body for `url`, replacing the file atomically so readers never see a partial body
+	///
+	/// # Errors
+	///
+	/// `IoError` when the cache directory or the body cannot be written
+	pub fn put(&self, url: &str, body: &[u8]) -> Result<()> {
+		fs::create_dir_all(&self.dir).map_err(|e| AppError::io_error(&self.dir, e))?;
+		let path = self.path(url);
+		let partial = path.with_extension("partial");
+		fs::write(&partial, body).map_err(|e| AppError::io_error(&partial, e))?;
+		fs::rename(&partial, &path).map_err(|e| AppError::io_error(&path, e))
+	}
+
+	fn path(&self, url: &str) -> PathBuf {
+		let name = Sha256::digest(url.as_bytes())
+			.iter()
+			.fold(String::with_capacity(64), |mut hex, byte| {
+				let _ = write!(hex, "{byte:02x}");
+				hex
+			});
+		self.dir.join(name)
+	}
+}
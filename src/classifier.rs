@@ -0,0 +1,183 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::CategoryConfig;
+use crate::error::{AppError, Result};
+use crate::model::Article;
+use aho_corasick::AhoCorasick;
+use rayon::prelude::*;
+use regex::RegexSet;
+use std::collections::{BTreeMap, HashMap};
+
+/// Compiled matchers for one category
+struct CategoryMatcher {
+	name: String,
+	keywords: Option<AhoCorasick>,
+	patterns: Option<RegexSet>,
+	tags: Vec<String>,
+}
+
+impl CategoryMatcher {
+	fn matches(&self, article: &Article) -> bool {
+		let text = article.searchable_text();
+		self.keywords.as_ref().is_some_and(|ac| ac.is_match(text.as_ref()))
+			|| self.patterns.as_ref().is_some_and(|set| set.is_match(&text))
+			|| article
+				.tags()
+				.iter()
+				.any(|tag| self.tags.iter().any(|wanted| wanted.eq_ignore_ascii_case(tag)))
+	}
+}
+
+/// Rule-based multi-label topic classifier built from `[categories.<name>]` config
+pub struct Classifier {
+	categories: Vec<CategoryMatcher>,
+}
+
+impl Classifier {
+	/// # Errors
+	///
+	/// `ConfigError` when a category pattern is not a valid regular expression
+	pub fn new(config: &BTreeMap<String, CategoryConfig>) -> Result<Self> {
+		let categories = config
+			.iter()
+			.map(|(name, category)| {
+				let keywords = if category.keywords.is_empty() {
+					None
+				} else {
+					let ac = AhoCorasick::builder()
+						.ascii_case_insensitive(true)
+						.build(&category.keywords)
+						.map_err(|e| AppError::AnalyzerError(format!("category {name}: {e}")))?;
+					Some(ac)
+				};
+				let patterns = if category.patterns.is_empty() {
+					None
+				} else {
+					let set = regex::RegexSetBuilder::new(&category.patterns)
+						.case_insensitive(true)
+						.build()
+						.map_err(|e| AppError::ConfigError(format!("categories.{name}.patterns: {e}")))?;
+					Some(set)
+				};
+				Ok(CategoryMatcher {
+					name: name.clone(),
+					keywords,
+					patterns,
+					tags: category.tags.clone(),
+				})
+			})
+			.collect::<Result<Vec<_>>>()?;
+		Ok(Self { categories })
+	}
+
+	#[must_use]
+	pub fn classify(&self, article: &Article) -> Vec<String> {
+		self.categories
+			.iter()
+			.filter(|category| category.matches(article))
+			.map(|category| category.name.clone())
+			.collect()
+	}
+
+	/// Assign categories to every article in parallel
+	pub fn classify_all(&self, articles: &mut [ScoredArticle]) {
+		articles.par_iter_mut().for_each(|scored| {
+			let categories = self.classify(scored.article());
+			scored.set_categories(categories);
+		});
+	}
+}
+
+/// Keep only articles in one of `wanted` categories; an empty list keeps everything
+pub fn filter_categories(articles: &mut Vec<ScoredArticle>, wanted: &[String]) {
+	if wanted.is_empty() {
+		return;
+	}
+	articles.retain(|scored| scored.categories().iter().any(|category| wanted.contains(category)));
+}
+
+/// Walk the ranking in order and drop articles that would exceed a category's `limit`
+pub fn apply_category_limits(articles: &mut Vec<ScoredArticle>, config: &BTreeMap<String, CategoryConfig>) {
+	let mut counts: HashMap<&str, usize> = HashMap::new();
+	articles.retain(|scored| {
+		let over_limit = scored.categories().iter().any(|category| {
+			let limit = config.get(category).and_then(|c| c.limit);
+			limit.is_some_and(|limit| counts.get(category.as_str()).copied().unwrap_or(0) >= limit)
+		});
+		if over_limit {
+			return false;
+		}
+		for category in scored.categories() {
+			if let Some((name, _)) = config.get_key_value(category) {
+				let count = counts.entry(name.as_str()).or_insert(0);
+				*count = count.saturating_add(1);
+			}
+		}
+		true
+	});
+}
+
+/// Make sure each category with `min_slots` has that many articles among the first `slots` of an ordered
+/// ranking, as far as it has articles at all.
+///
+/// Its best articles below the cut are moved up to the end of the digest; the lowest-ranked digest articles
+/// that no other guarantee needs move just below the cut, so nothing is dropped. Returns the number of
+/// articles moved up.
+pub fn reserve_category_slots(
+	articles: &mut Vec<ScoredArticle>,
+	config: &BTreeMap<String, CategoryConfig>,
+	slots: usize,
+) -> usize {
+	let reserved: Vec<(&str, usize)> = config
+		.iter()
+		.filter_map(|(name, category)| {
+			category
+				.min_slots
+				.filter(|&min| min > 0)
+				.map(|min| (name.as_str(), min))
+		})
+		.collect();
+	if reserved.is_empty() || articles.len() <= slots || slots == 0 {
+		return 0;
+	}
+	let in_category = |scored: &ScoredArticle, name: &str| scored.categories().iter().any(|c| c == name);
+	let count = |articles: &[ScoredArticle], name: &str| {
+		articles
+			.iter()
+			.take(slots)
+			.filter(|scored| in_category(scored, name))
+			.count()
+	};
+
+	let mut promoted = 0usize;
+	for &(name, min) in &reserved {
+		while count(articles, name) < min {
+			let Some(candidate) = articles
+				.iter()
+				.skip(slots)
+				.position(|scored| in_category(scored, name))
+				.map(|index| index.saturating_add(slots))
+			else {
+				break;
+			};
+			// The lowest-ranked digest article whose categories all stay above their own guarantee without it
+			let displaced = articles.iter().take(slots).rposition(|scored| {
+				!in_category(scored, name)
+					&& reserved
+						.iter()
+						.all(|&(other, other_min)| !in_category(scored, other) || count(articles, other) > other_min)
+			});
+			let Some(displaced) = displaced else {
+				break;
+			};
+			let article = articles.remove(candidate);
+			let moved_down = articles.remove(displaced);
+			articles.insert(slots.saturating_sub(1), article);
+			articles.insert(slots, moved_down);
+			promoted = promoted.saturating_add(1);
+		}
+	}
+	if let Some(digest) = articles.get_mut(..slots) {
+		digest.sort_by(|a, b| b.cmp(a));
+	}
+	promoted
+}
@@ -0,0 +1,272 @@
+use crate::bench;
+use crate::config::{Config, FixtureMode, FixturesConfig, GroupBy, LogFormat, ReadOrder};
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+/// Fetch, score and rank news articles by keyword relevance
+#[derive(Debug, Clone, Parser)]
+#[command(version, about)]
+// Independent command-line switches
+#[allow(clippy::struct_excessive_bools)]
+pub struct Cli {
+	#[command(subcommand)]
+	pub command: Option<Command>,
+
+	/// Number of top articles to display (overrides `display.limit`)
+	#[arg(long, global = true)]
+	pub limit: Option<usize>,
+
+	/// Drop articles scoring below this value (overrides `display.min_score`)
+	#[arg(long, global = true)]
+	pub min_score: Option<f64>,
+
+	/// Most articles from one domain in the displayed ranking (overrides `display.max_per_domain`)
+	#[arg(long, global = true)]
+	pub max_per_domain: Option<usize>,
+
+	/// Show top articles per source or per topic instead of one flat ranking (overrides `display.group_by`)
+	#[arg(long, value_enum, global = true)]
+	pub group_by: Option<GroupBy>,
+
+	/// Only show articles in this category; repeat for several (overrides `display.categories`)
+	#[arg(long = "category", global = true)]
+	pub categories: Vec<String>,
+
+	/// Attach and display a per-article score breakdown (overrides `analyzer.explain`)
+	#[arg(long, global = true)]
+	pub explain: bool,
+
+	/// Merge this `[profile.<name>]` table over the base configuration
+	#[arg(long, global = true)]
+	pub profile: Option<String>,
+
+	/// Fetch once, then score, display and deliver separately for every configured profile
+	#[arg(long, conflicts_with = "profile")]
+	pub all_profiles: bool,
+
+	/// Log line format (overrides `log.format`)
+	#[arg(long, value_enum, global = true)]
+	pub log_format: Option<LogFormat>,
+
+	/// Display every scored article, ignoring the limit and minimum score
+	#[arg(long, global = true)]
+	pub all: bool,
+
+	/// Only show and deliver articles no earlier run has ranked (sets `store.only_new`)
+	#[arg(long, global = true)]
+	pub only_new: bool,
+
+	/// Drop articles taking longer to read, in minutes (sets `content.max_read_minutes` and enables `[content]`)
+	#[arg(long, global = true)]
+	pub max_read_minutes: Option<u32>,
+
+	/// Order articles by reading time (sets `content.order` and enables `[content]`)
+	#[arg(long, value_enum, global = true)]
+	pub read_order: Option<ReadOrder>,
+
+	/// Serve every request from the response cache and never touch the network (sets `cache.offline`)
+	#[arg(long, global = true)]
+	pub offline: bool,
+
+	/// Save every source response to this directory as fixtures (sets `fixtures.mode = "record"`)
+	#[arg(long, value_name = "DIR", global = true, conflicts_with = "replay")]
+	pub record: Option<String>,
+
+	/// Serve every source from the fixtures recorded in this directory (sets `fixtures.mode = "replay"`)
+	#[arg(long, value_name = "DIR", global = true)]
+	pub replay: Option<String>,
+
+	/// Fetch and score, but only log what each sink would get, without delivering or writing the store
+	/// (sets `delivery.dry_run`)
+	#[arg(long, global = true)]
+	pub dry_run: bool,
+}
+
+/// Without a subcommand the full pipeline runs once
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+	/// Run a single pipeline stage
+	Pipeline {
+		#[command(subcommand)]
+		stage: Stage,
+	},
+	/// Re-score every article in the persistent store with the current configuration, without fetching
+	Rescore,
+	/// Prune the persistent store by `store.retention` now, instead of waiting for the end of a run
+	Purge {
+		/// Drop articles not seen for this many days (overrides `store.retention.max_age_days`)
+		#[arg(long)]
+		max_age_days: Option<u64>,
+		/// Keep at most this many articles (overrides `store.retention.max_articles`)
+		#[arg(long)]
+		max_articles: Option<usize>,
+	},
+	/// Write the whole store (articles with their starred flag and clicks, run rankings, the delivery ledger and read marks) to a bundle
+	Export {
+		/// One JSON document for a `.json` file, JSON Lines otherwise
+		path: PathBuf,
+	},
+	/// Merge a bundle written by `export` into the store, creating the store if needed
+	Import { path: PathBuf },
+	/// Propose new keywords: terms weighing most (TF-IDF) in stored articles that match the current keywords
+	SuggestKeywords {
+		/// Number of suggestions
+		#[arg(long, default_value_t = 20)]
+		top: usize,
+		/// Only suggest terms found in at least this many matching articles
+		#[arg(long, default_value_t = 2)]
+		min_articles: usize,
+		/// Example titles shown per suggestion
+		#[arg(long, default_value_t = 3)]
+		examples: usize,
+	},
+	/// Train the interest model on the starred and other stored articles (`learning` feature)
+	Retrain,
+	/// Time the scorer over a corpus and report its throughput in articles per second; nothing is fetched
+	Bench {
+		/// Corpus size
+		#[arg(long, default_value_t = 10_000)]
+		articles: usize,
+		/// Timed passes over the corpus, after one warm-up pass
+		#[arg(long, default_value_t = 5)]
+		iterations: usize,
+		/// Score the stored articles, repeated as needed to reach `--articles`, instead of synthetic ones
+		#[arg(long)]
+		stored: bool,
+		/// Words per synthetic description; a few thousand stand in for the text of extracted pages
+		#[arg(long, default_value_t = bench::DESCRIPTION_WORDS)]
+		words: usize,
+		/// Seed of the synthetic corpus, for comparing runs on different builds
+		#[arg(long, default_value_t = 42)]
+		seed: u64,
+	},
+	/// Star a stored article so retention never drops it (with `store.retention.keep_starred`)
+	Star {
+		url: String,
+		/// Unstar the article instead
+		#[arg(long)]
+		remove: bool,
+	},
+	/// Check or create the configuration file
+	Config {
+		#[command(subcommand)]
+		action: ConfigAction,
+	},
+	/// Keep running, repeating the full pipeline and reloading the configuration on SIGHUP or file change
+	Daemon {
+		/// Seconds between fetch cycles (overrides `daemon.interval_secs`)
+		#[arg(long)]
+		interval: Option<u64>,
+		/// Run as a Windows service started by the service control manager (`windows-service` feature)
+		#[arg(long)]
+		service: bool,
+	},
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Stage {
+	/// Fetch articles from all sources without scoring them
+	Fetch {
+		/// Write the raw articles to this JSON file instead of stdout
+		#[arg(long)]
+		save: Option<PathBuf>,
+	},
+	/// Normalize, dedupe, score, rank and output previously fetched articles
+	Score {
+		/// JSON file written by `pipeline fetch --save`
+		#[arg(long)]
+		input: PathBuf,
+	},
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ConfigAction {
+	/// Load and validate the configuration, then check that every source is reachable
+	Validate,
+	/// Write a commented default configuration to start from
+	Init {
+		/// Where to write the configuration
+		#[arg(long, default_value = "config.toml")]
+		path: PathBuf,
+		/// Overwrite an existing file
+		#[arg(long)]
+		force: bool,
+	},
+}
+
+impl Cli {
+	/// Layer command-line overrides on top of the loaded configuration
+	pub fn apply(&self, config: &mut Config) {
+		if let Some(limit) = self.limit {
+			config.display.limit = limit;
+		}
+		if let Some(min_score) = self.min_score {
+			config.display.min_score = min_score;
+		}
+		if let Some(max_per_domain) = self.max_per_domain {
+			config.display.max_per_domain = Some(max_per_domain);
+		}
+		if let Some(group_by) = self.group_by {
+			config.display.group_by = group_by;
+		}
+		if !self.categories.is_empty() {
+			config.display.categories.clone_from(&self.categories);
+		}
+		if let Some(format) = self.log_format {
+			config.log.format = format;
+		}
+		if self.explain {
+			config.analyzer.explain = true;
+		}
+		if let Some(Command::Daemon {
+			interval: Some(interval),
+			..
+		}) = self.command
+		{
+			config.daemon.interval_secs = interval;
+		}
+		if let Some(Command::Purge {
+			max_age_days,
+			max_articles,
+		}) = self.command
+		{
+			let retention = &mut config.store.retention;
+			retention.max_age_days = max_age_days.or(retention.max_age_days);
+			retention.max_articles = max_articles.or(retention.max_articles);
+		}
+		if self.all {
+			config.display.limit = usize::MAX;
+			config.display.min_score = f64::NEG_INFINITY;
+			config.analyzer.max_results = None;
+		}
+		if self.offline {
+			config.cache.offline = true;
+		}
+		if self.dry_run {
+			config.delivery.dry_run = true;
+		}
+		if let Some(dir) = &self.record {
+			config.fixtures = FixturesConfig {
+				mode: Some(FixtureMode::Record),
+				dir: dir.clone(),
+			};
+		}
+		if let Some(dir) = &self.replay {
+			config.fixtures = FixturesConfig {
+				mode: Some(FixtureMode::Replay),
+				dir: dir.clone(),
+			};
+		}
+		if self.only_new {
+			config.store.only_new = true;
+		}
+		if let Some(minutes) = self.max_read_minutes {
+			config.content.enabled = true;
+			config.content.max_read_minutes = Some(minutes);
+		}
+		if let Some(order) = self.read_order {
+			config.content.enabled = true;
+			config.content.order = order;
+		}
+	}
+}
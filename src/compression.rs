@@ -0,0 +1,89 @@
+use crate::error::{AppError, Result};
+use bytes::Bytes;
+use flate2::read::GzDecoder;
+use reqwest::header::{CONTENT_ENCODING, HeaderMap};
+use std::io::Read;
+
+/// Brotli decoder buffer size, the one its own examples use
+const BROTLI_BUFFER: usize = 4096;
+
+/// Decoded size a compressed body may reach when `fetcher.max_body_bytes` is unset.
+///
+/// Feeds and API responses stay far below it, while a small body that would inflate to gigabytes is stopped
+/// long before memory runs out.
+pub const DEFAULT_MAX_DECODED_BYTES: usize = 64 * 1024 * 1024;
+
+/// How a response body is compressed, from its `Content-Encoding`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+	Identity,
+	Gzip,
+	Brotli,
+}
+
+impl ContentEncoding {
+	/// The encoding of a response with `headers`; one the fetcher never asks for, such as `zstd`, is an error
+	/// naming `url`
+	///
+	/// # Errors
+	///
+	/// `ParseError` for any encoding other than identity, gzip and Brotli
+	pub fn of(headers: &HeaderMap, url: &str) -> Result<Self> {
+		let Some(value) = headers.get(CONTENT_ENCODING) else {
+			return Ok(Self::Identity);
+		};
+		let value = value.to_str().unwrap_or_default().trim().to_ascii_lowercase();
+		match value.as_str() {
+			"" | "identity" => Ok(Self::Identity),
+			"gzip" | "x-gzip" => Ok(Self::Gzip),
+			"br" => Ok(Self::Brotli),
+			other => Err(AppError::parse_error(
+				url,
+				format!("unsupported Content-Encoding {other}"),
+			)),
+		}
+	}
+
+	/// The decoded `body` of `url`. Decoding stops past `max_bytes`, or `DEFAULT_MAX_DECODED_BYTES` when
+	/// unset, so a small body that inflates to gigabytes fails with `BodyTooLarge` instead of filling memory.
+	///
+	/// # Errors
+	///
+	/// `ParseError` when the body is not valid for its encoding, `BodyTooLarge` when it decodes past `max_bytes`
+	pub fn decode(self, body: Bytes, url: &str, max_bytes: Option<usize>) -> Result<Bytes> {
+		let reader: Box<dyn Read + '_> = match self {
+			Self::Identity => return Ok(body),
+			Self::Gzip => Box::new(GzDecoder::new(body.as_ref())),
+			Self::Brotli => Box::new(brotli_decompressor::Decompressor::new(body.as_ref(), BROTLI_BUFFER)),
+		};
+		let max = max_bytes.unwrap_or(DEFAULT_MAX_DECODED_BYTES);
+		let mut decoded = Vec::new();
+		reader
+			.take(u64::try_from(max).unwrap_or(u64::MAX).saturating_add(1))
+			.read_to_end(&mut decoded)
+			.map_err(|e| AppError::parse_error(url, e))?;
+		if decoded.len() > max {
+			return Err(AppError::BodyTooLarge {
+				url: url.into(),
+				limit: max,
+			});
+		}
+		Ok(Bytes::from(decoded))
+	}
+
+	/// `decode` on the blocking thread pool, so that inflating a large body does not stall the other tasks of
+	/// a runtime worker
+	///
+	/// # Errors
+	///
+	/// As `decode`
+	pub async fn decode_blocking(self, body: Bytes, url: &str, max_bytes: Option<usize>) -> Result<Bytes> {
+		if self == Self::Identity {
+			return Ok(body);
+		}
+		let owned = url.to_string();
+		tokio::task::spawn_blocking(move || self.decode(body, &owned, max_bytes))
+			.await
+			.map_err(|e| AppError::parse_error(url, e))?
+	}
+}
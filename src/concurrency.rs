@@ -0,0 +1,246 @@
+use crate::config::FetcherConfig;
+use crate::error::{AppError, Result};
+use crate::metrics::Metrics;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tracing::debug;
+
+/// A response slower than this multiple of the recent average counts as a sign of congestion
+const LATENCY_TOLERANCE: u32 = 2;
+/// Weight of the newest sample in the moving latency average
+const LATENCY_SMOOTHING: f64 = 0.2;
+
+/// Limit on concurrent HTTP requests that adapts to how the remote end copes (AIMD).
+///
+/// Every healthy window of `limit` responses raises the limit by one, up to `max_concurrent_requests`.
+/// A transient failure (timeout, connection error, 429, 5xx) or a response much slower than the recent
+/// average halves it, at most once per window and never below `min_concurrent_requests`.
+/// With `adaptive_concurrency = false` the limit stays at `max_concurrent_requests`.
+#[derive(Debug)]
+pub struct ConcurrencyLimiter {
+	semaphore: Semaphore,
+	state: Mutex<State>,
+	min: usize,
+	max: usize,
+	adaptive: bool,
+	metrics: Metrics,
+}
+
+#[derive(Debug)]
+struct State {
+	limit: usize,
+	/// Healthy responses since the limit last grew
+	healthy: usize,
+	/// Responses since the limit last shrank
+	since_decrease: usize,
+	latency: Option<Duration>,
+	/// Permits still to be retired once in-flight requests hand them back
+	debt: usize,
+}
+
+impl ConcurrencyLimiter {
+	#[must_use]
+	pub fn new(config: &FetcherConfig, metrics: Metrics) -> Self {
+		let max = config.max_concurrent_requests;
+		let min = config.min_concurrent_requests.clamp(1, max);
+		let limit = if config.adaptive_concurrency {
+			max.div_ceil(2).max(min)
+		} else {
+			max
+		};
+		metrics.set_concurrency_limit(limit);
+		Self {
+			semaphore: Semaphore::new(limit),
+			state: Mutex::new(State {
+				limit,
+				healthy: 0,
+				since_decrease: 0,
+				latency: None,
+				debt: 0,
+			}),
+			min,
+			max,
+			adaptive: config.adaptive_concurrency,
+			metrics,
+		}
+	}
+
+	/// Run one request once a slot is free; `healthy` tells a transient failure apart from a usable answer
+	///
+	/// # Errors
+	///
+	/// `ShutdownError` when the limiter is closed while waiting for a permit, otherwise the error of `operation`
+	// The permit has to be held until the request completes and is handed back through `release`
+	#[allow(clippy::significant_drop_tightening)]
+	pub async fn run<T>(
+		&self,
+		request: impl Future<Output = (T, Duration)>,
+		healthy: impl FnOnce(&T) -> bool,
+	) -> Result<(T, Duration)> {
+		let permit = self.semaphore.acquire().await.map_err(|_| AppError::ShutdownError)?;
+		let (output, elapsed) = request.await;
+		self.release(permit);
+		self.record(elapsed, healthy(&output));
+		Ok((output, elapsed))
+	}
+
+	fn record(&self, latency: Duration, healthy: bool) {
+		if !self.adaptive {
+			return;
+		}
+		let Ok(mut state) = self.state.lock() else { return };
+		let slow = state
+			.latency
+			.is_some_and(|average| latency > average.saturating_mul(LATENCY_TOLERANCE));
+		if healthy {
+			state.latency = Some(state.latency.map_or(latency, |average| {
+				average
+					.mul_f64(1.0 - LATENCY_SMOOTHING)
+					.saturating_add(latency.mul_f64(LATENCY_SMOOTHING))
+			}));
+		}
+		state.since_decrease = state.since_decrease.saturating_add(1);
+
+		let current = state.limit;
+		if !healthy || slow {
+			state.healthy = 0;
+			if state.since_decrease >= current && current > self.min {
+				let limit = current.div_ceil(2).max(self.min);
+				let excess = current.saturating_sub(limit);
+				let retired = self.semaphore.forget_permits(excess);
+				state.debt = state.debt.saturating_add(excess.saturating_sub(retired));
+				state.limit = limit;
+				state.since_decrease = 0;
+				self.changed(limit, "Backing off concurrency");
+			}
+		} else {
+			state.healthy = state.healthy.saturating_add(1);
+			if state.healthy >= current && current < self.max {
+				if state.debt > 0 {
+					state.debt = state.debt.saturating_sub(1);
+				} else {
+					self.semaphore.add_permits(1);
+				}
+				state.limit = current.saturating_add(1);
+				state.healthy = 0;
+				self.changed(state.limit, "Raising concurrency");
+			}
+		}
+	}
+
+	fn changed(&self, limit: usize, message: &str) {
+		self.metrics.set_concurrency_limit(limit);
+		debug!(limit, "{message}");
+	}
+
+	/// Hand a permit back, or retire it to pay off a pending decrease
+	fn release(&self, permit: SemaphorePermit<'_>) {
+		let owed = self.state.lock().is_ok_and(|mut state| {
+			let owed = state.debt > 0;
+			state.debt = state.debt.saturating_sub(1);
+			owed
+		});
+		if owed {
+			permit.forget();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::config;
+
+	type TestResult = std::result::Result<(), Box<dyn std::error::Error>>;
+
+	const LATENCY: Duration = Duration::from_millis(100);
+
+	fn limiter(min: usize, max: usize, adaptive: bool) -> Result<ConcurrencyLimiter> {
+		let config = FetcherConfig {
+			min_concurrent_requests: min,
+			max_concurrent_requests: max,
+			adaptive_concurrency: adaptive,
+			..config::starter()?.fetcher
+		};
+		Ok(ConcurrencyLimiter::new(&config, Metrics::new()))
+	}
+
+	fn limit(limiter: &ConcurrencyLimiter) -> usize {
+		limiter.state.lock().map_or(0, |state| state.limit)
+	}
+
+	fn record(limiter: &ConcurrencyLimiter, healthy: bool, times: usize) {
+		for _ in 0..times {
+			limiter.record(LATENCY, healthy);
+		}
+	}
+
+	#[test]
+	fn starts_halfway_between_the_bounds() -> TestResult {
+		let limiter = limiter(1, 8, true)?;
+		assert_eq!(limit(&limiter), 4);
+		assert_eq!(limiter.semaphore.available_permits(), 4);
+		Ok(())
+	}
+
+	#[test]
+	fn a_healthy_window_raises_the_limit_by_one() -> TestResult {
+		let limiter = limiter(1, 8, true)?;
+		record(&limiter, true, 3);
+		assert_eq!(limit(&limiter), 4, "the window is not complete yet");
+		record(&limiter, true, 1);
+		assert_eq!(limit(&limiter), 5);
+		assert_eq!(limiter.semaphore.available_permits(), 5);
+		record(&limiter, true, 5);
+		assert_eq!(limit(&limiter), 6);
+		Ok(())
+	}
+
+	#[test]
+	fn failures_halve_the_limit_once_per_window() -> TestResult {
+		let limiter = limiter(1, 16, true)?;
+		record(&limiter, false, 7);
+		assert_eq!(limit(&limiter), 8, "no decrease before a full window of responses");
+		record(&limiter, false, 1);
+		assert_eq!(limit(&limiter), 4);
+		assert_eq!(limiter.semaphore.available_permits(), 4);
+		record(&limiter, false, 3);
+		assert_eq!(limit(&limiter), 4);
+		record(&limiter, false, 1);
+		assert_eq!(limit(&limiter), 2);
+		Ok(())
+	}
+
+	#[test]
+	fn a_response_much_slower_than_average_counts_as_a_failure() -> TestResult {
+		let limiter = limiter(1, 8, true)?;
+		record(&limiter, true, 3);
+		limiter.record(LATENCY.saturating_mul(LATENCY_TOLERANCE.saturating_add(1)), true);
+		assert_eq!(limit(&limiter), 2);
+		Ok(())
+	}
+
+	#[test]
+	fn the_limit_stays_within_its_bounds() -> TestResult {
+		let limiter = limiter(3, 8, true)?;
+		record(&limiter, false, 100);
+		assert_eq!(limit(&limiter), 3);
+		assert_eq!(limiter.semaphore.available_permits(), 3);
+		record(&limiter, true, 100);
+		assert_eq!(limit(&limiter), 8);
+		assert_eq!(limiter.semaphore.available_permits(), 8);
+		Ok(())
+	}
+
+	#[test]
+	fn a_fixed_limit_ignores_outcomes() -> TestResult {
+		let limiter = limiter(1, 8, false)?;
+		assert_eq!(limit(&limiter), 8);
+		record(&limiter, false, 100);
+		assert_eq!(limit(&limiter), 8);
+		assert_eq!(limiter.semaphore.available_permits(), 8);
+		Ok(())
+	}
+}
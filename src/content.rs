@@ -0,0 +1,92 @@
+use crate::analyzer::{ReadingTime, ScoredArticle};
+use crate::config::{ContentConfig, ReadOrder};
+use crate::extract::{Block, extract_body};
+use futures::stream::{self, StreamExt};
+use reqwest::Client;
+use reqwest::header::CONTENT_TYPE;
+use std::cmp::Reverse;
+use tracing::{debug, info};
+
+/// Attach the word count and reading time of each of the top `top_n` articles, from the readable text of its
+/// page. Pages that fail to load, are not HTML or have little text leave the article without one.
+pub async fn attach_read_times(client: &Client, config: &ContentConfig, scored: &mut [ScoredArticle]) {
+	let count = config.top_n.min(scored.len());
+	let Some(top) = scored.get_mut(..count) else {
+		return;
+	};
+	let urls: Vec<String> = top.iter().map(|item| item.article().url().to_string()).collect();
+	let bodies: Vec<Option<Vec<Block>>> = stream::iter(urls)
+		.map(|url| async move { fetch_body(client, &url).await })
+		.buffered(config.concurrency)
+		.collect()
+		.await;
+
+	let mut measured = 0usize;
+	for (item, body) in top.iter_mut().zip(bodies) {
+		if let Some(body) = body {
+			item.set_reading(reading_time(&body, config.words_per_minute));
+			measured = measured.saturating_add(1);
+		}
+	}
+	info!(articles = count, measured, "Measured reading times");
+}
+
+/// Drop the articles outside `min_read_minutes..=max_read_minutes`, then apply `order`.
+///
+/// Articles without a reading time are kept and, when reordering, follow the others in ranking order.
+/// Returns how many were dropped.
+pub fn apply_read_time_rules(config: &ContentConfig, scored: &mut Vec<ScoredArticle>) -> usize {
+	let before = scored.len();
+	scored.retain(|item| {
+		item.reading().is_none_or(|reading| {
+			config.min_read_minutes.is_none_or(|min| reading.minutes >= min)
+				&& config.max_read_minutes.is_none_or(|max| reading.minutes <= max)
+		})
+	});
+	match config.order {
+		ReadOrder::Score => {}
+		ReadOrder::Shortest => scored.sort_by_key(|item| item.reading().map_or(u32::MAX, |reading| reading.minutes)),
+		ReadOrder::Longest => scored.sort_by_key(|item| Reverse(item.reading().map(|reading| reading.minutes))),
+	}
+	before.saturating_sub(scored.len())
+}
+
+/// Words of the body text, and minutes to read them rounded up
+fn reading_time(body: &[Block], words_per_minute: u32) -> ReadingTime {
+	let words = body.iter().map(|block| block.text().split_whitespace().count()).sum();
+	let minutes = u32::try_from(words)
+		.unwrap_or(u32::MAX)
+		.div_ceil(words_per_minute.max(1))
+		.max(1);
+	ReadingTime { words, minutes }
+}
+
+/// The readable body of an article's page, if it is HTML and has one
+pub async fn fetch_body(client: &Client, url: &str) -> Option<Vec<Block>> {
+	extract_body(&fetch_html(client, url).await?)
+}
+
+/// An article's page, if it loads and is HTML
+pub async fn fetch_html(client: &Client, url: &str) -> Option<String> {
+	let response = match client
+		.get(url)
+		.send()
+		.await
+		.and_then(reqwest::Response::error_for_status)
+	{
+		Ok(response) => response,
+		Err(e) => {
+			debug!(url, error = %e, "Article page unavailable");
+			return None;
+		}
+	};
+	let is_html = response
+		.headers()
+		.get(CONTENT_TYPE)
+		.and_then(|value| value.to_str().ok())
+		.is_some_and(|value| value.contains("html"));
+	if !is_html {
+		return None;
+	}
+	response.text().await.ok()
+}
@@ -0,0 +1,446 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::{self, Config, ConfigHandle};
+use crate::error::{AppError, Result};
+use crate::fetcher;
+use chrono::{DateTime, TimeDelta, Utc};
+use croner::Cron;
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::Duration;
+use tokio::sync::{Notify, mpsc, oneshot, watch};
+use tokio::time::{self, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+/// Editors often write a file in several steps; wait this long for the burst to settle before reloading
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Progress of the daemon's fetch cycles, shared with the health endpoints
+#[derive(Debug, Clone, Default)]
+pub struct Health(Arc<Mutex<HealthState>>);
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HealthState {
+	pub cycles: u64,
+	/// Start of the cycle currently running, if any
+	pub running_since: Option<DateTime<Utc>>,
+	pub last_success: Option<DateTime<Utc>>,
+	pub last_failure: Option<DateTime<Utc>>,
+	pub last_error: Option<String>,
+	/// When the next cycle is due, once the first has run
+	pub next_cycle: Option<DateTime<Utc>>,
+	/// When each source on a schedule of its own is next due; the others are fetched every cycle
+	#[serde(skip)]
+	pub next_fetches: HashMap<String, DateTime<Utc>>,
+}
+
+impl HealthState {
+	/// Why the running cycle counts as stalled, once it has run longer than `stall_timeout_secs`
+	#[must_use]
+	pub fn stalled(&self, stall_timeout_secs: u64) -> Option<String> {
+		self.running_since.and_then(|since| {
+			let running = Utc::now().signed_duration_since(since).num_seconds();
+			(running > i64::try_from(stall_timeout_secs).unwrap_or(i64::MAX))
+				.then(|| format!("fetch cycle running for {running}s"))
+		})
+	}
+}
+
+impl Health {
+	#[must_use]
+	pub fn snapshot(&self) -> HealthState {
+		self.0.lock().map(|state| state.clone()).unwrap_or_default()
+	}
+
+	fn cycle_started(&self) {
+		if let Ok(mut state) = self.0.lock() {
+			state.cycles = state.cycles.saturating_add(1);
+			state.running_since = Some(Utc::now());
+		}
+	}
+
+	fn scheduled(&self, wait: Duration, schedule: &Schedule) {
+		if let Ok(mut state) = self.0.lock() {
+			state.next_cycle = TimeDelta::from_std(wait)
+				.ok()
+				.and_then(|wait| Utc::now().checked_add_signed(wait));
+			state.next_fetches.clone_from(&schedule.next);
+		}
+	}
+
+	fn cycle_finished(&self, error: Option<String>) {
+		if let Ok(mut state) = self.0.lock() {
+			state.running_since = None;
+			if error.is_some() {
+				state.last_failure = Some(Utc::now());
+				state.last_error = error;
+			} else {
+				state.last_success = Some(Utc::now());
+			}
+		}
+	}
+}
+
+/// Requests for a fetch cycle ahead of the schedule; one made while a cycle runs starts another right after it
+#[derive(Debug, Clone, Default)]
+pub struct Trigger(Arc<Notify>);
+
+impl Trigger {
+	pub fn fire(&self) {
+		self.0.notify_one();
+	}
+
+	async fn fired(&self) {
+		self.0.notified().await;
+	}
+}
+
+/// Requests to reload the configuration now, each answered with the number of changed settings or with why
+/// the configuration was rejected
+#[derive(Debug, Clone)]
+pub struct ReloadRequests(mpsc::UnboundedSender<oneshot::Sender<Result<usize>>>);
+
+impl ReloadRequests {
+	/// # Errors
+	///
+	/// `ShutdownError` when the reloader has stopped, otherwise the error that rejected the new configuration
+	pub async fn reload(&self) -> Result<usize> {
+		let (tx, rx) = oneshot::channel();
+		self.0.send(tx).map_err(|_| AppError::ShutdownError)?;
+		rx.await.map_err(|_| AppError::ShutdownError)?
+	}
+}
+
+/// What the daemon shares with its HTTP endpoint
+#[derive(Debug, Clone)]
+pub struct Handles {
+	pub health: Health,
+	pub trigger: Trigger,
+	pub rankings: Rankings,
+	pub reload: ReloadRequests,
+}
+
+/// The ranking of a finished fetch cycle
+#[derive(Debug)]
+pub struct Ranking {
+	pub run_id: String,
+	/// Every scored article; only the leading `displayed` ones are in ranking order
+	pub articles: Vec<ScoredArticle>,
+	pub displayed: usize,
+}
+
+/// The ranking of the last finished cycle, for the stream and API subscribers waiting on the next one,
+/// and that of each profile the users rank by
+#[derive(Debug, Clone)]
+pub struct Rankings {
+	latest: watch::Sender<Option<Arc<Ranking>>>,
+	profiles: Arc<RwLock<HashMap<String, Arc<Ranking>>>>,
+}
+
+impl Default for Rankings {
+	fn default() -> Self {
+		Self {
+			latest: watch::Sender::new(None),
+			profiles: Arc::default(),
+		}
+	}
+}
+
+impl Rankings {
+	pub fn publish(&self, ranking: Ranking) {
+		self.latest.send_replace(Some(Arc::new(ranking)));
+	}
+
+	#[must_use]
+	pub fn subscribe(&self) -> watch::Receiver<Option<Arc<Ranking>>> {
+		self.latest.subscribe()
+	}
+
+	/// The ranking of the last finished cycle, if any
+	#[must_use]
+	pub fn latest(&self) -> Option<Arc<Ranking>> {
+		self.latest.borrow().clone()
+	}
+
+	pub fn publish_profile(&self, profile: &str, ranking: Ranking) {
+		self.profiles
+			.write()
+			.unwrap_or_else(PoisonError::into_inner)
+			.insert(profile.to_string(), Arc::new(ranking));
+	}
+
+	/// The last ranking of `profile`, if a cycle has ranked by it yet
+	pub fn profile(&self, profile: &str) -> Option<Arc<Ranking>> {
+		self.profiles
+			.read()
+			.unwrap_or_else(PoisonError::into_inner)
+			.get(profile)
+			.cloned()
+	}
+}
+
+/// When each source is next due, for configurations that fetch sources on their own schedule
+#[derive(Debug, Default)]
+struct Schedule {
+	/// Sources without an entry have not been fetched yet and are due right away
+	next: HashMap<String, DateTime<Utc>>,
+}
+
+impl Schedule {
+	/// Every source of `config` due at `now`
+	fn due(&mut self, config: &Config, now: DateTime<Utc>) -> HashSet<String> {
+		let names: HashSet<String> = fetcher::source_names(config).map(str::to_string).collect();
+		self.next.retain(|name, _| names.contains(name));
+		names
+			.into_iter()
+			.filter(|name| self.next.get(name).is_none_or(|next| *next <= now))
+			.collect()
+	}
+
+	/// Schedule the next fetch of the sources fetched in the cycle started at `started`
+	fn advance(&mut self, config: &Config, fetched: &HashSet<String>, started: DateTime<Utc>) {
+		for name in fetched {
+			let next = next_fetch(config, name, started);
+			let jittered = TimeDelta::from_std(config.daemon.jitter())
+				.ok()
+				.and_then(|jitter| next.checked_add_signed(jitter))
+				.unwrap_or(next);
+			self.next.insert(name.clone(), jittered);
+		}
+	}
+
+	/// How long until the next source is due
+	fn wait(&self) -> Duration {
+		self.next
+			.values()
+			.min()
+			.and_then(|next| next.signed_duration_since(Utc::now()).to_std().ok())
+			.unwrap_or_default()
+	}
+}
+
+/// The next fetch of `source` after `after`: by its cron expression, else its `refresh_secs`, else `daemon.interval_secs`
+fn next_fetch(config: &Config, source: &str, after: DateTime<Utc>) -> DateTime<Utc> {
+	let source_config = config.source(source);
+	if let Some(expression) = source_config.and_then(|source| source.cron.as_deref()) {
+		match expression
+			.parse::<Cron>()
+			.and_then(|cron| cron.find_next_occurrence(&after, false))
+		{
+			Ok(next) => return next,
+			Err(e) => {
+				warn!(source, cron = expression, error = %e, "No next cron occurrence, using the daemon interval");
+			}
+		}
+	}
+	let secs = source_config
+		.and_then(|source| source.refresh_secs)
+		.unwrap_or(config.daemon.interval_secs);
+	TimeDelta::try_seconds(i64::try_from(secs).unwrap_or(i64::MAX))
+		.and_then(|interval| after.checked_add_signed(interval))
+		.unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// Repeat `cycle` with the current configuration until shutdown.
+///
+/// A reloaded configuration takes effect from the next cycle on; a failed cycle is logged and retried next interval.
+/// When sources have their own `refresh_secs` or `cron`, each cycle gets the names of the sources due,
+/// and runs whenever one is; otherwise every cycle fetches everything and gets `None`.
+/// Every wait is stretched by a random `daemon.jitter_secs`, and the first cycle by `daemon.start_jitter_secs`.
+/// A `trigger` cuts the wait short, making every source due.
+///
+/// # Errors
+///
+/// None: a failed cycle is logged and retried, and shutdown ends the loop with `Ok`
+pub async fn run<F, Fut>(
+	handle: &ConfigHandle,
+	health: &Health,
+	trigger: &Trigger,
+	cancel_token: &CancellationToken,
+	mut cycle: F,
+) -> Result<()>
+where
+	F: FnMut(Arc<Config>, Option<HashSet<String>>) -> Fut,
+	Fut: Future<Output = Result<()>>,
+{
+	let start_jitter = handle.current().daemon.start_jitter();
+	if !start_jitter.is_zero() {
+		info!(delay = ?start_jitter, "Delaying the first fetch cycle");
+		tokio::select! {
+			() = cancel_token.cancelled() => return Ok(()),
+			() = time::sleep(start_jitter) => {}
+		}
+	}
+
+	let mut schedule = Schedule::default();
+	loop {
+		let config = handle.current();
+		let started = Instant::now();
+		let started_at = Utc::now();
+		let due = config.has_source_schedules().then(|| schedule.due(&config, started_at));
+		if due.as_ref().is_none_or(|due| !due.is_empty()) {
+			info!(
+				interval_secs = config.daemon.interval_secs,
+				sources = due.as_ref().map(HashSet::len),
+				"Starting fetch cycle"
+			);
+			health.cycle_started();
+			match cycle(Arc::clone(&config), due.clone()).await {
+				Ok(()) => health.cycle_finished(None),
+				Err(AppError::ShutdownError) => return Ok(()),
+				Err(e) => {
+					error!(error = %e, "Fetch cycle failed");
+					health.cycle_finished(Some(e.to_string()));
+				}
+			}
+		}
+
+		let wait = due.as_ref().map_or_else(
+			|| {
+				config
+					.daemon
+					.interval()
+					.saturating_sub(started.elapsed())
+					.saturating_add(config.daemon.jitter())
+			},
+			|due| {
+				schedule.advance(&config, due, started_at);
+				schedule.wait()
+			},
+		);
+		health.scheduled(wait, &schedule);
+		tokio::select! {
+			() = cancel_token.cancelled() => return Ok(()),
+			() = time::sleep(wait) => {}
+			() = trigger.fired() => {
+				info!("Fetch cycle triggered");
+				schedule.next.clear();
+			}
+		}
+	}
+}
+
+/// Reload the configuration with `load` on SIGHUP, on the returned handle's requests and, if enabled,
+/// whenever the config file changes.
+///
+/// An invalid configuration is rejected and the previous one stays active.
+///
+/// # Errors
+///
+/// When the signal handler or the config file watcher cannot be installed
+pub fn spawn_reloader<L>(handle: ConfigHandle, cancel_token: CancellationToken, load: L) -> Result<ReloadRequests>
+where
+	L: Fn() -> Result<Config> + Send + 'static,
+{
+	let (tx, mut rx) = mpsc::unbounded_channel();
+	let (requests, mut requested) = mpsc::unbounded_channel::<oneshot::Sender<Result<usize>>>();
+	let watcher = if handle.current().daemon.watch_config {
+		Some(watch_config_file(tx)?)
+	} else {
+		None
+	};
+	let mut hangup = hangup_signal()?;
+
+	tokio::spawn(async move {
+		// Keep the watcher alive for as long as the task runs
+		let _watcher = watcher;
+		loop {
+			let (trigger, reply) = tokio::select! {
+				() = cancel_token.cancelled() => return,
+				() = hangup.recv() => ("SIGHUP", None),
+				Some(()) = rx.recv() => {
+					time::sleep(RELOAD_DEBOUNCE).await;
+					while rx.try_recv().is_ok() {}
+					("file change", None)
+				}
+				Some(reply) = requested.recv() => ("admin request", Some(reply)),
+			};
+			let result = reload(&handle, &load, trigger);
+			if let Some(reply) = reply {
+				// The requester may have given up waiting
+				let _ = reply.send(result);
+			}
+		}
+	});
+	Ok(ReloadRequests(requests))
+}
+
+/// Replace the configuration with the one `load` returns; the number of changed settings
+fn reload(handle: &ConfigHandle, load: &impl Fn() -> Result<Config>, trigger: &str) -> Result<usize> {
+	let config = load().inspect_err(|e| {
+		warn!(trigger, error = %e, "Config reload failed, keeping the current configuration");
+	})?;
+	let changes = config::diff(&handle.current(), &config);
+	if changes.is_empty() {
+		info!(trigger, "Config reloaded, nothing changed");
+		return Ok(0);
+	}
+	let count = changes.len();
+	handle.replace(config);
+	info!(trigger, changes = count, "Config reloaded, applying on the next cycle");
+	for change in changes {
+		info!(change, "Config changed");
+	}
+	Ok(count)
+}
+
+/// Watch the working directory for changes to `config.*`; the directory rather than the file
+/// is watched so that editors replacing the file on save are still picked up
+fn watch_config_file(tx: mpsc::UnboundedSender<()>) -> Result<notify::RecommendedWatcher> {
+	let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+		let Ok(event) = event else { return };
+		let is_config = event
+			.paths
+			.iter()
+			.any(|path| path.file_stem().is_some_and(|stem| stem == "config"));
+		if is_config && !matches!(event.kind, EventKind::Access(_)) {
+			let _ = tx.send(());
+		}
+	})
+	.map_err(|e| AppError::ConfigError(format!("failed to watch config file: {e}")))?;
+	watcher
+		.watch(Path::new("."), RecursiveMode::NonRecursive)
+		.map_err(|e| AppError::ConfigError(format!("failed to watch config file: {e}")))?;
+	Ok(watcher)
+}
+
+#[cfg(unix)]
+struct Hangup(tokio::signal::unix::Signal);
+
+#[cfg(unix)]
+impl Hangup {
+	async fn recv(&mut self) {
+		if self.0.recv().await.is_none() {
+			std::future::pending::<()>().await;
+		}
+	}
+}
+
+#[cfg(unix)]
+fn hangup_signal() -> Result<Hangup> {
+	use tokio::signal::unix::{SignalKind, signal};
+	signal(SignalKind::hangup())
+		.map(Hangup)
+		.map_err(|e| AppError::ConfigError(format!("failed to listen for SIGHUP: {e}")))
+}
+
+/// SIGHUP does not exist outside Unix; only file changes trigger a reload there
+#[cfg(not(unix))]
+struct Hangup;
+
+#[cfg(not(unix))]
+impl Hangup {
+	async fn recv(&mut self) {
+		std::future::pending::<()>().await;
+	}
+}
+
+#[cfg(not(unix))]
+#[allow(clippy::unnecessary_wraps)]
+const fn hangup_signal() -> Result<Hangup> {
+	Ok(Hangup)
+}
@@ -0,0 +1,111 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::DiscussionsConfig;
+use crate::error::{AppError, Result};
+use crate::fetcher::HACKER_NEWS;
+use crate::model::Discussion;
+use futures::stream::{self, StreamExt};
+use reqwest::{Client, Url};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::time::timeout;
+use tracing::{info, warn};
+
+/// Searches in flight at once
+const MAX_CONCURRENT_LOOKUPS: usize = 4;
+
+#[derive(Deserialize)]
+struct SearchResponse {
+	hits: Vec<Hit>,
+}
+
+#[derive(Deserialize)]
+struct Hit {
+	#[serde(rename = "objectID")]
+	object_id: String,
+	#[serde(default)]
+	url: Option<String>,
+	#[serde(default)]
+	points: Option<u64>,
+	#[serde(default)]
+	num_comments: Option<u64>,
+}
+
+/// Attach the Hacker News discussion of each of the top `top_n` articles that came from another source.
+///
+/// When a URL was submitted several times, the submission with the most points wins.
+/// Failed searches are logged and leave the article without a discussion link.
+pub async fn attach_discussions(client: &Client, config: &DiscussionsConfig, scored: &mut [ScoredArticle]) {
+	let count = config.top_n.min(scored.len());
+	let Some(top) = scored.get_mut(..count) else {
+		return;
+	};
+	let discussions: Vec<Option<Discussion>> = stream::iter(top.iter())
+		.map(|item| async move {
+			let article = item.article();
+			if article.source() == HACKER_NEWS {
+				return None;
+			}
+			search(client, config, article.url())
+				.await
+				.map_err(|e| warn!(url = article.url(), error = %e, "Hacker News discussion lookup failed"))
+				.ok()
+				.flatten()
+		})
+		.buffered(MAX_CONCURRENT_LOOKUPS)
+		.collect()
+		.await;
+
+	let mut attached = 0usize;
+	for (item, discussion) in top.iter_mut().zip(discussions) {
+		if let Some(discussion) = discussion {
+			item.set_discussion(discussion);
+			attached = attached.saturating_add(1);
+		}
+	}
+	info!(articles = count, attached, "Attached Hacker News discussions");
+}
+
+async fn search(client: &Client, config: &DiscussionsConfig, url: &str) -> Result<Option<Discussion>> {
+	let Some(wanted) = comparable(url) else {
+		return Ok(None);
+	};
+	let request = client
+		.get(&config.api_url)
+		.query(&[
+			("query", url),
+			("restrictSearchableAttributes", "url"),
+			("tags", "story"),
+		])
+		.send();
+	let response = timeout(Duration::from_secs(config.timeout_secs), request)
+		.await
+		.map_err(|_| AppError::TimeoutError(config.api_url.clone()))?
+		.and_then(reqwest::Response::error_for_status)
+		.map_err(|e| AppError::http_error(&config.api_url, e))?
+		.json::<SearchResponse>()
+		.await
+		.map_err(|e| AppError::parse_error("Algolia HN search", e))?;
+	Ok(response
+		.hits
+		.into_iter()
+		.filter(|hit| hit.url.as_deref().and_then(comparable).as_ref() == Some(&wanted))
+		.max_by_key(|hit| hit.points)
+		.map(|hit| Discussion {
+			url: format!("https://news.ycombinator.com/item?id={}", hit.object_id),
+			points: hit.points.unwrap_or_default(),
+			comments: hit.num_comments.unwrap_or_default(),
+		}))
+}
+
+/// The search matches URLs loosely; submissions count as the same article regardless of scheme,
+/// a `www.` prefix, a trailing slash or a fragment
+fn comparable(url: &str) -> Option<String> {
+	let url = Url::parse(url).ok()?;
+	let host = url.host_str()?.to_ascii_lowercase();
+	let host = host.strip_prefix("www.").unwrap_or(&host);
+	let path = url.path().trim_end_matches('/');
+	Some(
+		url.query()
+			.map_or_else(|| format!("{host}{path}"), |query| format!("{host}{path}?{query}")),
+	)
+}
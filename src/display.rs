@@ -0,0 +1,129 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::{Config, GroupBy};
+use crate::model::Extra;
+use crate::run::RunErrors;
+use tracing::{field, info};
+
+const UNMATCHED_TOPIC: &str = "other";
+const UNCATEGORIZED: &str = "uncategorized";
+
+/// Log the ranking, either as one flat list or as top articles per group
+pub fn display_results(articles: &[ScoredArticle], config: &Config) {
+	let settings = &config.display;
+	let limit_for = |group: &str| {
+		config
+			.categories
+			.get(group)
+			.and_then(|category| category.limit)
+			.map_or(settings.limit, |limit| limit.min(settings.limit))
+	};
+	match settings.group_by {
+		GroupBy::None => display_ranking("all", articles.iter(), settings.limit),
+		GroupBy::Source => display_groups(group_articles(articles, |s| vec![s.article().source()]), |_| {
+			settings.limit
+		}),
+		GroupBy::Topic => display_groups(group_articles(articles, |s| vec![primary_topic(s)]), |_| settings.limit),
+		GroupBy::Category => display_groups(group_articles(articles, article_categories), limit_for),
+	}
+}
+
+/// Log the non-fatal errors of the run below the ranking, one line per source, scope and kind
+pub fn display_errors(errors: &RunErrors) {
+	if errors.is_empty() {
+		return;
+	}
+	info!(total = errors.total, "=== Errors ===");
+	for group in &errors.groups {
+		info!(
+			source = group.source,
+			kind = group.kind,
+			count = group.count,
+			example = group.examples.first(),
+			"{group}"
+		);
+	}
+}
+
+fn article_categories(scored: &ScoredArticle) -> Vec<&str> {
+	if scored.categories().is_empty() {
+		return vec![UNCATEGORIZED];
+	}
+	scored.categories().iter().map(String::as_str).collect()
+}
+
+/// The first matched keyword decides the topic bucket, so every article is shown once
+fn primary_topic(scored: &ScoredArticle) -> &str {
+	scored
+		.matched_keywords()
+		.first()
+		.map_or(UNMATCHED_TOPIC, String::as_str)
+}
+
+/// Bucket articles by their keys; groups keep the order in which their best article appears in the ranking
+fn group_articles<'a, F>(articles: &'a [ScoredArticle], keys: F) -> Vec<(&'a str, Vec<&'a ScoredArticle>)>
+where
+	F: Fn(&'a ScoredArticle) -> Vec<&'a str>,
+{
+	let mut groups: Vec<(&str, Vec<&ScoredArticle>)> = Vec::new();
+	for scored in articles {
+		for group in keys(scored) {
+			match groups.iter_mut().find(|(name, _)| *name == group) {
+				Some((_, members)) => members.push(scored),
+				None => groups.push((group, vec![scored])),
+			}
+		}
+	}
+	groups
+}
+
+fn display_groups(groups: Vec<(&str, Vec<&ScoredArticle>)>, limit_for: impl Fn(&str) -> usize) {
+	for (group, members) in groups {
+		display_ranking(group, members.into_iter(), limit_for(group));
+	}
+}
+
+fn display_ranking<'a>(group: &str, articles: impl ExactSizeIterator<Item = &'a ScoredArticle>, limit: usize) {
+	let total = articles.len();
+	info!(group, shown = total.min(limit), total, "=== Top Relevant Articles ===");
+	for (i, scored) in articles.take(limit).enumerate() {
+		info!(
+			group,
+			rank = i.saturating_add(1),
+			score = format!("{:.2}", scored.relevance_score()),
+			change = scored.change().map(|change| change.to_string()),
+			title = scored.article().title(),
+			source = scored.article().source(),
+			url = scored.article().url(),
+			dead_link = scored.article().dead_link(),
+			author = scored.article().author(),
+			published_at = scored.article().published_at().map(|time| time.to_rfc3339()),
+			paywalled = scored.article().paywalled().then_some(true),
+			words = scored.reading().map(|reading| reading.words),
+			read_minutes = scored.reading().map(|reading| reading.minutes),
+			tags = (!scored.article().tags().is_empty()).then(|| field::debug(scored.article().tags())),
+			keywords = ?scored.matched_keywords(),
+			categories = ?scored.categories(),
+			entities = ?scored.entities(),
+			similarity = scored.semantic_similarity(),
+			summary = scored.summary(),
+			note = scored.note(),
+			archive = scored.archive_url(),
+			discussion = scored.discussion().map(|discussion| discussion.url.as_str()),
+			discussion_points = scored.discussion().map(|discussion| discussion.points),
+			audio = scored.article().extra("audio_url").and_then(Extra::as_text),
+			image = scored.article().image_url(),
+		);
+		if let Some(explanation) = scored.explanation() {
+			info!(
+				group,
+				rank = i.saturating_add(1),
+				formula = explanation.formula(),
+				breakdown = ?explanation.keywords,
+				keyword_score = explanation.keyword_score,
+				source_weight = explanation.source_weight,
+				semantic = ?explanation.semantic,
+				"Score explanation"
+			);
+		}
+	}
+}
@@ -0,0 +1,137 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::DiversityConfig;
+use crate::filter;
+use crate::suggest;
+use std::collections::{HashMap, HashSet};
+
+/// What two articles are compared on: the words of their titles and the domain they link to
+struct Features {
+	words: HashSet<String>,
+	host: String,
+}
+
+impl Features {
+	fn new(scored: &ScoredArticle) -> Self {
+		Self {
+			words: suggest::tokenize(scored.article().title()).collect(),
+			host: filter::host(scored.article().url()),
+		}
+	}
+
+	/// Jaccard overlap of the title words, raised to `domain_similarity` for a shared domain
+	fn similarity(&self, other: &Self, domain_similarity: f64) -> f64 {
+		let union = self.words.union(&other.words).count();
+		let overlap = if union == 0 {
+			0.0
+		} else {
+			len_as_f64(self.words.intersection(&other.words).count()) / len_as_f64(union)
+		};
+		if !self.host.is_empty() && self.host == other.host {
+			overlap.max(domain_similarity)
+		} else {
+			overlap
+		}
+	}
+}
+
+/// Fill the first `top_n` places of the score-ordered `articles` by maximal marginal relevance.
+///
+/// Each pick maximizes `lambda * relevance - (1 - lambda) * similarity` to the articles already picked,
+/// with relevance relative to the best score. The remaining articles keep their order below them.
+/// Returns how many of the top places changed hands.
+pub fn rerank(articles: &mut Vec<ScoredArticle>, config: &DiversityConfig, top_n: usize) -> usize {
+	let places = top_n.min(articles.len());
+	if places < 2 || config.lambda >= 1.0 {
+		return 0;
+	}
+	let best = articles
+		.iter()
+		.map(ScoredArticle::relevance_score)
+		.fold(0.0_f64, f64::max);
+	let scale = if best > 0.0 { best } else { 1.0 };
+	let relevance: Vec<f64> = articles.iter().map(|scored| scored.relevance_score() / scale).collect();
+	let features: Vec<Features> = articles.iter().map(Features::new).collect();
+
+	// Highest similarity of each article to the ones picked so far
+	let mut redundancy = vec![0.0_f64; articles.len()];
+	let mut remaining: Vec<usize> = (0..articles.len()).collect();
+	let mut order = Vec::with_capacity(articles.len());
+	let marginal = |index: usize, redundancy: &[f64]| {
+		let relevance = relevance.get(index).copied().unwrap_or_default();
+		let redundancy = redundancy.get(index).copied().unwrap_or_default();
+		config.lambda.mul_add(relevance, -(1.0 - config.lambda) * redundancy)
+	};
+	for _ in 0..places {
+		let Some(position) = remaining
+			.iter()
+			.enumerate()
+			.max_by(|a, b| {
+				marginal(*a.1, &redundancy)
+					.total_cmp(&marginal(*b.1, &redundancy))
+					.then_with(|| b.1.cmp(a.1))
+			})
+			.map(|(position, _)| position)
+		else {
+			break;
+		};
+		let picked = remaining.remove(position);
+		order.push(picked);
+		if let Some(picked) = features.get(picked) {
+			for &index in &remaining {
+				if let (Some(other), Some(redundancy)) = (features.get(index), redundancy.get_mut(index)) {
+					*redundancy = redundancy.max(picked.similarity(other, config.domain_similarity));
+				}
+			}
+		}
+	}
+	let moved = order
+		.iter()
+		.enumerate()
+		.filter(|&(place, &index)| place != index)
+		.count();
+	order.append(&mut remaining);
+
+	let mut slots: Vec<Option<ScoredArticle>> = articles.drain(..).map(Some).collect();
+	articles.extend(
+		order
+			.iter()
+			.filter_map(|&index| slots.get_mut(index).and_then(Option::take)),
+	);
+	moved
+}
+
+/// Keep any one domain to `max_per_domain` of the first `slots` articles.
+///
+/// Articles over the cap move, in their order, to just below the displayed ones, taking the places of the articles pulled up.
+/// Returns how many articles moved down.
+pub fn cap_domains(articles: &mut Vec<ScoredArticle>, max_per_domain: usize, slots: usize) -> usize {
+	let mut counts: HashMap<String, usize> = HashMap::new();
+	let mut shown = Vec::new();
+	let mut overflow = Vec::new();
+	let mut rest = Vec::new();
+	for scored in articles.drain(..) {
+		if shown.len() >= slots {
+			rest.push(scored);
+			continue;
+		}
+		let host = filter::host(scored.article().url());
+		let domain = host.strip_prefix("www.").unwrap_or(&host).to_string();
+		let count = counts.entry(domain.clone()).or_default();
+		if domain.is_empty() || *count < max_per_domain {
+			*count = count.saturating_add(1);
+			shown.push(scored);
+		} else {
+			overflow.push(scored);
+		}
+	}
+	let moved = overflow.len();
+	articles.append(&mut shown);
+	articles.append(&mut overflow);
+	articles.append(&mut rest);
+	moved
+}
+
+#[allow(clippy::cast_precision_loss)]
+const fn len_as_f64(len: usize) -> f64 {
+	len as f64
+}
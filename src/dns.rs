@@ -0,0 +1,98 @@
+use crate::config::DnsConfig;
+use crate::error::{AppError, Result};
+use reqwest::ClientBuilder;
+use std::net::{IpAddr, SocketAddr};
+
+/// `builder` resolving hostnames per `[dns]`: the static overrides first, then the DNS-over-HTTPS provider or
+/// the system resolver
+///
+/// # Errors
+///
+/// `ConfigError` when an override address is invalid or `doh` is set without the `doh` feature
+pub fn configure(builder: ClientBuilder, config: &DnsConfig) -> Result<ClientBuilder> {
+	let mut builder = match config.doh {
+		Some(provider) => doh::configure(builder, provider)?,
+		None => builder,
+	};
+	for (host, addrs) in overrides(config)? {
+		builder = builder.resolve_to_addrs(host, &addrs);
+	}
+	Ok(builder)
+}
+
+/// The parsed `dns.overrides`, with port 0 for addresses given without one
+///
+/// # Errors
+///
+/// `ConfigError` naming the first address that is neither `ip` nor `ip:port`
+pub fn overrides(config: &DnsConfig) -> Result<Vec<(&str, Vec<SocketAddr>)>> {
+	config
+		.overrides
+		.iter()
+		.map(|(host, addrs)| {
+			if addrs.is_empty() {
+				return Err(AppError::ConfigError(format!("dns.overrides.{host} lists no address")));
+			}
+			let addrs = addrs
+				.iter()
+				.map(|addr| {
+					addr.parse::<SocketAddr>()
+						.or_else(|_| addr.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 0)))
+						.map_err(|_| AppError::ConfigError(format!("dns.overrides.{host}: invalid address {addr}")))
+				})
+				.collect::<Result<Vec<_>>>()?;
+			Ok((host.as_str(), addrs))
+		})
+		.collect()
+}
+
+#[cfg(feature = "doh")]
+mod doh {
+	use crate::config::DohProvider;
+	use crate::error::Result;
+	use hickory_resolver::TokioResolver;
+	use hickory_resolver::config::ResolverConfig;
+	use hickory_resolver::name_server::TokioConnectionProvider;
+	use reqwest::ClientBuilder;
+	use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+	use std::net::SocketAddr;
+	use std::sync::Arc;
+
+	/// Hostname lookups sent over HTTPS to a public resolver
+	struct DohResolver(TokioResolver);
+
+	impl Resolve for DohResolver {
+		fn resolve(&self, name: Name) -> Resolving {
+			let resolver = self.0.clone();
+			Box::pin(async move {
+				let lookup = resolver.lookup_ip(name.as_str()).await?;
+				let addrs: Addrs = Box::new(lookup.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+				Ok(addrs)
+			})
+		}
+	}
+
+	#[allow(clippy::unnecessary_wraps)]
+	pub fn configure(builder: ClientBuilder, provider: DohProvider) -> Result<ClientBuilder> {
+		let config = match provider {
+			DohProvider::Cloudflare => ResolverConfig::cloudflare_https(),
+			DohProvider::Google => ResolverConfig::google_https(),
+			DohProvider::Quad9 => ResolverConfig::quad9_https(),
+		};
+		let resolver = TokioResolver::builder_with_config(config, TokioConnectionProvider::default()).build();
+		Ok(builder.dns_resolver(Arc::new(DohResolver(resolver))))
+	}
+}
+
+#[cfg(not(feature = "doh"))]
+mod doh {
+	use crate::config::DohProvider;
+	use crate::error::{AppError, Result};
+	use reqwest::ClientBuilder;
+
+	pub fn configure(_builder: ClientBuilder, _provider: DohProvider) -> Result<ClientBuilder> {
+		Err(AppError::ConfigError(
+			"dns.doh is set: built without the `doh` feature".into(),
+		))
+	}
+}
@@ -0,0 +1,93 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::EntitiesConfig;
+use crate::error::{AppError, Result};
+use aho_corasick::{AhoCorasick, MatchKind};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use tracing::info;
+
+/// Gazetteer-based extractor for configured project and company names
+pub struct EntityExtractor {
+	ac: AhoCorasick,
+	/// Canonical entity name for each automaton pattern
+	canonical: Vec<String>,
+}
+
+impl EntityExtractor {
+	/// # Errors
+	///
+	/// `AnalyzerError` when the gazetteer automaton cannot be built
+	pub fn new(config: &EntitiesConfig) -> Result<Self> {
+		let mut patterns = Vec::new();
+		let mut canonical = Vec::new();
+		for name in &config.names {
+			patterns.push(name.clone());
+			canonical.push(name.clone());
+			for alias in config.aliases.get(name).into_iter().flatten() {
+				patterns.push(alias.clone());
+				canonical.push(name.clone());
+			}
+		}
+
+		let ac = AhoCorasick::builder()
+			.ascii_case_insensitive(true)
+			.match_kind(MatchKind::LeftmostLongest)
+			.build(&patterns)
+			.map_err(|e| AppError::AnalyzerError(format!("failed to build entity gazetteer: {e}")))?;
+		Ok(Self { ac, canonical })
+	}
+
+	/// Canonical names of entities mentioned as whole words, in order of first mention
+	#[must_use]
+	pub fn extract(&self, text: &str) -> Vec<String> {
+		let mut found: Vec<String> = Vec::new();
+		for mat in self.ac.find_iter(text) {
+			if !is_word_boundary(text, mat.start(), mat.end()) {
+				continue;
+			}
+			if let Some(name) = self.canonical.get(mat.pattern().as_usize())
+				&& !found.contains(name)
+			{
+				found.push(name.clone());
+			}
+		}
+		found
+	}
+
+	pub fn extract_all(&self, articles: &mut [ScoredArticle]) {
+		articles.par_iter_mut().for_each(|scored| {
+			let entities = self.extract(&scored.article().searchable_text());
+			scored.set_entities(entities);
+		});
+	}
+}
+
+fn is_word_boundary(text: &str, start: usize, end: usize) -> bool {
+	let before = text.get(..start).and_then(|s| s.chars().next_back());
+	let after = text.get(end..).and_then(|s| s.chars().next());
+	!before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+}
+
+/// Entities ordered by the number of articles mentioning them, most mentioned first
+pub fn mention_counts(articles: &[ScoredArticle]) -> Vec<(&str, usize)> {
+	let mut counts: HashMap<&str, usize> = HashMap::new();
+	for entity in articles.iter().flat_map(ScoredArticle::entities) {
+		let count = counts.entry(entity.as_str()).or_insert(0);
+		*count = count.saturating_add(1);
+	}
+	let mut counts: Vec<(&str, usize)> = counts.into_iter().collect();
+	counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+	counts
+}
+
+/// Log the most-mentioned entities of this run
+pub fn report(articles: &[ScoredArticle], top: usize) {
+	let counts = mention_counts(articles);
+	if counts.is_empty() {
+		return;
+	}
+	info!("=== Most Mentioned Entities ===");
+	for (i, (entity, mentions)) in counts.into_iter().take(top).enumerate() {
+		info!(rank = i.saturating_add(1), entity, mentions);
+	}
+}
@@ -1,12 +1,22 @@
+use std::error::Error as StdError;
+use std::sync::Arc;
 use thiserror::Error;
 
+/// The underlying error an `AppError` was raised from, kept as its `source()`. Shared so that
+/// errors stay cheap to clone.
+pub type Cause = Arc<dyn StdError + Send + Sync>;
+
+#[allow(clippy::enum_variant_names)]
 #[derive(Error, Debug, Clone)]
 pub enum AppError {
-    #[error("HTTP request failed for {url}: {message}")]
-    HttpError { url: String, message: String },
+    #[error("HTTP request failed for {url}: {source}")]
+    HttpError { url: String, source: Cause },
+
+    #[error("HTTP {status} from {url}")]
+    HttpStatusError { url: String, status: u16 },
 
-    #[error("Failed to parse response from {origin}: {message}")]
-    ParseError { origin: String, message: String },
+    #[error("Failed to parse response from {origin}: {source}")]
+    ParseError { origin: String, source: Cause },
 
     #[error("Timeout error: {0}")]
     TimeoutError(String),
@@ -17,27 +27,209 @@ pub enum AppError {
     #[error("Shutdown requested")]
     ShutdownError,
 
+    #[error("Offline and no cached response for {0}")]
+    CacheMiss(String),
+
+    #[error("Replaying and no recorded fixture for {0}")]
+    FixtureMissing(String),
+
     #[error("No articles found from source: {0}")]
     NoArticlesError(String),
 
+    #[error("Response from {url} is larger than {limit} bytes")]
+    BodyTooLarge { url: String, limit: usize },
+
+    #[error("Response from {url} is {content_type}, expected {expected}")]
+    ContentTypeError {
+        url: String,
+        expected: &'static str,
+        content_type: String,
+    },
+
+    /// Ends a source's fetch once the run has sent `fetcher.max_total_articles`; the source keeps what it sent
+    #[error("Reached fetcher.max_total_articles ({0})")]
+    ArticleLimitReached(usize),
+
+    /// A source's fetch task panicked; the other sources are unaffected
+    #[error("Source {source_name} panicked: {message}")]
+    SourcePanicked { source_name: String, message: String },
+
+    /// A source skipped while its `fetcher.circuit_breaker` is open
+    #[error("Circuit breaker of {source_name} is open until {until}")]
+    CircuitOpen {
+        source_name: String,
+        until: chrono::DateTime<chrono::Utc>,
+    },
+
+    #[error("Hacker News item {id}: {cause}")]
+    HackerNewsItemError {
+        id: u64,
+        #[source]
+        cause: Box<Self>,
+    },
+
+    #[error("Invalid selector {selector} for {source_name}: {message}")]
+    SelectorError {
+        source_name: String,
+        selector: String,
+        message: String,
+    },
+
+    #[error(
+        "{source_name}: selector {selector} matched {matched} elements and no article, the site layout probably changed"
+    )]
+    LayoutChangedError {
+        source_name: String,
+        selector: String,
+        matched: usize,
+    },
+
+    #[error("Sitemap {source_name} page {url}: {cause}")]
+    SitemapPageError {
+        source_name: String,
+        url: String,
+        #[source]
+        cause: Box<Self>,
+    },
+
+    #[error("{source_name}: daily quota of {limit} requests used up")]
+    QuotaError { source_name: String, limit: u32 },
+
+    #[error("No article title found on {url}")]
+    ExtractError { url: String },
+
     #[error("Analyzer error: {0}")]
     AnalyzerError(String),
+
+    #[cfg(feature = "llm")]
+    #[error("LLM error: {0}")]
+    LlmError(String),
+
+    #[error("{sink} delivery failed: {source}")]
+    #[cfg_attr(
+        not(any(feature = "epub", feature = "mqtt", feature = "nats", feature = "kafka")),
+        allow(dead_code)
+    )]
+    DeliveryError { sink: String, source: Cause },
+
+    #[error("Storage error: {0}")]
+    StorageError(#[source] Cause),
+
+    #[error("I/O error on {path}: {source}")]
+    IoError { path: String, source: Cause },
 }
 
 impl AppError {
-    pub fn http_error(url: impl Into<String>, err: impl std::fmt::Display) -> Self {
+    pub fn http_error(url: impl Into<String>, err: impl Into<Box<dyn StdError + Send + Sync>>) -> Self {
         Self::HttpError {
             url: url.into(),
-            message: err.to_string(),
+            source: Arc::from(err.into()),
         }
     }
 
-    pub fn parse_error(origin: impl Into<String>, err: impl std::fmt::Display) -> Self {
+    pub fn parse_error(origin: impl Into<String>, err: impl Into<Box<dyn StdError + Send + Sync>>) -> Self {
         Self::ParseError {
             origin: origin.into(),
-            message: err.to_string(),
+            source: Arc::from(err.into()),
         }
     }
+
+    /// Wrap the error in a per-source variant built by `wrap`; a shutdown is passed through as is
+    #[must_use]
+    pub fn within(self, wrap: impl FnOnce(Box<Self>) -> Self) -> Self {
+        match self {
+            Self::ShutdownError => self,
+            cause => wrap(Box::new(cause)),
+        }
+    }
+
+    /// Transient failures worth another attempt: transport errors, timeouts, 429 and 5xx responses.
+    /// A request that could not be built, looped on redirects or came back with a client error is not
+    /// retried, and per-source errors follow the failure they wrap.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::HttpError { source, .. } => source.downcast_ref::<reqwest::Error>().is_none_or(|e| {
+                !e.is_builder() && !e.is_redirect() && e.status().is_none_or(|status| retryable_status(status.as_u16()))
+            }),
+            Self::TimeoutError(_) => true,
+            Self::HttpStatusError { status, .. } => retryable_status(*status),
+            Self::HackerNewsItemError { cause, .. } | Self::SitemapPageError { cause, .. } => cause.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// Short name of the kind of failure, stable across releases, for logs, metrics and run reports.
+    /// Per-source errors report the kind of the failure they wrap.
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::HttpError { .. } => "http",
+            Self::HttpStatusError { .. } => "http_status",
+            Self::ParseError { .. } => "parse",
+            Self::TimeoutError(_) => "timeout",
+            Self::ConfigError(_) => "config",
+            Self::ShutdownError => "shutdown",
+            Self::CacheMiss(_) => "cache_miss",
+            Self::FixtureMissing(_) => "fixture_missing",
+            Self::NoArticlesError(_) => "no_articles",
+            Self::BodyTooLarge { .. } => "body_too_large",
+            Self::ContentTypeError { .. } => "content_type",
+            Self::ArticleLimitReached(_) => "article_limit",
+            Self::SourcePanicked { .. } => "panic",
+            Self::CircuitOpen { .. } => "circuit_open",
+            Self::HackerNewsItemError { cause, .. } | Self::SitemapPageError { cause, .. } => cause.kind(),
+            Self::SelectorError { .. } => "selector",
+            Self::LayoutChangedError { .. } => "layout_changed",
+            Self::QuotaError { .. } => "quota",
+            Self::ExtractError { .. } => "extract",
+            Self::AnalyzerError(_) => "analyzer",
+            #[cfg(feature = "llm")]
+            Self::LlmError(_) => "llm",
+            Self::DeliveryError { .. } => "delivery",
+            Self::StorageError(_) => "storage",
+            Self::IoError { .. } => "io",
+        }
+    }
+
+    /// Process exit status following sysexits.h, so that a service manager can tell a broken
+    /// configuration, which no restart will fix, from a transient failure
+    #[must_use]
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::ConfigError(_) | Self::SelectorError { .. } => 78,
+            Self::IoError { .. } | Self::StorageError(_) => 74,
+            Self::NoArticlesError(_) | Self::CacheMiss(_) | Self::QuotaError { .. } => 75,
+            _ if self.is_retryable() => 75,
+            _ => 1,
+        }
+    }
+
+    #[cfg_attr(
+        not(any(feature = "epub", feature = "mqtt", feature = "nats", feature = "kafka")),
+        allow(dead_code)
+    )]
+    pub fn delivery_error(sink: impl Into<String>, err: impl Into<Box<dyn StdError + Send + Sync>>) -> Self {
+        Self::DeliveryError {
+            sink: sink.into(),
+            source: Arc::from(err.into()),
+        }
+    }
+
+    pub fn storage_error(err: impl Into<Box<dyn StdError + Send + Sync>>) -> Self {
+        Self::StorageError(Arc::from(err.into()))
+    }
+
+    pub fn io_error(path: impl AsRef<std::path::Path>, err: impl Into<Box<dyn StdError + Send + Sync>>) -> Self {
+        Self::IoError {
+            path: path.as_ref().display().to_string(),
+            source: Arc::from(err.into()),
+        }
+    }
+}
+
+const fn retryable_status(status: u16) -> bool {
+    status == 429 || status >= 500
 }
 
 pub type Result<T> = std::result::Result<T, AppError>;
@@ -0,0 +1,224 @@
+use crate::model::{Article, Extra};
+use crate::paywall;
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use scraper::{Html, Selector};
+
+/// Build an article from a standalone web page.
+///
+/// The title comes from the Open Graph `og:title`, falling back to `<title>` and then the first `<h1>`;
+/// the description from `og:description` or the meta description, and the publication time from
+/// `article:published_time`; the author, tags and site name from their meta tags where present, and the
+/// `image_url` extra from the preview image, see [`page_image`]. Pages without any title yield `None`.
+/// With `sniff_paywall`, pages whose markup declares restricted access are tagged as paywalled.
+#[must_use]
+pub fn extract_article(html: &str, url: &str, source: &str, sniff_paywall: bool) -> Option<Article> {
+	let document = Html::parse_document(html);
+	let title = meta(&document, r#"meta[property="og:title"]"#)
+		.or_else(|| text(&document, "title"))
+		.or_else(|| text(&document, "h1"))?;
+	let description = meta(&document, r#"meta[property="og:description"]"#)
+		.or_else(|| meta(&document, r#"meta[name="description"]"#));
+
+	let published_at = meta(&document, r#"meta[property="article:published_time"]"#)
+		.and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+		.map(|time| time.with_timezone(&Utc));
+
+	let author =
+		meta(&document, r#"meta[name="author"]"#).or_else(|| meta(&document, r#"meta[property="article:author"]"#));
+	let mut tags = metas(&document, r#"meta[property="article:tag"]"#);
+	if tags.is_empty() {
+		tags = meta(&document, r#"meta[name="keywords"]"#)
+			.map(|keywords| {
+				keywords
+					.split(',')
+					.map(str::trim)
+					.filter(|tag| !tag.is_empty())
+					.map(str::to_string)
+					.collect()
+			})
+			.unwrap_or_default();
+	}
+
+	let mut article = Article::new(title, url.to_string(), source.to_string())
+		.with_published_at(published_at)
+		.with_author(author)
+		.with_tags(tags);
+	if let Some(site) = meta(&document, r#"meta[property="og:site_name"]"#) {
+		article = article.with_extra("site_name", Extra::Text(site));
+	}
+	if let Some(image) = image(&document, url) {
+		article = article.with_extra("image_url", Extra::Text(image));
+	}
+	let mut article = match description {
+		Some(description) => article.with_description(description),
+		None => article,
+	};
+	if sniff_paywall && paywall::sniff(&document) {
+		article.mark_paywalled();
+	}
+	Some(article)
+}
+
+/// Pages with less body text than this, in characters, have no readable body: index pages, paywalls and
+/// pages rendered by scripts
+const MIN_BODY_CHARS: usize = 200;
+
+/// A block of an article's body text
+pub enum Block {
+	Heading(String),
+	Paragraph(String),
+	/// Code or other preformatted text, whitespace kept
+	Preformatted(String),
+}
+
+impl Block {
+	#[must_use]
+	pub fn text(&self) -> &str {
+		match self {
+			Self::Heading(text) | Self::Paragraph(text) | Self::Preformatted(text) => text,
+		}
+	}
+}
+
+/// The readable body of a web page: the headings, paragraphs and code blocks of its `<article>`,
+/// else its `<main>`, else its `<body>`, in document order. `None` when there is too little text.
+pub fn extract_body(html: &str) -> Option<Vec<Block>> {
+	let document = Html::parse_document(html);
+	let blocks = Selector::parse("h2, h3, h4, p, pre").ok()?;
+	let root = ["article", "main, [role=main]", "body"]
+		.iter()
+		.filter_map(|selector| Selector::parse(selector).ok())
+		.find_map(|selector| document.select(&selector).next())?;
+
+	let mut body = Vec::new();
+	let mut chars = 0usize;
+	for element in root.select(&blocks) {
+		// Paragraphs inside code blocks are part of the code block
+		if element
+			.ancestors()
+			.filter_map(scraper::ElementRef::wrap)
+			.any(|ancestor| ancestor.value().name() == "pre")
+		{
+			continue;
+		}
+		let raw: String = element.text().collect();
+		let block = match element.value().name() {
+			"pre" => Block::Preformatted(raw.trim_matches('\n').to_string()),
+			name => {
+				let text = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+				if text.is_empty() {
+					continue;
+				}
+				if name == "p" {
+					Block::Paragraph(text)
+				} else {
+					Block::Heading(text)
+				}
+			}
+		};
+		chars = chars.saturating_add(block.text().chars().count());
+		body.push(block);
+	}
+	(chars >= MIN_BODY_CHARS).then_some(body)
+}
+
+/// What a page's `<head>` declares about it for link previews
+#[derive(Debug, Default)]
+pub struct PageMetadata {
+	pub title: Option<String>,
+	pub description: Option<String>,
+	/// The page's `rel="canonical"` link, else its `og:url`, as an absolute http(s) URL
+	pub canonical_url: Option<String>,
+	pub published_at: Option<DateTime<Utc>>,
+	pub image_url: Option<String>,
+}
+
+/// The Open Graph and meta tags of a page, read the same way as by [`extract_article`]
+#[must_use]
+pub fn page_metadata(html: &str, url: &str) -> PageMetadata {
+	let document = Html::parse_document(html);
+	let canonical =
+		link(&document, r#"link[rel="canonical"]"#).or_else(|| meta(&document, r#"meta[property="og:url"]"#));
+	PageMetadata {
+		title: meta(&document, r#"meta[property="og:title"]"#).or_else(|| text(&document, "title")),
+		description: meta(&document, r#"meta[property="og:description"]"#)
+			.or_else(|| meta(&document, r#"meta[name="description"]"#)),
+		canonical_url: canonical.and_then(|canonical| absolute_http(url, &canonical)),
+		published_at: meta(&document, r#"meta[property="article:published_time"]"#)
+			.and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+			.map(|time| time.with_timezone(&Utc)),
+		image_url: image(&document, url),
+	}
+}
+
+/// The preview image a page declares for link cards: `og:image`, else `twitter:image`, as an absolute
+/// http(s) URL
+#[must_use]
+pub fn page_image(html: &str, url: &str) -> Option<String> {
+	image(&Html::parse_document(html), url)
+}
+
+fn image(document: &Html, url: &str) -> Option<String> {
+	let image = [
+		r#"meta[property="og:image:secure_url"]"#,
+		r#"meta[property="og:image"]"#,
+		r#"meta[property="og:image:url"]"#,
+		r#"meta[name="twitter:image"]"#,
+		r#"meta[name="twitter:image:src"]"#,
+	]
+	.iter()
+	.find_map(|selector| meta(document, selector))?;
+	absolute_http(url, &image)
+}
+
+/// `reference` resolved against the page at `url`, if that makes an http(s) URL
+fn absolute_http(url: &str, reference: &str) -> Option<String> {
+	let resolved = Url::parse(url).ok()?.join(reference).ok()?;
+	matches!(resolved.scheme(), "http" | "https").then(|| resolved.into())
+}
+
+/// Trimmed, non-empty `href` attribute of the first element matching `selector`
+fn link(document: &Html, selector: &str) -> Option<String> {
+	let selector = Selector::parse(selector).ok()?;
+	document
+		.select(&selector)
+		.find_map(|element| element.value().attr("href"))
+		.map(|href| href.trim().to_string())
+		.filter(|href| !href.is_empty())
+}
+
+/// Trimmed, non-empty `content` attribute of the first element matching `selector`
+fn meta(document: &Html, selector: &str) -> Option<String> {
+	let selector = Selector::parse(selector).ok()?;
+	document
+		.select(&selector)
+		.find_map(|element| element.value().attr("content"))
+		.map(|content| content.trim().to_string())
+		.filter(|content| !content.is_empty())
+}
+
+/// Trimmed, non-empty `content` attributes of every element matching `selector`
+fn metas(document: &Html, selector: &str) -> Vec<String> {
+	let Ok(selector) = Selector::parse(selector) else {
+		return Vec::new();
+	};
+	document
+		.select(&selector)
+		.filter_map(|element| element.value().attr("content"))
+		.map(str::trim)
+		.filter(|content| !content.is_empty())
+		.map(str::to_string)
+		.collect()
+}
+
+/// Whitespace-normalized, non-empty text of the first element matching `selector`
+fn text(document: &Html, selector: &str) -> Option<String> {
+	let selector = Selector::parse(selector).ok()?;
+	document
+		.select(&selector)
+		.next()
+		.map(|element| element.text().collect::<Vec<_>>().join(" "))
+		.map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+		.filter(|text| !text.is_empty())
+}
@@ -0,0 +1,174 @@
+use crate::error::{AppError, Result};
+use crate::model::{Article, Extra};
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use roxmltree::Node;
+
+/// Articles of an Atom or RSS 2.0 feed, in feed order.
+///
+/// Links are resolved against the feed `url` and entries without a title or link are skipped. The entry
+/// `id` or `guid` is kept as the article GUID and the categories as its tags. Feeds describing their
+/// entries only through Media RSS, like `YouTube`'s, are described by their `<media:description>`.
+///
+/// An audio enclosure, as in podcast feeds, is kept in the `audio_url` extra along with its `audio_type`
+/// and the iTunes `duration_secs`, and stands in for the link of an entry that has none. A Media RSS
+/// thumbnail or image, or an image enclosure, is kept in the `image_url` extra.
+///
+/// # Errors
+///
+/// `ParseError` when the body is neither an Atom nor an RSS feed
+pub fn parse_feed(body: &str, url: &str, source: &str) -> Result<Vec<Article>> {
+	let document = roxmltree::Document::parse(body).map_err(|e| AppError::parse_error(url, e))?;
+	let base = Url::parse(url).map_err(|e| AppError::parse_error(url, e))?;
+	let root = document.root_element();
+	let entries: Vec<Node<'_, '_>> = match root.tag_name().name() {
+		"feed" => root.children().filter(|node| node.has_tag_name("entry")).collect(),
+		"rss" => root
+			.children()
+			.filter(|node| node.has_tag_name("channel"))
+			.flat_map(|channel| channel.children().filter(|node| node.has_tag_name("item")))
+			.collect(),
+		other => {
+			return Err(AppError::parse_error(url, format!("unexpected root element <{other}>")));
+		}
+	};
+	Ok(entries
+		.into_iter()
+		.filter_map(|entry| entry_article(entry, &base, source))
+		.collect())
+}
+
+/// An Atom `<entry>` or RSS `<item>` as an article
+fn entry_article(entry: Node<'_, '_>, base: &Url, source: &str) -> Option<Article> {
+	let child = |tag: &str| entry.children().find(|node| node.has_tag_name(tag));
+	let text = |tag: &str| child(tag).and_then(trimmed);
+
+	let title = text("title")?;
+	let audio = audio_enclosure(entry).and_then(|(href, kind)| Some((base.join(href).ok()?, kind)));
+	// Atom links carry an `href`, the alternate one being the article; RSS links are text
+	let link = entry
+		.children()
+		.filter(|node| node.has_tag_name("link"))
+		.find_map(|link| {
+			link.attribute("href").map_or_else(
+				|| trimmed(link),
+				|href| matches!(link.attribute("rel"), None | Some("alternate")).then_some(href.trim()),
+			)
+		})
+		.filter(|link| !link.is_empty())
+		.and_then(|link| base.join(link).ok());
+	let url = link.or_else(|| audio.as_ref().map(|(audio, _)| audio.clone()))?;
+
+	let description = text("summary")
+		.or_else(|| text("description"))
+		.or_else(|| text("content"))
+		.or_else(|| text("encoded"))
+		.or_else(|| {
+			child("group")
+				.and_then(|group| group.children().find(|node| node.has_tag_name("description")))
+				.and_then(trimmed)
+		});
+	let published_at = text("published")
+		.or_else(|| text("pubDate"))
+		.or_else(|| text("updated"))
+		.and_then(parse_date);
+	let author = child("author")
+		.and_then(|author| {
+			author
+				.children()
+				.find(|node| node.has_tag_name("name"))
+				.map_or_else(|| trimmed(author), trimmed)
+		})
+		.or_else(|| text("creator"));
+	let tags = entry
+		.children()
+		.filter(|node| node.has_tag_name("category"))
+		.filter_map(|category| category.attribute("term").map(str::trim).or_else(|| trimmed(category)))
+		.filter(|tag| !tag.is_empty())
+		.map(str::to_string)
+		.collect();
+
+	let mut article = Article::new(title.to_string(), url.into(), source.to_string())
+		.with_published_at(published_at)
+		.with_author(author.map(str::to_string))
+		.with_tags(tags);
+	if let Some(guid) = text("id").or_else(|| text("guid")) {
+		article = article.with_guid(guid.to_string());
+	}
+	if let Some((audio, kind)) = audio {
+		article = article
+			.with_extra("audio_url", Extra::Text(audio.into()))
+			.with_extra("audio_type", Extra::Text(kind.to_string()));
+	}
+	if let Some(seconds) = text("duration").and_then(parse_duration) {
+		article = article.with_extra("duration_secs", Extra::Integer(seconds));
+	}
+	if let Some(image) = entry_image(entry).and_then(|image| base.join(image).ok()) {
+		article = article.with_extra("image_url", Extra::Text(image.into()));
+	}
+	Some(match description {
+		Some(description) => article.with_description(description.to_string()),
+		None => article,
+	})
+}
+
+/// URL and media type of an audio enclosure: an RSS `<enclosure>` or an Atom `rel="enclosure"` link
+fn audio_enclosure<'a>(entry: Node<'a, '_>) -> Option<(&'a str, &'a str)> {
+	entry.children().find_map(|node| {
+		let href = if node.has_tag_name("enclosure") {
+			node.attribute("url")
+		} else if node.has_tag_name("link") && node.attribute("rel") == Some("enclosure") {
+			node.attribute("href")
+		} else {
+			None
+		}?;
+		let kind = node.attribute("type").filter(|kind| kind.starts_with("audio/"))?;
+		Some((href.trim(), kind)).filter(|(href, _)| !href.is_empty())
+	})
+}
+
+/// A `<media:thumbnail>`, an image `<media:content>` or an image enclosure, also inside a `<media:group>`
+fn entry_image<'a>(entry: Node<'a, '_>) -> Option<&'a str> {
+	let image = |node: Node<'a, '_>| {
+		let is_image = node.has_tag_name("thumbnail")
+			|| (node.has_tag_name("content")
+				&& (node.attribute("medium") == Some("image")
+					|| node.attribute("type").is_some_and(|kind| kind.starts_with("image/"))))
+			|| (node.has_tag_name("enclosure")
+				&& node.attribute("type").is_some_and(|kind| kind.starts_with("image/")));
+		is_image
+			.then(|| node.attribute("url"))
+			.flatten()
+			.map(str::trim)
+			.filter(|url| !url.is_empty())
+	};
+	entry.children().find_map(image).or_else(|| {
+		entry
+			.children()
+			.filter(|node| node.has_tag_name("group"))
+			.flat_map(|group| group.children())
+			.find_map(image)
+	})
+}
+
+/// An iTunes `<itunes:duration>`: plain seconds, `MM:SS` or `HH:MM:SS`
+fn parse_duration(text: &str) -> Option<i64> {
+	text.split(':').try_fold(0_i64, |total, part| {
+		total
+			.checked_mul(60)?
+			.checked_add(i64::from(part.trim().parse::<u32>().ok()?))
+	})
+}
+
+/// Trimmed, non-empty text of an element
+fn trimmed<'a>(node: Node<'a, '_>) -> Option<&'a str> {
+	node.text().map(str::trim).filter(|text| !text.is_empty())
+}
+
+/// Atom dates are RFC 3339, RSS dates RFC 2822
+fn parse_date(text: &str) -> Option<DateTime<Utc>> {
+	DateTime::parse_from_rfc3339(text)
+		.or_else(|_| DateTime::parse_from_rfc2822(text))
+		.ok()
+		.map(|date| date.with_timezone(&Utc))
+}
@@ -1,289 +1,1394 @@
-use crate::config::Config;
+use crate::auth::SourceAuth;
+use crate::cache::ResponseCache;
+use crate::compression::ContentEncoding;
+use crate::concurrency::ConcurrencyLimiter;
+use crate::config::{Config, PodcastConfig, ScraperConfig, SitemapConfig, YoutubeConfig};
 use crate::error::{AppError, Result};
-use crate::metrics::Metrics;
-use crate::model::{Article, HackerNewsItem};
+use crate::extract::extract_article;
+use crate::feed::parse_feed;
+use crate::fixtures::Fixtures;
+use crate::metrics::{Breaker, Metrics};
+use crate::model::{Article, Extra, HackerNewsItem};
+use crate::newsapi::{Quota, SearchPage};
+use crate::producthunt::{parse_posts, posts_query};
 use crate::rate_limiter::RateLimiter;
-use futures::stream::{self, StreamExt};
-use reqwest::Client;
-use scraper::{Html, Selector};
-use std::sync::Arc;
-use tokio::time::{sleep, timeout};
+use crate::releases::parse_releases;
+use crate::retry::RetryPolicy;
+use crate::run::RunContext;
+use crate::scrape::scrape;
+use crate::sitemap::{Entry, Robots, Sitemap};
+use crate::wikipedia::{
+	MAX_TITLES_PER_REQUEST, current_events_url, parse_current_events, parse_revisions, revisions_url,
+};
+use bytes::{Bytes, BytesMut};
+use chrono::{DateTime, Days, TimeDelta, Utc};
+use futures::stream::{self, Stream, StreamExt};
+use regex::RegexSet;
+use reqwest::header::{ACCEPT_ENCODING, CONTENT_TYPE};
+use reqwest::{Client, RequestBuilder, Response, Url};
+use std::any::Any;
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::pin::pin;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::{self, JoinSet};
+use tokio::time::timeout;
 use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 
+pub const HACKER_NEWS: &str = "HackerNews";
+const RUST_BLOG: &str = "Rust Blog";
+const HACKER_NEWS_TOP_STORIES_URL: &str = "https://hacker-news.firebaseio.com/v0/topstories.json";
+const RUST_BLOG_URL: &str = "https://blog.rust-lang.org/";
+const RUST_BLOG_FEED_URL: &str = "https://blog.rust-lang.org/feed.xml";
+const INSIDE_RUST: &str = "Inside Rust";
+const INSIDE_RUST_URL: &str = "https://blog.rust-lang.org/inside-rust/";
+const INSIDE_RUST_FEED_URL: &str = "https://blog.rust-lang.org/inside-rust/feed.xml";
+const RUST_RELEASES: &str = "Rust Releases";
+const RUST_RELEASES_URL: &str = "https://raw.githubusercontent.com/rust-lang/rust/master/RELEASES.md";
+/// Where release notes articles link to, the rendered counterpart of `RUST_RELEASES_URL`
+const RUST_RELEASES_PAGE: &str = "https://github.com/rust-lang/rust/blob/master/RELEASES.md";
+const DEFAULT_RELEASE_NOTES_LIMIT: usize = 3;
+
+/// Tag of the articles about the Rust toolchain itself, for `categories.<name>.tags`
+const TOOLCHAIN_TAG: &str = "rust-toolchain";
+/// Tag of every article of a `[[youtube]]` source
+const VIDEO_TAG: &str = "video";
+/// Tag of every article of a `[[podcasts]]` source
+const PODCAST_TAG: &str = "podcast";
+const PRODUCT_HUNT: &str = "Product Hunt";
+const CHANGELOG_NEWS: &str = "Changelog News";
+const WIKIPEDIA: &str = "Wikipedia";
+
+/// Entry point of every built-in source, used by `config validate` to check reachability
+const SOURCE_URLS: [(&str, &str); 4] = [
+	(HACKER_NEWS, HACKER_NEWS_TOP_STORIES_URL),
+	(RUST_BLOG, RUST_BLOG_FEED_URL),
+	(INSIDE_RUST, INSIDE_RUST_FEED_URL),
+	(RUST_RELEASES, RUST_RELEASES_URL),
+];
+
+/// Child sitemaps read from a sitemap index, most recently modified first
+const MAX_CHILD_SITEMAPS: usize = 20;
+
+/// What a response is parsed as, checked against its `Content-Type` before the body is read
+#[derive(Debug, Clone, Copy)]
+enum Format {
+	Json,
+	/// A page, scraped or read for its article
+	Html,
+	/// A feed or a sitemap
+	Xml,
+	/// Markdown or robots.txt
+	Text,
+}
+
+impl Format {
+	const fn name(self) -> &'static str {
+		match self {
+			Self::Json => "json",
+			Self::Html => "html",
+			Self::Xml => "xml",
+			Self::Text => "text",
+		}
+	}
+
+	/// Whether a body of media type `essence`, such as `application/rss+xml`, can hold this format.
+	/// `text/plain`, which many servers send for JSON and feeds, passes for all but HTML.
+	fn accepts(self, essence: &str) -> bool {
+		let plain = essence == "text/plain";
+		match self {
+			Self::Json => plain || matches!(essence, "application/json" | "text/json") || essence.ends_with("+json"),
+			Self::Html => matches!(essence, "text/html" | "application/xhtml+xml"),
+			Self::Xml => plain || matches!(essence, "application/xml" | "text/xml") || essence.ends_with("+xml"),
+			Self::Text => essence.starts_with("text/"),
+		}
+	}
+
+	/// Fail with `ContentTypeError` unless the `Content-Type` of `response` fits; a response without one
+	/// is given the benefit of the doubt and left to the parser
+	fn check(self, response: &Response, url: &str) -> Result<()> {
+		let Some(content_type) = response
+			.headers()
+			.get(CONTENT_TYPE)
+			.and_then(|value| value.to_str().ok())
+		else {
+			return Ok(());
+		};
+		let essence = content_type
+			.split(';')
+			.next()
+			.unwrap_or_default()
+			.trim()
+			.to_ascii_lowercase();
+		if self.accepts(&essence) {
+			return Ok(());
+		}
+		Err(AppError::ContentTypeError {
+			url: url.into(),
+			expected: self.name(),
+			content_type: essence,
+		})
+	}
+}
+
+/// Outcome of fetching one source, `truncated` when its deadline or `fetcher.max_total_articles` cut it short
+struct SourceFetch {
+	/// Number of articles sent
+	result: Result<usize>,
+	elapsed: Duration,
+	truncated: bool,
+}
+
+/// The source fetches of one run, each a task of its own so that a panic fails only its source.
+/// Dropping it aborts the fetches still running.
+struct SourceTasks {
+	fetcher: Arc<Fetcher>,
+	run: RunContext,
+	tx: mpsc::Sender<Article>,
+	tasks: JoinSet<SourceFetch>,
+	/// Source name and start of each task, to report one that panicked
+	started: HashMap<task::Id, (String, Instant)>,
+}
+
+impl SourceTasks {
+	fn new(fetcher: Arc<Fetcher>, run: RunContext, tx: mpsc::Sender<Article>) -> Self {
+		Self {
+			fetcher,
+			run,
+			tx,
+			tasks: JoinSet::new(),
+			started: HashMap::new(),
+		}
+	}
+
+	/// Start the fetch of `source` under its deadline when it is selected
+	fn spawn<F, Fut>(&mut self, source: &str, fetch: F)
+	where
+		F: FnOnce(Arc<Fetcher>, RunContext, mpsc::Sender<Article>, CancellationToken) -> Fut + Send + 'static,
+		Fut: Future<Output = Result<usize>> + Send + 'static,
+	{
+		if !self.fetcher.selected(source) {
+			return;
+		}
+		let (fetcher, run, tx) = (Arc::clone(&self.fetcher), self.run.clone(), self.tx.clone());
+		let name = source.to_string();
+		let handle = self.tasks.spawn(async move {
+			let task_fetcher = Arc::clone(&fetcher);
+			fetcher
+				.fetch_source(&name, |cancel_token| fetch(task_fetcher, run, tx, cancel_token))
+				.await
+		});
+		self.started.insert(handle.id(), (source.to_string(), Instant::now()));
+	}
+
+	/// The next source to finish with its outcome; a task that panicked or was aborted is that source's
+	/// failure
+	async fn next(&mut self) -> Option<(String, SourceFetch)> {
+		let joined = self.tasks.join_next_with_id().await?;
+		let id = match &joined {
+			Ok((id, _)) => *id,
+			Err(e) => e.id(),
+		};
+		let (source, started) = self
+			.started
+			.remove(&id)
+			.unwrap_or_else(|| (String::new(), Instant::now()));
+		let fetch = match joined {
+			Ok((_, fetch)) => fetch,
+			Err(e) => SourceFetch {
+				result: Err(if e.is_panic() {
+					AppError::SourcePanicked {
+						source_name: source.clone(),
+						message: panic_message(e.into_panic()),
+					}
+				} else {
+					AppError::ShutdownError
+				}),
+				elapsed: started.elapsed(),
+				truncated: false,
+			},
+		};
+		Some((source, fetch))
+	}
+}
+
+/// An article counted as sent by `Fetcher::emit` while it waits for room in the channel; counted out again
+/// when given up on, or dropped with its source past the deadline
+struct PendingArticle<'a> {
+	sent: &'a Mutex<HashMap<String, usize>>,
+	source: &'a str,
+	delivered: bool,
+}
+
+impl PendingArticle<'_> {
+	fn delivered(mut self) {
+		self.delivered = true;
+	}
+}
+
+impl Drop for PendingArticle<'_> {
+	fn drop(&mut self) {
+		if !self.delivered {
+			let mut sent = self.sent.lock().unwrap_or_else(PoisonError::into_inner);
+			if let Some(count) = sent.get_mut(self.source) {
+				*count = count.saturating_sub(1);
+			}
+		}
+	}
+}
+
 pub struct Fetcher {
 	client: Client,
 	rate_limiter: Arc<RateLimiter>,
+	retry: RetryPolicy,
+	concurrency: ConcurrencyLimiter,
+	/// Set when `cache.enabled` or offline
+	cache: Option<ResponseCache>,
+	/// Set when recording or replaying `[fixtures]`
+	fixtures: Option<Fixtures>,
+	/// Credentials and headers per source name
+	auth: HashMap<String, SourceAuth>,
+	/// Names of the sources to fetch; all of them when unset
+	only: Option<HashSet<String>>,
+	/// Articles sent per source in the current run, counted against `fetcher.max_total_articles` and kept
+	/// for a source cut off by its deadline
+	sent: Mutex<HashMap<String, usize>>,
 	cancel_token: CancellationToken,
 	metrics: Metrics,
 	config: Arc<Config>,
 }
 
 impl Fetcher {
-	pub fn new(client: Client, cancel_token: CancellationToken, metrics: Metrics, config: &Config) -> Self {
+	#[must_use]
+	pub fn new(client: Client, cancel_token: CancellationToken, metrics: Metrics, config: Arc<Config>) -> Self {
 		let rate_limiter = Arc::new(RateLimiter::new(config.rate_limit.requests_per_second));
+		let retry = RetryPolicy::new(&config.http);
+		let concurrency = ConcurrencyLimiter::new(&config.fetcher, metrics.clone());
+		let cache = (config.cache.enabled || config.cache.offline).then(|| ResponseCache::new(&config.cache));
+		let fixtures = Fixtures::new(&config.fixtures);
+		let auth = entry_points(&config)
+			.into_iter()
+			.filter_map(|(name, _)| Some((name.to_string(), SourceAuth::new(name, config.source(name)?))))
+			.collect();
 		Self {
 			client,
 			rate_limiter,
+			retry,
+			concurrency,
+			cache,
+			fixtures,
+			auth,
+			only: None,
+			sent: Mutex::default(),
 			cancel_token,
 			metrics,
-			config: Arc::new(config.clone()),
+			config,
 		}
 	}
 
-	/// Fetch with retry logic and exponential backoff
-	async fn fetch_with_retry<F, Fut>(&self, url: &str, operation: F) -> Result<String>
-	where
-		F: Fn() -> Fut,
-		Fut: std::future::Future<Output = Result<String>>,
-	{
-		if self.config.http.retry_attempts == 0 {
-			return Err(AppError::ConfigError("retry_attempts must be at least 1".into()));
+	/// Fetch only the named sources, e.g. those due in a scheduled daemon cycle
+	#[must_use]
+	pub fn only(mut self, sources: HashSet<String>) -> Self {
+		self.only = Some(sources);
+		self
+	}
+
+	fn selected(&self, source: &str) -> bool {
+		self.only.as_ref().is_none_or(|only| only.contains(source))
+	}
+
+	/// Body of `url`, from the response cache while it is fresh for `source`, otherwise from the network.
+	/// Offline, any cached body is used and a miss is an error.
+	async fn fetch(&self, source: &str, url: &str, format: Format, cancel_token: &CancellationToken) -> Result<Bytes> {
+		self.fetch_cached(source, url, url, format, || self.request(source, url), cancel_token)
+			.await
+	}
+
+	/// Body of the request built by `build`, cached under `key` like `fetch` caches a GET under its URL;
+	/// `url` names the request in logs and metrics. Replaying, the body recorded under `key` is served
+	/// instead, and recording, whatever body is served is recorded.
+	async fn fetch_cached(
+		&self,
+		source: &str,
+		url: &str,
+		key: &str,
+		format: Format,
+		build: impl Fn() -> RequestBuilder + Sync,
+		cancel_token: &CancellationToken,
+	) -> Result<Bytes> {
+		let Some(fixtures) = &self.fixtures else {
+			return self
+				.fetch_through_cache(source, url, key, format, &build, cancel_token)
+				.await;
+		};
+		if fixtures.replaying() {
+			return tokio::task::block_in_place(|| fixtures.replay(key))
+				.ok_or_else(|| AppError::FixtureMissing(url.into()));
+		}
+		let body = self
+			.fetch_through_cache(source, url, key, format, &build, cancel_token)
+			.await?;
+		if let Err(e) = tokio::task::block_in_place(|| fixtures.record(source, url, key, &body)) {
+			warn!(url, error = %e, "Failed to record a fixture");
 		}
+		Ok(body)
+	}
 
-		let mut attempts = 0;
-		let max_attempts = self.config.http.retry_attempts;
+	async fn fetch_through_cache(
+		&self,
+		source: &str,
+		url: &str,
+		key: &str,
+		format: Format,
+		build: &(impl Fn() -> RequestBuilder + Sync),
+		cancel_token: &CancellationToken,
+	) -> Result<Bytes> {
+		let Some(cache) = &self.cache else {
+			return self.fetch_with_retry(url, format, build, cancel_token).await;
+		};
+		let offline = self.config.cache.offline;
+		let max_age = (!offline).then(|| self.config.cache_ttl(source));
+		if let Some(body) = tokio::task::block_in_place(|| cache.get(key, max_age)) {
+			self.metrics.record_cache_hit(url);
+			return Ok(body);
+		}
+		if offline {
+			return Err(AppError::CacheMiss(url.into()));
+		}
 
-		loop {
-			if self.cancel_token.is_cancelled() {
-				return Err(AppError::ShutdownError);
+		let body = self.fetch_with_retry(url, format, build, cancel_token).await?;
+		if let Err(e) = tokio::task::block_in_place(|| cache.put(key, &body)) {
+			warn!(url, error = %e, "Failed to cache response");
+		}
+		Ok(body)
+	}
+
+	/// Send the request built by `build` under the retry policy, giving up once `cancel_token` is cancelled
+	async fn fetch_with_retry(
+		&self,
+		url: &str,
+		format: Format,
+		build: &(impl Fn() -> RequestBuilder + Sync),
+		cancel_token: &CancellationToken,
+	) -> Result<Bytes> {
+		let attempt = || async move {
+			self.rate_limiter.wait(cancel_token).await?;
+			let request = self.concurrency.run(timed(self.send(url, format, build())), |result| {
+				!result.as_ref().is_err_and(AppError::is_retryable)
+			});
+			let (result, elapsed) = until_cancelled(cancel_token, request).await??;
+			self.metrics.record_http_request(url, elapsed, result.is_ok());
+			result
+		};
+		self.retry
+			.run(cancel_token, attempt, |error, attempt, backoff| {
+				self.metrics.record_retry(url);
+				warn!(
+					url,
+					attempt,
+					backoff_ms = backoff.as_millis(),
+					error = %error,
+					"Request failed, retrying"
+				);
+			})
+			.await
+	}
+
+	/// A single attempt; error statuses are reported as `HttpStatusError` so the retry policy can classify them.
+	/// A body whose `Content-Type` does not fit `format` is not read, and a compressed one is decoded.
+	async fn send(&self, url: &str, format: Format, request: RequestBuilder) -> Result<Bytes> {
+		let request = match self.config.http.accept_encoding() {
+			Some(encodings) => request.header(ACCEPT_ENCODING, encodings),
+			None => request,
+		};
+		let response = timeout(self.config.timeout(), request.send())
+			.await
+			.map_err(|_| AppError::TimeoutError(url.into()))?
+			.map_err(|e| AppError::http_error(url, e.without_url()))?;
+		let status = response.status();
+		if !status.is_success() {
+			return Err(AppError::HttpStatusError {
+				url: url.into(),
+				status: status.as_u16(),
+			});
+		}
+		format.check(&response, url)?;
+		let encoding = ContentEncoding::of(response.headers(), url)?;
+		let max_bytes = self.config.fetcher.max_body_bytes;
+		let decoded = match read_body(response, url, max_bytes).await {
+			Ok(received) => {
+				let received_bytes = received.len();
+				encoding
+					.decode_blocking(received, url, max_bytes)
+					.await
+					.inspect(|body| self.metrics.record_transfer(url, received_bytes, body.len()))
+			}
+			Err(e) => Err(e),
+		};
+		decoded.inspect_err(|e| {
+			if matches!(e, AppError::BodyTooLarge { .. }) {
+				warn!(url, error = %e, "Response exceeds the body size limit, abandoned");
+				self.metrics.record_limit_reached("body");
 			}
+		})
+	}
 
-			self.rate_limiter.wait().await?;
-			self.metrics.record_http_request();
+	/// Hand an article of `source` to the consumer, its text cut to `analyzer.max_text_bytes`. Fails with
+	/// `ArticleLimitReached` once the run has sent `fetcher.max_total_articles`, which ends the source's fetch;
+	/// a closed channel means nobody is waiting for the rest of the run. Waiting for room in the channel ends
+	/// with `ShutdownError` when `cancel_token` is cancelled.
+	async fn emit(
+		&self,
+		source: &str,
+		cancel_token: &CancellationToken,
+		tx: &mpsc::Sender<Article>,
+		mut article: Article,
+	) -> Result<()> {
+		let pending = self.count_sent(source)?;
+		if self
+			.config
+			.analyzer
+			.max_text_bytes
+			.is_some_and(|max| article.truncate_text(max))
+		{
+			self.metrics.record_limit_reached("text");
+		}
+		tokio::select! {
+			sent = tx.send(article.with_fetched_at(Utc::now())) => sent.map_err(|_| AppError::ShutdownError)?,
+			() = cancel_token.cancelled() => return Err(AppError::ShutdownError),
+		}
+		pending.delivered();
+		Ok(())
+	}
 
-			match operation().await {
-				Ok(body) => return Ok(body),
-				Err(e) => {
-					attempts += 1;
-					self.metrics.record_http_failure();
-
-					if attempts >= max_attempts {
-						return Err(AppError::http_error(
-							url,
-							format!("All {max_attempts} attempts failed: {e}"),
-						));
-					}
+	/// Count an article of `source` as sent, until the returned guard is dropped without being delivered. Fails
+	/// with `ArticleLimitReached` once the run has sent `fetcher.max_total_articles`.
+	fn count_sent<'s>(&'s self, source: &'s str) -> Result<PendingArticle<'s>> {
+		let mut sent = self.sent.lock().unwrap_or_else(PoisonError::into_inner);
+		if let Some(max) = self
+			.config
+			.fetcher
+			.max_total_articles
+			.filter(|&max| sent.values().sum::<usize>() >= max)
+		{
+			return Err(AppError::ArticleLimitReached(max));
+		}
+		let count = sent.entry(source.to_string()).or_default();
+		*count = count.saturating_add(1);
+		drop(sent);
+		Ok(PendingArticle {
+			sent: &self.sent,
+			source,
+			delivered: false,
+		})
+	}
 
-					let backoff = self.config.retry_delay().as_millis() as u64 * 2u64.pow(attempts - 1);
-					warn!(
-						url,
-						attempt = attempts,
-						backoff_ms = backoff,
-						"Request failed, retrying"
-					);
-					sleep(std::time::Duration::from_millis(backoff)).await;
-				}
-			}
+	/// Articles of `source` sent in the current run
+	fn sent(&self, source: &str) -> usize {
+		let sent = self.sent.lock().unwrap_or_else(PoisonError::into_inner);
+		sent.get(source).copied().unwrap_or_default()
+	}
+
+	/// GET request for `url` carrying the credentials and headers configured for `source`
+	fn request(&self, source: &str, url: &str) -> RequestBuilder {
+		self.authorized(source, self.client.get(url))
+	}
+
+	/// `request` with the credentials and headers configured for `source`
+	fn authorized(&self, source: &str, request: RequestBuilder) -> RequestBuilder {
+		match self.auth.get(source) {
+			Some(auth) => auth.apply(request),
+			None => request,
 		}
 	}
 
-	pub async fn fetch_all(&self) -> Result<Vec<Article>> {
-		let hn_fut = self.fetch_hacker_news();
-		let rust_fut = self.fetch_rust_blog();
+	/// Fetch every source and collect the articles
+	///
+	/// # Errors
+	///
+	/// As `fetch_into`
+	pub async fn fetch_all(self: &Arc<Self>, run: &RunContext) -> Result<Vec<Article>> {
+		let (tx, mut rx) = mpsc::channel(self.config.pipeline.fetched_capacity);
+		let collect = async {
+			let mut articles = Vec::new();
+			while let Some(article) = rx.recv().await {
+				articles.push(article);
+			}
+			articles
+		};
+		let (fetched, articles) = tokio::join!(self.fetch_into(run, tx), collect);
+		fetched?;
+		Ok(articles)
+	}
 
-		let (hn_result, rust_result) = tokio::join!(hn_fut, rust_fut);
+	/// Fetch every source concurrently, each as a task of its own, sending each article into `tx` as soon
+	/// as it is parsed. The channel is bounded, so a consumer that falls behind holds the fetchers back
+	/// instead of letting articles pile up in memory. Every source is reported as it finishes, and one
+	/// that panics fails alone. Returns the number of articles sent.
+	///
+	/// # Errors
+	///
+	/// `NoArticlesError` when no source yielded an article, `ShutdownError` when that is because of a shutdown
+	pub async fn fetch_into(self: &Arc<Self>, run: &RunContext, tx: mpsc::Sender<Article>) -> Result<usize> {
+		self.sent.lock().unwrap_or_else(PoisonError::into_inner).clear();
+		let mut tasks = self.spawn_sources(run, tx);
 
-		let mut all_articles = Vec::new();
+		let mut total: usize = 0;
+		while let Some((source, fetch)) = tasks.next().await {
+			let source = source.as_str();
+			// A skipped source leaves its health alone, so that its cooldown runs from its last real fetch
+			if let Err(e @ AppError::CircuitOpen { .. }) = &fetch.result {
+				info!(source, error = %e, "Skipped source");
+				run.record_source(source, 0, fetch.elapsed, false, Some(e));
+				continue;
+			}
+			self.metrics.record_source_fetch(source, fetch.elapsed);
+			if fetch.truncated {
+				self.metrics.record_source_truncated(source);
+			}
+			match fetch.result {
+				Ok(count) => {
+					info!(source, count, truncated = fetch.truncated, "Fetched source");
+					run.record_source(source, count, fetch.elapsed, fetch.truncated, None);
+					self.metrics.record_source_result(source, Ok(count));
+					total = total.saturating_add(count);
+				}
+				Err(e) => {
+					warn!(source, kind = e.kind(), error = %e, "Failed to fetch source");
+					run.record_source(source, 0, fetch.elapsed, fetch.truncated, Some(&e));
+					self.metrics.record_source_result(source, Err(e.to_string()));
+				}
+			}
+		}
 
-		match hn_result {
-			Ok(mut articles) => {
-				info!(count = articles.len(), "Fetched HackerNews articles");
-				all_articles.append(&mut articles);
+		if total == 0 {
+			if self.cancel_token.is_cancelled() {
+				return Err(AppError::ShutdownError);
 			}
-			Err(e) => warn!(error = %e, "Failed to fetch HackerNews"),
+			return Err(AppError::NoArticlesError("all sources".into()));
+		}
+
+		Ok(total)
+	}
+
+	/// One task per selected source, each reporting under its source name
+	fn spawn_sources(self: &Arc<Self>, run: &RunContext, tx: mpsc::Sender<Article>) -> SourceTasks {
+		let mut tasks = SourceTasks::new(Arc::clone(self), run.clone(), tx);
+		tasks.spawn(HACKER_NEWS, |fetcher, run, tx, cancel_token| async move {
+			fetcher.fetch_hacker_news(&run, cancel_token, &tx).await
+		});
+		tasks.spawn(RUST_RELEASES, |fetcher, _, tx, cancel_token| async move {
+			fetcher.fetch_release_notes(cancel_token, &tx).await
+		});
+		let blogs = [rust_blog_scraper(), inside_rust_scraper()];
+		for scraper in blogs.into_iter().chain(self.config.scrapers.iter().cloned()) {
+			let name = scraper.name.clone();
+			tasks.spawn(&name, |fetcher, _, tx, cancel_token| async move {
+				fetcher.fetch_scraped(&scraper, cancel_token, &tx).await
+			});
+		}
+		for sitemap in self.config.sitemaps.iter().cloned() {
+			let name = sitemap.name.clone();
+			tasks.spawn(&name, |fetcher, run, tx, cancel_token| async move {
+				fetcher.fetch_sitemap(&sitemap, &run, cancel_token, &tx).await
+			});
+		}
+		for channel in self.config.youtube.iter().cloned() {
+			let name = channel.name.clone();
+			tasks.spawn(&name, |fetcher, _, tx, cancel_token| async move {
+				fetcher.fetch_youtube(&channel, cancel_token, &tx).await
+			});
 		}
+		for podcast in self.config.podcasts.iter().cloned() {
+			let name = podcast.name.clone();
+			tasks.spawn(&name, |fetcher, _, tx, cancel_token| async move {
+				fetcher.fetch_podcast(&podcast, cancel_token, &tx).await
+			});
+		}
+		if self.config.product_hunt.enabled {
+			tasks.spawn(PRODUCT_HUNT, |fetcher, _, tx, cancel_token| async move {
+				fetcher.fetch_product_hunt(cancel_token, &tx).await
+			});
+		}
+		if self.config.news_api.enabled {
+			tasks.spawn(
+				self.config.news_api.provider.name(),
+				|fetcher, _, tx, cancel_token| async move { fetcher.fetch_news_api(cancel_token, &tx).await },
+			);
+		}
+		if self.config.wikipedia.enabled {
+			tasks.spawn(WIKIPEDIA, |fetcher, _, tx, cancel_token| async move {
+				fetcher.fetch_wikipedia(cancel_token, &tx).await
+			});
+		}
+		if self.config.changelog.enabled {
+			tasks.spawn(CHANGELOG_NEWS, |fetcher, _, tx, cancel_token| async move {
+				fetcher.fetch_changelog(cancel_token, &tx).await
+			});
+		}
+		tasks
+	}
 
-		match rust_result {
-			Ok(mut articles) => {
-				info!(count = articles.len(), "Fetched Rust Blog articles");
-				all_articles.append(&mut articles);
+	/// Run one source under `fetcher.source_timeout_secs`. Past the deadline the fetch is dropped with its
+	/// in-flight requests and the articles it already sent are kept, so one hung source cannot hold up the
+	/// digest. A source whose circuit breaker is open is not fetched at all.
+	async fn fetch_source<F, Fut>(&self, source: &str, fetch: F) -> SourceFetch
+	where
+		F: FnOnce(CancellationToken) -> Fut,
+		Fut: Future<Output = Result<usize>>,
+	{
+		let breaker = self
+			.metrics
+			.source_breaker(source, &self.config.fetcher.circuit_breaker, Utc::now());
+		if let Breaker::Open { until } = breaker {
+			return SourceFetch {
+				result: Err(AppError::CircuitOpen {
+					source_name: source.to_string(),
+					until,
+				}),
+				elapsed: Duration::ZERO,
+				truncated: false,
+			};
+		}
+		if breaker == Breaker::HalfOpen {
+			info!(source, "Circuit breaker cooled down, trying the source again");
+		}
+		let fetch = timed(fetch(self.cancel_token.child_token()));
+		let ((result, elapsed), timed_out) = match self.config.source_timeout() {
+			None => (fetch.await, false),
+			Some(limit) => timeout(limit, fetch).await.map_or_else(
+				|_| ((self.past_deadline(source, limit), limit), true),
+				|outcome| (outcome, false),
+			),
+		};
+		let capped = matches!(result, Err(AppError::ArticleLimitReached(_)));
+		let result = match result {
+			Err(AppError::ArticleLimitReached(max)) => {
+				info!(
+					source,
+					max_total_articles = max,
+					"Article cap reached, keeping the articles sent"
+				);
+				self.metrics.record_limit_reached("articles");
+				Ok(self.sent(source))
 			}
-			Err(e) => warn!(error = %e, "Failed to fetch Rust Blog"),
+			other => other,
+		};
+		SourceFetch {
+			result,
+			elapsed,
+			truncated: timed_out || capped,
 		}
+	}
 
-		if all_articles.is_empty() {
-			return Err(AppError::NoArticlesError("all sources".into()));
+	/// Outcome of a source dropped at its deadline: the articles it sent, or a timeout when it sent none
+	fn past_deadline(&self, source: &str, limit: Duration) -> Result<usize> {
+		let sent = self.sent(source);
+		warn!(
+			source,
+			timeout_secs = limit.as_secs(),
+			count = sent,
+			"Source deadline reached, keeping the articles sent"
+		);
+		if sent > 0 {
+			Ok(sent)
+		} else {
+			Err(AppError::TimeoutError(format!("{source} exceeded source_timeout_secs")))
 		}
+	}
 
-		Ok(all_articles)
+	/// Request each source's entry point once, without retries, and report whether it answered successfully
+	pub async fn check_sources(&self) -> Vec<(&str, Result<()>)> {
+		let checks = entry_points(&self.config).into_iter().map(|(name, url)| async move {
+			let url = url.as_ref();
+			let result = timeout(self.config.timeout(), self.request(name, url).send())
+				.await
+				.map_err(|_| AppError::TimeoutError(url.into()))
+				.and_then(|response| response.map_err(|e| AppError::http_error(url, e)))
+				.and_then(|response| {
+					response
+						.error_for_status()
+						.map(|_| ())
+						.map_err(|e| AppError::http_error(url, e))
+				});
+			(name, result)
+		});
+		futures::future::join_all(checks).await
 	}
 
-	async fn fetch_hacker_news(&self) -> Result<Vec<Article>> {
-		let top_url = "https://hacker-news.firebaseio.com/v0/topstories.json";
+	async fn fetch_hacker_news(
+		&self,
+		run: &RunContext,
+		cancel_token: CancellationToken,
+		tx: &mpsc::Sender<Article>,
+	) -> Result<usize> {
+		let top_url = HACKER_NEWS_TOP_STORIES_URL;
 
-		let body = self
-			.fetch_with_retry(top_url, || {
-				let client = self.client.clone();
-				async move {
-					timeout(self.config.timeout(), client.get(top_url).send())
-						.await
-						.map_err(|_| AppError::TimeoutError(top_url.into()))?
-						.map_err(|e| AppError::http_error(top_url, e))?
-						.text()
-						.await
-						.map_err(|e| AppError::http_error(top_url, e))
-				}
-			})
-			.await?;
+		let body = self.fetch(HACKER_NEWS, top_url, Format::Json, &cancel_token).await?;
 
-		let ids: Vec<u64> =
-			serde_json::from_str(&body).map_err(|e| AppError::parse_error("HackerNews top stories", e))?;
+		let ids: Vec<u64> = serde_json::from_slice(&body).map_err(|e| {
+			self.metrics.record_parse_failure(HACKER_NEWS);
+			AppError::parse_error("HackerNews top stories", e)
+		})?;
 
 		let ids_to_fetch: Vec<u64> = ids.into_iter().take(self.config.fetcher.hacker_news_limit).collect();
 
-		let articles: Vec<Article> = stream::iter(ids_to_fetch)
-			.map(|id| {
-				let client = self.client.clone();
-				let rate_limiter = self.rate_limiter.clone();
-				let cancel_token = self.cancel_token.clone();
-				let metrics = self.metrics.clone();
-				let timeout_duration = self.config.timeout();
-				let max_attempts = self.config.http.retry_attempts;
-				let retry_delay = self.config.retry_delay();
-
-				async move {
-					if cancel_token.is_cancelled() {
-						return Err(AppError::ShutdownError);
+		// Stop handing out ids once the source is cut short; items in flight are dropped below
+		let items = pin!(
+			stream::iter(ids_to_fetch)
+				.take_until(cancel_token.cancelled())
+				.map(|id| {
+					let cancel_token = &cancel_token;
+					async move {
+						let url = format!("https://hacker-news.firebaseio.com/v0/item/{id}.json");
+						let in_item = |e: AppError| e.within(|cause| AppError::HackerNewsItemError { id, cause });
+						let body = self
+							.fetch(HACKER_NEWS, &url, Format::Json, cancel_token)
+							.await
+							.map_err(in_item)
+							.inspect_err(|_| self.metrics.record_article_failed(HACKER_NEWS))?;
+						let item: HackerNewsItem = serde_json::from_slice(&body).map_err(|e| {
+							self.metrics.record_parse_failure(HACKER_NEWS);
+							self.metrics.record_article_failed(HACKER_NEWS);
+							in_item(AppError::parse_error(url.as_str(), e))
+						})?;
+						self.metrics.record_article_fetched(HACKER_NEWS);
+						Ok(hacker_news_article(item))
 					}
+				})
+				.buffer_unordered(self.config.fetcher.max_concurrent_requests)
+		);
 
-					let url = format!("https://hacker-news.firebaseio.com/v0/item/{id}.json");
-
-					let mut attempts = 0;
-					loop {
-						rate_limiter.wait().await?;
-						metrics.record_http_request();
-
-						let result = timeout(timeout_duration, client.get(&url).send()).await;
-
-						if let Ok(Ok(response)) = result {
-							match response.text().await {
-								Ok(text) => {
-									let item: HackerNewsItem = serde_json::from_str(&text)
-										.map_err(|e| AppError::parse_error("HackerNews item", e))?;
-
-									let article_url = item
-										.url
-										.unwrap_or_else(|| format!("https://news.ycombinator.com/item?id={}", item.id));
-
-									let mut article = Article::new(item.title, article_url, "HackerNews".into());
-									if let Some(text) = item.text {
-										article = article.with_description(text);
-									}
-
-									metrics.record_article_fetched();
-									return Ok(article);
-								}
-								Err(e) => {
-									attempts += 1;
-									metrics.record_http_failure();
-
-									if attempts >= max_attempts {
-										metrics.record_article_failed();
-										return Err(AppError::http_error(&url, e));
-									}
-
-									let backoff = retry_delay.as_millis() as u64 * 2u64.pow(attempts - 1);
-									sleep(std::time::Duration::from_millis(backoff)).await;
-								}
-							}
-						} else {
-							attempts += 1;
-							metrics.record_http_failure();
-
-							if attempts >= max_attempts {
-								metrics.record_article_failed();
-								return Err(AppError::http_error(
-									&url,
-									format!("Failed after {max_attempts} attempts"),
-								));
-							}
-
-							let backoff = retry_delay.as_millis() as u64 * 2u64.pow(attempts - 1);
-							sleep(std::time::Duration::from_millis(backoff)).await;
-						}
-					}
-				}
-			})
-			.buffer_unordered(self.config.fetcher.max_concurrent_requests)
-			.filter_map(|res| async {
-				match res {
-					Ok(article) => Some(article),
-					Err(e) => {
-						warn!(error = %e, "Failed to fetch HN article");
-						None
-					}
-				}
-			})
-			.collect()
-			.await;
+		self.forward(HACKER_NEWS, run, items, tx, &cancel_token).await
+	}
 
-		Ok(articles)
+	async fn fetch_scraped(
+		&self,
+		scraper: &ScraperConfig,
+		cancel_token: CancellationToken,
+		tx: &mpsc::Sender<Article>,
+	) -> Result<usize> {
+		let source = scraper.name.as_str();
+		let articles = if let Some(articles) = self.fetch_feed(scraper, &cancel_token).await? {
+			articles
+		} else {
+			let body = self.fetch(source, &scraper.url, Format::Html, &cancel_token).await?;
+			// Parsed in one go: the HTML document cannot be held across an await
+			scrape(scraper, &String::from_utf8_lossy(&body)).inspect_err(|e| match e {
+				AppError::LayoutChangedError { .. } => self.metrics.record_layout_change(source),
+				_ => self.metrics.record_parse_failure(source),
+			})?
+		};
+		let count = articles.len();
+		for mut article in articles {
+			article.add_tags(&scraper.tags);
+			self.metrics.record_article_fetched(source);
+			self.emit(source, &cancel_token, tx, article).await?;
+		}
+		Ok(count)
 	}
 
-	async fn fetch_rust_blog(&self) -> Result<Vec<Article>> {
-		let url = "https://blog.rust-lang.org/";
+	/// The latest releases in Rust's RELEASES.md, one article each
+	async fn fetch_release_notes(&self, cancel_token: CancellationToken, tx: &mpsc::Sender<Article>) -> Result<usize> {
+		let body = self
+			.fetch(RUST_RELEASES, RUST_RELEASES_URL, Format::Text, &cancel_token)
+			.await?;
+		let articles = parse_releases(
+			&String::from_utf8_lossy(&body),
+			RUST_RELEASES_PAGE,
+			RUST_RELEASES,
+			&["release", TOOLCHAIN_TAG],
+			self.config
+				.fetcher
+				.release_notes_limit
+				.unwrap_or(DEFAULT_RELEASE_NOTES_LIMIT),
+			Utc::now(),
+		);
+		if articles.is_empty() {
+			self.metrics.record_parse_failure(RUST_RELEASES);
+			return Err(AppError::parse_error(RUST_RELEASES_URL, "no released version found"));
+		}
+		self.emit_all(RUST_RELEASES, &cancel_token, tx, articles).await
+	}
 
+	/// The latest videos of a `YouTube` channel or playlist, from its feed
+	async fn fetch_youtube(
+		&self,
+		channel: &YoutubeConfig,
+		cancel_token: CancellationToken,
+		tx: &mpsc::Sender<Article>,
+	) -> Result<usize> {
+		let limit = channel.max_videos.unwrap_or(usize::MAX);
+		let tags = [&[VIDEO_TAG.to_string()], channel.tags.as_slice()].concat();
+		self.fetch_feed_source(&channel.name, &channel.feed_url(), limit, &tags, &cancel_token, tx)
+			.await
+	}
+
+	/// The latest episodes of a podcast, described by their show notes, with their audio enclosure
+	async fn fetch_podcast(
+		&self,
+		podcast: &PodcastConfig,
+		cancel_token: CancellationToken,
+		tx: &mpsc::Sender<Article>,
+	) -> Result<usize> {
+		let limit = podcast.max_episodes.unwrap_or(usize::MAX);
+		let tags = [&[PODCAST_TAG.to_string()], podcast.tags.as_slice()].concat();
+		self.fetch_feed_source(&podcast.name, &podcast.url, limit, &tags, &cancel_token, tx)
+			.await
+	}
+
+	/// The most voted recent launches of the configured Product Hunt topic
+	async fn fetch_product_hunt(&self, cancel_token: CancellationToken, tx: &mpsc::Sender<Article>) -> Result<usize> {
+		let config = &self.config.product_hunt;
+		let token = config.token.resolve()?;
+		let after = TimeDelta::try_hours(i64::try_from(config.max_age_hours).unwrap_or(i64::MAX))
+			.and_then(|age| Utc::now().checked_sub_signed(age))
+			.unwrap_or(DateTime::<Utc>::MIN_UTC);
+		let query = posts_query(&config.topic, config.limit, after);
+		let url = config.api_url.as_str();
+		// The query moves with the clock, so responses are cached by what they list
+		let key = format!("{url}?topic={}&first={}", config.topic, config.limit);
+		let build = || {
+			self.authorized(PRODUCT_HUNT, self.client.post(url))
+				.bearer_auth(&token)
+				.json(&query)
+		};
 		let body = self
-			.fetch_with_retry(url, || {
-				let client = self.client.clone();
-				async move {
-					timeout(self.config.timeout(), client.get(url).send())
-						.await
-						.map_err(|_| AppError::TimeoutError(url.into()))?
-						.map_err(|e| AppError::http_error(url, e))?
-						.text()
-						.await
-						.map_err(|e| AppError::http_error(url, e))
-				}
-			})
+			.fetch_cached(PRODUCT_HUNT, url, &key, Format::Json, build, &cancel_token)
 			.await?;
+		let articles =
+			parse_posts(&body, url, PRODUCT_HUNT).inspect_err(|_| self.metrics.record_parse_failure(PRODUCT_HUNT))?;
+		self.emit_all(PRODUCT_HUNT, &cancel_token, tx, articles).await
+	}
 
-		let document = Html::parse_document(&body);
+	/// News search results for the configured query, counting requests against the daily quota
+	async fn fetch_news_api(&self, cancel_token: CancellationToken, tx: &mpsc::Sender<Article>) -> Result<usize> {
+		let config = &self.config.news_api;
+		let path = Path::new(&config.quota_path);
+		let mut quota = tokio::task::block_in_place(|| Quota::load(path, Utc::now().date_naive()));
+		let result = self.search_news(&mut quota, &cancel_token, tx).await;
+		let source = config.provider.name();
+		info!(
+			source,
+			used = quota.used(),
+			remaining = quota.remaining(config.daily_quota),
+			"News API quota"
+		);
+		if let Err(e) = tokio::task::block_in_place(|| quota.save(path)) {
+			warn!(source, error = %e, "Failed to save news API quota");
+		}
+		result
+	}
 
-		// More robust selectors with validation
-		let article_selector = Selector::parse("article.post, div.post, section.post")
-			.map_err(|e| AppError::parse_error("Rust Blog", format!("Invalid article selector: {e}")))?;
+	/// Follow the pages of the search results while the quota lasts
+	async fn search_news(
+		&self,
+		quota: &mut Quota,
+		cancel_token: &CancellationToken,
+		tx: &mpsc::Sender<Article>,
+	) -> Result<usize> {
+		let config = &self.config.news_api;
+		let provider = config.provider;
+		let source = provider.name();
+		let key = config.api_key.resolve()?;
+		let query = config
+			.query
+			.clone()
+			.unwrap_or_else(|| provider.keyword_query(&self.config.keywords.values));
+		if query.is_empty() {
+			return Err(AppError::ConfigError(
+				"news_api.query is unset and there are no keywords".into(),
+			));
+		}
+		let endpoint = config.api_url.as_deref().unwrap_or_else(|| provider.default_url());
+		let from = TimeDelta::try_hours(i64::try_from(config.max_age_hours).unwrap_or(i64::MAX))
+			.and_then(|age| Utc::now().checked_sub_signed(age))
+			.unwrap_or(DateTime::<Utc>::MIN_UTC);
 
-		let title_selector = Selector::parse("h2 a, h3 a, .post-title a")
-			.map_err(|e| AppError::parse_error("Rust Blog", format!("Invalid title selector: {e}")))?;
+		let mut count: usize = 0;
+		for page in 1..=config.max_pages {
+			if quota.remaining(config.daily_quota) == 0 {
+				if page == 1 {
+					return Err(AppError::QuotaError {
+						source_name: source.into(),
+						limit: config.daily_quota,
+					});
+				}
+				warn!(source, page, "Daily quota used up, skipping the remaining pages");
+				break;
+			}
+			let url = provider.search_url(
+				endpoint,
+				&query,
+				config.language.as_deref(),
+				config.page_size,
+				page,
+				from,
+			)?;
+			// Every attempt, retries included, counts against the quota
+			let sent = AtomicU32::new(0);
+			let build = || {
+				sent.fetch_add(1, Ordering::Relaxed);
+				provider.authenticate(self.authorized(source, self.client.get(url.as_str())), &key)
+			};
+			let result = self
+				.fetch_cached(source, url.as_str(), url.as_str(), Format::Json, build, cancel_token)
+				.await;
+			quota.record(sent.load(Ordering::Relaxed));
+			let body = match result {
+				Err(AppError::HttpStatusError { status: 429, url }) => {
+					quota.exhaust(config.daily_quota);
+					return Err(AppError::HttpStatusError { status: 429, url });
+				}
+				other => other?,
+			};
+			let results =
+				SearchPage::parse(&body, url.as_str()).inspect_err(|_| self.metrics.record_parse_failure(source))?;
+			let last = results.len() < config.page_size
+				|| results.total().is_some_and(|total| {
+					u64::try_from(page.saturating_mul(config.page_size)).is_ok_and(|seen| seen >= total)
+				});
+			for article in results.into_articles(source) {
+				self.metrics.record_article_fetched(source);
+				self.emit(source, cancel_token, tx, article).await?;
+				count = count.saturating_add(1);
+			}
+			if last {
+				break;
+			}
+		}
+		Ok(count)
+	}
 
-		let mut articles = Vec::new();
+	/// The latest Current Events entries, newest day first, and the recent edits of watched pages
+	async fn fetch_wikipedia(&self, cancel_token: CancellationToken, tx: &mpsc::Sender<Article>) -> Result<usize> {
+		let config = &self.config.wikipedia;
+		let api = Url::parse(&config.api_url).map_err(|e| AppError::ConfigError(format!("wikipedia.api_url: {e}")))?;
+		// Each response's articles are sent before the next request, so a deadline keeps the earlier ones
+		let mut count: usize = 0;
+		if config.current_events {
+			let today = Utc::now().date_naive();
+			for day in (0..config.days).filter_map(|back| today.checked_sub_days(Days::new(back.into()))) {
+				let url = current_events_url(&api, day);
+				let body = self.fetch(WIKIPEDIA, url.as_str(), Format::Json, &cancel_token).await?;
+				let articles = parse_current_events(&body, url.as_str(), &api, day, WIKIPEDIA)
+					.inspect_err(|_| self.metrics.record_parse_failure(WIKIPEDIA))?;
+				count = count.saturating_add(self.emit_all(WIKIPEDIA, &cancel_token, tx, articles).await?);
+			}
+		}
+		let since = TimeDelta::try_hours(i64::try_from(config.watch_max_age_hours).unwrap_or(i64::MAX))
+			.and_then(|age| Utc::now().checked_sub_signed(age))
+			.unwrap_or(DateTime::<Utc>::MIN_UTC);
+		for titles in config.watch.chunks(MAX_TITLES_PER_REQUEST) {
+			let url = revisions_url(&api, titles);
+			let body = self.fetch(WIKIPEDIA, url.as_str(), Format::Json, &cancel_token).await?;
+			let articles = parse_revisions(&body, url.as_str(), &api, since, WIKIPEDIA)
+				.inspect_err(|_| self.metrics.record_parse_failure(WIKIPEDIA))?;
+			count = count.saturating_add(self.emit_all(WIKIPEDIA, &cancel_token, tx, articles).await?);
+		}
+		Ok(count)
+	}
 
-		for article_elem in document.select(&article_selector) {
-			if let Some(link_elem) = article_elem.select(&title_selector).next() {
-				let title = link_elem.text().collect::<String>().trim().to_string();
+	/// `emit` each of the parsed `articles` of `source`; returns how many there were
+	async fn emit_all(
+		&self,
+		source: &str,
+		cancel_token: &CancellationToken,
+		tx: &mpsc::Sender<Article>,
+		articles: Vec<Article>,
+	) -> Result<usize> {
+		let count = articles.len();
+		for article in articles {
+			self.metrics.record_article_fetched(source);
+			self.emit(source, cancel_token, tx, article).await?;
+		}
+		Ok(count)
+	}
 
-				if let Some(href) = link_elem.value().attr("href") {
-					// Validate URL is not empty
-					if href.is_empty() {
-						warn!("Skipping article with empty URL: {}", title);
-						continue;
-					}
+	/// Changelog News, from its feed
+	async fn fetch_changelog(&self, cancel_token: CancellationToken, tx: &mpsc::Sender<Article>) -> Result<usize> {
+		let feed = &self.config.changelog.url;
+		self.fetch_feed_source(CHANGELOG_NEWS, feed, usize::MAX, &[], &cancel_token, tx)
+			.await
+	}
 
-					let article_url = if href.starts_with("http") {
-						href.to_string()
-					} else {
-						format!("https://blog.rust-lang.org{href}")
-					};
+	/// The first `limit` entries of a source read only from its feed, with `tags` added to each
+	async fn fetch_feed_source(
+		&self,
+		source: &str,
+		feed: &str,
+		limit: usize,
+		tags: &[String],
+		cancel_token: &CancellationToken,
+		tx: &mpsc::Sender<Article>,
+	) -> Result<usize> {
+		let body = self.fetch(source, feed, Format::Xml, cancel_token).await?;
+		let articles = parse_feed(&String::from_utf8_lossy(&body), feed, source)
+			.inspect_err(|_| self.metrics.record_parse_failure(source))?;
+		let mut count: usize = 0;
+		for mut article in articles.into_iter().take(limit) {
+			article.add_tags(tags);
+			self.metrics.record_article_fetched(source);
+			self.emit(source, cancel_token, tx, article).await?;
+			count = count.saturating_add(1);
+		}
+		Ok(count)
+	}
 
-					// Validate title is not empty
-					if title.is_empty() {
-						warn!("Skipping article with empty title at URL: {}", article_url);
-						continue;
-					}
+	/// Articles of the scraper's `feed`; `None` without one, or when the feed failed or listed nothing,
+	/// so that the page is scraped instead
+	async fn fetch_feed(
+		&self,
+		scraper: &ScraperConfig,
+		cancel_token: &CancellationToken,
+	) -> Result<Option<Vec<Article>>> {
+		let Some(feed) = scraper.feed.as_deref() else {
+			return Ok(None);
+		};
+		let source = scraper.name.as_str();
+		let parsed = match self.fetch(source, feed, Format::Xml, cancel_token).await {
+			Ok(body) => parse_feed(&String::from_utf8_lossy(&body), feed, source)
+				.inspect_err(|_| self.metrics.record_parse_failure(source)),
+			Err(e) => Err(e),
+		};
+		match parsed {
+			Ok(articles) if !articles.is_empty() => return Ok(Some(articles)),
+			Ok(_) => warn!(source, feed, "Feed lists no articles, scraping the page instead"),
+			Err(AppError::ShutdownError) => return Err(AppError::ShutdownError),
+			Err(e) => warn!(source, feed, kind = e.kind(), error = %e, "Feed failed, scraping the page instead"),
+		}
+		self.metrics.record_feed_fallback(source);
+		Ok(None)
+	}
 
-					let article = Article::new(title, article_url, "Rust Blog".into());
-					articles.push(article);
-					self.metrics.record_article_fetched();
+	/// Send each fetched article of `source` into `tx`, logging the ones that failed and counting them in the run
+	async fn forward(
+		&self,
+		source: &str,
+		run: &RunContext,
+		mut items: impl Stream<Item = Result<Article>> + Unpin,
+		tx: &mpsc::Sender<Article>,
+		cancel_token: &CancellationToken,
+	) -> Result<usize> {
+		let mut sent: usize = 0;
+		while let Some(result) = items.next().await {
+			match result {
+				Ok(article) => {
+					self.emit(source, cancel_token, tx, article).await?;
+					sent = sent.saturating_add(1);
+				}
+				Err(AppError::ShutdownError) => {}
+				Err(e) => {
+					warn!(source, kind = e.kind(), error = %e, "Failed to fetch article");
+					run.record_item_error(source, &e);
 				}
 			}
 		}
 
-		if articles.is_empty() {
-			return Err(AppError::NoArticlesError("Rust Blog".into()));
+		if cancel_token.is_cancelled() {
+			warn!(source, count = sent, "Fetch cut short, keeping partial results");
 		}
+		Ok(sent)
+	}
 
-		Ok(articles)
+	/// Crawl a sitemap source: list its pages, keep the most recent ones allowed by its patterns and
+	/// the site's robots.txt, and extract an article from each page
+	async fn fetch_sitemap(
+		&self,
+		sitemap: &SitemapConfig,
+		run: &RunContext,
+		cancel_token: CancellationToken,
+		tx: &mpsc::Sender<Article>,
+	) -> Result<usize> {
+		let source = sitemap.name.as_str();
+		let robots = if sitemap.respect_robots {
+			self.robots(source, &sitemap.url, &cancel_token).await?
+		} else {
+			Robots::default()
+		};
+		let cutoff = sitemap
+			.max_age_days
+			.and_then(|days| TimeDelta::try_days(i64::try_from(days).ok()?))
+			.and_then(|age| Utc::now().checked_sub_signed(age));
+		// Patterns are checked when the configuration is validated
+		let include = RegexSet::new(&sitemap.include).map_err(|e| AppError::ConfigError(e.to_string()))?;
+		let exclude = RegexSet::new(&sitemap.exclude).map_err(|e| AppError::ConfigError(e.to_string()))?;
+
+		let mut pages: Vec<Entry> = self
+			.sitemap_pages(sitemap, run, cutoff, &cancel_token)
+			.await?
+			.into_iter()
+			.filter(|page| {
+				let Ok(url) = Url::parse(&page.url) else { return false };
+				let path = url.path();
+				let target = url
+					.query()
+					.map_or_else(|| path.to_string(), |query| format!("{path}?{query}"));
+				(include.is_empty() || include.is_match(path))
+					&& !exclude.is_match(path)
+					&& robots.allows(&target)
+					&& cutoff.is_none_or(|cutoff| page.lastmod.is_some_and(|lastmod| lastmod >= cutoff))
+			})
+			.collect();
+		pages.sort_by_key(|page| Reverse(page.lastmod));
+		pages.truncate(sitemap.max_pages);
+		info!(source, pages = pages.len(), "Sitemap pages selected");
+
+		let items = pin!(
+			stream::iter(pages)
+				.take_until(cancel_token.cancelled())
+				.map(|page| {
+					let cancel_token = &cancel_token;
+					async move {
+						let body = self
+							.fetch(source, &page.url, Format::Html, cancel_token)
+							.await
+							.map_err(|e| {
+								e.within(|cause| AppError::SitemapPageError {
+									source_name: source.into(),
+									url: page.url.clone(),
+									cause,
+								})
+							})
+							.inspect_err(|_| self.metrics.record_article_failed(source))?;
+						let sniff_paywall = self.config.paywall.enabled && self.config.paywall.sniff;
+						let article =
+							extract_article(&String::from_utf8_lossy(&body), &page.url, source, sniff_paywall)
+								.ok_or_else(|| {
+									self.metrics.record_parse_failure(source);
+									self.metrics.record_article_failed(source);
+									AppError::ExtractError { url: page.url.clone() }
+								})?;
+						self.metrics.record_article_fetched(source);
+						let published_at = article.published_at().or(page.lastmod);
+						Ok(article.with_published_at(published_at))
+					}
+				})
+				.buffer_unordered(self.config.fetcher.max_concurrent_requests)
+		);
+		self.forward(source, run, items, tx, &cancel_token).await
+	}
+
+	/// robots.txt of the site serving `url`; a missing one (any 4xx, or a page in its place) allows everything
+	async fn robots(&self, source: &str, url: &str, cancel_token: &CancellationToken) -> Result<Robots> {
+		let robots_url = Url::parse(url)
+			.and_then(|url| url.join("/robots.txt"))
+			.map_err(|e| AppError::parse_error(source, e))?;
+		match self
+			.fetch(source, robots_url.as_str(), Format::Text, cancel_token)
+			.await
+		{
+			Ok(body) => Ok(Robots::parse(&String::from_utf8_lossy(&body))),
+			Err(AppError::HttpStatusError { status, .. }) if (400..500).contains(&status) => Ok(Robots::default()),
+			Err(AppError::ContentTypeError { .. }) => Ok(Robots::default()),
+			Err(e) => Err(e),
+		}
+	}
+
+	/// Page entries of a sitemap, following one level of sitemap index. Child sitemaps last modified
+	/// before `cutoff` cannot list recent pages and are not fetched.
+	async fn sitemap_pages(
+		&self,
+		sitemap: &SitemapConfig,
+		run: &RunContext,
+		cutoff: Option<DateTime<Utc>>,
+		cancel_token: &CancellationToken,
+	) -> Result<Vec<Entry>> {
+		let source = sitemap.name.as_str();
+		let body = self.fetch(source, &sitemap.url, Format::Xml, cancel_token).await?;
+		let mut children = match self.parse_sitemap(source, &sitemap.url, &body)? {
+			Sitemap::Pages(pages) => return Ok(pages),
+			Sitemap::Index(children) => children,
+		};
+		children.retain(|child| cutoff.is_none_or(|cutoff| child.lastmod.is_none_or(|lastmod| lastmod >= cutoff)));
+		children.sort_by_key(|child| Reverse(child.lastmod));
+
+		let mut pages = Vec::new();
+		for child in children.into_iter().take(MAX_CHILD_SITEMAPS) {
+			let parsed = match self.fetch(source, &child.url, Format::Xml, cancel_token).await {
+				Ok(body) => self.parse_sitemap(source, &child.url, &body),
+				Err(e) => Err(e),
+			};
+			match parsed {
+				Ok(Sitemap::Pages(entries)) => pages.extend(entries),
+				Ok(Sitemap::Index(_)) => warn!(source, url = child.url, "Skipping nested sitemap index"),
+				Err(AppError::ShutdownError) => return Err(AppError::ShutdownError),
+				Err(e) => {
+					warn!(source, url = child.url, kind = e.kind(), error = %e, "Failed to read child sitemap");
+					run.record_item_error(source, &e);
+				}
+			}
+		}
+		Ok(pages)
+	}
+
+	fn parse_sitemap(&self, source: &str, url: &str, body: &[u8]) -> Result<Sitemap> {
+		Sitemap::parse(url, &String::from_utf8_lossy(body)).inspect_err(|_| self.metrics.record_parse_failure(source))
+	}
+}
+
+/// Name of every source, built-in and configured
+pub fn source_names(config: &Config) -> impl Iterator<Item = &str> {
+	entry_points(config).into_iter().map(|(name, _)| name)
+}
+
+/// Name and entry point of every source, built-in and configured
+fn entry_points(config: &Config) -> Vec<(&str, Cow<'_, str>)> {
+	let builtin = SOURCE_URLS.into_iter().map(|(name, url)| (name, Cow::Borrowed(url)));
+	let scrapers = config.scrapers.iter().map(|scraper| {
+		let url = scraper.feed.as_deref().unwrap_or(&scraper.url);
+		(scraper.name.as_str(), Cow::Borrowed(url))
+	});
+	let sitemaps = config
+		.sitemaps
+		.iter()
+		.map(|sitemap| (sitemap.name.as_str(), Cow::Borrowed(sitemap.url.as_str())));
+	let youtube = config
+		.youtube
+		.iter()
+		.map(|channel| (channel.name.as_str(), Cow::Owned(channel.feed_url())));
+	let podcasts = config
+		.podcasts
+		.iter()
+		.map(|podcast| (podcast.name.as_str(), Cow::Borrowed(podcast.url.as_str())));
+	// The Product Hunt API only answers authenticated queries, so its site stands in for it
+	let product_hunt = config.product_hunt.enabled.then(|| {
+		let topic = format!("https://www.producthunt.com/topics/{}", config.product_hunt.topic);
+		(PRODUCT_HUNT, Cow::Owned(topic))
+	});
+	let news_api = config
+		.news_api
+		.enabled
+		.then_some(config.news_api.provider)
+		.map(|provider| (provider.name(), Cow::Borrowed(provider.home_url())));
+	let wikipedia = config
+		.wikipedia
+		.enabled
+		.then_some((WIKIPEDIA, Cow::Borrowed(config.wikipedia.api_url.as_str())));
+	let changelog = config
+		.changelog
+		.enabled
+		.then_some((CHANGELOG_NEWS, Cow::Borrowed(config.changelog.url.as_str())));
+	builtin
+		.chain(scrapers)
+		.chain(sitemaps)
+		.chain(youtube)
+		.chain(podcasts)
+		.chain(product_hunt)
+		.chain(news_api)
+		.chain(wikipedia)
+		.chain(changelog)
+		.collect()
+}
+
+/// The Rust blog is read like any `[[scrapers]]` entry: from its feed, with its front page scraped when the feed fails
+fn rust_blog_scraper() -> ScraperConfig {
+	ScraperConfig {
+		name: RUST_BLOG.into(),
+		url: RUST_BLOG_URL.into(),
+		feed: Some(RUST_BLOG_FEED_URL.into()),
+		article_selector: "article.post, div.post, section.post".into(),
+		title_selector: "h2 a, h3 a, .post-title a".into(),
+		link_selector: None,
+		description_selector: None,
+		tags: Vec::new(),
+	}
+}
+
+/// The Inside Rust blog, for the work of the Rust teams, read like the main Rust blog
+fn inside_rust_scraper() -> ScraperConfig {
+	ScraperConfig {
+		name: INSIDE_RUST.into(),
+		url: INSIDE_RUST_URL.into(),
+		feed: Some(INSIDE_RUST_FEED_URL.into()),
+		tags: vec!["inside-rust".into(), TOOLCHAIN_TAG.into()],
+		..rust_blog_scraper()
+	}
+}
+
+/// Self posts without an external URL link to their discussion page
+fn hacker_news_article(item: HackerNewsItem) -> Article {
+	let article_url = item
+		.url
+		.unwrap_or_else(|| format!("https://news.ycombinator.com/item?id={}", item.id));
+
+	let article = Article::new(item.title, article_url, HACKER_NEWS.into())
+		.with_engagement(item.score, item.descendants)
+		.with_published_at(item.time.and_then(|time| DateTime::from_timestamp(time, 0)))
+		.with_author(item.by)
+		.with_guid(item.id.to_string())
+		.with_extra(
+			"hn_discussion",
+			Extra::Text(format!("https://news.ycombinator.com/item?id={}", item.id)),
+		);
+	let article = match i64::try_from(item.id) {
+		Ok(id) => article.with_extra("hn_id", Extra::Integer(id)),
+		Err(_) => article,
+	};
+	match item.text {
+		Some(text) => article.with_description(text),
+		None => article,
 	}
 }
+
+/// The body of `response`, read chunk by chunk so that one larger than `max_bytes` is abandoned as soon as
+/// its `Content-Length` or the bytes received so far show it, instead of being buffered whole
+async fn read_body(mut response: Response, url: &str, max_bytes: Option<usize>) -> Result<Bytes> {
+	let Some(max) = max_bytes else {
+		return response
+			.bytes()
+			.await
+			.map_err(|e| AppError::http_error(url, e.without_url()));
+	};
+	let too_large = || AppError::BodyTooLarge {
+		url: url.into(),
+		limit: max,
+	};
+	if response
+		.content_length()
+		.is_some_and(|length| usize::try_from(length).map_or(true, |length| length > max))
+	{
+		return Err(too_large());
+	}
+	let mut body = BytesMut::new();
+	while let Some(chunk) = response
+		.chunk()
+		.await
+		.map_err(|e| AppError::http_error(url, e.without_url()))?
+	{
+		if body.len().saturating_add(chunk.len()) > max {
+			return Err(too_large());
+		}
+		body.extend_from_slice(&chunk);
+	}
+	Ok(body.freeze())
+}
+
+/// Run `future` unless `cancel_token` is cancelled first, which drops it and yields `ShutdownError`
+async fn until_cancelled<T>(cancel_token: &CancellationToken, future: impl Future<Output = T>) -> Result<T> {
+	cancel_token
+		.run_until_cancelled(future)
+		.await
+		.ok_or(AppError::ShutdownError)
+}
+
+/// The message a task panicked with, when it was given one
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+	payload
+		.downcast::<String>()
+		.map(|message| *message)
+		.or_else(|payload| payload.downcast::<&str>().map(|message| (*message).to_string()))
+		.unwrap_or_else(|_| "no message".into())
+}
+
+async fn timed<T>(future: impl Future<Output = T>) -> (T, Duration) {
+	let start = Instant::now();
+	let output = future.await;
+	(output, start.elapsed())
+}
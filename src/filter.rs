@@ -0,0 +1,134 @@
+use crate::config::{Config, source_key};
+use crate::error::{AppError, Result};
+use crate::model::Article;
+use regex::{RegexSet, RegexSetBuilder};
+use reqwest::Url;
+use std::collections::{BTreeMap, HashMap};
+
+/// The `[filters]` rules plus each source's own excludes, compiled once per pipeline.
+///
+/// Domains match the article's host and any of its subdomains; title patterns ignore case.
+/// Rules are checked in a fixed order and an article is counted against the first one that drops it.
+#[derive(Debug)]
+pub struct ArticleFilter {
+	allow_domains: Vec<String>,
+	deny_domains: Vec<String>,
+	exclude_titles: RegexSet,
+	min_title_length: usize,
+	drop_paywalled: bool,
+	/// Per-source rules keyed by the normalized source name
+	sources: HashMap<String, SourceRules>,
+}
+
+#[derive(Debug)]
+struct SourceRules {
+	deny_domains: Vec<String>,
+	exclude_titles: RegexSet,
+}
+
+impl ArticleFilter {
+	/// # Errors
+	///
+	/// `ConfigError` when a filter pattern is not a valid regular expression
+	pub fn new(config: &Config) -> Result<Self> {
+		let filters = &config.filters;
+		let sources = config
+			.sources
+			.iter()
+			.filter(|(_, source)| !source.deny_domains.is_empty() || !source.exclude_title_patterns.is_empty())
+			.map(|(name, source)| {
+				Ok((
+					source_key(name),
+					SourceRules {
+						deny_domains: domains(&source.deny_domains),
+						exclude_titles: patterns(
+							&format!("sources.{name}.exclude_title_patterns"),
+							&source.exclude_title_patterns,
+						)?,
+					},
+				))
+			})
+			.collect::<Result<_>>()?;
+		Ok(Self {
+			allow_domains: domains(&filters.allow_domains),
+			deny_domains: domains(&filters.deny_domains),
+			exclude_titles: patterns("filters.exclude_title_patterns", &filters.exclude_title_patterns)?,
+			min_title_length: filters.min_title_length,
+			drop_paywalled: filters.drop_paywalled,
+			sources,
+		})
+	}
+
+	/// Drop the articles matching any rule, returning how many each rule dropped
+	pub fn apply(&self, articles: &mut Vec<Article>) -> BTreeMap<&'static str, usize> {
+		let mut dropped = BTreeMap::new();
+		articles.retain(|article| {
+			let Some(rule) = self.rejects(article) else {
+				return true;
+			};
+			let count: &mut usize = dropped.entry(rule).or_default();
+			*count = count.saturating_add(1);
+			false
+		});
+		dropped
+	}
+
+	/// Name of the first rule dropping `article`
+	fn rejects(&self, article: &Article) -> Option<&'static str> {
+		let host = host(article.url());
+		let title = article.title();
+		let source = self.sources.get(&source_key(article.source()));
+		if !self.allow_domains.is_empty() && !matches_domain(&host, &self.allow_domains) {
+			Some("allow_domains")
+		} else if matches_domain(&host, &self.deny_domains) {
+			Some("deny_domains")
+		} else if self.drop_paywalled && article.paywalled() {
+			Some("drop_paywalled")
+		} else if self.exclude_titles.is_match(title) {
+			Some("exclude_title_patterns")
+		} else if title.chars().count() < self.min_title_length {
+			Some("min_title_length")
+		} else if source.is_some_and(|rules| matches_domain(&host, &rules.deny_domains)) {
+			Some("source_deny_domains")
+		} else if source.is_some_and(|rules| rules.exclude_titles.is_match(title)) {
+			Some("source_exclude_title_patterns")
+		} else {
+			None
+		}
+	}
+}
+
+/// Lowercase host of `url`, empty when it has none
+#[must_use]
+pub fn host(url: &str) -> String {
+	Url::parse(url)
+		.ok()
+		.and_then(|url| url.host_str().map(str::to_ascii_lowercase))
+		.unwrap_or_default()
+}
+
+/// Configured domains normalized for `matches_domain`
+#[must_use]
+pub fn domains(domains: &[String]) -> Vec<String> {
+	domains
+		.iter()
+		.map(|domain| domain.trim().trim_start_matches('.').to_ascii_lowercase())
+		.filter(|domain| !domain.is_empty())
+		.collect()
+}
+
+fn patterns(setting: &str, patterns: &[String]) -> Result<RegexSet> {
+	RegexSetBuilder::new(patterns)
+		.case_insensitive(true)
+		.build()
+		.map_err(|e| AppError::ConfigError(format!("{setting}: {e}")))
+}
+
+/// Whether `host` is one of `domains` or a subdomain of one
+#[must_use]
+pub fn matches_domain(host: &str, domains: &[String]) -> bool {
+	domains.iter().any(|domain| {
+		host.strip_suffix(domain.as_str())
+			.is_some_and(|prefix| prefix.is_empty() || prefix.ends_with('.'))
+	})
+}
@@ -0,0 +1,86 @@
+use crate::cache::ResponseCache;
+use crate::config::{FixtureMode, FixturesConfig};
+use crate::error::{AppError, Result};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+
+/// File in the fixture directory listing what was recorded
+const INDEX: &str = "index.json";
+
+/// One recorded response
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+	source: String,
+	url: String,
+	recorded_at: DateTime<Utc>,
+}
+
+/// Source responses recorded to a directory and served back from it, so that a run can be reproduced
+/// without the network.
+///
+/// Bodies are stored the way the response cache stores them, under the SHA-256 of their request key, and
+/// `index.json` maps every key to its source and URL for whoever reads a bug report.
+#[derive(Debug)]
+pub struct Fixtures {
+	mode: FixtureMode,
+	bodies: ResponseCache,
+	index_path: PathBuf,
+	/// By request key
+	index: Mutex<BTreeMap<String, Entry>>,
+}
+
+impl Fixtures {
+	/// The fixtures of `config`, when recording or replaying
+	#[must_use]
+	pub fn new(config: &FixturesConfig) -> Option<Self> {
+		let mode = config.mode?;
+		let dir = PathBuf::from(&config.dir);
+		let index_path = dir.join(INDEX);
+		// Recording again into a directory adds to its index
+		let index = fs::read(&index_path)
+			.ok()
+			.and_then(|index| serde_json::from_slice(&index).ok())
+			.unwrap_or_default();
+		Some(Self {
+			mode,
+			bodies: ResponseCache::in_dir(dir),
+			index_path,
+			index: Mutex::new(index),
+		})
+	}
+
+	pub fn replaying(&self) -> bool {
+		self.mode == FixtureMode::Replay
+	}
+
+	/// The body recorded for `key`
+	pub fn replay(&self, key: &str) -> Option<Bytes> {
+		self.bodies.get(key, None)
+	}
+
+	/// Save `body` as the fixture for `key`, fetched from `url` for `source`
+	///
+	/// # Errors
+	///
+	/// `IoError` when the fixture or its index cannot be written
+	pub fn record(&self, source: &str, url: &str, key: &str, body: &[u8]) -> Result<()> {
+		self.bodies.put(key, body)?;
+		let mut index = self.index.lock().unwrap_or_else(PoisonError::into_inner);
+		index.insert(
+			key.to_string(),
+			Entry {
+				source: source.to_string(),
+				url: url.to_string(),
+				recorded_at: Utc::now(),
+			},
+		);
+		let json = serde_json::to_vec_pretty(&*index).map_err(|e| AppError::parse_error("fixture index", e))?;
+		drop(index);
+		fs::write(&self.index_path, json).map_err(|e| AppError::io_error(&self.index_path, e))
+	}
+}
@@ -0,0 +1,119 @@
+use crate::error::{AppError, Result};
+use evalexpr::error::EvalexprResultValue;
+use evalexpr::{
+	ContextWithMutableFunctions, ContextWithMutableVariables, DefaultNumericTypes, Function, HashMapContext, Node,
+	Value,
+};
+
+/// Variables an expression may reference
+pub const VARIABLES: &[&str] = &[
+	"keyword_score",
+	"source_weight",
+	"keyword_count",
+	"keyword_hits",
+	"hn_points",
+	"comments",
+	"title_length",
+];
+
+/// Per-article inputs to a scoring expression
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScoreInputs {
+	pub keyword_score: f64,
+	pub source_weight: f64,
+	/// Number of distinct keywords matched
+	pub keyword_count: u32,
+	/// Total keyword occurrences
+	pub keyword_hits: u32,
+	pub hn_points: u64,
+	pub comments: u64,
+	pub title_length: u32,
+}
+
+/// User-defined final score, e.g. `keyword_score * source_weight + ln(hn_points + 1) * 0.5`
+pub struct ScoreFormula {
+	source: String,
+	tree: Node<DefaultNumericTypes>,
+	functions: HashMapContext,
+}
+
+impl ScoreFormula {
+	/// # Errors
+	///
+	/// `ConfigError` when `source` is not a valid expression or uses an unknown variable
+	pub fn parse(source: &str) -> Result<Self> {
+		let tree = evalexpr::build_operator_tree::<DefaultNumericTypes>(source)
+			.map_err(|e| AppError::ConfigError(format!("invalid score formula `{source}`: {e}")))?;
+
+		if let Some(unknown) = tree
+			.iter_read_variable_identifiers()
+			.find(|name| !VARIABLES.contains(name))
+		{
+			return Err(AppError::ConfigError(format!(
+				"score formula references unknown variable `{unknown}`; available: {}",
+				VARIABLES.join(", ")
+			)));
+		}
+
+		let mut functions = HashMapContext::new();
+		for (name, function) in [
+			("ln", f64::ln as fn(f64) -> f64),
+			("log10", f64::log10),
+			("sqrt", f64::sqrt),
+			("exp", f64::exp),
+		] {
+			functions
+				.set_function(name.into(), Function::new(move |arg| unary_float(arg, function)))
+				.map_err(|e| AppError::ConfigError(format!("score formula setup: {e}")))?;
+		}
+
+		let formula = Self {
+			source: source.to_string(),
+			tree,
+			functions,
+		};
+		// Catch type errors such as string literals up front rather than once per article
+		formula.evaluate(&ScoreInputs::default())?;
+		Ok(formula)
+	}
+
+	#[must_use]
+	pub fn source(&self) -> &str {
+		&self.source
+	}
+
+	/// # Errors
+	///
+	/// `AnalyzerError` when the formula cannot be evaluated to a number
+	pub fn evaluate(&self, inputs: &ScoreInputs) -> Result<f64> {
+		let mut context = self.functions.clone();
+		let values = [
+			("keyword_score", Value::from_float(inputs.keyword_score)),
+			("source_weight", Value::from_float(inputs.source_weight)),
+			("keyword_count", Value::from_float(f64::from(inputs.keyword_count))),
+			("keyword_hits", Value::from_float(f64::from(inputs.keyword_hits))),
+			("hn_points", Value::from_float(u64_as_f64(inputs.hn_points))),
+			("comments", Value::from_float(u64_as_f64(inputs.comments))),
+			("title_length", Value::from_float(f64::from(inputs.title_length))),
+		];
+		for (name, value) in values {
+			context
+				.set_value(name.into(), value)
+				.map_err(|e| AppError::AnalyzerError(format!("score formula variable {name}: {e}")))?;
+		}
+
+		self.tree
+			.eval_number_with_context(&context)
+			.map_err(|e| AppError::AnalyzerError(format!("score formula `{}` failed: {e}", self.source)))
+	}
+}
+
+fn unary_float(arg: &Value, function: fn(f64) -> f64) -> EvalexprResultValue<DefaultNumericTypes> {
+	let number = arg.as_number()?;
+	Ok(Value::from_float(function(number)))
+}
+
+/// Engagement counts fit comfortably in f64's 52-bit mantissa; clamp anything absurd
+fn u64_as_f64(value: u64) -> f64 {
+	f64::from(u32::try_from(value).unwrap_or(u32::MAX))
+}
@@ -0,0 +1,213 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::ConfigHandle;
+use crate::daemon::{Health, Ranking, Rankings, Trigger};
+use crate::error::{AppError, Result};
+use crate::model::Article;
+use crate::store;
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt, stream};
+use prost_types::Timestamp;
+use std::pin::Pin;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::{TcpListenerStream, WatchStream};
+use tokio_util::sync::CancellationToken;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+use tracing::{error, info};
+
+/// Code generated from proto/news_aggregator.proto
+#[allow(clippy::all, clippy::pedantic, clippy::nursery)]
+pub mod proto {
+	tonic::include_proto!("news_aggregator.v1");
+}
+
+use proto::aggregator_server::{Aggregator, AggregatorServer};
+
+struct Service {
+	config: ConfigHandle,
+	health: Health,
+	trigger: Trigger,
+	rankings: Rankings,
+	cancel_token: CancellationToken,
+}
+
+/// Bind the gRPC endpoint and serve it in the background until shutdown
+///
+/// # Errors
+///
+/// `ConfigError` when the listen address cannot be bound
+pub async fn spawn(
+	config: ConfigHandle,
+	health: Health,
+	trigger: Trigger,
+	rankings: Rankings,
+	cancel_token: CancellationToken,
+) -> Result<()> {
+	let listen = config.current().grpc.listen.clone();
+	let listener = TcpListener::bind(&listen)
+		.await
+		.map_err(|e| AppError::ConfigError(format!("cannot listen on {listen}: {e}")))?;
+	info!(listen = %listen, "gRPC server listening");
+
+	let service = Service {
+		config,
+		health,
+		trigger,
+		rankings,
+		cancel_token: cancel_token.clone(),
+	};
+	tokio::spawn(async move {
+		if let Err(e) = Server::builder()
+			.add_service(AggregatorServer::new(service))
+			.serve_with_incoming_shutdown(TcpListenerStream::new(listener), cancel_token.cancelled_owned())
+			.await
+		{
+			error!(error = %e, "gRPC server failed");
+		}
+	});
+	Ok(())
+}
+
+type ArticleStream = Pin<Box<dyn Stream<Item = std::result::Result<proto::ScoredArticle, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl Aggregator for Service {
+	async fn trigger_fetch(
+		&self,
+		_request: Request<proto::TriggerFetchRequest>,
+	) -> std::result::Result<Response<proto::TriggerFetchResponse>, Status> {
+		let state = self.health.snapshot();
+		self.trigger.fire();
+		Ok(Response::new(proto::TriggerFetchResponse {
+			queued: state.running_since.is_some(),
+			cycles: state.cycles,
+		}))
+	}
+
+	type StreamArticlesStream = ArticleStream;
+
+	/// Each cycle's ranking as it is published; streams end on shutdown so that the server can stop
+	async fn stream_articles(
+		&self,
+		request: Request<proto::StreamArticlesRequest>,
+	) -> std::result::Result<Response<Self::StreamArticlesStream>, Status> {
+		let request = request.into_inner();
+		let receiver = self.rankings.subscribe();
+		let rankings = if request.include_latest {
+			WatchStream::new(receiver)
+		} else {
+			WatchStream::from_changes(receiver)
+		};
+		let limit = usize::try_from(request.limit).unwrap_or(usize::MAX);
+		let articles = rankings
+			.flat_map(move |ranking| {
+				let messages = ranking.map_or_else(Vec::new, |ranking| ranked(&ranking, request.min_score, limit));
+				stream::iter(messages.into_iter().map(Ok))
+			})
+			.take_until(self.cancel_token.clone().cancelled_owned());
+		Ok(Response::new(Box::pin(articles)))
+	}
+
+	async fn query_archive(
+		&self,
+		request: Request<proto::QueryArchiveRequest>,
+	) -> std::result::Result<Response<proto::QueryArchiveResponse>, Status> {
+		let config = self.config.current();
+		if !config.store.enabled {
+			return Err(Status::failed_precondition("the article store is not enabled"));
+		}
+		let query = request.into_inner();
+		let since = query
+			.since
+			.map(|since| {
+				DateTime::from_timestamp(since.seconds, u32::try_from(since.nanos).unwrap_or(0))
+					.ok_or_else(|| Status::invalid_argument("since is out of range"))
+			})
+			.transpose()?;
+		let max_results = config.grpc.max_results;
+		let limit = match usize::try_from(query.limit) {
+			Ok(0) | Err(_) => max_results,
+			Ok(limit) => limit.min(max_results),
+		};
+		let words: Vec<String> = query.query.split_whitespace().map(str::to_lowercase).collect();
+
+		let stored = tokio::task::block_in_place(|| store::open(&config.store)?.load_articles())
+			.map_err(|e| Status::internal(e.to_string()))?;
+		let articles = stored
+			.iter()
+			.filter(|article| query.source.is_empty() || article.source().eq_ignore_ascii_case(&query.source))
+			.filter(|article| {
+				since.is_none_or(|since| {
+					article
+						.published_at()
+						.or_else(|| article.fetched_at())
+						.is_some_and(|at| at >= since)
+				})
+			})
+			.filter(|article| matches_words(article, &words))
+			.take(limit)
+			.map(article_message)
+			.collect();
+		Ok(Response::new(proto::QueryArchiveResponse { articles }))
+	}
+}
+
+/// The displayed articles of a ranking scoring at least `min_score`, at most `limit` of them
+fn ranked(ranking: &Ranking, min_score: f64, limit: usize) -> Vec<proto::ScoredArticle> {
+	ranking
+		.articles
+		.iter()
+		.take(ranking.displayed)
+		.enumerate()
+		.filter(|(_, scored)| scored.relevance_score() >= min_score)
+		.take(limit)
+		.map(|(index, scored)| scored_message(scored, &ranking.run_id, index))
+		.collect()
+}
+
+/// Whether the title or description contains every word
+fn matches_words(article: &Article, words: &[String]) -> bool {
+	if words.is_empty() {
+		return true;
+	}
+	let text = format!("{} {}", article.title(), article.description().unwrap_or_default()).to_lowercase();
+	words.iter().all(|word| text.contains(word.as_str()))
+}
+
+fn timestamp(at: DateTime<Utc>) -> Timestamp {
+	Timestamp {
+		seconds: at.timestamp(),
+		nanos: i32::try_from(at.timestamp_subsec_nanos()).unwrap_or(0),
+	}
+}
+
+fn article_message(article: &Article) -> proto::Article {
+	proto::Article {
+		title: article.title().to_string(),
+		url: article.url().to_string(),
+		source: article.source().to_string(),
+		author: article.author().unwrap_or_default().to_string(),
+		description: article.description().unwrap_or_default().to_string(),
+		published_at: article.published_at().map(timestamp),
+		fetched_at: article.fetched_at().map(timestamp),
+		tags: article.tags().to_vec(),
+		points: article.points(),
+		comments: article.comments(),
+	}
+}
+
+fn scored_message(scored: &ScoredArticle, run_id: &str, index: usize) -> proto::ScoredArticle {
+	proto::ScoredArticle {
+		article: Some(article_message(scored.article())),
+		run_id: run_id.to_string(),
+		rank: u32::try_from(index.saturating_add(1)).unwrap_or(u32::MAX),
+		score: scored.relevance_score(),
+		matched_keywords: scored.matched_keywords().to_vec(),
+		categories: scored.categories().to_vec(),
+		summary: scored.summary().unwrap_or_default().to_string(),
+		word_count: scored
+			.reading()
+			.map(|reading| u64::try_from(reading.words).unwrap_or(u64::MAX)),
+		read_minutes: scored.reading().map(|reading| reading.minutes),
+	}
+}
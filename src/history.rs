@@ -0,0 +1,89 @@
+use crate::analyzer::ScoredArticle;
+use crate::error::Result;
+use crate::model::Article;
+use crate::run::RunContext;
+use crate::store::Store;
+use serde::Serialize;
+use std::fmt;
+use tracing::info;
+
+/// Where an article stands compared with the previous run's ranking
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum RankChange {
+	/// Never ranked by an earlier run
+	New,
+	Risen {
+		from: usize,
+	},
+	Fallen {
+		from: usize,
+	},
+	Unchanged,
+	/// Ranked by an earlier run, but not the previous one
+	Returning,
+}
+
+impl fmt::Display for RankChange {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::New => write!(f, "new"),
+			Self::Risen { from } => write!(f, "risen from #{from}"),
+			Self::Fallen { from } => write!(f, "fallen from #{from}"),
+			Self::Unchanged => write!(f, "unchanged"),
+			Self::Returning => write!(f, "returning"),
+		}
+	}
+}
+
+/// Mark every article against the profile's previous ranking, record the leading `recorded` articles
+/// as this run's ranking, then with `only_new` drop the articles any earlier run already ranked
+///
+/// # Errors
+///
+/// `StorageError` when the store cannot be read or written
+pub fn compare_with_previous(
+	store: &mut Store,
+	profile: &str,
+	run: &RunContext,
+	scored: &mut Vec<ScoredArticle>,
+	recorded: usize,
+	only_new: bool,
+) -> Result<()> {
+	let history = store.ranking_history(profile)?;
+	let mut new = 0usize;
+	for (position, item) in (1usize..).zip(scored.iter_mut()) {
+		let id = item.article().id();
+		let change = match history.previous.get(&id) {
+			Some(&from) if from > position => RankChange::Risen { from },
+			Some(&from) if from < position => RankChange::Fallen { from },
+			Some(_) => RankChange::Unchanged,
+			None if history.seen.contains(&id) => RankChange::Returning,
+			None => {
+				new = new.saturating_add(1);
+				RankChange::New
+			}
+		};
+		item.set_change(change);
+	}
+
+	let ranking: Vec<(&Article, f64)> = scored
+		.iter()
+		.take(recorded)
+		.map(|item| (item.article(), item.relevance_score()))
+		.collect();
+	store.save_ranking(profile, run.id(), run.started_at(), &ranking)?;
+
+	let total = scored.len();
+	if only_new {
+		scored.retain(|item| item.change() == Some(RankChange::New));
+	}
+	info!(
+		new,
+		total,
+		shown = scored.len(),
+		first_run = history.seen.is_empty(),
+		"Compared ranking with the previous run"
+	);
+	Ok(())
+}
@@ -0,0 +1,114 @@
+use crate::model::Article;
+use rayon::prelude::*;
+use whatlang::Lang;
+
+/// ISO 639-3 (whatlang) to ISO 639-1 codes for every language whatlang can detect
+const ISO_639_1: &[(&str, &str)] = &[
+	("afr", "af"),
+	("aka", "ak"),
+	("amh", "am"),
+	("ara", "ar"),
+	("aze", "az"),
+	("bel", "be"),
+	("ben", "bn"),
+	("bul", "bg"),
+	("cat", "ca"),
+	("ces", "cs"),
+	("cmn", "zh"),
+	("cym", "cy"),
+	("dan", "da"),
+	("deu", "de"),
+	("ell", "el"),
+	("eng", "en"),
+	("epo", "eo"),
+	("est", "et"),
+	("fin", "fi"),
+	("fra", "fr"),
+	("guj", "gu"),
+	("heb", "he"),
+	("hin", "hi"),
+	("hrv", "hr"),
+	("hun", "hu"),
+	("hye", "hy"),
+	("ind", "id"),
+	("ita", "it"),
+	("jav", "jv"),
+	("jpn", "ja"),
+	("kan", "kn"),
+	("kat", "ka"),
+	("khm", "km"),
+	("kor", "ko"),
+	("lat", "la"),
+	("lav", "lv"),
+	("lit", "lt"),
+	("mal", "ml"),
+	("mar", "mr"),
+	("mkd", "mk"),
+	("mya", "my"),
+	("nep", "ne"),
+	("nld", "nl"),
+	("nob", "nb"),
+	("ori", "or"),
+	("pan", "pa"),
+	("pes", "fa"),
+	("pol", "pl"),
+	("por", "pt"),
+	("ron", "ro"),
+	("rus", "ru"),
+	("sin", "si"),
+	("slk", "sk"),
+	("slv", "sl"),
+	("sna", "sn"),
+	("spa", "es"),
+	("srp", "sr"),
+	("swe", "sv"),
+	("tam", "ta"),
+	("tel", "te"),
+	("tgl", "tl"),
+	("tha", "th"),
+	("tuk", "tk"),
+	("tur", "tr"),
+	("ukr", "uk"),
+	("urd", "ur"),
+	("uzb", "uz"),
+	("vie", "vi"),
+	("yid", "yi"),
+	("zul", "zu"),
+];
+
+/// Canonical form of a configured language code: ISO 639-1 when known, otherwise lowercased as given
+#[must_use]
+pub fn normalize_code(code: &str) -> String {
+	let code = code.trim().to_ascii_lowercase();
+	ISO_639_1
+		.iter()
+		.find_map(|&(iso3, iso1)| (iso3 == code).then(|| iso1.to_string()))
+		.unwrap_or(code)
+}
+
+fn code_for(lang: Lang) -> String {
+	normalize_code(lang.code())
+}
+
+/// Detect the language of every article in parallel; only reliable detections are recorded
+pub fn detect_languages(articles: &mut [Article]) {
+	articles.par_iter_mut().for_each(|article| {
+		let info = whatlang::detect(&article.searchable_text());
+		if let Some(info) = info.filter(whatlang::Info::is_reliable) {
+			article.set_language(code_for(info.lang()));
+		}
+	});
+}
+
+/// Keep articles in one of the allowed languages; undetected articles are always kept
+pub fn filter_languages(articles: &mut Vec<Article>, allowed: &[String]) {
+	if allowed.is_empty() {
+		return;
+	}
+	let allowed: Vec<String> = allowed.iter().map(|code| normalize_code(code)).collect();
+	articles.retain(|article| {
+		article
+			.language()
+			.is_none_or(|lang| allowed.iter().any(|code| code == lang))
+	});
+}
@@ -0,0 +1,151 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::LearningConfig;
+use crate::error::{AppError, Result};
+use crate::store::StoredArticle;
+use crate::suggest;
+use chrono::{DateTime, Utc};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// Multinomial naive Bayes over article words, with starred articles as the liked class
+/// and every other stored article as the background.
+///
+/// Articles whose links readers followed `learning.min_clicks` times count as starred.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InterestModel {
+	trained_at: DateTime<Utc>,
+	starred: usize,
+	others: usize,
+	/// Word occurrences in starred and in other articles
+	words: HashMap<String, [u64; 2]>,
+	totals: [u64; 2],
+}
+
+impl InterestModel {
+	/// Count the words of every stored article by whether it is starred
+	///
+	/// # Errors
+	///
+	/// `AnalyzerError` when fewer articles are starred or clicked than `min_starred`, or none are not
+	pub fn train(articles: &[StoredArticle], config: &LearningConfig) -> Result<Self> {
+		let liked =
+			|stored: &StoredArticle| stored.starred || (config.min_clicks > 0 && stored.clicks >= config.min_clicks);
+		let starred = articles.iter().filter(|stored| liked(stored)).count();
+		let others = articles.len().saturating_sub(starred);
+		if starred < config.min_starred.max(1) || others == 0 {
+			return Err(AppError::AnalyzerError(format!(
+				"need at least {} starred or clicked and one other stored article to learn from, found {starred} and {others}",
+				config.min_starred.max(1)
+			)));
+		}
+
+		let mut words: HashMap<String, [u64; 2]> = HashMap::new();
+		let mut totals = [0u64; 2];
+		for stored in articles {
+			let class = usize::from(liked(stored));
+			for word in suggest::tokenize(&stored.article.searchable_text()) {
+				let counts = words.entry(word).or_default();
+				if let (Some(count), Some(total)) = (counts.get_mut(class), totals.get_mut(class)) {
+					*count = count.saturating_add(1);
+					*total = total.saturating_add(1);
+				}
+			}
+		}
+		Ok(Self {
+			trained_at: Utc::now(),
+			starred,
+			others,
+			words,
+			totals,
+		})
+	}
+
+	/// # Errors
+	///
+	/// `IoError` when the file cannot be read, `ParseError` when it is not a saved model
+	pub fn load(path: &Path) -> Result<Self> {
+		let json = fs::read(path).map_err(|e| AppError::io_error(path, e))?;
+		serde_json::from_slice(&json).map_err(|e| AppError::parse_error(path.display().to_string(), e))
+	}
+
+	/// # Errors
+	///
+	/// `IoError` when the file cannot be written
+	pub fn save(&self, path: &Path) -> Result<()> {
+		if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+			fs::create_dir_all(parent).map_err(|e| AppError::io_error(parent, e))?;
+		}
+		let json = serde_json::to_vec(self).map_err(|e| AppError::parse_error("interest model", e))?;
+		fs::write(path, json).map_err(|e| AppError::io_error(path, e))
+	}
+
+	#[must_use]
+	pub const fn starred(&self) -> usize {
+		self.starred
+	}
+
+	#[must_use]
+	pub const fn others(&self) -> usize {
+		self.others
+	}
+
+	#[must_use]
+	pub fn vocabulary(&self) -> usize {
+		self.words.len()
+	}
+
+	/// Mean log-likelihood ratio of the article's known words, squashed into -1..1 by `tanh`.
+	/// Class priors are left out, so the rarity of starring does not push every article down,
+	/// and averaging keeps long descriptions from outweighing titles.
+	#[must_use]
+	pub fn signal(&self, text: &str) -> f64 {
+		let vocabulary = len_as_f64(self.words.len());
+		let [other_total, starred_total] = self.totals.map(|total| u64_as_f64(total) + vocabulary);
+		let words: HashSet<String> = suggest::tokenize(text).collect();
+		let ratios: Vec<f64> = words
+			.iter()
+			.filter_map(|word| self.words.get(word))
+			.map(|&[other, starred]| {
+				((u64_as_f64(starred) + 1.0) / starred_total).ln() - ((u64_as_f64(other) + 1.0) / other_total).ln()
+			})
+			.collect();
+		if ratios.is_empty() {
+			return 0.0;
+		}
+		(ratios.iter().sum::<f64>() / len_as_f64(ratios.len())).tanh()
+	}
+}
+
+/// Blend the learned component into every score; without a trained model the scores are left alone
+pub fn apply(config: &LearningConfig, articles: &mut [ScoredArticle]) {
+	let model = match InterestModel::load(Path::new(&config.model_path)) {
+		Ok(model) => model,
+		Err(e) => {
+			warn!(error = %e, "No interest model to score with, run `retrain` first");
+			return;
+		}
+	};
+	articles.par_iter_mut().for_each(|scored| {
+		let signal = model.signal(&scored.article().searchable_text());
+		scored.blend_learned(signal, config.weight);
+	});
+	info!(
+		trained_at = %model.trained_at,
+		starred = model.starred,
+		"Blended the learned interest score"
+	);
+}
+
+#[allow(clippy::cast_precision_loss)]
+const fn len_as_f64(len: usize) -> f64 {
+	len as f64
+}
+
+#[allow(clippy::cast_precision_loss)]
+const fn u64_as_f64(value: u64) -> f64 {
+	value as f64
+}
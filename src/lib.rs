@@ -0,0 +1,69 @@
+pub mod analyzer;
+pub mod auth;
+pub mod bench;
+pub mod cache;
+pub mod classifier;
+pub mod cli;
+pub mod compression;
+pub mod concurrency;
+pub mod config;
+pub mod content;
+pub mod daemon;
+pub mod discussion;
+pub mod display;
+pub mod diversity;
+pub mod dns;
+pub mod entities;
+pub mod error;
+pub mod extract;
+pub mod feed;
+pub mod fetcher;
+pub mod filter;
+pub mod fixtures;
+pub mod formula;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod history;
+pub mod language;
+#[cfg(feature = "learning")]
+pub mod learning;
+pub mod link_check;
+#[cfg(feature = "llm")]
+pub mod llm;
+pub mod logging;
+pub mod metadata;
+pub mod metrics;
+pub mod model;
+pub mod newsapi;
+#[cfg(feature = "ollama")]
+pub mod ollama;
+pub mod paywall;
+pub mod pipeline;
+pub mod producthunt;
+pub mod rate_limiter;
+pub mod releases;
+pub mod report;
+pub mod retry;
+pub mod run;
+pub mod runtime;
+pub mod sanitize;
+pub mod scrape;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod secret;
+#[cfg(feature = "semantic")]
+pub mod semantic;
+pub mod server;
+pub mod service;
+pub mod shutdown;
+pub mod sink;
+pub mod site;
+pub mod sitemap;
+pub mod store;
+pub mod suggest;
+pub mod summarizer;
+pub mod systemd;
+pub mod thumbnails;
+pub mod tls;
+pub mod wayback;
+pub mod wikipedia;
@@ -0,0 +1,125 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::{DeadLinkAction, LinkCheckConfig};
+use crate::metrics::Metrics;
+use futures::stream::{self, StreamExt};
+use reqwest::{Client, Response, StatusCode};
+use std::error::Error as _;
+use std::time::Duration;
+use tokio::time::timeout;
+use tracing::{debug, info};
+
+/// What checking a link found
+enum LinkStatus {
+	Alive,
+	/// 404 or 410, or a host that does not resolve, with the reason recorded as `dead_link`
+	Dead(String),
+	/// Timeouts, refused connections and other statuses, which may pass; such links are kept
+	Unknown,
+}
+
+impl LinkStatus {
+	fn outcome(&self) -> &str {
+		match self {
+			Self::Alive => "alive",
+			Self::Dead(reason) => reason,
+			Self::Unknown => "unknown",
+		}
+	}
+}
+
+/// Check the links of the top `top_n` articles with a HEAD request, falling back to GET for servers that
+/// refuse HEAD, then drop or flag the dead ones per `action`. Every outcome is counted in the metrics.
+pub async fn check_links(
+	client: &Client,
+	config: &LinkCheckConfig,
+	metrics: &Metrics,
+	scored: &mut Vec<ScoredArticle>,
+) {
+	let count = config.top_n.min(scored.len());
+	let Some(top) = scored.get(..count) else {
+		return;
+	};
+	let urls: Vec<String> = top.iter().map(|item| item.article().url().to_string()).collect();
+	let limit = Duration::from_secs(config.timeout_secs);
+	let statuses: Vec<LinkStatus> = stream::iter(urls)
+		.map(|url| async move { check(client, &url, limit).await })
+		.buffered(config.concurrency)
+		.collect()
+		.await;
+
+	let mut dead = Vec::new();
+	for (index, status) in statuses.into_iter().enumerate() {
+		metrics.record_link_check(status.outcome());
+		if let LinkStatus::Dead(reason) = status {
+			dead.push((index, reason));
+		}
+	}
+	info!(checked = count, dead = dead.len(), action = ?config.action, "Checked article links");
+	match config.action {
+		DeadLinkAction::Flag => {
+			for (index, reason) in dead {
+				if let Some(item) = scored.get_mut(index) {
+					item.set_dead_link(reason);
+				}
+			}
+		}
+		DeadLinkAction::Drop => {
+			let mut index = 0usize;
+			scored.retain(|_| {
+				let keep = !dead.iter().any(|(dead, _)| *dead == index);
+				index = index.saturating_add(1);
+				keep
+			});
+		}
+	}
+}
+
+async fn check(client: &Client, url: &str, limit: Duration) -> LinkStatus {
+	let mut response = match request(client.head(url), limit).await {
+		Ok(response) => response,
+		Err(status) => return status,
+	};
+	if matches!(
+		response.status(),
+		StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+	) {
+		response = match request(client.get(url), limit).await {
+			Ok(response) => response,
+			Err(status) => return status,
+		};
+	}
+	let status = response.status();
+	if matches!(status, StatusCode::NOT_FOUND | StatusCode::GONE) {
+		LinkStatus::Dead(status.as_u16().to_string())
+	} else if status.is_success() || status.is_redirection() {
+		LinkStatus::Alive
+	} else {
+		debug!(url, %status, "Link check inconclusive");
+		LinkStatus::Unknown
+	}
+}
+
+/// Send a link check request; a failure is already the link's status
+async fn request(request: reqwest::RequestBuilder, limit: Duration) -> Result<Response, LinkStatus> {
+	match timeout(limit, request.send()).await {
+		Ok(Ok(response)) => Ok(response),
+		Ok(Err(e)) if is_dns_failure(&e) => Err(LinkStatus::Dead("dns".into())),
+		Ok(Err(e)) => {
+			debug!(error = %e, "Link check failed");
+			Err(LinkStatus::Unknown)
+		}
+		Err(_) => Err(LinkStatus::Unknown),
+	}
+}
+
+/// Whether the request failed resolving the host; reqwest only tells through the chain of error messages
+fn is_dns_failure(error: &reqwest::Error) -> bool {
+	let mut source = error.source();
+	while let Some(cause) = source {
+		if cause.to_string().contains("dns error") {
+			return true;
+		}
+		source = cause.source();
+	}
+	false
+}
@@ -0,0 +1,233 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::LlmConfig;
+use crate::error::{AppError, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::time::Duration;
+use tokio::time::timeout;
+use tracing::{info, warn};
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+	model: &'a str,
+	messages: Vec<ChatMessage<'a>>,
+	max_tokens: u32,
+	temperature: f32,
+}
+
+#[derive(Serialize)]
+struct ChatMessage<'a> {
+	role: &'a str,
+	content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+	choices: Vec<ChatChoice>,
+	#[serde(default)]
+	usage: Option<ChatUsage>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+	message: ChatReply,
+}
+
+#[derive(Deserialize)]
+struct ChatReply {
+	content: String,
+}
+
+#[derive(Deserialize)]
+struct ChatUsage {
+	total_tokens: u32,
+}
+
+/// Client for an OpenAI-compatible chat completions endpoint with a per-run token budget
+pub struct LlmClient<'a> {
+	client: &'a Client,
+	config: &'a LlmConfig,
+	api_key: Option<String>,
+	tokens_used: u32,
+}
+
+impl<'a> LlmClient<'a> {
+	#[must_use]
+	pub fn new(client: &'a Client, config: &'a LlmConfig) -> Self {
+		let api_key = config.api_key.as_ref().and_then(|secret| {
+			secret
+				.resolve()
+				.map_err(|e| warn!(error = %e, "LLM API key unavailable, sending unauthenticated requests"))
+				.ok()
+		});
+		Self {
+			client,
+			config,
+			api_key,
+			tokens_used: 0,
+		}
+	}
+
+	const fn budget_exhausted(&self) -> bool {
+		self.tokens_used >= self.config.max_total_tokens
+	}
+
+	async fn complete(&mut self, system: &str, prompt: &str) -> Result<String> {
+		if self.budget_exhausted() {
+			return Err(AppError::LlmError(format!(
+				"token budget of {} exhausted",
+				self.config.max_total_tokens
+			)));
+		}
+
+		let url = format!("{}/chat/completions", self.config.base_url.trim_end_matches('/'));
+		let request = ChatRequest {
+			model: &self.config.model,
+			messages: vec![
+				ChatMessage {
+					role: "system",
+					content: system,
+				},
+				ChatMessage {
+					role: "user",
+					content: prompt,
+				},
+			],
+			max_tokens: self.config.max_tokens,
+			temperature: 0.0,
+		};
+
+		let mut builder = self.client.post(&url).json(&request);
+		if let Some(key) = &self.api_key {
+			builder = builder.bearer_auth(key);
+		}
+
+		let response = timeout(Duration::from_secs(self.config.timeout_secs), builder.send())
+			.await
+			.map_err(|_| AppError::TimeoutError(url.clone()))?
+			.and_then(reqwest::Response::error_for_status)
+			.map_err(|e| AppError::http_error(&url, e))?
+			.json::<ChatResponse>()
+			.await
+			.map_err(|e| AppError::parse_error("LLM response", e))?;
+
+		// Servers that omit usage are charged the full per-request allowance
+		let spent = response
+			.usage
+			.map_or(self.config.max_tokens, |usage| usage.total_tokens);
+		self.tokens_used = self.tokens_used.saturating_add(spent);
+
+		response
+			.choices
+			.into_iter()
+			.next()
+			.map(|choice| choice.message.content.trim().to_string())
+			.ok_or_else(|| AppError::LlmError("response contained no choices".into()))
+	}
+
+	/// Reorder the leading `max_articles` articles by how well they fit the interest profile
+	async fn rerank(&mut self, articles: &mut [ScoredArticle]) -> Result<()> {
+		let count = self.config.max_articles.min(articles.len());
+		let Some(top) = articles.get_mut(..count) else {
+			return Ok(());
+		};
+		if top.len() < 2 {
+			return Ok(());
+		}
+
+		let mut prompt = format!("Interest profile:\n{}\n\nArticles:\n", self.config.interest_profile);
+		for (index, scored) in top.iter().enumerate() {
+			let _ = writeln!(
+				prompt,
+				"{index}. {} ({})",
+				scored.article().title(),
+				scored.article().source()
+			);
+		}
+		prompt.push_str("\nReply with only a JSON array of the article numbers, most relevant first.");
+
+		let reply = self
+			.complete("You rank news articles by relevance to a reader's interests.", &prompt)
+			.await?;
+		let order = parse_order(&reply, top.len())?;
+
+		let reordered: Vec<ScoredArticle> = order.iter().filter_map(|&index| top.get(index).cloned()).collect();
+		if reordered.len() != top.len() {
+			return Err(AppError::LlmError("re-ranking lost articles".into()));
+		}
+		for (slot, scored) in top.iter_mut().zip(reordered) {
+			*slot = scored;
+		}
+		Ok(())
+	}
+
+	/// Replace the summaries of the leading `max_articles` articles, stopping when the budget runs out
+	async fn summarize(&mut self, articles: &mut [ScoredArticle]) {
+		let count = self.config.max_articles.min(articles.len());
+		for scored in articles.iter_mut().take(count) {
+			if self.budget_exhausted() {
+				warn!(
+					tokens_used = self.tokens_used,
+					"LLM token budget exhausted, skipping remaining summaries"
+				);
+				break;
+			}
+
+			let article = scored.article();
+			let prompt = format!(
+				"Title: {}\nURL: {}\n{}\n\nSummarize this article in two or three sentences for the reader profile:\n{}",
+				article.title(),
+				article.url(),
+				article.description().unwrap_or_default(),
+				self.config.interest_profile
+			);
+			match self.complete("You write short, factual news summaries.", &prompt).await {
+				Ok(summary) if !summary.is_empty() => scored.set_summary(summary),
+				Ok(_) => {}
+				Err(e) => warn!(url = scored.article().url(), error = %e, "LLM summary failed"),
+			}
+		}
+	}
+}
+
+/// Parse a JSON array of indices, keeping each valid index once and appending any the model skipped
+fn parse_order(reply: &str, len: usize) -> Result<Vec<usize>> {
+	let start = reply.find('[').unwrap_or(0);
+	let end = reply.rfind(']').map_or(reply.len(), |end| end.saturating_add(1));
+	let json = reply.get(start..end).unwrap_or(reply);
+	let indices: Vec<usize> = serde_json::from_str(json).map_err(|e| AppError::parse_error("LLM ranking", e))?;
+
+	let mut order: Vec<usize> = Vec::with_capacity(len);
+	for index in indices {
+		if index < len && !order.contains(&index) {
+			order.push(index);
+		}
+	}
+	for index in 0..len {
+		if !order.contains(&index) {
+			order.push(index);
+		}
+	}
+	Ok(order)
+}
+
+/// Run the configured LLM passes over the ranked articles; failures leave the keyword ranking intact
+pub async fn enhance(client: &Client, config: &LlmConfig, articles: &mut [ScoredArticle]) {
+	let mut llm = LlmClient::new(client, config);
+
+	if config.rerank {
+		match llm.rerank(articles).await {
+			Ok(()) => info!(
+				count = config.max_articles.min(articles.len()),
+				"LLM re-ranked top articles"
+			),
+			Err(e) => warn!(error = %e, "LLM re-ranking failed, keeping keyword ranking"),
+		}
+	}
+	if config.summarize {
+		llm.summarize(articles).await;
+	}
+
+	info!(tokens_used = llm.tokens_used, "LLM pass finished");
+}
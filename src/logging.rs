@@ -0,0 +1,63 @@
+use crate::config::{LogConfig, LogFormat, LogRotation};
+use crate::error::{AppError, Result};
+use crate::service;
+use std::path::Path;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+
+/// Install the global subscriber. `RUST_LOG` still selects the level.
+///
+/// With `log.event_log` set, lines go to the Windows Event Log instead.
+/// With `log.file` set, the returned guard must be kept alive so buffered lines are flushed on exit.
+///
+/// # Errors
+///
+/// `IoError` when the log directory cannot be created, `ConfigError` when the Event Log is unavailable or a
+/// subscriber is already installed
+pub fn init(config: &LogConfig) -> Result<Option<WorkerGuard>> {
+	let (writer, guard) = match &config.file {
+		_ if config.event_log => (service::event_log()?, None),
+		Some(file) => {
+			let path = Path::new(file);
+			let directory = path
+				.parent()
+				.filter(|p| !p.as_os_str().is_empty())
+				.unwrap_or_else(|| Path::new("."));
+			let file_name = path
+				.file_name()
+				.ok_or_else(|| AppError::ConfigError(format!("log.file {file} has no file name")))?;
+			let appender = RollingFileAppender::builder()
+				.rotation(rotation(config.rotation))
+				.filename_prefix(file_name.to_string_lossy())
+				.build(directory)
+				.map_err(|e| AppError::io_error(directory, e))?;
+			let (writer, guard) = tracing_appender::non_blocking(appender);
+			(BoxMakeWriter::new(writer), Some(guard))
+		}
+		None => (BoxMakeWriter::new(std::io::stdout), None),
+	};
+
+	let builder = tracing_subscriber::fmt()
+		.with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+		.with_target(false)
+		.with_thread_ids(true)
+		.with_ansi(config.file.is_none() && !config.event_log)
+		.with_writer(writer);
+	let result = match config.format {
+		LogFormat::Json => builder.json().try_init(),
+		LogFormat::Pretty => builder.pretty().try_init(),
+		LogFormat::Compact => builder.compact().try_init(),
+	};
+	result.map_err(|e| AppError::ConfigError(format!("cannot initialize logging: {e}")))?;
+	Ok(guard)
+}
+
+const fn rotation(rotation: LogRotation) -> Rotation {
+	match rotation {
+		LogRotation::Never => Rotation::NEVER,
+		LogRotation::Hourly => Rotation::HOURLY,
+		LogRotation::Daily => Rotation::DAILY,
+	}
+}
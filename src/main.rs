@@ -1,106 +1,99 @@
-// Allow non snake case
-#![allow(non_snake_case)]
-// === PANIC PREVENTION ===
-#![deny(clippy::unwrap_used)]
-#![deny(clippy::expect_used)]
-#![deny(clippy::panic)]
-#![deny(clippy::unreachable)]
-#![deny(clippy::unimplemented)]
-#![deny(clippy::todo)]
-#![deny(clippy::indexing_slicing)]
-#![deny(clippy::missing_panics_doc)]
-// === ARITHMETIC SAFETY ===
-#![deny(clippy::arithmetic_side_effects)]
-#![deny(clippy::integer_division)]
-#![deny(clippy::cast_possible_truncation)]
-#![deny(clippy::cast_possible_wrap)]
-#![deny(clippy::cast_sign_loss)]
-#![deny(clippy::cast_precision_loss)]
-// === MEMORY SAFETY ===
-#![deny(clippy::mem_forget)]
-#![deny(clippy::large_stack_arrays)]
-#![deny(clippy::fn_to_numeric_cast_any)]
-// === CODE QUALITY ===
-#![warn(clippy::all)]
-#![warn(clippy::pedantic)]
-#![warn(clippy::nursery)]
-mod analyzer;
-mod config;
-mod error;
-mod fetcher;
-mod metrics;
-mod model;
-mod rate_limiter;
-
-use crate::analyzer::ScoredArticle;
-use crate::config::Config;
-use crate::error::{AppError, Result};
-use crate::fetcher::Fetcher;
-use crate::metrics::Metrics;
+use chrono::Utc;
+use clap::Parser;
+use news_aggregator::analyzer::ScoredArticle;
+use news_aggregator::cli::{Cli, Command, ConfigAction, Stage};
+use news_aggregator::config::{Config, ConfigHandle, LogConfig, S3Format, StoreBackend, StoreConfig};
+use news_aggregator::daemon::{Handles, Health, Ranking, Rankings, Trigger};
+use news_aggregator::error::{AppError, Result};
+use news_aggregator::fetcher::Fetcher;
+use news_aggregator::metrics::Metrics;
+use news_aggregator::model::Article;
+use news_aggregator::pipeline::{Pipeline, ScorerCache};
+use news_aggregator::report::RunReport;
+use news_aggregator::run::RunContext;
+use news_aggregator::shutdown::Shutdown;
+use news_aggregator::sink::{S3Sink, Sink};
+use news_aggregator::store::Store;
+use news_aggregator::{
+	analyzer, auth, bench, config, daemon, dns, fetcher, logging, pipeline, runtime, server, service, sink, store,
+	suggest, systemd, tls,
+};
 use reqwest::Client;
-use tokio::signal;
-use tokio_util::sync::CancellationToken;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
 use tracing::{error, info, warn};
 
-#[tokio::main(flavor = "multi_thread")]
-async fn main() -> Result<()> {
-	tracing_subscriber::fmt()
-		.with_env_filter(
-			tracing_subscriber::EnvFilter::try_from_default_env()
-				.unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
-		)
-		.with_target(false)
-		.with_thread_ids(true)
-		.json()
-		.init();
+fn main() -> ExitCode {
+	match start() {
+		Ok(()) => ExitCode::SUCCESS,
+		Err(e) => {
+			eprintln!("Error: {e:?}");
+			ExitCode::from(e.exit_code())
+		}
+	}
+}
 
-	info!("Starting article aggregator");
+/// Load the configuration, then run on a runtime built from its `[runtime]` settings
+fn start() -> Result<()> {
+	let cli = Cli::parse();
+	let service = matches!(cli.command, Some(Command::Daemon { service: true, .. }));
+	if service {
+		service::enter_install_dir()?;
+	}
 
-	// Load and validate configuration explicitly
-	let config = Config::load()?;
+	// `config init` has to work before any configuration exists
+	if let Some(Command::Config {
+		action: ConfigAction::Init { path, force },
+	}) = &cli.command
+	{
+		let log = LogConfig {
+			format: cli.log_format.unwrap_or_default(),
+			..LogConfig::default()
+		};
+		let _log_guard = logging::init(&log)?;
+		config::write_default(path, *force)?;
+		info!(path = %path.display(), "Wrote default configuration");
+		return Ok(());
+	}
+
+	let config = Arc::new(load_config(&cli, cli.profile.as_deref())?);
+	runtime::build(&config.runtime)?.block_on(run(&cli, config))
+}
+
+async fn run(cli: &Cli, config: Arc<Config>) -> Result<()> {
+	let _log_guard = logging::init(&config.log)?;
+	info!("Starting article aggregator");
 
 	info!(
 		timeout_secs = config.http.timeout_secs,
 		max_concurrent = config.fetcher.max_concurrent_requests,
 		rate_limit = config.rate_limit.requests_per_second,
 		rayon_threads = config.analyzer.rayon_threads,
+		worker_threads = tokio::runtime::Handle::current().metrics().num_workers(),
+		max_blocking_threads = config.runtime.max_blocking_threads,
 		"Configuration loaded"
 	);
 
 	analyzer::init_rayon_pool(config.analyzer.rayon_threads)
 		.map_err(|e| AppError::ConfigError(format!("can not init thread pool: {e}")))?;
 
-	let client = Client::builder()
-		.timeout(config.timeout())
-		.pool_max_idle_per_host(config.http.pool_max_idle_per_host)
-		.build()
-		.map_err(|e| AppError::ConfigError(format!("Failed to build HTTP client: {e}")))?;
-
-	let cancel_token = CancellationToken::new();
-	let cancel_clone = cancel_token.clone();
+	let client = http_client(&config)?;
 
-	tokio::spawn(async move {
-		match signal::ctrl_c().await {
-			Ok(()) => {
-				info!("Shutdown signal received");
-				cancel_clone.cancel();
-			}
-			Err(err) => {
-				error!(error = %err, "Failed to listen for shutdown signal");
-			}
-		}
-	});
+	let shutdown = Shutdown::new();
+	shutdown.listen(config.shutdown.grace_period())?;
 
 	let metrics = Metrics::new();
-	let fetcher = Fetcher::new(client, cancel_token.clone(), metrics.clone(), &config);
+	let result = execute(cli, &config, &client, &shutdown, &metrics).await;
 
-	match run_aggregator(fetcher, &config).await {
-		Ok(scored) => {
-			display_results(&scored);
+	match result {
+		Ok(()) => {
 			metrics.log_summary();
 			Ok(())
 		}
-		Err(e) if matches!(e, AppError::ShutdownError) => {
+		Err(AppError::ShutdownError) => {
 			warn!("Gracefully shutting down");
 			metrics.log_summary();
 			Ok(())
@@ -113,39 +106,744 @@ async fn main() -> Result<()> {
 	}
 }
 
-async fn run_aggregator(fetcher: Fetcher, config: &Config) -> Result<Vec<ScoredArticle>> {
-	let articles = fetcher.fetch_all().await?;
+/// The HTTP client shared by every request, tuned by `[http]`
+fn http_client(config: &Config) -> Result<Client> {
+	let http = &config.http;
+	let mut builder = Client::builder()
+		.timeout(config.timeout())
+		.pool_max_idle_per_host(http.pool_max_idle_per_host)
+		.cookie_store(http.cookie_store)
+		.user_agent(http.user_agent())
+		.default_headers(auth::header_map(&http.headers, None))
+		.http2_adaptive_window(http.http2_adaptive_window)
+		.tcp_keepalive(http.tcp_keepalive_secs.map(Duration::from_secs));
+	if let Some(secs) = http.pool_idle_timeout_secs {
+		builder = builder.pool_idle_timeout(Duration::from_secs(secs));
+	}
+	if let Some(secs) = http.http2_keep_alive_interval_secs {
+		builder = builder
+			.http2_keep_alive_interval(Duration::from_secs(secs))
+			.http2_keep_alive_while_idle(true);
+	}
+	if let Some(secs) = http.http2_keep_alive_timeout_secs {
+		builder = builder.http2_keep_alive_timeout(Duration::from_secs(secs));
+	}
+	builder = tls::configure(builder, &http.tls)?;
+	dns::configure(builder, &config.dns)?
+		.build()
+		.map_err(|e| AppError::ConfigError(format!("Failed to build HTTP client: {e}")))
+}
 
-	if articles.is_empty() {
-		warn!("No articles fetched from any source");
-		return Ok(Vec::new());
+/// Build the per-run components and run the requested command
+async fn execute(
+	cli: &Cli,
+	config: &Arc<Config>,
+	client: &Client,
+	shutdown: &Shutdown,
+	metrics: &Metrics,
+) -> Result<()> {
+	let run = RunContext::new();
+	info!(run_id = run.id(), "Run started");
+
+	let sinks = sink::from_config(config, client);
+	let fetcher = Arc::new(Fetcher::new(
+		client.clone(),
+		shutdown.abort().clone(),
+		metrics.clone(),
+		Arc::clone(config),
+	));
+	// Daemon cycles keep using the scorers compiled here while the scoring settings stay the same
+	let scorers = ScorerCache::default();
+	let pipeline = Pipeline::new(config, client, shutdown.force(), metrics, &scorers)?;
+
+	if config.delivery.dry_run {
+		info!("Dry run: nothing is delivered or stored");
 	}
+	let result = match &cli.command {
+		Some(_) if cli.all_profiles => {
+			return Err(AppError::ConfigError(
+				"--all-profiles only applies to a plain run without a subcommand".into(),
+			));
+		}
+		// Every daemon cycle is a run of its own with its own report
+		Some(Command::Daemon { .. }) => return run_daemon(cli, config, client, shutdown, metrics, scorers).await,
+		Some(Command::Config { action }) => {
+			return match action {
+				ConfigAction::Validate => validate_config(config, &fetcher).await,
+				// Handled before the configuration is loaded
+				ConfigAction::Init { .. } => Ok(()),
+			};
+		}
+		// Only a run opens the store up front; the store subcommands open it themselves, and
+		// `open_existing_store` refuses to create one for those that need stored articles
+		None => {
+			let mut store = run_store(config)?;
+			let result = if cli.all_profiles {
+				run_all_profiles(cli, client, shutdown, metrics, &fetcher, store.as_mut(), &run).await
+			} else {
+				run_aggregator(&pipeline, &fetcher, store.as_mut(), &run, &sinks, None)
+					.await
+					.map(drop)
+			};
+			if let (Ok(()), Some(store)) = (&result, store.as_mut()) {
+				prune_store(store, &config.store);
+			}
+			result
+		}
+		Some(Command::Rescore) => rescore(&pipeline, &config.store, &run).await,
+		Some(Command::Purge { .. }) => purge(&config.store),
+		Some(Command::Star { url, remove }) => star(&config.store, url, !remove),
+		Some(Command::Export { path }) => export(&config.store, path),
+		Some(Command::Retrain) => retrain(config),
+		Some(Command::Bench {
+			articles,
+			iterations,
+			stored,
+			words,
+			seed,
+		}) => benchmark(config, *articles, *iterations, *stored, *words, *seed),
+		Some(Command::SuggestKeywords {
+			top,
+			min_articles,
+			examples,
+		}) => suggest_keywords(config, *top, *min_articles, *examples),
+		Some(Command::Import { path }) => import(&config.store, path),
+		Some(Command::Pipeline { stage }) => match stage {
+			Stage::Fetch { save } => fetch_stage(&fetcher, save.as_deref(), &run).await,
+			Stage::Score { input } => score_stage(&pipeline, input, &run, &sinks).await,
+		},
+	};
+	write_report(config, client, &run, &result).await;
+	result
+}
 
-	info!(count = articles.len(), "Fetched articles successfully");
+/// The store of a run, when `[store]` is enabled; a dry run neither reads nor writes it
+fn run_store(config: &Config) -> Result<Option<Store>> {
+	if config.store.enabled && !config.delivery.dry_run {
+		store::open(&config.store).map(Some)
+	} else {
+		Ok(None)
+	}
+}
 
-	let mut scored = analyzer::score_articles(articles, &config.keywords.values)?;
+async fn run_daemon(
+	cli: &Cli,
+	config: &Arc<Config>,
+	client: &Client,
+	shutdown: &Shutdown,
+	metrics: &Metrics,
+	scorers: ScorerCache,
+) -> Result<()> {
+	let handle = ConfigHandle::new(Arc::clone(config));
+	let health = Health::default();
+	let trigger = Trigger::default();
+	let rankings = Rankings::default();
+	let reload_cli = cli.clone();
+	let reload = daemon::spawn_reloader(handle.clone(), shutdown.requested().clone(), move || {
+		load_config(&reload_cli, reload_cli.profile.as_deref())
+	})?;
+	if config.server.enabled {
+		let daemon = Handles {
+			health: health.clone(),
+			trigger: trigger.clone(),
+			rankings: rankings.clone(),
+			reload,
+		};
+		server::spawn(
+			&config.server,
+			metrics.clone(),
+			daemon,
+			handle.clone(),
+			shutdown.requested().clone(),
+			(config.clicks.enabled || !config.users.is_empty())
+				.then(|| store::open(&config.store))
+				.transpose()?,
+		)
+		.await?;
+	}
+	if config.grpc.enabled {
+		spawn_grpc(&handle, &health, &trigger, &rankings, shutdown).await?;
+	}
+	systemd::spawn(&health, &handle, shutdown.requested());
+	let service = match cli.command {
+		Some(Command::Daemon { service: true, .. }) => Some(service::start(shutdown)?),
+		_ => None,
+	};
+	let cycles = Cycles {
+		cli,
+		client,
+		shutdown,
+		metrics,
+		latest: LatestArticles::default(),
+		rankings,
+		scorers,
+	};
+	let result = daemon::run(&handle, &health, &trigger, shutdown.requested(), |config, sources| {
+		run_cycle(config, sources, &cycles)
+	})
+	.await;
+	if let Some(service) = service {
+		service.stopped(&result);
+	}
+	result
+}
+
+#[cfg(feature = "grpc")]
+async fn spawn_grpc(
+	handle: &ConfigHandle,
+	health: &Health,
+	trigger: &Trigger,
+	rankings: &Rankings,
+	shutdown: &Shutdown,
+) -> Result<()> {
+	news_aggregator::grpc::spawn(
+		handle.clone(),
+		health.clone(),
+		trigger.clone(),
+		rankings.clone(),
+		shutdown.requested().clone(),
+	)
+	.await
+}
+
+#[cfg(not(feature = "grpc"))]
+#[allow(clippy::unused_async)]
+async fn spawn_grpc(
+	_handle: &ConfigHandle,
+	_health: &Health,
+	_trigger: &Trigger,
+	_rankings: &Rankings,
+	_shutdown: &Shutdown,
+) -> Result<()> {
+	warn!("grpc.enabled is set but the binary was built without the `grpc` feature");
+	Ok(())
+}
+
+/// The most recent articles of each source, kept between daemon cycles that fetch only the sources due
+type LatestArticles = Mutex<HashMap<String, Vec<Article>>>;
+
+/// What every daemon cycle runs with
+struct Cycles<'a> {
+	/// To load the profiles of the users with
+	cli: &'a Cli,
+	client: &'a Client,
+	shutdown: &'a Shutdown,
+	metrics: &'a Metrics,
+	latest: LatestArticles,
+	rankings: Rankings,
+	scorers: ScorerCache,
+}
+
+/// Write the run report under `[report]`, and upload it when `s3.formats` has `report` unless this is a dry run
+async fn write_report(config: &Config, client: &Client, run: &RunContext, result: &Result<()>) {
+	let upload = config.s3.enabled && config.s3.formats.contains(&S3Format::Report) && !config.delivery.dry_run;
+	if !config.report.enabled && !upload {
+		return;
+	}
+	let report = RunReport::new(run, config, result);
+	if config.report.enabled {
+		match report.write(&config.report) {
+			Ok(path) => info!(path = %path.display(), "Wrote run report"),
+			Err(e) => warn!(error = %e, "Failed to write run report"),
+		}
+	}
+	if upload {
+		let sink = S3Sink::new(client.clone(), &config.s3, config.profile.as_deref());
+		match sink.upload_report(run, &report).await {
+			Ok(key) => info!(key, "Uploaded run report"),
+			Err(e) => warn!(error = %e, "Failed to upload run report"),
+		}
+	}
+}
+
+/// Report the already validated configuration, resolve its secrets and probe every source once
+async fn validate_config(config: &Config, fetcher: &Fetcher) -> Result<()> {
+	info!(
+		profile = config.profile.as_deref().unwrap_or("default"),
+		keywords = config.keywords.values.len(),
+		categories = config.categories.len(),
+		"Configuration is valid"
+	);
+	let mut missing = 0usize;
+	for (setting, secret) in config.secrets() {
+		if let Err(e) = secret.resolve() {
+			warn!(setting, error = %e, "Secret unavailable");
+			missing = missing.saturating_add(1);
+		}
+	}
+	let mut unreachable = 0usize;
+	for (source, result) in fetcher.check_sources().await {
+		match result {
+			Ok(()) => info!(source, "Source reachable"),
+			Err(e) => {
+				warn!(source, error = %e, "Source unreachable");
+				unreachable = unreachable.saturating_add(1);
+			}
+		}
+	}
+	if missing > 0 || unreachable > 0 {
+		return Err(AppError::ConfigError(format!(
+			"{missing} secret(s) unavailable, {unreachable} source(s) unreachable"
+		)));
+	}
+	Ok(())
+}
 
-	// Filter out NaN scores and sort
-	scored.retain(|article| article.relevance_score().is_finite());
-	scored.sort_by(|a, b| {
-		b.relevance_score()
-			.partial_cmp(&a.relevance_score())
-			.unwrap_or(std::cmp::Ordering::Equal)
+/// Load and validate configuration explicitly, then layer CLI overrides on top
+fn load_config(cli: &Cli, profile: Option<&str>) -> Result<Config> {
+	let mut config = Config::load(profile)?;
+	cli.apply(&mut config);
+	config.validate()?;
+	Ok(config)
+}
+
+/// One daemon cycle: the full pipeline built from a configuration snapshot, over every source
+/// or, on a per-source schedule, over the named sources due. The ranking is published to `rankings`,
+/// followed by that of each profile the users rank by.
+async fn run_cycle(config: Arc<Config>, sources: Option<HashSet<String>>, cycles: &Cycles<'_>) -> Result<()> {
+	let Cycles {
+		client,
+		shutdown,
+		metrics,
+		..
+	} = *cycles;
+	let run = RunContext::new();
+	info!(run_id = run.id(), "Run started");
+	let sinks = sink::from_config(&config, client);
+	let fetcher = Fetcher::new(
+		client.clone(),
+		shutdown.abort().clone(),
+		metrics.clone(),
+		Arc::clone(&config),
+	);
+	let pipeline = Pipeline::new(&config, client, shutdown.force(), metrics, &cycles.scorers)?;
+	let mut store = run_store(&config)?;
+	let mut collected = (!config.users.is_empty()).then(Vec::new);
+	let result = match sources {
+		Some(sources) => {
+			let fetcher = Arc::new(fetcher.only(sources));
+			let result = run_scheduled(
+				&config,
+				&pipeline,
+				&fetcher,
+				store.as_mut(),
+				&cycles.latest,
+				&run,
+				&sinks,
+			)
+			.await;
+			if let Some(collected) = collected.as_mut() {
+				let latest = cycles.latest.lock().unwrap_or_else(PoisonError::into_inner);
+				collected.extend(latest.values().flatten().cloned());
+			}
+			result
+		}
+		None => {
+			run_aggregator(
+				&pipeline,
+				&Arc::new(fetcher),
+				store.as_mut(),
+				&run,
+				&sinks,
+				collected.as_mut(),
+			)
+			.await
+		}
+	};
+	let result = result.map(|scored| {
+		cycles.rankings.publish(Ranking {
+			run_id: run.id().to_string(),
+			displayed: config.display.limit.min(scored.len()),
+			articles: scored,
+		});
 	});
+	let result = match (result, collected) {
+		(Ok(()), Some(articles)) => run_users(&config, cycles, articles, store.as_mut(), &run).await,
+		(result, _) => result,
+	};
+	if let (Ok(()), Some(store)) = (&result, store.as_mut()) {
+		prune_store(store, &config.store);
+	}
+	write_report(&config, client, &run, &result).await;
+	result
+}
 
+/// Scheduled daemon cycle: fetch the sources due, then rank their articles together with
+/// the latest articles of every other source
+async fn run_scheduled(
+	config: &Config,
+	pipeline: &Pipeline<'_>,
+	fetcher: &Arc<Fetcher>,
+	store: Option<&mut Store>,
+	latest: &LatestArticles,
+	run: &RunContext,
+	sinks: &[Box<dyn Sink>],
+) -> Result<Vec<ScoredArticle>> {
+	let due = match Pipeline::fetch(fetcher, run).await {
+		Ok(articles) => articles,
+		Err(AppError::NoArticlesError(_)) => {
+			warn!("No articles from the sources due, ranking the latest articles of the others");
+			Vec::new()
+		}
+		Err(e) => return Err(e),
+	};
+	let mut store = store;
+	if let Some(store) = store.as_deref_mut() {
+		save_to_store(store, &due, run)?;
+	}
+
+	let articles = {
+		let mut latest = latest.lock().unwrap_or_else(PoisonError::into_inner);
+		let sources: HashSet<&str> = fetcher::source_names(config).collect();
+		latest.retain(|source, _| sources.contains(source.as_str()));
+		// A source that returned articles replaces its previous ones; one that failed keeps them
+		let mut fresh: HashMap<String, Vec<Article>> = HashMap::new();
+		for article in due {
+			fresh.entry(article.source().to_string()).or_default().push(article);
+		}
+		latest.extend(fresh);
+		latest.values().flatten().cloned().collect::<Vec<_>>()
+	};
+	if articles.is_empty() {
+		return Err(AppError::NoArticlesError("all sources".into()));
+	}
+	let mut scored = pipeline.process(run, articles).await?;
+	if let Some(store) = store.as_deref_mut() {
+		pipeline.compare_with_previous(store, run, &mut scored)?;
+	}
+	pipeline.output(run, sinks, store, &scored).await;
+	Ok(scored)
+}
+
+/// Full pipeline: fetch and process concurrently, then output; returns the ranking. The fetched articles
+/// are also collected into `collected`, when given.
+async fn run_aggregator(
+	pipeline: &Pipeline<'_>,
+	fetcher: &Arc<Fetcher>,
+	store: Option<&mut Store>,
+	run: &RunContext,
+	sinks: &[Box<dyn Sink>],
+	collected: Option<&mut Vec<Article>>,
+) -> Result<Vec<ScoredArticle>> {
+	let mut store = store;
+	let mut collected = collected;
+	let mut scored = pipeline
+		.fetch_and_process(fetcher, run, |articles| {
+			if let Some(collected) = collected.as_deref_mut() {
+				collected.extend_from_slice(articles);
+			}
+			store
+				.as_deref_mut()
+				.map_or(Ok(()), |store| save_to_store(store, articles, run))
+		})
+		.await?;
+	if let Some(store) = store.as_deref_mut() {
+		pipeline.compare_with_previous(store, run, &mut scored)?;
+	}
+	pipeline.output(run, sinks, store, &scored).await;
 	Ok(scored)
 }
 
-fn display_results(articles: &[ScoredArticle]) {
-	info!("=== Top Relevant Articles ===");
-	for (i, scored) in articles.iter().take(10).enumerate() {
+/// Share one fetch between every profile, then score, display and deliver each separately
+async fn run_all_profiles(
+	cli: &Cli,
+	client: &Client,
+	shutdown: &Shutdown,
+	metrics: &Metrics,
+	fetcher: &Arc<Fetcher>,
+	store: Option<&mut Store>,
+	run: &RunContext,
+) -> Result<()> {
+	let profiles = Config::profile_names()?;
+	if profiles.is_empty() {
+		return Err(AppError::ConfigError(
+			"--all-profiles needs at least one [profile.<name>] table".into(),
+		));
+	}
+	let articles = Pipeline::fetch(fetcher, run).await?;
+	let mut store = store;
+	if let Some(store) = store.as_deref_mut() {
+		save_to_store(store, &articles, run)?;
+	}
+	let scorers = ScorerCache::default();
+	for profile in &profiles {
+		let config = load_config(cli, Some(profile))?;
+		info!(profile = %profile, "=== Profile ===");
+		let pipeline = Pipeline::new(&config, client, shutdown.force(), metrics, &scorers)?;
+		let sinks = sink::from_config(&config, client);
+		let mut scored = pipeline.process(run, articles.clone()).await?;
+		if let Some(store) = store.as_deref_mut() {
+			pipeline.compare_with_previous(store, run, &mut scored)?;
+		}
+		pipeline.output(run, &sinks, store.as_deref_mut(), &scored).await;
+	}
+	Ok(())
+}
+
+/// Rank the articles of a daemon cycle by each profile the users rank by, delivering to the profile's
+/// sinks and publishing the ranking for the users' API
+async fn run_users(
+	config: &Config,
+	cycles: &Cycles<'_>,
+	articles: Vec<Article>,
+	store: Option<&mut Store>,
+	run: &RunContext,
+) -> Result<()> {
+	let profiles: BTreeSet<&str> = config
+		.users
+		.values()
+		.filter_map(|user| user.profile.as_deref())
+		.collect();
+	let mut store = store;
+	for profile in profiles {
+		let config = load_config(cycles.cli, Some(profile))?;
+		info!(profile, "=== User profile ===");
+		let pipeline = Pipeline::new(
+			&config,
+			cycles.client,
+			cycles.shutdown.force(),
+			cycles.metrics,
+			&cycles.scorers,
+		)?;
+		let sinks = sink::from_config(&config, cycles.client);
+		let mut scored = pipeline.process(run, articles.clone()).await?;
+		if let Some(store) = store.as_deref_mut() {
+			pipeline.compare_with_previous(store, run, &mut scored)?;
+		}
+		pipeline.output(run, &sinks, store.as_deref_mut(), &scored).await;
+		cycles.rankings.publish_profile(
+			profile,
+			Ranking {
+				run_id: run.id().to_string(),
+				displayed: config.display.limit.min(scored.len()),
+				articles: scored,
+			},
+		);
+	}
+	Ok(())
+}
+
+/// End-of-run pruning by `store.retention`; a failure is logged and does not fail the run
+fn prune_store(store: &mut Store, config: &StoreConfig) {
+	if !config.retention.is_limited() {
+		return;
+	}
+	match tokio::task::block_in_place(|| store::apply_retention(store, &config.retention, Utc::now())) {
+		Ok(pruned) if pruned.is_empty() => {}
+		Ok(pruned) => info!(
+			articles = pruned.articles,
+			rankings = pruned.rankings,
+			deliveries = pruned.deliveries,
+			"Pruned the article store"
+		),
+		Err(e) => warn!(error = %e, "Failed to prune the article store"),
+	}
+}
+
+fn save_to_store(store: &mut Store, articles: &[Article], run: &RunContext) -> Result<()> {
+	let saved = tokio::task::block_in_place(|| store.save_articles(articles, run.started_at()))?;
+	info!(saved, "Stored fetched articles");
+	Ok(())
+}
+
+/// Fetch only, writing the raw articles to a file or stdout
+async fn fetch_stage(fetcher: &Arc<Fetcher>, save: Option<&Path>, run: &RunContext) -> Result<()> {
+	let articles = Pipeline::fetch(fetcher, run).await?;
+	if let Some(path) = save {
+		pipeline::save_articles(path, &articles)?;
+		info!(count = articles.len(), path = %path.display(), "Saved raw articles");
+	} else {
+		let json = serde_json::to_string_pretty(&articles).map_err(|e| AppError::parse_error("raw articles", e))?;
+		println!("{json}");
+	}
+	Ok(())
+}
+
+/// Score previously fetched articles without touching the network sources
+async fn score_stage(pipeline: &Pipeline<'_>, input: &Path, run: &RunContext, sinks: &[Box<dyn Sink>]) -> Result<()> {
+	let articles = pipeline::load_articles(input)?;
+	info!(count = articles.len(), path = %input.display(), "Loaded raw articles");
+	let scored = pipeline.process(run, articles).await?;
+	pipeline.output(run, sinks, None, &scored).await;
+	Ok(())
+}
+
+/// Open a store an earlier run created, without creating an empty SQLite file
+fn open_existing_store(config: &StoreConfig) -> Result<Store> {
+	if config.backend == StoreBackend::Sqlite && !Path::new(&config.path).exists() {
+		return Err(AppError::storage_error(format!(
+			"no article store at {}; enable [store] and run the aggregator first",
+			config.path
+		)));
+	}
+	store::open(config)
+}
+
+/// Rank the stored articles again with the current keywords and weights; nothing is fetched or delivered
+async fn rescore(pipeline: &Pipeline<'_>, config: &StoreConfig, run: &RunContext) -> Result<()> {
+	let store = open_existing_store(config)?;
+	let articles = tokio::task::block_in_place(|| store.load_articles())?;
+	info!(count = articles.len(), "Loaded stored articles for rescoring");
+	let scored = pipeline.process(run, articles).await?;
+	pipeline.output(run, &[], None, &scored).await;
+	Ok(())
+}
+
+/// Prune the store now by the retention policy, with the command-line overrides already applied
+fn purge(config: &StoreConfig) -> Result<()> {
+	if !config.retention.is_limited() {
+		return Err(AppError::ConfigError(
+			"nothing to purge by: set store.retention.max_age_days or max_articles, or pass --max-age-days or --max-articles"
+				.into(),
+		));
+	}
+	let mut store = open_existing_store(config)?;
+	let pruned = tokio::task::block_in_place(|| store::apply_retention(&mut store, &config.retention, Utc::now()))?;
+	info!(
+		articles = pruned.articles,
+		rankings = pruned.rankings,
+		deliveries = pruned.deliveries,
+		"Purged the article store"
+	);
+	Ok(())
+}
+
+fn star(config: &StoreConfig, url: &str, starred: bool) -> Result<()> {
+	let mut store = open_existing_store(config)?;
+	if !tokio::task::block_in_place(|| store.set_starred(url, starred))? {
+		return Err(AppError::storage_error(format!("no stored article with URL {url}")));
+	}
+	info!(url, starred, "Updated the stored article");
+	Ok(())
+}
+
+/// Dump the whole store to a portable bundle, e.g. to move it to another machine or backend
+fn export(config: &StoreConfig, path: &Path) -> Result<()> {
+	let store = open_existing_store(config)?;
+	let contents = tokio::task::block_in_place(|| store.dump())?;
+	store::write_bundle(path, &contents)?;
+	info!(
+		path = %path.display(),
+		articles = contents.articles.len(),
+		rankings = contents.rankings.len(),
+		deliveries = contents.deliveries.len(),
+		reads = contents.reads.len(),
+		"Exported the article store"
+	);
+	Ok(())
+}
+
+/// Merge the bundle at `path` into the store, creating the store when there is none yet
+fn import(config: &StoreConfig, path: &Path) -> Result<()> {
+	let contents = store::read_bundle(path)?;
+	let mut store = store::open(config)?;
+	tokio::task::block_in_place(|| store.restore(&contents))?;
+	info!(
+		path = %path.display(),
+		articles = contents.articles.len(),
+		rankings = contents.rankings.len(),
+		deliveries = contents.deliveries.len(),
+		reads = contents.reads.len(),
+		"Imported into the article store"
+	);
+	Ok(())
+}
+
+/// Score a synthetic or stored corpus of `articles` articles `iterations` times and log the throughput
+fn benchmark(config: &Config, articles: usize, iterations: usize, stored: bool, words: usize, seed: u64) -> Result<()> {
+	let corpus = if stored {
+		let store = open_existing_store(&config.store)?;
+		let loaded = tokio::task::block_in_place(|| store.load_articles())?;
+		if loaded.is_empty() {
+			return Err(AppError::storage_error("the article store is empty"));
+		}
+		bench::resize_corpus(loaded, articles)
+	} else {
+		bench::synthetic_corpus(&config.keywords.values, articles, words, seed)
+	};
+	info!(
+		articles = corpus.len(),
+		iterations,
+		corpus = if stored { "stored" } else { "synthetic" },
+		keywords = config.keywords.values.len(),
+		threads = rayon::current_num_threads(),
+		"Benchmarking the scorer"
+	);
+	let report = tokio::task::block_in_place(|| bench::run(config, &corpus, iterations))?;
+	for (iteration, elapsed) in (1usize..).zip(&report.timings) {
+		info!(
+			iteration,
+			elapsed_ms = format!("{:.2}", elapsed.as_secs_f64() * 1000.0),
+			articles_per_sec = format!("{:.0}", report.throughput(*elapsed)),
+		);
+	}
+	info!(
+		articles = report.articles,
+		matched = report.matched,
+		best_ms = format!("{:.2}", report.best().as_secs_f64() * 1000.0),
+		median_ms = format!("{:.2}", report.median().as_secs_f64() * 1000.0),
+		articles_per_sec = format!("{:.0}", report.throughput(report.median())),
+		"=== Scoring throughput ==="
+	);
+	Ok(())
+}
+
+/// Mine the stored articles for keyword candidates and log each with its evidence
+fn suggest_keywords(config: &Config, top: usize, min_articles: usize, examples: usize) -> Result<()> {
+	let store = open_existing_store(&config.store)?;
+	let articles = tokio::task::block_in_place(|| store.load_articles())?;
+	let mut keywords: Vec<String> = config
+		.keywords
+		.values
+		.iter()
+		.chain(config.keywords.languages.values().flatten())
+		.cloned()
+		.collect();
+	keywords.sort_unstable();
+	keywords.dedup();
+	let suggestions =
+		tokio::task::block_in_place(|| suggest::suggest(&articles, &keywords, top, min_articles, examples))?;
+	info!(
+		articles = articles.len(),
+		shown = suggestions.len(),
+		"=== Keyword suggestions ==="
+	);
+	for (rank, suggestion) in (1usize..).zip(&suggestions) {
 		info!(
-			rank = i + 1,
-			score = format!("{:.2}", scored.relevance_score()),
-			title = scored.article().title(),
-			source = scored.article().source(),
-			url = scored.article().url(),
-			keywords = ?scored.matched_keywords(),
+			rank,
+			term = suggestion.term,
+			score = format!("{:.3}", suggestion.score),
+			articles = suggestion.articles,
+			with = ?suggestion.keywords,
+			examples = ?suggestion.examples,
 		);
 	}
+	Ok(())
+}
+
+/// Learn the interest model from the store's starred flags and save it for scoring
+#[cfg(feature = "learning")]
+fn retrain(config: &Config) -> Result<()> {
+	let store = open_existing_store(&config.store)?;
+	let contents = tokio::task::block_in_place(|| store.dump())?;
+	let model = tokio::task::block_in_place(|| {
+		news_aggregator::learning::InterestModel::train(&contents.articles, &config.learning)
+	})?;
+	let path = Path::new(&config.learning.model_path);
+	model.save(path)?;
+	info!(
+		path = %path.display(),
+		starred = model.starred(),
+		others = model.others(),
+		vocabulary = model.vocabulary(),
+		"Trained the interest model"
+	);
+	Ok(())
+}
+
+#[cfg(not(feature = "learning"))]
+fn retrain(_config: &Config) -> Result<()> {
+	Err(AppError::ConfigError(
+		"retrain: built without the `learning` feature".into(),
+	))
 }
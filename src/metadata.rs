@@ -0,0 +1,100 @@
+use crate::config::MetadataConfig;
+use crate::content::fetch_html;
+use crate::extract::{PageMetadata, page_metadata};
+use crate::model::{Article, Extra};
+use crate::sanitize::{plain_text, truncate};
+use futures::stream::{self, StreamExt};
+use reqwest::Client;
+use std::time::Duration;
+use tokio::time::timeout;
+use tracing::{debug, info};
+
+/// Fill in what the source left out of the articles without a description, from the `<head>` of their
+/// pages.
+///
+/// That is the description, the publication time, the `canonical_url` and `image_url` extras, and the
+/// title when the source gave only the URL. At most `budget` pages are fetched, and `budget` is reduced by
+/// the number fetched, so one budget can span every chunk of a run.
+pub async fn enrich(
+	client: &Client,
+	config: &MetadataConfig,
+	max_description_chars: Option<usize>,
+	articles: &mut [Article],
+	budget: &mut usize,
+) {
+	let missing: Vec<(usize, String)> = articles
+		.iter()
+		.enumerate()
+		.filter(|(_, article)| article.description().is_none())
+		.map(|(index, article)| (index, article.url().to_string()))
+		.take(*budget)
+		.collect();
+	if missing.is_empty() {
+		return;
+	}
+	*budget = budget.saturating_sub(missing.len());
+	let looked_up = missing.len();
+	let limit = Duration::from_secs(config.timeout_secs);
+	let pages: Vec<(usize, Option<PageMetadata>)> = stream::iter(missing)
+		.map(|(index, url)| async move {
+			let html = timeout(limit, fetch_html(client, &url)).await.unwrap_or_else(|_| {
+				debug!(url, "Article page timed out");
+				None
+			});
+			(index, html.map(|html| page_metadata(&html, &url)))
+		})
+		.buffer_unordered(config.concurrency)
+		.collect()
+		.await;
+
+	let mut described = 0usize;
+	for (index, page) in pages {
+		let (Some(article), Some(page)) = (articles.get_mut(index), page) else {
+			continue;
+		};
+		if apply(article, page, max_description_chars) {
+			described = described.saturating_add(1);
+		}
+	}
+	info!(
+		looked_up,
+		described,
+		remaining = *budget,
+		"Enriched articles from page metadata"
+	);
+}
+
+/// Fill the article's missing fields from its page; true when it gained a description
+fn apply(article: &mut Article, page: PageMetadata, max_description_chars: Option<usize>) -> bool {
+	let untitled = article.title().trim().is_empty() || article.title() == article.url();
+	if let Some(title) = page
+		.title
+		.filter(|_| untitled)
+		.map(|title| plain_text(&title))
+		.filter(|title| !title.is_empty())
+	{
+		article.set_title(title);
+	}
+	if let Some(published_at) = page.published_at.filter(|_| article.published_at().is_none()) {
+		article.set_published_at(published_at);
+	}
+	if let Some(canonical) = page.canonical_url {
+		article.set_extra("canonical_url", Extra::Text(canonical));
+	}
+	if let Some(image) = page.image_url.filter(|_| article.image_url().is_none()) {
+		article.set_image_url(image);
+	}
+	let description = page
+		.description
+		.map(|description| plain_text(&description))
+		.filter(|description| !description.is_empty())
+		.map(|description| match max_description_chars {
+			Some(max) => truncate(&description, max),
+			None => description,
+		});
+	let described = description.is_some();
+	if described {
+		article.set_description(description);
+	}
+	described
+}
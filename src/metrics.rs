@@ -1,34 +1,331 @@
+use crate::config::CircuitBreakerConfig;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tracing::info;
 
+/// Upper bounds, in seconds, of the latency histogram buckets
+const LATENCY_BUCKETS: [f64; 10] = [0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
 #[derive(Debug, Clone, Default)]
 pub struct Metrics {
     articles_fetched: Arc<AtomicU64>,
     articles_failed: Arc<AtomicU64>,
     http_requests: Arc<AtomicU64>,
     http_failures: Arc<AtomicU64>,
+    concurrency_limit: Arc<AtomicU64>,
+    /// Delivered links followed through the `/r/<id>` redirect
+    clicks: Arc<AtomicU64>,
+    sources: Arc<Mutex<BTreeMap<String, SourceStats>>>,
+    hosts: Arc<Mutex<BTreeMap<String, HostStats>>>,
+    /// Articles dropped by each `[filters]` rule
+    filtered: Arc<Mutex<BTreeMap<String, u64>>>,
+    /// Links checked before delivery, by outcome: `alive`, `404`, `410`, `dns` or `unknown`
+    link_checks: Arc<Mutex<BTreeMap<String, u64>>>,
+    /// Cuts made by the memory limits, by limit: `body`, `articles` or `text`
+    limits: Arc<Mutex<BTreeMap<String, u64>>>,
+}
+
+/// Latency distribution over `LATENCY_BUCKETS`, plus an overflow bucket
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    overflow: u64,
+    count: u64,
+    sum_secs: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+        match LATENCY_BUCKETS
+            .iter()
+            .zip(self.buckets.iter_mut())
+            .find(|(bound, _)| secs <= **bound)
+        {
+            Some((_, bucket)) => *bucket = bucket.saturating_add(1),
+            None => self.overflow = self.overflow.saturating_add(1),
+        }
+        self.count = self.count.saturating_add(1);
+        self.sum_secs += secs;
+    }
+
+    /// Upper bound of the bucket holding the given percentile, in milliseconds; `None` when empty or in the overflow bucket
+    fn percentile_ms(&self, percent: u64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let mut seen = 0u64;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(self.buckets) {
+            seen = seen.saturating_add(count);
+            if seen.saturating_mul(100) >= self.count.saturating_mul(percent) {
+                return Some(bound * 1000.0);
+            }
+        }
+        None
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0u64;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(self.buckets) {
+            cumulative = cumulative.saturating_add(count);
+            let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{bound}\"}} {cumulative}");
+        }
+        let _ = writeln!(out, "{name}_bucket{{{labels},le=\"+Inf\"}} {}", self.count);
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", self.sum_secs);
+        let _ = writeln!(out, "{name}_count{{{labels}}} {}", self.count);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct SourceStats {
+    articles: u64,
+    articles_failed: u64,
+    parse_failures: u64,
+    truncations: u64,
+    layout_changes: u64,
+    feed_fallbacks: u64,
+    fetch_duration: Histogram,
+    health: SourceHealth,
+}
+
+/// How the fetches of a source have been going, for `/admin/sources`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SourceHealth {
+    pub last_fetch: Option<DateTime<Utc>>,
+    pub last_success: Option<DateTime<Utc>>,
+    /// Articles of the last successful fetch
+    pub last_articles: usize,
+    pub last_error: Option<String>,
+    /// Failed fetches since the last success
+    pub consecutive_failures: u64,
+}
+
+/// Whether a source is fetched, by `fetcher.circuit_breaker`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "state")]
+pub enum Breaker {
+    Closed,
+    /// Skipped until `until`, after `failure_threshold` failed fetches in a row
+    Open { until: DateTime<Utc> },
+    /// Cooled down; the next fetch is a trial that closes the breaker again or reopens it
+    HalfOpen,
+}
+
+impl SourceHealth {
+    /// State of the source's breaker at `now`; always closed while the breaker is disabled
+    #[must_use]
+    pub fn breaker(&self, config: &CircuitBreakerConfig, now: DateTime<Utc>) -> Breaker {
+        if !config.enabled || self.consecutive_failures < config.failure_threshold {
+            return Breaker::Closed;
+        }
+        let Some(last_fetch) = self.last_fetch else {
+            return Breaker::HalfOpen;
+        };
+        let until = TimeDelta::try_seconds(i64::try_from(config.cooldown_secs).unwrap_or(i64::MAX))
+            .and_then(|cooldown| last_fetch.checked_add_signed(cooldown))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        if until > now {
+            Breaker::Open { until }
+        } else {
+            Breaker::HalfOpen
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct HostStats {
+    requests: u64,
+    failures: u64,
+    retries: u64,
+    cache_hits: u64,
+    /// Response body bytes as sent, compressed or not
+    received_bytes: u64,
+    /// Response body bytes once decompressed
+    body_bytes: u64,
+    latency: Histogram,
 }
 
 impl Metrics {
+    #[must_use]
     pub fn new() -> Self {
         Self::default()
     }
 
-    pub fn record_article_fetched(&self) {
+    pub fn record_article_fetched(&self, source: &str) {
         self.articles_fetched.fetch_add(1, Ordering::Relaxed);
+        self.update_source(source, |stats| stats.articles = stats.articles.saturating_add(1));
     }
 
-    pub fn record_article_failed(&self) {
+    pub fn record_article_failed(&self, source: &str) {
         self.articles_failed.fetch_add(1, Ordering::Relaxed);
+        self.update_source(source, |stats| stats.articles_failed = stats.articles_failed.saturating_add(1));
+    }
+
+    /// A response arrived but could not be turned into articles
+    pub fn record_parse_failure(&self, source: &str) {
+        self.update_source(source, |stats| stats.parse_failures = stats.parse_failures.saturating_add(1));
     }
 
-    pub fn record_http_request(&self) {
+    /// A scraped page yielded no article, which usually means the site was restyled
+    pub fn record_layout_change(&self, source: &str) {
+        self.update_source(source, |stats| stats.layout_changes = stats.layout_changes.saturating_add(1));
+    }
+
+    /// A source's feed failed or was empty and its page was scraped instead
+    pub fn record_feed_fallback(&self, source: &str) {
+        self.update_source(source, |stats| stats.feed_fallbacks = stats.feed_fallbacks.saturating_add(1));
+    }
+
+    /// Current limit on concurrent HTTP requests
+    pub fn set_concurrency_limit(&self, limit: usize) {
+        self.concurrency_limit
+            .store(u64::try_from(limit).unwrap_or(u64::MAX), Ordering::Relaxed);
+    }
+
+    /// A fetch of `source` abandoned at its deadline with partial results
+    pub fn record_source_truncated(&self, source: &str) {
+        self.update_source(source, |stats| stats.truncations = stats.truncations.saturating_add(1));
+    }
+
+    /// Wall-clock time of fetching one source end to end
+    pub fn record_source_fetch(&self, source: &str, duration: Duration) {
+        self.update_source(source, |stats| stats.fetch_duration.observe(duration));
+    }
+
+    /// How a fetch of `source` ended: with its number of articles, or with an error
+    pub fn record_source_result(&self, source: &str, result: Result<usize, String>) {
+        let now = Utc::now();
+        self.update_source(source, |stats| {
+            let health = &mut stats.health;
+            health.last_fetch = Some(now);
+            match result {
+                Ok(count) => {
+                    health.last_success = Some(now);
+                    health.last_articles = count;
+                    health.consecutive_failures = 0;
+                }
+                Err(error) => {
+                    health.last_error = Some(error);
+                    health.consecutive_failures = health.consecutive_failures.saturating_add(1);
+                }
+            }
+        });
+    }
+
+    /// Breaker state of `source` at `now`
+    #[must_use]
+    pub fn source_breaker(&self, source: &str, config: &CircuitBreakerConfig, now: DateTime<Utc>) -> Breaker {
+        self.sources
+            .lock()
+            .ok()
+            .and_then(|sources| sources.get(source).map(|stats| stats.health.breaker(config, now)))
+            .unwrap_or(Breaker::Closed)
+    }
+
+    /// Fetch health of every source fetched so far
+    #[must_use]
+    pub fn source_health(&self) -> BTreeMap<String, SourceHealth> {
+        self.sources()
+            .into_iter()
+            .map(|(source, stats)| (source, stats.health))
+            .collect()
+    }
+
+    /// One HTTP attempt against `url`, successful or not
+    pub fn record_http_request(&self, url: &str, duration: Duration, success: bool) {
         self.http_requests.fetch_add(1, Ordering::Relaxed);
+        if !success {
+            self.http_failures.fetch_add(1, Ordering::Relaxed);
+        }
+        self.update_host(url, |stats| {
+            stats.requests = stats.requests.saturating_add(1);
+            if !success {
+                stats.failures = stats.failures.saturating_add(1);
+            }
+            stats.latency.observe(duration);
+        });
+    }
+
+    pub fn record_retry(&self, url: &str) {
+        self.update_host(url, |stats| stats.retries = stats.retries.saturating_add(1));
+    }
+
+    pub fn record_cache_hit(&self, url: &str) {
+        self.update_host(url, |stats| stats.cache_hits = stats.cache_hits.saturating_add(1));
+    }
+
+    /// A response body of `received` bytes on the wire and `decoded` bytes once decompressed
+    pub fn record_transfer(&self, url: &str, received: usize, decoded: usize) {
+        self.update_host(url, |stats| {
+            stats.received_bytes = stats.received_bytes.saturating_add(u64::try_from(received).unwrap_or(u64::MAX));
+            stats.body_bytes = stats.body_bytes.saturating_add(u64::try_from(decoded).unwrap_or(u64::MAX));
+        });
+    }
+
+    /// Articles dropped by a filter rule before scoring
+    pub fn record_articles_filtered(&self, rule: &str, count: usize) {
+        if let Ok(mut filtered) = self.filtered.lock() {
+            let total = filtered.entry(rule.to_string()).or_default();
+            *total = total.saturating_add(u64::try_from(count).unwrap_or(u64::MAX));
+        }
+    }
+
+    pub fn record_click(&self) {
+        self.clicks.fetch_add(1, Ordering::Relaxed);
     }
 
-    pub fn record_http_failure(&self) {
-        self.http_failures.fetch_add(1, Ordering::Relaxed);
+    /// One link checked by `[link_check]`
+    pub fn record_link_check(&self, outcome: &str) {
+        if let Ok(mut checks) = self.link_checks.lock() {
+            let total = checks.entry(outcome.to_string()).or_default();
+            *total = total.saturating_add(1);
+        }
+    }
+
+    /// A response, source or article text cut short by `fetcher.max_body_bytes`,
+    /// `fetcher.max_total_articles` or `analyzer.max_text_bytes`
+    pub fn record_limit_reached(&self, limit: &str) {
+        if let Ok(mut limits) = self.limits.lock() {
+            let total = limits.entry(limit.to_string()).or_default();
+            *total = total.saturating_add(1);
+        }
+    }
+
+    fn update_source(&self, source: &str, f: impl FnOnce(&mut SourceStats)) {
+        if let Ok(mut sources) = self.sources.lock() {
+            f(sources.entry(source.to_string()).or_default());
+        }
+    }
+
+    fn update_host(&self, url: &str, f: impl FnOnce(&mut HostStats)) {
+        if let Ok(mut hosts) = self.hosts.lock() {
+            f(hosts.entry(host(url)).or_default());
+        }
+    }
+
+    fn sources(&self) -> BTreeMap<String, SourceStats> {
+        self.sources.lock().map(|sources| sources.clone()).unwrap_or_default()
+    }
+
+    fn hosts(&self) -> BTreeMap<String, HostStats> {
+        self.hosts.lock().map(|hosts| hosts.clone()).unwrap_or_default()
+    }
+
+    fn filtered(&self) -> BTreeMap<String, u64> {
+        self.filtered.lock().map(|filtered| filtered.clone()).unwrap_or_default()
+    }
+
+    fn link_checks(&self) -> BTreeMap<String, u64> {
+        self.link_checks.lock().map(|checks| checks.clone()).unwrap_or_default()
+    }
+
+    fn limits(&self) -> BTreeMap<String, u64> {
+        self.limits.lock().map(|limits| limits.clone()).unwrap_or_default()
     }
 
     pub fn log_summary(&self) {
@@ -37,7 +334,218 @@ impl Metrics {
             articles_failed = self.articles_failed.load(Ordering::Relaxed),
             http_requests = self.http_requests.load(Ordering::Relaxed),
             http_failures = self.http_failures.load(Ordering::Relaxed),
+            concurrency_limit = self.concurrency_limit.load(Ordering::Relaxed),
             "Final metrics"
         );
+        for (source, stats) in self.sources() {
+            info!(
+                source,
+                articles = stats.articles,
+                articles_failed = stats.articles_failed,
+                parse_failures = stats.parse_failures,
+                truncations = stats.truncations,
+                layout_changes = stats.layout_changes,
+                feed_fallbacks = stats.feed_fallbacks,
+                fetches = stats.fetch_duration.count,
+                fetch_p50_ms = stats.fetch_duration.percentile_ms(50),
+                fetch_p95_ms = stats.fetch_duration.percentile_ms(95),
+                "Source metrics"
+            );
+        }
+        for (host, stats) in self.hosts() {
+            info!(
+                host,
+                requests = stats.requests,
+                failures = stats.failures,
+                retries = stats.retries,
+                cache_hits = stats.cache_hits,
+                received_bytes = stats.received_bytes,
+                body_bytes = stats.body_bytes,
+                latency_p50_ms = stats.latency.percentile_ms(50),
+                latency_p95_ms = stats.latency.percentile_ms(95),
+                "Host metrics"
+            );
+        }
+        for (rule, dropped) in self.filtered() {
+            info!(rule, dropped, "Filter metrics");
+        }
+        for (outcome, links) in self.link_checks() {
+            info!(outcome, links, "Link check metrics");
+        }
+        for (limit, truncations) in self.limits() {
+            info!(limit, truncations, "Memory limit metrics");
+        }
+    }
+
+    /// Prometheus text exposition format
+    #[must_use]
+    pub fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+        for (name, help, value) in [
+            ("news_articles_fetched_total", "Articles fetched", &self.articles_fetched),
+            ("news_articles_failed_total", "Articles that failed to fetch", &self.articles_failed),
+            ("news_http_requests_total", "HTTP requests sent", &self.http_requests),
+            ("news_http_failures_total", "HTTP requests that failed", &self.http_failures),
+            ("news_clicks_total", "Delivered links followed through the click redirect", &self.clicks),
+        ] {
+            let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} counter");
+            let _ = writeln!(out, "{name} {}", value.load(Ordering::Relaxed));
+        }
+        let _ = writeln!(
+            out,
+            "# HELP news_concurrency_limit Current limit on concurrent HTTP requests\n# TYPE news_concurrency_limit gauge\nnews_concurrency_limit {}",
+            self.concurrency_limit.load(Ordering::Relaxed)
+        );
+
+        let sources = self.sources();
+        render_counters(&mut out, "source", &sources, &[
+            ("news_source_articles_total", "Articles fetched per source", |s: &SourceStats| s.articles),
+            ("news_source_articles_failed_total", "Articles that failed per source", |s| s.articles_failed),
+            ("news_source_parse_failures_total", "Unparseable responses per source", |s| s.parse_failures),
+            ("news_source_truncations_total", "Fetches cut short by the source deadline or article cap", |s| s.truncations),
+            ("news_source_layout_changes_total", "Scraped pages that yielded no article", |s| s.layout_changes),
+            ("news_source_feed_fallbacks_total", "Feeds that failed and were replaced by scraping", |s| s.feed_fallbacks),
+        ]);
+        let _ = writeln!(
+            out,
+            "# HELP news_source_fetch_duration_seconds Time to fetch a source end to end\n# TYPE news_source_fetch_duration_seconds histogram"
+        );
+        for (source, stats) in &sources {
+            stats.fetch_duration.render(&mut out, "news_source_fetch_duration_seconds", &label("source", source));
+        }
+
+        let hosts = self.hosts();
+        render_counters(&mut out, "host", &hosts, &[
+            ("news_host_requests_total", "HTTP requests per host", |s: &HostStats| s.requests),
+            ("news_host_failures_total", "Failed HTTP requests per host", |s| s.failures),
+            ("news_host_retries_total", "Retried HTTP requests per host", |s| s.retries),
+            ("news_host_cache_hits_total", "Responses served from the cache per host", |s| s.cache_hits),
+            ("news_host_received_bytes_total", "Response body bytes received per host, as sent", |s| s.received_bytes),
+            ("news_host_body_bytes_total", "Response body bytes per host once decompressed", |s| s.body_bytes),
+        ]);
+        let _ = writeln!(
+            out,
+            "# HELP news_host_request_duration_seconds HTTP request latency per host\n# TYPE news_host_request_duration_seconds histogram"
+        );
+        for (host, stats) in &hosts {
+            stats.latency.render(&mut out, "news_host_request_duration_seconds", &label("host", host));
+        }
+
+        render_counters(&mut out, "rule", &self.filtered(), &[(
+            "news_articles_filtered_total",
+            "Articles dropped by each filter rule",
+            |count: &u64| *count,
+        )]);
+        render_counters(&mut out, "outcome", &self.link_checks(), &[(
+            "news_link_checks_total",
+            "Links of top-ranked articles checked before delivery, by outcome",
+            |count: &u64| *count,
+        )]);
+        render_counters(&mut out, "limit", &self.limits(), &[(
+            "news_limit_truncations_total",
+            "Responses, sources and article texts cut short by a memory limit",
+            |count: &u64| *count,
+        )]);
+        out
+    }
+}
+
+type Counter<T> = (&'static str, &'static str, fn(&T) -> u64);
+
+fn render_counters<T>(out: &mut String, key: &str, stats: &BTreeMap<String, T>, counters: &[Counter<T>]) {
+    for (name, help, value) in counters {
+        let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} counter");
+        for (value_key, entry) in stats {
+            let _ = writeln!(out, "{name}{{{}}} {}", label(key, value_key), value(entry));
+        }
+    }
+}
+
+fn label(key: &str, value: &str) -> String {
+    format!("{key}=\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn host(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| "unknown".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "Example Engineering";
+
+    fn breaker_config() -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            enabled: true,
+            failure_threshold: 3,
+            cooldown_secs: 60,
+        }
+    }
+
+    fn fail(metrics: &Metrics, times: usize) {
+        for _ in 0..times {
+            metrics.record_source_result(SOURCE, Err("HTTP 503".into()));
+        }
+    }
+
+    fn last_fetch(metrics: &Metrics) -> DateTime<Utc> {
+        metrics
+            .source_health()
+            .get(SOURCE)
+            .and_then(|health| health.last_fetch)
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn the_breaker_stays_closed_below_the_threshold() {
+        let metrics = Metrics::new();
+        assert_eq!(metrics.source_breaker(SOURCE, &breaker_config(), Utc::now()), Breaker::Closed);
+        fail(&metrics, 2);
+        assert_eq!(metrics.source_breaker(SOURCE, &breaker_config(), Utc::now()), Breaker::Closed);
+    }
+
+    #[test]
+    fn the_breaker_opens_at_the_threshold_for_the_cooldown() {
+        let metrics = Metrics::new();
+        fail(&metrics, 3);
+        let until = last_fetch(&metrics) + TimeDelta::seconds(60);
+        let now = last_fetch(&metrics) + TimeDelta::seconds(59);
+        assert_eq!(metrics.source_breaker(SOURCE, &breaker_config(), now), Breaker::Open { until });
+    }
+
+    #[test]
+    fn the_breaker_half_opens_once_the_cooldown_is_over() {
+        let metrics = Metrics::new();
+        fail(&metrics, 3);
+        let now = last_fetch(&metrics) + TimeDelta::seconds(60);
+        assert_eq!(metrics.source_breaker(SOURCE, &breaker_config(), now), Breaker::HalfOpen);
+    }
+
+    #[test]
+    fn a_failed_trial_reopens_the_breaker_and_a_success_closes_it() {
+        let metrics = Metrics::new();
+        fail(&metrics, 4);
+        let now = last_fetch(&metrics);
+        assert!(matches!(
+            metrics.source_breaker(SOURCE, &breaker_config(), now),
+            Breaker::Open { until } if until > now
+        ));
+        metrics.record_source_result(SOURCE, Ok(12));
+        assert_eq!(metrics.source_breaker(SOURCE, &breaker_config(), now), Breaker::Closed);
+    }
+
+    #[test]
+    fn a_disabled_breaker_never_opens() {
+        let metrics = Metrics::new();
+        fail(&metrics, 10);
+        let config = CircuitBreakerConfig {
+            enabled: false,
+            ..breaker_config()
+        };
+        assert_eq!(metrics.source_breaker(SOURCE, &config, Utc::now()), Breaker::Closed);
     }
 }
@@ -1,5 +1,10 @@
+use chrono::{DateTime, Utc};
+use reqwest::Url;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Article {
@@ -7,41 +12,358 @@ pub struct Article {
     url: String,
     source: String,
     description: Option<String>,
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    points: Option<u64>,
+    #[serde(default)]
+    comments: Option<u64>,
+    /// Likely behind a paywall, from the domain or the fetched page
+    #[serde(default)]
+    paywalled: bool,
+    /// When the source says the article was published, if it does
+    #[serde(default)]
+    published_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    author: Option<String>,
+    /// Tags or keywords the source attached to the article
+    #[serde(default)]
+    tags: Vec<String>,
+    /// When this run received the article from its source
+    #[serde(default)]
+    fetched_at: Option<DateTime<Utc>>,
+    /// The source's own identifier for the article, e.g. a feed entry GUID
+    #[serde(default)]
+    guid: Option<String>,
+    /// Source-specific values without a field of their own, e.g. the Hacker News item id
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    extras: BTreeMap<String, Extra>,
+}
+
+/// A source-specific value attached to an article
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Extra {
+    Flag(bool),
+    Integer(i64),
+    Number(f64),
+    Text(String),
+}
+
+impl Extra {
+    #[must_use]
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Self::Text(text) => Some(text),
+            _ => None,
+        }
+    }
 }
 
 impl Article {
-    pub fn new(title: String, url: String, source: String) -> Self {
+    #[must_use]
+    pub const fn new(title: String, url: String, source: String) -> Self {
         Self {
             title,
             url,
             source,
             description: None,
+            language: None,
+            points: None,
+            comments: None,
+            paywalled: false,
+            published_at: None,
+            author: None,
+            tags: Vec::new(),
+            fetched_at: None,
+            guid: None,
+            extras: BTreeMap::new(),
         }
     }
 
+    #[must_use]
     pub fn with_description(mut self, description: String) -> Self {
         self.description = Some(description);
         self
     }
 
+    /// Record engagement counts (upvotes and comments) reported by the source
+    #[must_use]
+    pub const fn with_engagement(mut self, points: Option<u64>, comments: Option<u64>) -> Self {
+        self.points = points;
+        self.comments = comments;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_published_at(mut self, published_at: Option<DateTime<Utc>>) -> Self {
+        self.published_at = published_at;
+        self
+    }
+
+    pub const fn set_published_at(&mut self, published_at: DateTime<Utc>) {
+        self.published_at = Some(published_at);
+    }
+
+    /// Stable identity used to key the store, deduplication and the delivery ledger: a hash of the
+    /// canonical URL, or of the source's GUID and then of the source and title when the URL is unusable
+    #[must_use]
+    pub fn id(&self) -> String {
+        let key = canonical_url(&self.url).map_or_else(
+            || {
+                self.guid.as_ref().map_or_else(
+                    || format!("content:{}\n{}", self.source, self.title),
+                    |guid| format!("guid:{}\n{guid}", self.source),
+                )
+            },
+            |url| format!("url:{url}"),
+        );
+        hash(&key)
+    }
+
+    #[must_use]
     pub fn title(&self) -> &str {
         &self.title
     }
 
+    #[must_use]
+    pub fn with_author(mut self, author: Option<String>) -> Self {
+        self.author = author;
+        self
+    }
+
+    #[must_use]
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Add the tags the article does not carry yet
+    pub fn add_tags(&mut self, tags: &[String]) {
+        for tag in tags {
+            if !self.tags.iter().any(|own| own.eq_ignore_ascii_case(tag)) {
+                self.tags.push(tag.clone());
+            }
+        }
+    }
+
+    #[must_use]
+    pub const fn with_fetched_at(mut self, fetched_at: DateTime<Utc>) -> Self {
+        self.fetched_at = Some(fetched_at);
+        self
+    }
+
+    #[must_use]
+    pub fn with_guid(mut self, guid: String) -> Self {
+        self.guid = Some(guid);
+        self
+    }
+
+    #[must_use]
+    pub fn with_extra(mut self, key: &str, value: Extra) -> Self {
+        self.extras.insert(key.to_string(), value);
+        self
+    }
+
+    /// Preview image of the article, from its feed entry or page
+    pub fn image_url(&self) -> Option<&str> {
+        self.extra("image_url").and_then(Extra::as_text)
+    }
+
+    pub fn set_image_url(&mut self, url: String) {
+        self.extras.insert("image_url".into(), Extra::Text(url));
+    }
+
+    pub fn set_extra(&mut self, key: &str, value: Extra) {
+        self.extras.insert(key.to_string(), value);
+    }
+
+    /// Where outputs send readers: the click-counting redirect of `[clicks]` when set, else the article itself
+    pub fn link(&self) -> &str {
+        self.extra("click_url").and_then(Extra::as_text).unwrap_or(&self.url)
+    }
+
+    pub fn set_click_url(&mut self, url: String) {
+        self.extras.insert("click_url".into(), Extra::Text(url));
+    }
+
+    /// Why the article's link was found dead before delivery: its HTTP status or `dns`
+    pub fn dead_link(&self) -> Option<&str> {
+        self.extra("dead_link").and_then(Extra::as_text)
+    }
+
+    pub fn set_dead_link(&mut self, reason: String) {
+        self.extras.insert("dead_link".into(), Extra::Text(reason));
+    }
+
+    /// A source-specific value, e.g. `audio_url` of a podcast episode
+    #[must_use]
+    pub fn extra(&self, key: &str) -> Option<&Extra> {
+        self.extras.get(key)
+    }
+
+    pub fn set_title(&mut self, title: String) {
+        self.title = title;
+    }
+
+    #[must_use]
     pub fn url(&self) -> &str {
         &self.url
     }
 
+    #[must_use]
     pub fn source(&self) -> &str {
         &self.source
     }
 
-    pub fn searchable_text(&self) -> Cow<'_, str> {
-        match &self.description {
-            Some(desc) => Cow::Owned(format!("{} {}", self.title, desc)),
-            None => Cow::Borrowed(&self.title),
+    #[must_use]
+    pub const fn points(&self) -> Option<u64> {
+        self.points
+    }
+
+    #[must_use]
+    pub const fn comments(&self) -> Option<u64> {
+        self.comments
+    }
+
+    #[must_use]
+    pub const fn published_at(&self) -> Option<DateTime<Utc>> {
+        self.published_at
+    }
+
+    #[cfg(feature = "grpc")]
+    #[must_use]
+    pub const fn fetched_at(&self) -> Option<DateTime<Utc>> {
+        self.fetched_at
+    }
+
+    #[must_use]
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    #[must_use]
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    #[must_use]
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn set_description(&mut self, description: Option<String>) {
+        self.description = description;
+    }
+
+    /// Cut the title and the description to at most `max_bytes` bytes each, at a character boundary;
+    /// true when either was longer
+    pub fn truncate_text(&mut self, max_bytes: usize) -> bool {
+        let mut cut = false;
+        for text in std::iter::once(&mut self.title).chain(self.description.as_mut()) {
+            if text.len() > max_bytes {
+                text.truncate(text.floor_char_boundary(max_bytes));
+                cut = true;
+            }
         }
+        cut
+    }
+
+    /// Detected ISO 639-1 language code, if detection was confident
+    #[must_use]
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
     }
+
+    #[must_use]
+    pub const fn paywalled(&self) -> bool {
+        self.paywalled
+    }
+
+    pub const fn mark_paywalled(&mut self) {
+        self.paywalled = true;
+    }
+
+    pub fn set_language(&mut self, language: String) {
+        self.language = Some(language);
+    }
+
+    #[must_use]
+    pub fn searchable_text(&self) -> Cow<'_, str> {
+        self.description.as_ref().map_or_else(
+            || Cow::Borrowed(self.title.as_str()),
+            |desc| Cow::Owned(format!("{} {}", self.title, desc)),
+        )
+    }
+}
+
+/// The identity of an article known only by its URL, as `Article::id` computes it
+#[must_use]
+pub fn id_for_url(url: &str) -> String {
+    hash(&format!("url:{}", canonical_url(url).as_deref().unwrap_or(url)))
+}
+
+/// Query parameters that only track where a reader came from
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "mc_cid", "mc_eid", "ref", "ref_src", "igshid"];
+
+/// The URL with its tracking query parameters removed; URLs that do not parse are returned as they are
+#[must_use]
+pub fn strip_tracking(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url.trim()) else {
+        return url.to_string();
+    };
+    let query: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(name, _)| !name.starts_with("utm_") && !TRACKING_PARAMS.contains(&name.as_ref()))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    if query.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(query);
+    }
+    parsed.into()
+}
+
+/// The parts of an http(s) URL that name the page.
+///
+/// That is the host without `www.` and the path without a trailing slash, with tracking parameters dropped
+/// and the others sorted. Scheme, default port and fragment are left out.
+#[must_use]
+pub fn canonical_url(url: &str) -> Option<String> {
+    let url = Url::parse(url.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let host = url.host_str()?.to_ascii_lowercase();
+    let mut canonical = host.strip_prefix("www.").unwrap_or(&host).to_string();
+    if let Some(port) = url.port() {
+        let _ = write!(canonical, ":{port}");
+    }
+    let path = url.path().trim_end_matches('/');
+    canonical.push_str(if path.is_empty() { "/" } else { path });
+    let mut query: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(name, _)| !name.starts_with("utm_") && !TRACKING_PARAMS.contains(&name.as_ref()))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    query.sort();
+    for (index, (name, value)) in query.iter().enumerate() {
+        let separator = if index == 0 { '?' } else { '&' };
+        let _ = write!(canonical, "{separator}{name}={value}");
+    }
+    Some(canonical)
+}
+
+/// First 128 bits of the SHA-256 of `key`, in hex
+fn hash(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .take(16)
+        .fold(String::with_capacity(32), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
 }
 
 #[derive(Debug, Deserialize)]
@@ -52,4 +374,111 @@ pub struct HackerNewsItem {
     pub url: Option<String>,
     #[serde(default)]
     pub text: Option<String>,
+    #[serde(default)]
+    pub score: Option<u64>,
+    #[serde(default)]
+    pub descendants: Option<u64>,
+    /// Submission time in Unix seconds
+    #[serde(default)]
+    pub time: Option<i64>,
+    /// Submitter's username
+    #[serde(default)]
+    pub by: Option<String>,
+}
+
+/// A Hacker News submission of an article found on another source
+#[derive(Debug, Clone, Serialize)]
+pub struct Discussion {
+    pub url: String,
+    pub points: u64,
+    pub comments: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article(url: &str) -> Article {
+        Article::new(
+            "Faster builds".into(),
+            url.into(),
+            "Example Engineering".into(),
+        )
+    }
+
+    #[test]
+    fn canonical_urls_drop_www_scheme_and_trailing_slash() {
+        let canonical = Some("blog.example/posts/faster-builds".to_string());
+        assert_eq!(canonical_url("https://www.blog.example/posts/faster-builds/"), canonical);
+        assert_eq!(canonical_url("http://Blog.Example/posts/faster-builds"), canonical);
+        assert_eq!(canonical_url("https://blog.example/posts/faster-builds#comments"), canonical);
+        assert_eq!(canonical_url("https://www.blog.example/").as_deref(), Some("blog.example/"));
+    }
+
+    #[test]
+    fn canonical_urls_drop_tracking_parameters_and_sort_the_others() {
+        assert_eq!(
+            canonical_url("https://blog.example/search?utm_source=rss&q=rust&fbclid=abc&page=2&utm_medium=feed")
+                .as_deref(),
+            Some("blog.example/search?page=2&q=rust")
+        );
+        assert_eq!(
+            canonical_url("https://blog.example/post?utm_campaign=weekly").as_deref(),
+            Some("blog.example/post")
+        );
+    }
+
+    #[test]
+    fn canonical_urls_keep_explicit_ports_but_not_default_ones() {
+        assert_eq!(
+            canonical_url("http://blog.example:8080/post").as_deref(),
+            Some("blog.example:8080/post")
+        );
+        assert_eq!(canonical_url("https://blog.example:443/post").as_deref(), Some("blog.example/post"));
+        assert_eq!(canonical_url("http://blog.example:80/post").as_deref(), Some("blog.example/post"));
+    }
+
+    #[test]
+    fn only_http_urls_have_a_canonical_form() {
+        assert_eq!(canonical_url("ftp://blog.example/post"), None);
+        assert_eq!(canonical_url("mailto:editor@blog.example"), None);
+        assert_eq!(canonical_url("not a url"), None);
+    }
+
+    #[test]
+    fn the_same_page_under_different_urls_has_one_id() {
+        let id = article("https://blog.example/posts/faster-builds").id();
+        assert_eq!(article("http://www.blog.example/posts/faster-builds/?utm_source=rss").id(), id);
+        assert_eq!(id_for_url("https://blog.example/posts/faster-builds/"), id);
+        assert_ne!(article("https://blog.example/posts/slower-builds").id(), id);
+    }
+
+    #[test]
+    fn articles_without_an_http_url_fall_back_to_their_guid_then_to_source_and_title() {
+        let guid = article("ftp://blog.example/post").with_guid("tag:blog.example,2026:42".into());
+        assert_eq!(
+            guid.id(),
+            article("gopher://blog.example/post")
+                .with_guid("tag:blog.example,2026:42".into())
+                .id()
+        );
+        assert_ne!(
+            guid.id(),
+            article("ftp://blog.example/post").with_guid("tag:blog.example,2026:43".into()).id()
+        );
+
+        let content = article("ftp://blog.example/post");
+        assert_ne!(content.id(), guid.id());
+        assert_eq!(content.id(), article("mailto:editor@blog.example").id());
+        assert_ne!(
+            content.id(),
+            Article::new("Slower builds".into(), "ftp://blog.example/post".into(), "Example Engineering".into()).id()
+        );
+    }
+
+    #[test]
+    fn a_guid_does_not_override_an_http_url() {
+        let url = "https://blog.example/posts/faster-builds";
+        assert_eq!(article(url).with_guid("tag:blog.example,2026:42".into()).id(), article(url).id());
+    }
 }
@@ -0,0 +1,239 @@
+use crate::config::NewsApiProvider;
+use crate::error::{AppError, Result};
+use crate::model::{Article, Extra};
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use reqwest::{RequestBuilder, Url};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Title `NewsAPI` gives articles taken down after indexing
+const REMOVED_TITLE: &str = "[Removed]";
+
+impl NewsApiProvider {
+	/// Source name of its articles
+	#[must_use]
+	pub const fn name(self) -> &'static str {
+		match self {
+			Self::NewsApi => "NewsAPI",
+			Self::GNews => "GNews",
+		}
+	}
+
+	#[must_use]
+	pub const fn default_url(self) -> &'static str {
+		match self {
+			Self::NewsApi => "https://newsapi.org/v2/everything",
+			Self::GNews => "https://gnews.io/api/v4/search",
+		}
+	}
+
+	/// The site, answering without a key, for `config validate`
+	#[must_use]
+	pub const fn home_url(self) -> &'static str {
+		match self {
+			Self::NewsApi => "https://newsapi.org/",
+			Self::GNews => "https://gnews.io/",
+		}
+	}
+
+	/// Longest query the service accepts
+	const fn max_query_chars(self) -> usize {
+		match self {
+			Self::NewsApi => 500,
+			Self::GNews => 200,
+		}
+	}
+
+	/// Search URL for one page of results, without the key so that it can be logged and cached
+	///
+	/// # Errors
+	///
+	/// `ConfigError` when `news_api.api_url` is not a valid URL
+	pub fn search_url(
+		self,
+		endpoint: &str,
+		query: &str,
+		language: Option<&str>,
+		page_size: usize,
+		page: usize,
+		from: DateTime<Utc>,
+	) -> Result<Url> {
+		let (language_param, size_param, sort_param) = match self {
+			Self::NewsApi => ("language", "pageSize", "sortBy"),
+			Self::GNews => ("lang", "max", "sortby"),
+		};
+		let mut url = Url::parse(endpoint).map_err(|e| AppError::ConfigError(format!("news_api.api_url: {e}")))?;
+		url.query_pairs_mut()
+			.append_pair("q", query)
+			.append_pair(size_param, &page_size.to_string())
+			.append_pair("page", &page.to_string())
+			.append_pair("from", &from.to_rfc3339_opts(SecondsFormat::Secs, true))
+			.append_pair(sort_param, "publishedAt");
+		if let Some(language) = language {
+			url.query_pairs_mut().append_pair(language_param, language);
+		}
+		Ok(url)
+	}
+
+	pub fn authenticate(self, request: RequestBuilder, key: &str) -> RequestBuilder {
+		match self {
+			Self::NewsApi => request.header("X-Api-Key", key),
+			Self::GNews => request.query(&[("apikey", key)]),
+		}
+	}
+
+	/// Search terms from the configured keywords: any of them, phrases quoted, as many as the service
+	/// accepts in one query
+	pub fn keyword_query(self, keywords: &[String]) -> String {
+		let mut query = String::new();
+		for keyword in keywords
+			.iter()
+			.map(|keyword| keyword.trim())
+			.filter(|keyword| !keyword.is_empty())
+		{
+			let term = if keyword.chars().all(char::is_alphanumeric) {
+				keyword.to_string()
+			} else {
+				format!("\"{}\"", keyword.replace('"', ""))
+			};
+			let next = if query.is_empty() {
+				term
+			} else {
+				format!("{query} OR {term}")
+			};
+			if next.chars().count() > self.max_query_chars() {
+				break;
+			}
+			query = next;
+		}
+		query
+	}
+}
+
+/// One page of search results, in the shape both providers share
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchPage {
+	#[serde(default)]
+	articles: Vec<Item>,
+	#[serde(alias = "totalArticles")]
+	total_results: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Item {
+	title: Option<String>,
+	description: Option<String>,
+	content: Option<String>,
+	url: Option<String>,
+	author: Option<String>,
+	published_at: Option<DateTime<Utc>>,
+	source: Option<Publisher>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Publisher {
+	name: Option<String>,
+}
+
+impl SearchPage {
+	/// # Errors
+	///
+	/// `ParseError` when the body is not a search response
+	pub fn parse(body: &[u8], url: &str) -> Result<Self> {
+		serde_json::from_slice(body).map_err(|e| AppError::parse_error(url, e))
+	}
+
+	#[must_use]
+	pub const fn len(&self) -> usize {
+		self.articles.len()
+	}
+
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.articles.is_empty()
+	}
+
+	#[must_use]
+	pub const fn total(&self) -> Option<u64> {
+		self.total_results
+	}
+
+	/// Articles of the page; results without a title or URL, or taken down, are skipped. The
+	/// publication that ran the article is kept in the `publisher` extra.
+	#[must_use]
+	pub fn into_articles(self, source: &str) -> Vec<Article> {
+		self.articles
+			.into_iter()
+			.filter_map(|item| {
+				let title = item
+					.title
+					.filter(|title| !title.trim().is_empty() && title != REMOVED_TITLE)?;
+				let url = item.url.filter(|url| !url.trim().is_empty())?;
+				let article = Article::new(title, url, source.to_string())
+					.with_published_at(item.published_at)
+					.with_author(item.author.filter(|author| !author.trim().is_empty()));
+				let article = match item.description.or(item.content) {
+					Some(description) => article.with_description(description),
+					None => article,
+				};
+				Some(match item.source.and_then(|publisher| publisher.name) {
+					Some(publisher) => article.with_extra("publisher", Extra::Text(publisher)),
+					None => article,
+				})
+			})
+			.collect()
+	}
+}
+
+/// Requests sent to the provider on one UTC day, kept in a file so that every run of the day draws
+/// on the same quota
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Quota {
+	day: NaiveDate,
+	used: u32,
+}
+
+impl Quota {
+	/// Today's count; a missing or unreadable file, or one from another day, starts from zero
+	#[must_use]
+	pub fn load(path: &Path, today: NaiveDate) -> Self {
+		fs::read(path)
+			.ok()
+			.and_then(|json| serde_json::from_slice::<Self>(&json).ok())
+			.filter(|quota| quota.day == today)
+			.unwrap_or(Self { day: today, used: 0 })
+	}
+
+	/// # Errors
+	///
+	/// `IoError` when the quota file cannot be written
+	pub fn save(&self, path: &Path) -> Result<()> {
+		if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+			fs::create_dir_all(parent).map_err(|e| AppError::io_error(parent, e))?;
+		}
+		let json = serde_json::to_vec(self).map_err(|e| AppError::parse_error("news API quota", e))?;
+		fs::write(path, json).map_err(|e| AppError::io_error(path, e))
+	}
+
+	#[must_use]
+	pub const fn used(&self) -> u32 {
+		self.used
+	}
+
+	#[must_use]
+	pub const fn remaining(&self, limit: u32) -> u32 {
+		limit.saturating_sub(self.used)
+	}
+
+	pub const fn record(&mut self, requests: u32) {
+		self.used = self.used.saturating_add(requests);
+	}
+
+	/// The provider refused a request for the quota: nothing more today
+	pub fn exhaust(&mut self, limit: u32) {
+		self.used = self.used.max(limit);
+	}
+}
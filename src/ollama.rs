@@ -0,0 +1,185 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::OllamaConfig;
+use crate::error::{AppError, Result};
+use futures::{StreamExt, stream};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+use tracing::{info, warn};
+
+/// Longest free-form tag kept, in characters
+const MAX_TAG_CHARS: usize = 40;
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+	model: &'a str,
+	messages: [ChatMessage<'a>; 2],
+	stream: bool,
+	format: &'a str,
+	options: serde_json::Value,
+}
+
+#[derive(Serialize)]
+struct ChatMessage<'a> {
+	role: &'a str,
+	content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+	message: ChatReply,
+}
+
+#[derive(Deserialize)]
+struct ChatReply {
+	content: String,
+}
+
+#[derive(Deserialize)]
+struct Tags {
+	#[serde(default)]
+	tags: Vec<String>,
+}
+
+/// Instructions for the model: pick from the labels when there are any, otherwise name the topics
+fn system_prompt(config: &OllamaConfig) -> String {
+	let choice = if config.labels.is_empty() {
+		"short lowercase topic tags".to_string()
+	} else {
+		format!("labels from this list only: {}", config.labels.join(", "))
+	};
+	format!(
+		"You tag news articles for a reader. Reply with a JSON object {{\"tags\": [...]}} holding at most {} {choice}.",
+		config.max_tags
+	)
+}
+
+/// Ask the model for the tags of one article
+async fn request_tags(
+	client: &Client,
+	config: &OllamaConfig,
+	system: &str,
+	scored: &ScoredArticle,
+) -> Result<Vec<String>> {
+	let article = scored.article();
+	let prompt = format!(
+		"Title: {}\nSource: {}\n{}",
+		article.title(),
+		article.source(),
+		article.description().unwrap_or_default()
+	);
+	let url = format!("{}/api/chat", config.base_url.trim_end_matches('/'));
+	let request = ChatRequest {
+		model: &config.model,
+		messages: [
+			ChatMessage {
+				role: "system",
+				content: system,
+			},
+			ChatMessage {
+				role: "user",
+				content: &prompt,
+			},
+		],
+		stream: false,
+		format: "json",
+		options: json!({ "temperature": 0 }),
+	};
+	let response = timeout(
+		Duration::from_secs(config.timeout_secs),
+		client.post(&url).json(&request).send(),
+	)
+	.await
+	.map_err(|_| AppError::TimeoutError(url.clone()))?
+	.and_then(reqwest::Response::error_for_status)
+	.map_err(|e| AppError::http_error(&url, e))?
+	.json::<ChatResponse>()
+	.await
+	.map_err(|e| AppError::parse_error("Ollama response", e))?;
+	let tags: Tags =
+		serde_json::from_str(&response.message.content).map_err(|e| AppError::parse_error("Ollama tag list", e))?;
+	Ok(clean_tags(tags.tags, config))
+}
+
+/// Lowercased, trimmed tags without repeats, at most `max_tags` of them. With labels configured, only
+/// the labels the model picked are kept, spelled as configured.
+fn clean_tags(tags: Vec<String>, config: &OllamaConfig) -> Vec<String> {
+	let mut cleaned: Vec<String> = Vec::new();
+	for tag in tags {
+		let tag = tag.trim().trim_start_matches('#').trim();
+		let tag = if config.labels.is_empty() {
+			if tag.is_empty() || tag.chars().count() > MAX_TAG_CHARS {
+				continue;
+			}
+			tag.to_lowercase()
+		} else {
+			let Some(label) = config.labels.iter().find(|label| label.eq_ignore_ascii_case(tag)) else {
+				continue;
+			};
+			label.clone()
+		};
+		if !cleaned.contains(&tag) {
+			cleaned.push(tag);
+		}
+		if cleaned.len() >= config.max_tags {
+			break;
+		}
+	}
+	cleaned
+}
+
+/// Tag the leading `top_k` articles, a few at a time, for as long as the budget lasts. Tags naming a
+/// configured category also put the article in it. Failures only leave articles untagged.
+pub async fn tag_top(client: &Client, config: &OllamaConfig, categories: &[String], scored: &mut [ScoredArticle]) {
+	let count = config.top_k.min(scored.len());
+	let Some(top) = scored.get_mut(..count) else {
+		return;
+	};
+	let system = system_prompt(config);
+	let budget = Duration::from_secs(config.budget_secs);
+	let results: Vec<(usize, Result<Vec<String>>)> = stream::iter(top.iter().enumerate())
+		.map(|(index, item)| {
+			let system = &system;
+			async move { (index, request_tags(client, config, system, item).await) }
+		})
+		.buffer_unordered(config.concurrency)
+		.take_until(sleep(budget))
+		.collect()
+		.await;
+
+	let answered = results.len();
+	if answered < count {
+		warn!(
+			answered,
+			articles = count,
+			budget_secs = config.budget_secs,
+			"Ollama tagging budget ran out"
+		);
+	}
+	let mut tagged = 0usize;
+	for (index, result) in results {
+		let Some(item) = top.get_mut(index) else {
+			continue;
+		};
+		match result {
+			Ok(tags) if !tags.is_empty() => {
+				let mut in_categories = item.categories().to_vec();
+				for tag in &tags {
+					if let Some(category) = categories.iter().find(|category| category.eq_ignore_ascii_case(tag))
+						&& !in_categories.contains(category)
+					{
+						in_categories.push(category.clone());
+					}
+				}
+				item.set_categories(in_categories);
+				item.add_tags(&tags);
+				tagged = tagged.saturating_add(1);
+			}
+			Ok(_) => {}
+			Err(e) => warn!(url = item.article().url(), error = %e, "Ollama tagging failed"),
+		}
+	}
+	info!(articles = count, tagged, model = %config.model, "Tagged top articles with Ollama");
+}
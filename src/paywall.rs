@@ -0,0 +1,69 @@
+use crate::config::PaywallConfig;
+use crate::filter::{domains, host, matches_domain};
+use crate::model::Article;
+use scraper::{Html, Selector};
+use serde_json::Value;
+
+/// Tags articles on known paywalled domains; pages fetched in full are also checked with `sniff`
+#[derive(Debug)]
+pub struct Paywall {
+	domains: Vec<String>,
+}
+
+impl Paywall {
+	/// `None` when paywall tagging is disabled
+	#[must_use]
+	pub fn new(config: &PaywallConfig) -> Option<Self> {
+		config.enabled.then(|| Self {
+			domains: domains(&config.domains),
+		})
+	}
+
+	/// Tag the articles linking to a paywalled domain, returning how many were newly tagged
+	pub fn tag(&self, articles: &mut [Article]) -> usize {
+		let mut tagged = 0usize;
+		for article in articles {
+			if !article.paywalled() && matches_domain(&host(article.url()), &self.domains) {
+				article.mark_paywalled();
+				tagged = tagged.saturating_add(1);
+			}
+		}
+		tagged
+	}
+}
+
+/// Whether a page's markup declares restricted access: schema.org `isAccessibleForFree: false`
+/// in its JSON-LD, a locked or metered `article:content_tier`, or an element named after a paywall
+#[must_use]
+pub fn sniff(document: &Html) -> bool {
+	let select = |selector: &str| Selector::parse(selector).ok();
+	let json_ld = select(r#"script[type="application/ld+json"]"#).is_some_and(|selector| {
+		document.select(&selector).any(|script| {
+			serde_json::from_str::<Value>(&script.text().collect::<String>()).is_ok_and(|value| restricted(&value))
+		})
+	});
+	let content_tier = select(r#"meta[property="article:content_tier"], meta[name="article:content_tier"]"#)
+		.is_some_and(|selector| {
+			document
+				.select(&selector)
+				.filter_map(|meta| meta.value().attr("content"))
+				.any(|tier| ["locked", "metered"].contains(&tier.trim().to_ascii_lowercase().as_str()))
+		});
+	let marker = select(r#"[class*="paywall"], [id*="paywall"]"#)
+		.is_some_and(|selector| document.select(&selector).next().is_some());
+	json_ld || content_tier || marker
+}
+
+/// Whether a JSON-LD value, or any object nested in it, sets `isAccessibleForFree` to false
+fn restricted(value: &Value) -> bool {
+	match value {
+		Value::Object(object) => object.iter().any(|(key, value)| {
+			(key == "isAccessibleForFree"
+				&& (value == &Value::Bool(false)
+					|| value.as_str().is_some_and(|free| free.eq_ignore_ascii_case("false"))))
+				|| restricted(value)
+		}),
+		Value::Array(values) => values.iter().any(restricted),
+		_ => false,
+	}
+}
@@ -0,0 +1,911 @@
+use crate::analyzer::{Analyzer, ScoredArticle};
+use crate::classifier::{self, Classifier};
+#[cfg(feature = "semantic")]
+use crate::config::SemanticBackend;
+use crate::config::{Config, GroupBy};
+use crate::content;
+use crate::discussion;
+use crate::display::{display_errors, display_results};
+use crate::diversity;
+use crate::entities::{self, EntityExtractor};
+use crate::error::{AppError, Result};
+use crate::fetcher::Fetcher;
+use crate::filter::ArticleFilter;
+use crate::history;
+use crate::language;
+use crate::link_check;
+use crate::metadata;
+use crate::metrics::Metrics;
+use crate::model::Article;
+use crate::paywall::Paywall;
+use crate::run::RunContext;
+use crate::sanitize;
+#[cfg(feature = "scripting")]
+use crate::scripting::Scripts;
+#[cfg(feature = "semantic")]
+use crate::semantic::{LocalModel, Session};
+use crate::server;
+use crate::sink::{self, DeliveryLedger, Sink};
+use crate::site;
+use crate::store::Store;
+use crate::summarizer;
+use crate::thumbnails;
+use crate::wayback;
+use reqwest::Client;
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::fs;
+use std::mem;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// Compiled scorers kept for this many distinct configurations, e.g. the profiles of a daemon's users
+const CACHED_SCORERS: usize = 8;
+
+/// What scoring compiles from the configuration: the keyword automata and score formula, the category
+/// classifier, the entity gazetteer and the embedding model of the `local` semantic backend
+pub struct Scorers {
+	analyzer: Analyzer,
+	classifier: Option<Classifier>,
+	entities: Option<EntityExtractor>,
+	#[cfg(feature = "semantic")]
+	embedding_model: Option<Arc<LocalModel>>,
+}
+
+impl Scorers {
+	fn new(config: &Config) -> Result<Self> {
+		Ok(Self {
+			analyzer: Analyzer::new(config)?,
+			classifier: (!config.categories.is_empty())
+				.then(|| Classifier::new(&config.categories))
+				.transpose()?,
+			entities: (!config.entities.names.is_empty())
+				.then(|| EntityExtractor::new(&config.entities))
+				.transpose()?,
+			#[cfg(feature = "semantic")]
+			embedding_model: (config.semantic.enabled && config.semantic.backend == SemanticBackend::Local)
+				.then(|| Arc::new(LocalModel::new(&config.semantic))),
+		})
+	}
+}
+
+/// Scorers compiled for recently used configurations, shared by the cycles of a daemon so the automata and
+/// regex sets are only rebuilt when the settings they come from change
+#[derive(Clone, Default)]
+pub struct ScorerCache(Arc<Mutex<Vec<CachedScorers>>>);
+
+/// Scoring settings as JSON, to compare with, and the scorers compiled from them
+type CachedScorers = (serde_json::Value, Arc<Scorers>);
+
+impl ScorerCache {
+	/// The scorers for `config`, compiled now unless its keywords, score formula, categories, entities and
+	/// local embedding model match a cached configuration's
+	///
+	/// # Errors
+	///
+	/// When compiling the scorers fails, as in `Analyzer::new`
+	pub fn get(&self, config: &Config) -> Result<Arc<Scorers>> {
+		let key = serde_json::to_value((
+			&config.keywords,
+			&config.analyzer.score_formula,
+			&config.categories,
+			&config.entities,
+			(
+				config.semantic.enabled,
+				config.semantic.backend,
+				&config.semantic.local_model,
+				&config.semantic.model_dir,
+				config.semantic.batch_size,
+			),
+		))
+		.map_err(|e| AppError::parse_error("scoring settings", e))?;
+		let mut cached = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+		if let Some(position) = cached.iter().position(|(settings, _)| *settings == key) {
+			// Most recently used last, so the oldest is evicted first
+			let entry = cached.remove(position);
+			let scorers = Arc::clone(&entry.1);
+			cached.push(entry);
+			drop(cached);
+			debug!("Reusing the compiled scorers");
+			return Ok(scorers);
+		}
+		let scorers = Arc::new(Scorers::new(config)?);
+		if cached.len() >= CACHED_SCORERS {
+			cached.remove(0);
+		}
+		cached.push((key, Arc::clone(&scorers)));
+		drop(cached);
+		debug!("Compiled the scorers");
+		Ok(scorers)
+	}
+}
+
+/// The aggregation pipeline: fetch -> normalize -> dedupe -> score -> rank -> output.
+/// Each stage can also be run on its own, e.g. to re-score previously fetched articles.
+pub struct Pipeline<'a> {
+	config: &'a Config,
+	client: &'a Client,
+	cancel_token: &'a CancellationToken,
+	metrics: &'a Metrics,
+	filter: ArticleFilter,
+	paywall: Option<Paywall>,
+	scorers: Arc<Scorers>,
+	#[cfg(feature = "semantic")]
+	semantic: tokio::sync::Mutex<Session<'a>>,
+	#[cfg(feature = "scripting")]
+	scripts: Option<Scripts>,
+}
+
+impl<'a> Pipeline<'a> {
+	/// The scorers come from `scorers` when it compiled them for the same settings before.
+	///
+	/// # Errors
+	///
+	/// When a filter or category pattern is invalid, or `scripting` is enabled and a script cannot be read or
+	/// compiled
+	pub fn new(
+		config: &'a Config,
+		client: &'a Client,
+		cancel_token: &'a CancellationToken,
+		metrics: &'a Metrics,
+		scorers: &ScorerCache,
+	) -> Result<Self> {
+		#[cfg(not(feature = "scripting"))]
+		if config.scripting.enabled {
+			warn!("scripting.enabled is set but the binary was built without the `scripting` feature");
+		}
+		let scorers = scorers.get(config)?;
+		Ok(Self {
+			config,
+			client,
+			cancel_token,
+			metrics,
+			filter: ArticleFilter::new(config)?,
+			paywall: Paywall::new(&config.paywall),
+			#[cfg(feature = "semantic")]
+			semantic: tokio::sync::Mutex::new(Session::new(client, &config.semantic, scorers.embedding_model.clone())),
+			scorers,
+			#[cfg(feature = "scripting")]
+			scripts: config
+				.scripting
+				.enabled
+				.then(|| Scripts::load(&config.scripting))
+				.transpose()?,
+		})
+	}
+
+	/// # Errors
+	///
+	/// As `Fetcher::fetch_all`
+	pub async fn fetch(fetcher: &Arc<Fetcher>, run: &RunContext) -> Result<Vec<Article>> {
+		let start = Instant::now();
+		let articles = fetcher.fetch_all(run).await?;
+		run.record_stage("fetch", articles.len(), start.elapsed());
+		info!(count = articles.len(), "Fetched articles successfully");
+		Ok(articles)
+	}
+
+	/// Reduce titles and descriptions to plain text, detect languages and drop articles in unwanted ones,
+	/// tag paywalled articles, drop those matching
+	/// the `[filters]` rules, then run the `filter` and `transform` script hooks
+	pub fn normalize(&self, mut articles: Vec<Article>) -> Vec<Article> {
+		sanitize::sanitize_articles(&mut articles, self.config.analyzer.max_description_chars);
+		language::detect_languages(&mut articles);
+		let before = articles.len();
+		language::filter_languages(&mut articles, &self.config.analyzer.languages);
+		if articles.len() < before {
+			info!(
+				dropped = before.saturating_sub(articles.len()),
+				"Dropped articles in unwanted languages"
+			);
+		}
+		if let Some(paywall) = &self.paywall {
+			let tagged = paywall.tag(&mut articles);
+			if tagged > 0 {
+				info!(tagged, "Tagged paywalled articles");
+			}
+		}
+		for (rule, dropped) in self.filter.apply(&mut articles) {
+			self.metrics.record_articles_filtered(rule, dropped);
+			info!(rule, dropped, "Dropped articles by filter rule");
+		}
+		#[cfg(feature = "scripting")]
+		if let Some(scripts) = &self.scripts {
+			let before = articles.len();
+			articles = scripts.on_fetched(articles);
+			if articles.len() < before {
+				info!(
+					dropped = before.saturating_sub(articles.len()),
+					"Dropped articles by script filter"
+				);
+			}
+		}
+		articles
+	}
+
+	/// Drop articles whose id is already in `seen`, keeping the first occurrence
+	fn dedupe(seen: &mut HashSet<String>, mut articles: Vec<Article>) -> Vec<Article> {
+		let before = articles.len();
+		articles.retain(|article| seen.insert(article.id()));
+		if articles.len() < before {
+			info!(
+				dropped = before.saturating_sub(articles.len()),
+				"Dropped duplicate articles"
+			);
+		}
+		articles
+	}
+
+	/// Keyword scoring plus semantic and learned blending, classification, entity extraction and the `adjust_score` script hook
+	///
+	/// # Errors
+	///
+	/// `ShutdownError` when scoring is interrupted, or the error of the semantic or script stage
+	pub async fn score(&self, articles: Vec<Article>) -> Result<Vec<ScoredArticle>> {
+		let config = self.config;
+		// Keep the runtime's other tasks, such as the fetchers, going while rayon scores the chunk
+		let analyzer = &self.scorers.analyzer;
+		let mut scored = tokio::task::block_in_place(|| analyzer.score(articles, config, self.cancel_token))?;
+
+		if config.semantic.enabled {
+			self.apply_semantic(&mut scored).await;
+		}
+
+		if config.learning.enabled {
+			apply_learning(config, &mut scored);
+		}
+
+		if let Some(classifier) = &self.scorers.classifier {
+			classifier.classify_all(&mut scored);
+			classifier::filter_categories(&mut scored, &config.display.categories);
+		}
+
+		if let Some(entities) = &self.scorers.entities {
+			entities.extract_all(&mut scored);
+		}
+
+		#[cfg(feature = "scripting")]
+		if let Some(scripts) = &self.scripts {
+			scripts.adjust_scores(&mut scored);
+		}
+
+		Ok(scored)
+	}
+
+	/// Semantic scoring of a chunk, sharing the interest embeddings and the embedding budget of the run
+	#[cfg(feature = "semantic")]
+	async fn apply_semantic(&self, scored: &mut [ScoredArticle]) {
+		if let Err(e) = self.semantic.lock().await.apply(scored).await {
+			warn!(error = %e, "Semantic scoring failed, keeping keyword scores");
+		}
+	}
+
+	#[cfg(not(feature = "semantic"))]
+	#[allow(clippy::unused_async, clippy::unused_self)]
+	async fn apply_semantic(&self, _scored: &mut [ScoredArticle]) {
+		warn!("semantic.enabled is set but the binary was built without the `semantic` feature");
+	}
+
+	/// Filter, order and cap the scored articles, then enrich the top of the ranking.
+	/// A dry run skips the enrichment that acts on other services: link checks, the LLM and Wayback Machine saves.
+	/// Only the leading articles that are shown or enriched are put in order, picked with a bounded heap;
+	/// the rest follow in no particular order and are only passed on to the sinks.
+	pub async fn rank(&self, scored: Vec<ScoredArticle>) -> Vec<ScoredArticle> {
+		let config = self.config;
+
+		// Filter out low-relevance scores, then select the top
+		let min_score = config.display.min_score;
+		let mut top = TopK::new(self.ranked_prefix());
+		let mut rest = Vec::new();
+		for article in scored {
+			if article.relevance_score() >= min_score {
+				rest.extend(top.push(article));
+			}
+		}
+		let mut scored = top.into_vec();
+		scored.append(&mut rest);
+		classifier::apply_category_limits(&mut scored, &config.categories);
+		if config.diversity.enabled {
+			let top_n = config.diversity.top_n.unwrap_or(config.display.limit);
+			let moved = diversity::rerank(&mut scored, &config.diversity, top_n);
+			if moved > 0 {
+				info!(moved, top_n, "Re-ranked the top articles for diversity");
+			}
+		}
+		let promoted = classifier::reserve_category_slots(&mut scored, &config.categories, config.display.limit);
+		if promoted > 0 {
+			info!(promoted, "Moved articles up into slots reserved for their categories");
+		}
+		if let Some(max_per_domain) = config.display.max_per_domain {
+			let moved = diversity::cap_domains(&mut scored, max_per_domain, config.display.limit);
+			if moved > 0 {
+				info!(
+					moved,
+					max_per_domain, "Moved articles below the ranking to cap articles per domain"
+				);
+			}
+		}
+
+		let dry_run = config.delivery.dry_run;
+		if dry_run
+			&& (config.link_check.enabled
+				|| config.llm.enabled
+				|| (config.wayback.enabled && config.wayback.save_missing))
+		{
+			info!("Dry run: skipping link checks, LLM enrichment and Wayback Machine saves");
+		}
+
+		if config.link_check.enabled && !dry_run {
+			link_check::check_links(self.client, &config.link_check, self.metrics, &mut scored).await;
+		}
+
+		if config.content.enabled {
+			content::attach_read_times(self.client, &config.content, &mut scored).await;
+			let dropped = content::apply_read_time_rules(&config.content, &mut scored);
+			if dropped > 0 {
+				info!(dropped, "Dropped articles outside the reading time limits");
+			}
+		}
+
+		if config.summarizer.enabled {
+			summarizer::summarize_top(&mut scored, &config.summarizer);
+		}
+
+		if config.llm.enabled && !dry_run {
+			enhance_with_llm(self.client, config, &mut scored).await;
+		}
+
+		if config.ollama.enabled {
+			tag_with_ollama(self.client, config, &mut scored).await;
+		}
+
+		if config.thumbnails.enabled {
+			thumbnails::attach_thumbnails(self.client, &config.thumbnails, &mut scored).await;
+		}
+
+		if config.wayback.enabled {
+			wayback::attach_snapshots(self.client, &config.wayback, !dry_run, &mut scored).await;
+		}
+
+		if config.discussions.enabled {
+			discussion::attach_discussions(self.client, &config.discussions, &mut scored).await;
+		}
+
+		#[cfg(feature = "scripting")]
+		if let Some(scripts) = &self.scripts {
+			scripts.decorate(&mut scored);
+		}
+
+		scored
+	}
+
+	/// How many leading articles of the ranking have to be in score order: those displayed or enriched.
+	/// Grouped displays, diversity re-ranking, domain caps and per-category limits and reserved slots look at
+	/// the whole ranking.
+	fn ranked_prefix(&self) -> Option<usize> {
+		let config = self.config;
+		if config.display.group_by != GroupBy::None
+			|| config.diversity.enabled
+			|| config.display.max_per_domain.is_some()
+			|| config
+				.categories
+				.values()
+				.any(|c| c.limit.is_some() || c.min_slots.is_some())
+		{
+			return None;
+		}
+		let mut prefix = config.display.limit;
+		if config.link_check.enabled && !config.delivery.dry_run {
+			prefix = prefix.max(config.link_check.top_n);
+		}
+		if config.content.enabled {
+			prefix = prefix.max(config.content.top_n);
+		}
+		if config.summarizer.enabled {
+			prefix = prefix.max(config.summarizer.top_n);
+		}
+		if config.llm.enabled && !config.delivery.dry_run {
+			prefix = prefix.max(config.llm.max_articles);
+		}
+		if config.ollama.enabled {
+			prefix = prefix.max(config.ollama.top_k);
+		}
+		if config.thumbnails.enabled {
+			prefix = prefix.max(config.thumbnails.top_n);
+		}
+		if config.wayback.enabled {
+			prefix = prefix.max(config.wayback.top_n);
+		}
+		if config.discussions.enabled {
+			prefix = prefix.max(config.discussions.top_n);
+		}
+		Some(prefix)
+	}
+
+	/// Compare the ranking with the previous run of this profile and record it, when `store.compare_runs` is on
+	///
+	/// # Errors
+	///
+	/// `StorageError` when the store cannot be read or written
+	pub fn compare_with_previous(
+		&self,
+		store: &mut Store,
+		run: &RunContext,
+		scored: &mut Vec<ScoredArticle>,
+	) -> Result<()> {
+		let config = self.config;
+		if !config.store.compare_runs {
+			return Ok(());
+		}
+		let profile = config.profile.as_deref().unwrap_or_default();
+		tokio::task::block_in_place(|| {
+			history::compare_with_previous(store, profile, run, scored, config.display.limit, config.store.only_new)
+		})
+	}
+
+	/// Display the ranking and deliver it, or with `delivery.dry_run` only log what would be delivered; with
+	/// `delivery.dedupe` the store keeps sinks from getting an article twice
+	pub async fn output(
+		&self,
+		run: &RunContext,
+		sinks: &[Box<dyn Sink>],
+		store: Option<&mut Store>,
+		scored: &[ScoredArticle],
+	) {
+		display_results(scored, self.config);
+		entities::report(scored, self.config.entities.report_top);
+		if self.config.display.show_errors {
+			display_errors(&run.record().errors);
+		}
+		if self.config.delivery.dry_run {
+			sink::preview_all(sinks, &self.with_click_urls(scored));
+			return;
+		}
+		let mut store = store;
+		if let Some(store) = store.as_deref_mut().filter(|_| self.config.thumbnails.enabled) {
+			self.store_thumbnails(store, run, scored);
+		}
+		let ledger = store
+			.as_deref_mut()
+			.filter(|_| self.config.delivery.dedupe)
+			.map(|store| DeliveryLedger::new(store, &self.config.delivery));
+		sink::deliver_all(sinks, run, &self.with_click_urls(scored), ledger).await;
+		if let Some(store) = store.filter(|_| self.config.site.enabled) {
+			self.publish_site(store, run);
+		}
+	}
+
+	/// The articles linking to the click-counting redirect instead of themselves, when `[clicks]` is enabled
+	fn with_click_urls<'s>(&self, scored: &'s [ScoredArticle]) -> Cow<'s, [ScoredArticle]> {
+		if !self.config.clicks.enabled {
+			return Cow::Borrowed(scored);
+		}
+		Cow::Owned(
+			scored
+				.iter()
+				.map(|item| {
+					let mut item = item.clone();
+					item.set_click_url(server::click_url(&self.config.clicks, item.article()));
+					item
+				})
+				.collect(),
+		)
+	}
+
+	/// Keep the thumbnails found for the top articles in the store, for the static site of later runs
+	fn store_thumbnails(&self, store: &mut Store, run: &RunContext, scored: &[ScoredArticle]) {
+		let articles: Vec<Article> = scored
+			.iter()
+			.take(self.config.thumbnails.top_n)
+			.map(ScoredArticle::article)
+			.filter(|article| article.image_url().is_some())
+			.cloned()
+			.collect();
+		if articles.is_empty() {
+			return;
+		}
+		if let Err(e) = tokio::task::block_in_place(|| store.save_articles(&articles, run.started_at())) {
+			warn!(error = %e, "Failed to store article thumbnails");
+		}
+	}
+
+	/// Regenerate the static site from every ranking the store keeps, including this run's
+	fn publish_site(&self, store: &Store, run: &RunContext) {
+		let config = &self.config.site;
+		let profile = self.config.profile.as_deref().unwrap_or_default();
+		let result = tokio::task::block_in_place(|| {
+			let contents = store.dump()?;
+			site::publish(config, &contents, profile, run.started_at())
+		});
+		match &result {
+			Ok(count) => info!(count, dir = %config.dir, "Published the static site"),
+			Err(e) => warn!(error = %e, dir = %config.dir, "Failed to publish the static site"),
+		}
+		run.record_output(
+			"site",
+			config.dir.clone(),
+			result.as_ref().ok().copied().unwrap_or_default(),
+			result.err().map(|e| e.to_string()),
+		);
+	}
+
+	/// Everything between fetching and output, timing each stage into the run record
+	///
+	/// # Errors
+	///
+	/// As `score`
+	pub async fn process(&self, run: &RunContext, articles: Vec<Article>) -> Result<Vec<ScoredArticle>> {
+		let mut enrichment = self.enrichment();
+		let articles = enrichment.push(articles).await;
+		let mut score = StageTotal::default();
+		let mut results = TopK::new(self.config.analyzer.max_results);
+		if !articles.is_empty() {
+			let start = Instant::now();
+			let scored = self.score(articles).await?;
+			score.add(scored.len(), start);
+			self.collect(&mut results, scored);
+		}
+		Ok(self.finish(run, &enrichment, &score, results).await)
+	}
+
+	/// Fetch, enrich, score and collect the ranking at the same time, each stage handing its output on
+	/// through a channel bounded by `[pipeline]`, instead of waiting for the slowest source. A stage that
+	/// falls behind fills the channel before it and holds the earlier stages back, down to the fetchers.
+	/// `on_fetched` sees every chunk before it is enriched.
+	///
+	/// # Errors
+	///
+	/// The error of the first stage to fail, or that of `on_fetched`
+	pub async fn fetch_and_process(
+		&self,
+		fetcher: &Arc<Fetcher>,
+		run: &RunContext,
+		mut on_fetched: impl FnMut(&[Article]) -> Result<()>,
+	) -> Result<Vec<ScoredArticle>> {
+		let capacities = &self.config.pipeline;
+		let chunk_size = capacities.chunk_size;
+		let (fetched_tx, mut fetched_rx) = mpsc::channel(capacities.fetched_capacity);
+		let (enriched_tx, mut enriched_rx) = mpsc::channel::<Vec<Article>>(capacities.enriched_capacity);
+		let (scored_tx, mut scored_rx) = mpsc::channel::<Vec<ScoredArticle>>(capacities.scored_capacity);
+		let fetch = async {
+			let start = Instant::now();
+			let count = fetcher.fetch_into(run, fetched_tx).await?;
+			run.record_stage("fetch", count, start.elapsed());
+			info!(count, "Fetched articles successfully");
+			Ok(())
+		};
+		let enrich = async move {
+			let mut enrichment = self.enrichment();
+			let mut chunk = Vec::with_capacity(chunk_size);
+			while fetched_rx.recv_many(&mut chunk, chunk_size).await > 0 {
+				let articles = mem::take(&mut chunk);
+				on_fetched(&articles)?;
+				let articles = enrichment.push(articles).await;
+				// A closed channel means scoring failed, and its error is the one reported
+				if !articles.is_empty() && enriched_tx.send(articles).await.is_err() {
+					break;
+				}
+			}
+			Ok(enrichment)
+		};
+		let score = async move {
+			let mut score = StageTotal::default();
+			while let Some(articles) = enriched_rx.recv().await {
+				let start = Instant::now();
+				let scored = self.score(articles).await?;
+				score.add(scored.len(), start);
+				if scored_tx.send(scored).await.is_err() {
+					break;
+				}
+			}
+			Ok(score)
+		};
+		let collect = async {
+			let mut results = TopK::new(self.config.analyzer.max_results);
+			while let Some(scored) = scored_rx.recv().await {
+				self.collect(&mut results, scored);
+			}
+			results
+		};
+		let (fetch_result, enrichment, score, results): (
+			Result<()>,
+			Result<Enrichment<'_, 'a>>,
+			Result<StageTotal>,
+			_,
+		) = tokio::join!(fetch, enrich, score, collect);
+		// A failed stage closes the channel into it, so its error explains a failure of the stages before
+		// it rather than the other way round
+		let score = score?;
+		let enrichment = enrichment?;
+		fetch_result?;
+		Ok(self.finish(run, &enrichment, &score, results).await)
+	}
+
+	fn enrichment(&self) -> Enrichment<'_, 'a> {
+		Enrichment {
+			pipeline: self,
+			seen: HashSet::new(),
+			received: 0,
+			normalize: StageTotal::default(),
+			dedupe: StageTotal::default(),
+			metadata: StageTotal::default(),
+			metadata_budget: self.config.metadata.max_requests,
+		}
+	}
+
+	/// Keep the scored articles that reach `display.min_score`; beyond `analyzer.max_results` the
+	/// lowest-scoring article is dropped
+	fn collect(&self, results: &mut TopK, scored: Vec<ScoredArticle>) {
+		let min_score = self.config.display.min_score;
+		for article in scored {
+			if article.relevance_score() >= min_score {
+				let _ = results.push(article);
+			}
+		}
+	}
+
+	/// Record the time spent per stage and rank the collected articles
+	async fn finish(
+		&self,
+		run: &RunContext,
+		enrichment: &Enrichment<'_, '_>,
+		score: &StageTotal,
+		results: TopK,
+	) -> Vec<ScoredArticle> {
+		if enrichment.received == 0 {
+			warn!("No articles to process");
+			return Vec::new();
+		}
+		let metadata = self.config.metadata.enabled;
+		for (stage, total) in [
+			("normalize", &enrichment.normalize),
+			("dedupe", &enrichment.dedupe),
+			("metadata", &enrichment.metadata),
+			("score", score),
+		] {
+			if stage != "metadata" || metadata {
+				run.record_stage(stage, total.articles, total.elapsed);
+			}
+		}
+
+		let start = Instant::now();
+		let ranked = self.rank(results.into_vec()).await;
+		run.record_stage("rank", ranked.len(), start.elapsed());
+		ranked
+	}
+}
+
+/// A run's articles going through normalize, dedupe and page metadata one chunk at a time
+struct Enrichment<'p, 'a> {
+	pipeline: &'p Pipeline<'a>,
+	seen: HashSet<String>,
+	received: usize,
+	normalize: StageTotal,
+	dedupe: StageTotal,
+	/// Enriched from page metadata, see `[metadata]`
+	metadata: StageTotal,
+	/// Pages the metadata stage may still fetch this run
+	metadata_budget: usize,
+}
+
+/// Output and time of a stage summed over every chunk
+#[derive(Default)]
+struct StageTotal {
+	articles: usize,
+	elapsed: Duration,
+}
+
+impl StageTotal {
+	fn add(&mut self, articles: usize, start: Instant) {
+		self.articles = self.articles.saturating_add(articles);
+		self.elapsed = self.elapsed.saturating_add(start.elapsed());
+	}
+}
+
+impl Enrichment<'_, '_> {
+	/// The chunk's articles that are new to the run, ready to score
+	async fn push(&mut self, articles: Vec<Article>) -> Vec<Article> {
+		self.received = self.received.saturating_add(articles.len());
+
+		let start = Instant::now();
+		let articles = self.pipeline.normalize(articles);
+		self.normalize.add(articles.len(), start);
+
+		let start = Instant::now();
+		let mut articles = Pipeline::dedupe(&mut self.seen, articles);
+		self.dedupe.add(articles.len(), start);
+
+		let config = self.pipeline.config;
+		if !articles.is_empty() && config.metadata.enabled && self.metadata_budget > 0 {
+			let start = Instant::now();
+			metadata::enrich(
+				self.pipeline.client,
+				&config.metadata,
+				config.analyzer.max_description_chars,
+				&mut articles,
+				&mut self.metadata_budget,
+			)
+			.await;
+			self.metadata.add(articles.len(), start);
+		}
+		articles
+	}
+}
+
+/// The best-scoring articles seen so far, at most `capacity` of them when set
+struct TopK {
+	capacity: Option<usize>,
+	heap: BinaryHeap<Reverse<ScoredArticle>>,
+}
+
+impl TopK {
+	const fn new(capacity: Option<usize>) -> Self {
+		Self {
+			capacity,
+			heap: BinaryHeap::new(),
+		}
+	}
+
+	/// Add an article, handing back the lowest-scoring one once over capacity
+	#[must_use]
+	fn push(&mut self, article: ScoredArticle) -> Option<ScoredArticle> {
+		self.heap.push(Reverse(article));
+		if self.capacity.is_some_and(|capacity| self.heap.len() > capacity) {
+			// The root of the reversed heap is the lowest score
+			return self.heap.pop().map(|Reverse(article)| article);
+		}
+		None
+	}
+
+	/// Best-ranked first, see the `Ord` of `ScoredArticle`
+	fn into_vec(self) -> Vec<ScoredArticle> {
+		self.heap
+			.into_sorted_vec()
+			.into_iter()
+			.map(|Reverse(article)| article)
+			.collect()
+	}
+}
+
+/// Write fetched articles as a JSON array for later `pipeline score --input`
+///
+/// # Errors
+///
+/// `IoError` when the file cannot be written
+pub fn save_articles(path: &Path, articles: &[Article]) -> Result<()> {
+	if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+		fs::create_dir_all(parent).map_err(|e| AppError::io_error(parent, e))?;
+	}
+	let json = serde_json::to_vec_pretty(articles).map_err(|e| AppError::parse_error("raw articles", e))?;
+	fs::write(path, json).map_err(|e| AppError::io_error(path, e))
+}
+
+/// # Errors
+///
+/// `IoError` when the file cannot be read, `ParseError` when it is not a saved article list
+pub fn load_articles(path: &Path) -> Result<Vec<Article>> {
+	let json = fs::read(path).map_err(|e| AppError::io_error(path, e))?;
+	serde_json::from_slice(&json).map_err(|e| AppError::parse_error(path.display().to_string(), e))
+}
+
+#[cfg(feature = "learning")]
+fn apply_learning(config: &Config, scored: &mut [ScoredArticle]) {
+	tokio::task::block_in_place(|| crate::learning::apply(&config.learning, scored));
+}
+
+#[cfg(not(feature = "learning"))]
+fn apply_learning(_config: &Config, _scored: &mut [ScoredArticle]) {
+	warn!("learning.enabled is set but the binary was built without the `learning` feature");
+}
+
+#[cfg(feature = "llm")]
+async fn enhance_with_llm(client: &Client, config: &Config, scored: &mut [ScoredArticle]) {
+	crate::llm::enhance(client, &config.llm, scored).await;
+}
+
+#[cfg(not(feature = "llm"))]
+#[allow(clippy::unused_async)]
+async fn enhance_with_llm(_client: &Client, _config: &Config, _scored: &mut [ScoredArticle]) {
+	warn!("llm.enabled is set but the binary was built without the `llm` feature");
+}
+
+#[cfg(feature = "ollama")]
+async fn tag_with_ollama(client: &Client, config: &Config, scored: &mut [ScoredArticle]) {
+	let categories: Vec<String> = config.categories.keys().cloned().collect();
+	crate::ollama::tag_top(client, &config.ollama, &categories, scored).await;
+}
+
+#[cfg(not(feature = "ollama"))]
+#[allow(clippy::unused_async)]
+async fn tag_with_ollama(_client: &Client, _config: &Config, _scored: &mut [ScoredArticle]) {
+	warn!("ollama.enabled is set but the binary was built without the `ollama` feature");
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::analyzer::score_articles;
+	use crate::config;
+
+	type TestResult = std::result::Result<(), Box<dyn std::error::Error>>;
+
+	/// Articles with many equal scores, some of them also published at the same time or undated, so that
+	/// the URL tie-break decides which of them make a cut
+	fn tied_articles() -> Result<Vec<ScoredArticle>> {
+		let config = config::starter()?;
+		let first = "2026-10-01T08:00:00Z".parse().ok();
+		let second = "2026-10-02T08:00:00Z".parse().ok();
+		let titles = [
+			"Rust async runtimes",
+			"Rust",
+			"Rust async runtimes",
+			"Gardening",
+			"Rust",
+		];
+		let articles = [first, first, None, second, first, None, second]
+			.into_iter()
+			.cycle()
+			.zip(titles.into_iter().cycle())
+			.take(40)
+			.enumerate()
+			.map(|(index, (published_at, title))| {
+				Article::new(
+					title.into(),
+					format!("https://blog.example/{index}"),
+					"Example Engineering".into(),
+				)
+				.with_published_at(published_at)
+			})
+			.collect();
+		score_articles(articles, &config, &CancellationToken::new())
+	}
+
+	fn urls(articles: &[ScoredArticle]) -> Vec<&str> {
+		articles.iter().map(|article| article.article().url()).collect()
+	}
+
+	#[test]
+	fn top_k_keeps_the_same_top_articles_as_sorting_and_truncating() -> TestResult {
+		let articles = tied_articles()?;
+		assert_eq!(articles.len(), 40);
+		let mut sorted = articles.clone();
+		sorted.sort_by(|a, b| b.cmp(a));
+		let tied = |a: &ScoredArticle, b: &ScoredArticle| {
+			a.relevance_score().total_cmp(&b.relevance_score()).is_eq()
+				&& a.article().published_at() == b.article().published_at()
+		};
+		assert!(sorted.iter().zip(sorted.iter().skip(1)).any(|(a, b)| tied(a, b)));
+
+		for capacity in [1, 3, 7, 12, 39, 40, 100] {
+			let mut top = TopK::new(Some(capacity));
+			let dropped = articles.iter().cloned().filter_map(|article| top.push(article)).count();
+			let kept = top.into_vec();
+			assert_eq!(
+				urls(&kept),
+				urls(sorted.get(..capacity.min(sorted.len())).unwrap_or_default()),
+				"capacity {capacity}"
+			);
+			assert_eq!(dropped, articles.len().saturating_sub(capacity));
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn top_k_without_a_capacity_keeps_everything_in_ranking_order() -> TestResult {
+		let articles = tied_articles()?;
+		let mut sorted = articles.clone();
+		sorted.sort_by(|a, b| b.cmp(a));
+
+		let mut top = TopK::new(None);
+		for article in articles {
+			assert!(top.push(article).is_none());
+		}
+		assert_eq!(urls(&top.into_vec()), urls(&sorted));
+		Ok(())
+	}
+}
@@ -0,0 +1,122 @@
+use crate::error::{AppError, Result};
+use crate::model::{Article, Extra};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+/// Most voted posts of a topic since a given time, with what an article needs
+const POSTS_QUERY: &str = "query Posts($topic: String!, $first: Int!, $after: DateTime!) {
+  posts(topic: $topic, order: VOTES, first: $first, postedAfter: $after) {
+    edges { node {
+      id name tagline description url website votesCount commentsCount createdAt
+      topics(first: 5) { edges { node { slug } } }
+    } }
+  }
+}";
+
+/// Tag of every Product Hunt launch
+const LAUNCH_TAG: &str = "launch";
+
+#[derive(Debug, Deserialize)]
+struct Response {
+	data: Option<Data>,
+	#[serde(default)]
+	errors: Vec<GraphQlError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlError {
+	message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Data {
+	posts: Connection<Post>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Connection<T> {
+	edges: Vec<Edge<T>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Edge<T> {
+	node: T,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Post {
+	id: String,
+	name: String,
+	tagline: String,
+	description: Option<String>,
+	url: String,
+	website: Option<String>,
+	votes_count: u64,
+	comments_count: u64,
+	created_at: Option<DateTime<Utc>>,
+	topics: Option<Connection<Topic>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Topic {
+	slug: String,
+}
+
+/// GraphQL request body for the `limit` most voted posts of `topic` posted after `after`
+#[must_use]
+pub fn posts_query(topic: &str, limit: usize, after: DateTime<Utc>) -> Value {
+	json!({
+		"query": POSTS_QUERY,
+		"variables": { "topic": topic, "first": limit, "after": after.to_rfc3339() },
+	})
+}
+
+/// Articles of a posts query response, most voted first. A launch links to its Product Hunt page,
+/// carries its votes and comments as engagement and its tagline as the start of the description.
+///
+/// # Errors
+///
+/// `ParseError` when the body is not a posts query response
+pub fn parse_posts(body: &[u8], url: &str, source: &str) -> Result<Vec<Article>> {
+	let response: Response = serde_json::from_slice(body).map_err(|e| AppError::parse_error(url, e))?;
+	let data = match response.data {
+		Some(data) if response.errors.is_empty() => data,
+		_ => {
+			let messages: Vec<String> = response.errors.into_iter().map(|error| error.message).collect();
+			return Err(AppError::parse_error(url, messages.join("; ")));
+		}
+	};
+	Ok(data
+		.posts
+		.edges
+		.into_iter()
+		.map(|edge| post_article(edge.node, source))
+		.collect())
+}
+
+fn post_article(post: Post, source: &str) -> Article {
+	let description = match post.description.as_deref().map(str::trim) {
+		Some(description) if !description.is_empty() => format!("{}. {description}", post.tagline),
+		_ => post.tagline,
+	};
+	let tags = std::iter::once(LAUNCH_TAG.to_string())
+		.chain(
+			post.topics
+				.into_iter()
+				.flat_map(|topics| topics.edges)
+				.map(|edge| edge.node.slug),
+		)
+		.collect();
+	let article = Article::new(post.name, post.url, source.to_string())
+		.with_description(description)
+		.with_engagement(Some(post.votes_count), Some(post.comments_count))
+		.with_published_at(post.created_at)
+		.with_tags(tags)
+		.with_guid(post.id);
+	match post.website {
+		Some(website) => article.with_extra("website", Extra::Text(website)),
+		None => article,
+	}
+}
@@ -1,6 +1,10 @@
-use crate::error::Result;
+use crate::error::{AppError, Result};
+use governor::clock::{Clock, DefaultClock};
+use governor::state::keyed::DefaultKeyedStateStore;
 use governor::{Quota, RateLimiter as GovernorLimiter};
 use std::num::NonZeroU32;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 
 type DirectLimiter = GovernorLimiter<
     governor::state::direct::NotKeyed,
@@ -8,21 +12,62 @@ type DirectLimiter = GovernorLimiter<
     governor::clock::DefaultClock,
 >;
 
+type KeyedLimiter = GovernorLimiter<String, DefaultKeyedStateStore<String>, DefaultClock>;
+
+/// Clients tracked before those whose limit has recovered are forgotten
+const MAX_CLIENTS: usize = 10_000;
+
 pub struct RateLimiter {
     limiter: DirectLimiter,
 }
 
 impl RateLimiter {
+    #[must_use]
     pub fn new(requests_per_second: u32) -> Self {
-        let quota =
-            Quota::per_second(NonZeroU32::new(requests_per_second).expect("Rate must be non-zero"));
+        // Config validation rejects a zero rate; fall back to the slowest quota regardless
+        let quota = Quota::per_second(NonZeroU32::new(requests_per_second).unwrap_or(NonZeroU32::MIN));
         Self {
             limiter: GovernorLimiter::direct(quota),
         }
     }
 
-    pub async fn wait(&self) -> Result<()> {
-        self.limiter.until_ready().await;
-        Ok(())
+    /// Wait for a permit, giving up with `ShutdownError` once `cancel_token` is cancelled
+    ///
+    /// # Errors
+    ///
+    /// `ShutdownError` when `cancel_token` is cancelled while waiting
+    pub async fn wait(&self, cancel_token: &CancellationToken) -> Result<()> {
+        cancel_token
+            .run_until_cancelled(self.limiter.until_ready())
+            .await
+            .ok_or(AppError::ShutdownError)
+    }
+}
+
+/// Separate request limits per client of the HTTP endpoint
+pub struct ClientRateLimiter {
+    limiter: KeyedLimiter,
+}
+
+impl ClientRateLimiter {
+    #[must_use]
+    pub fn per_minute(requests: NonZeroU32) -> Self {
+        Self {
+            limiter: GovernorLimiter::keyed(Quota::per_minute(requests)),
+        }
+    }
+
+    /// Take a permit for `client`, or tell how long until the next one
+    ///
+    /// # Errors
+    ///
+    /// The time until `client` gets its next permit
+    pub fn check(&self, client: &str) -> std::result::Result<(), Duration> {
+        if self.limiter.len() > MAX_CLIENTS {
+            self.limiter.retain_recent();
+        }
+        self.limiter
+            .check_key(&client.to_string())
+            .map_err(|not_until| not_until.wait_time_from(self.limiter.clock().now()))
     }
 }
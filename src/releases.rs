@@ -0,0 +1,85 @@
+use crate::model::{Article, Extra};
+use chrono::{DateTime, NaiveDate, Utc};
+use regex::Regex;
+
+/// Notes of one release are summarized by their first few entries
+const SUMMARY_ENTRIES: usize = 5;
+
+/// The latest `limit` released versions in Rust's `RELEASES.md`, newest first, one article each.
+///
+/// Releases are the `Version x.y.z (yyyy-mm-dd)` headings. A version dated after `now` has only been
+/// merged ahead of its release and is skipped. Each article links to its section of the notes at
+/// `url`, summarizes it by its first entries and carries `tags`.
+#[must_use]
+pub fn parse_releases(
+	body: &str,
+	url: &str,
+	source: &str,
+	tags: &[&str],
+	limit: usize,
+	now: DateTime<Utc>,
+) -> Vec<Article> {
+	// `[text](url)` links of the Markdown notes are reduced to their text
+	let link = Regex::new(r"\[([^\]]*)\]\([^)]*\)").ok();
+	let lines: Vec<&str> = body.lines().collect();
+	let headings: Vec<(usize, &str, DateTime<Utc>)> = lines
+		.iter()
+		.enumerate()
+		.filter_map(|(index, line)| {
+			let (version, date) = parse_heading(line)?;
+			Some((index, version, date))
+		})
+		.filter(|&(_, _, date)| date <= now)
+		.take(limit)
+		.collect();
+
+	headings
+		.iter()
+		.map(|&(index, version, date)| {
+			let section = lines
+				.iter()
+				.skip(index.saturating_add(1))
+				.take_while(|line| parse_heading(line).is_none());
+			let entries: Vec<String> = section
+				.filter_map(|line| line.trim_start().strip_prefix("- "))
+				.take(SUMMARY_ENTRIES)
+				.map(|entry| {
+					link.as_ref()
+						.map_or_else(|| entry.to_string(), |link| link.replace_all(entry, "$1").into_owned())
+				})
+				.collect();
+			let heading = lines.get(index).map_or("", |line| line.trim());
+			let article = Article::new(
+				format!("Rust {version} release notes"),
+				format!("{url}#{}", anchor(heading)),
+				source.to_string(),
+			)
+			.with_published_at(Some(date))
+			.with_tags(tags.iter().map(ToString::to_string).collect())
+			.with_guid(format!("rust-{version}"))
+			.with_extra("rust_version", Extra::Text(version.to_string()));
+			if entries.is_empty() {
+				article
+			} else {
+				article.with_description(entries.join("; "))
+			}
+		})
+		.collect()
+}
+
+/// Version and date of a `Version 1.82.0 (2024-10-17)` heading
+fn parse_heading(line: &str) -> Option<(&str, DateTime<Utc>)> {
+	let (version, date) = line.trim().strip_prefix("Version ")?.split_once(' ')?;
+	let date = date.strip_prefix('(')?.strip_suffix(')')?;
+	let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0)?;
+	Some((version, date.and_utc()))
+}
+
+/// GitHub's anchor for a Markdown heading: lowercased, punctuation dropped, spaces as hyphens
+fn anchor(heading: &str) -> String {
+	heading
+		.chars()
+		.filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '-' || *c == '_')
+		.map(|c| if c == ' ' { '-' } else { c.to_ascii_lowercase() })
+		.collect()
+}
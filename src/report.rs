@@ -0,0 +1,89 @@
+use crate::config::{Config, ReportConfig};
+use crate::error::{AppError, Result};
+use crate::run::{self, RunContext, RunRecord};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+
+/// Machine-readable summary of one run, for finding out after the fact why a digest looked the way it did
+#[derive(Debug, Serialize)]
+pub struct RunReport<'a> {
+	run_id: &'a str,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	profile: Option<&'a str>,
+	started_at: DateTime<Utc>,
+	finished_at: DateTime<Utc>,
+	duration_ms: u64,
+	/// `ok`, `cancelled` or `failed`
+	status: &'static str,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	error: Option<String>,
+	/// SHA-256 of the effective configuration, to tell whether two runs used the same settings
+	config_hash: String,
+	#[serde(flatten)]
+	record: RunRecord,
+}
+
+impl<'a> RunReport<'a> {
+	pub fn new(run: &'a RunContext, config: &'a Config, result: &Result<()>) -> Self {
+		let finished_at = Utc::now();
+		let (status, error) = match result {
+			Ok(()) => ("ok", None),
+			Err(AppError::ShutdownError) => ("cancelled", None),
+			Err(e) => ("failed", Some(e.to_string())),
+		};
+		Self {
+			run_id: run.id(),
+			profile: config.profile.as_deref(),
+			started_at: run.started_at(),
+			finished_at,
+			duration_ms: finished_at
+				.signed_duration_since(run.started_at())
+				.to_std()
+				.map_or(0, run::millis),
+			status,
+			error,
+			config_hash: config_hash(config),
+			record: run.record(),
+		}
+	}
+
+	/// The report as pretty-printed JSON
+	///
+	/// # Errors
+	///
+	/// `ParseError` when the report does not serialize
+	pub fn to_json(&self) -> Result<Vec<u8>> {
+		serde_json::to_vec_pretty(self).map_err(|e| AppError::parse_error("run report", e))
+	}
+
+	/// Write the report to `report.path`, with `{run_id}` replaced by the run id
+	///
+	/// # Errors
+	///
+	/// `IoError` when the report cannot be written
+	pub fn write(&self, config: &ReportConfig) -> Result<PathBuf> {
+		let path = PathBuf::from(config.path.replace("{run_id}", self.run_id));
+		if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+			fs::create_dir_all(parent).map_err(|e| AppError::io_error(parent, e))?;
+		}
+		fs::write(&path, self.to_json()?).map_err(|e| AppError::io_error(&path, e))?;
+		Ok(path)
+	}
+}
+
+/// Hash of the configuration serialized with sorted keys, so map ordering does not change it
+fn config_hash(config: &Config) -> String {
+	let canonical = serde_json::to_value(config)
+		.and_then(|value| serde_json::to_vec(&value))
+		.unwrap_or_default();
+	Sha256::digest(&canonical)
+		.iter()
+		.fold(String::with_capacity(64), |mut hex, byte| {
+			let _ = write!(hex, "{byte:02x}");
+			hex
+		})
+}
@@ -0,0 +1,187 @@
+use crate::config::HttpConfig;
+use crate::error::{AppError, Result};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+
+/// How failed requests are retried, shared by every source.
+///
+/// Delays grow exponentially from `http.retry_delay_ms` with jitter, so concurrent requests that failed
+/// together do not retry in lockstep. Retrying stops after `http.retry_attempts` attempts, once the next
+/// delay would overrun `http.retry_max_elapsed_secs`, or as soon as an error is not worth retrying.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+	max_attempts: u32,
+	base_delay: Duration,
+	max_elapsed: Option<Duration>,
+}
+
+impl RetryPolicy {
+	pub fn new(config: &HttpConfig) -> Self {
+		Self {
+			max_attempts: config.retry_attempts,
+			base_delay: Duration::from_millis(config.retry_delay_ms),
+			max_elapsed: config.retry_max_elapsed_secs.map(Duration::from_secs),
+		}
+	}
+
+	/// Delay before retry number `attempt` (1-based): half of `base * 2^(attempt - 1)` plus a random share of the other half
+	#[must_use]
+	pub fn backoff(&self, attempt: u32) -> Duration {
+		let exponential = self
+			.base_delay
+			.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+		exponential.mul_f64(fastrand::f64().mul_add(0.5, 0.5))
+	}
+
+	/// Run `operation` until it succeeds or the policy gives up, returning the last error in that case.
+	/// `on_retry` sees each retried error with its attempt number and the delay before the next attempt.
+	///
+	/// # Errors
+	///
+	/// The last error of `operation` once the policy gives up, or at once when it is not retryable
+	pub async fn run<T, F, Fut>(
+		&self,
+		cancel_token: &CancellationToken,
+		mut operation: F,
+		on_retry: impl Fn(&AppError, u32, Duration),
+	) -> Result<T>
+	where
+		F: FnMut() -> Fut,
+		Fut: Future<Output = Result<T>>,
+	{
+		let started = Instant::now();
+		let mut attempts: u32 = 0;
+		loop {
+			if cancel_token.is_cancelled() {
+				return Err(AppError::ShutdownError);
+			}
+
+			let error = match operation().await {
+				Ok(value) => return Ok(value),
+				Err(error) => error,
+			};
+			attempts = attempts.saturating_add(1);
+			if attempts >= self.max_attempts || !error.is_retryable() {
+				return Err(error);
+			}
+
+			let delay = self.backoff(attempts);
+			if self
+				.max_elapsed
+				.is_some_and(|max| started.elapsed().saturating_add(delay) > max)
+			{
+				return Err(error);
+			}
+			on_retry(&error, attempts, delay);
+			cancel_token
+				.run_until_cancelled(sleep(delay))
+				.await
+				.ok_or(AppError::ShutdownError)?;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::atomic::{AtomicU32, Ordering};
+
+	type TestResult = std::result::Result<(), Box<dyn std::error::Error>>;
+
+	const BASE: Duration = Duration::from_millis(100);
+
+	fn policy(max_attempts: u32, base_delay: Duration, max_elapsed: Option<Duration>) -> RetryPolicy {
+		RetryPolicy {
+			max_attempts,
+			base_delay,
+			max_elapsed,
+		}
+	}
+
+	/// Run `policy` against an operation failing with `error` every time, returning how often it was called
+	async fn attempts(policy: &RetryPolicy, error: fn() -> AppError) -> u32 {
+		let calls = AtomicU32::new(0);
+		let result: Result<()> = policy
+			.run(
+				&CancellationToken::new(),
+				|| {
+					calls.fetch_add(1, Ordering::SeqCst);
+					async move { Err(error()) }
+				},
+				|_, _, _| {},
+			)
+			.await;
+		assert!(result.is_err());
+		calls.load(Ordering::SeqCst)
+	}
+
+	fn timeout() -> AppError {
+		AppError::TimeoutError("https://blog.example/feed.xml".into())
+	}
+
+	#[test]
+	fn each_delay_is_between_half_and_all_of_the_exponential_delay() {
+		fastrand::seed(7);
+		let policy = policy(10, BASE, None);
+		let mut full = BASE;
+		for attempt in 1..=8 {
+			let half = full.checked_div(2).unwrap_or_default();
+			for _ in 0..100 {
+				let delay = policy.backoff(attempt);
+				assert!(delay >= half && delay <= full, "attempt {attempt}: {delay:?}");
+			}
+			full = full.saturating_mul(2);
+		}
+	}
+
+	#[test]
+	fn the_same_seed_gives_the_same_delays() {
+		let policy = policy(10, BASE, None);
+		let delays = |seed| {
+			fastrand::seed(seed);
+			(1..=5).map(|attempt| policy.backoff(attempt)).collect::<Vec<_>>()
+		};
+		assert_eq!(delays(42), delays(42));
+	}
+
+	#[tokio::test]
+	async fn retrying_stops_after_max_attempts() {
+		fastrand::seed(7);
+		assert_eq!(attempts(&policy(3, Duration::from_millis(1), None), timeout).await, 3);
+		assert_eq!(attempts(&policy(1, Duration::from_millis(1), None), timeout).await, 1);
+	}
+
+	#[tokio::test]
+	async fn retrying_stops_when_the_next_delay_would_pass_max_elapsed() {
+		fastrand::seed(7);
+		// The first delay is at least 5 seconds, past the 1 second allowed
+		let policy = policy(5, Duration::from_secs(10), Some(Duration::from_secs(1)));
+		assert_eq!(attempts(&policy, timeout).await, 1);
+	}
+
+	#[tokio::test]
+	async fn an_error_not_worth_retrying_fails_at_once() {
+		let error = || AppError::ConfigError("bad".into());
+		assert_eq!(attempts(&policy(5, Duration::from_millis(1), None), error).await, 1);
+	}
+
+	#[tokio::test]
+	async fn a_success_after_failures_is_returned() -> TestResult {
+		let calls = AtomicU32::new(0);
+		let value = policy(3, Duration::from_millis(1), None)
+			.run(
+				&CancellationToken::new(),
+				|| {
+					let call = calls.fetch_add(1, Ordering::SeqCst).saturating_add(1);
+					let result = if call < 3 { Err(timeout()) } else { Ok(call) };
+					async move { result }
+				},
+				|_, _, _| {},
+			)
+			.await?;
+		assert_eq!(value, 3);
+		Ok(())
+	}
+}
@@ -0,0 +1,230 @@
+use crate::error::AppError;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fmt;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Identity of a single aggregator run, stamped onto everything it emits.
+/// Clones share one record of what happened during the run, used for the run report.
+#[derive(Debug, Clone)]
+pub struct RunContext {
+	id: String,
+	started_at: DateTime<Utc>,
+	record: Arc<Mutex<RunRecord>>,
+}
+
+/// Outcome of fetching one source
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceRecord {
+	pub source: String,
+	pub articles: usize,
+	pub duration_ms: u64,
+	/// Cut short by `fetcher.source_timeout_secs`; `articles` is what arrived before the deadline
+	pub truncated: bool,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+	/// `AppError::kind` of the error, e.g. `http_status` or `selector`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error_kind: Option<&'static str>,
+}
+
+/// Time spent in a pipeline stage and the number of articles it produced
+#[derive(Debug, Clone, Serialize)]
+pub struct StageRecord {
+	pub stage: &'static str,
+	pub articles: usize,
+	pub duration_ms: u64,
+}
+
+/// Outcome of delivering to one sink
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputRecord {
+	pub sink: String,
+	pub destination: String,
+	pub articles: usize,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+/// How many error messages each `ErrorGroup` keeps
+const MAX_ERROR_EXAMPLES: usize = 3;
+
+/// Non-fatal errors of a run, counted by source, scope and kind
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunErrors {
+	pub total: usize,
+	pub groups: Vec<ErrorGroup>,
+}
+
+/// What a non-fatal error cost the run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorScope {
+	/// One article, page or child sitemap was skipped
+	Item,
+	/// The whole source failed
+	Source,
+}
+
+/// Errors of one kind and scope in one source
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorGroup {
+	pub source: String,
+	pub scope: ErrorScope,
+	/// `AppError::kind` shared by the errors
+	pub kind: &'static str,
+	pub count: usize,
+	/// Messages of the first errors
+	pub examples: Vec<String>,
+}
+
+impl RunErrors {
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.total == 0
+	}
+
+	fn add(&mut self, source: &str, scope: ErrorScope, error: &AppError) {
+		if matches!(error, AppError::ShutdownError) {
+			return;
+		}
+		self.total = self.total.saturating_add(1);
+		let kind = error.kind();
+		let position = self
+			.groups
+			.iter()
+			.position(|group| group.source == source && group.scope == scope && group.kind == kind)
+			.unwrap_or_else(|| {
+				self.groups.push(ErrorGroup {
+					source: source.to_string(),
+					scope,
+					kind,
+					count: 0,
+					examples: Vec::new(),
+				});
+				self.groups.len().saturating_sub(1)
+			});
+		if let Some(group) = self.groups.get_mut(position) {
+			group.count = group.count.saturating_add(1);
+			if group.examples.len() < MAX_ERROR_EXAMPLES {
+				group.examples.push(error.to_string());
+			}
+		}
+	}
+}
+
+/// One line per group, e.g. "3 `HackerNews` items failed (parse)"
+impl fmt::Display for ErrorGroup {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.scope {
+			ErrorScope::Item if self.count == 1 => write!(f, "1 {} item failed ({})", self.source, self.kind),
+			ErrorScope::Item => write!(f, "{} {} items failed ({})", self.count, self.source, self.kind),
+			ErrorScope::Source => write!(f, "{} failed ({})", self.source, self.kind),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunRecord {
+	pub sources: Vec<SourceRecord>,
+	pub stages: Vec<StageRecord>,
+	pub outputs: Vec<OutputRecord>,
+	#[serde(skip_serializing_if = "RunErrors::is_empty")]
+	pub errors: RunErrors,
+}
+
+impl RunContext {
+	#[must_use]
+	pub fn new() -> Self {
+		let started_at = Utc::now();
+		let id = format!("{}-{:x}", started_at.format("%Y%m%dT%H%M%S%3fZ"), process::id());
+		Self {
+			id,
+			started_at,
+			record: Arc::default(),
+		}
+	}
+
+	#[must_use]
+	pub fn id(&self) -> &str {
+		&self.id
+	}
+
+	#[must_use]
+	pub const fn started_at(&self) -> DateTime<Utc> {
+		self.started_at
+	}
+
+	pub fn record_source(
+		&self,
+		source: &str,
+		articles: usize,
+		duration: Duration,
+		truncated: bool,
+		error: Option<&AppError>,
+	) {
+		let record = SourceRecord {
+			source: source.to_string(),
+			articles,
+			duration_ms: millis(duration),
+			truncated,
+			error: error.map(ToString::to_string),
+			error_kind: error.map(AppError::kind),
+		};
+		self.update(|run| {
+			if let Some(error) = error {
+				run.errors.add(source, ErrorScope::Source, error);
+			}
+			run.sources.push(record);
+		});
+	}
+
+	/// Count an error that cost `source` one article, page or child sitemap but not the whole fetch
+	pub fn record_item_error(&self, source: &str, error: &AppError) {
+		self.update(|run| run.errors.add(source, ErrorScope::Item, error));
+	}
+
+	pub fn record_stage(&self, stage: &'static str, articles: usize, duration: Duration) {
+		let record = StageRecord {
+			stage,
+			articles,
+			duration_ms: millis(duration),
+		};
+		self.update(|run| run.stages.push(record));
+	}
+
+	pub fn record_output(&self, sink: &str, destination: String, articles: usize, error: Option<String>) {
+		let record = OutputRecord {
+			sink: sink.to_string(),
+			destination,
+			articles,
+			error,
+		};
+		self.update(|run| run.outputs.push(record));
+	}
+
+	/// Snapshot of everything recorded so far
+	#[must_use]
+	pub fn record(&self) -> RunRecord {
+		self.record.lock().map(|run| run.clone()).unwrap_or_default()
+	}
+
+	fn update(&self, f: impl FnOnce(&mut RunRecord)) {
+		if let Ok(mut run) = self.record.lock() {
+			f(&mut run);
+		}
+	}
+}
+
+impl Default for RunContext {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[must_use]
+pub fn millis(duration: Duration) -> u64 {
+	u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
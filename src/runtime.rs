@@ -0,0 +1,25 @@
+use crate::config::RuntimeConfig;
+use crate::error::{AppError, Result};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::runtime::{Builder, Runtime};
+
+/// The multi-threaded async runtime everything runs on, sized and named by `[runtime]`
+///
+/// # Errors
+///
+/// `ConfigError` when the runtime cannot be started
+pub fn build(config: &RuntimeConfig) -> Result<Runtime> {
+	let mut builder = Builder::new_multi_thread();
+	builder.enable_all().max_blocking_threads(config.max_blocking_threads);
+	if let Some(threads) = config.worker_threads {
+		builder.worker_threads(threads);
+	}
+	// Workers and blocking threads share the name, numbered in the order they start
+	let name = config.thread_name.clone();
+	let started = Arc::new(AtomicUsize::new(0));
+	builder.thread_name_fn(move || format!("{name}-{}", started.fetch_add(1, Ordering::Relaxed)));
+	builder
+		.build()
+		.map_err(|e| AppError::ConfigError(format!("can not start the async runtime: {e}")))
+}
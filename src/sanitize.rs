@@ -0,0 +1,83 @@
+use crate::model::Article;
+use rayon::prelude::*;
+use scraper::{Html, Node};
+
+/// Elements whose boundaries separate words even without whitespace in the markup
+const BLOCK_ELEMENTS: &[&str] = &[
+	"address", "article", "br", "dd", "div", "dl", "dt", "footer", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr",
+	"li", "ol", "p", "pre", "section", "table", "td", "th", "tr", "ul",
+];
+
+/// Turn titles and descriptions into plain text before filtering and scoring, so markup and entities
+/// neither match keywords nor leak into the output.
+///
+/// Descriptions are cut to `max_description_chars` characters when set, and dropped when nothing is left
+/// of them.
+pub fn sanitize_articles(articles: &mut [Article], max_description_chars: Option<usize>) {
+	articles.par_iter_mut().for_each(|article| {
+		let title = plain_text(article.title());
+		if !title.is_empty() {
+			article.set_title(title);
+		}
+		let description = article
+			.description()
+			.map(plain_text)
+			.filter(|description| !description.is_empty())
+			.map(|description| match max_description_chars {
+				Some(max) => truncate(&description, max),
+				None => description,
+			});
+		article.set_description(description);
+	});
+}
+
+/// Text of an HTML fragment with tags stripped, entities decoded and whitespace collapsed
+#[must_use]
+pub fn plain_text(html: &str) -> String {
+	if !html.contains(['<', '&']) {
+		return html.split_whitespace().collect::<Vec<_>>().join(" ");
+	}
+	let fragment = Html::parse_fragment(html);
+	let mut text = String::with_capacity(html.len());
+	for node in fragment.root_element().descendants() {
+		match node.value() {
+			Node::Text(chunk) => text.push_str(chunk),
+			Node::Element(element) if BLOCK_ELEMENTS.contains(&element.name()) => text.push(' '),
+			_ => {}
+		}
+	}
+	text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `text` with the characters that are markup in HTML replaced by entities
+#[must_use]
+pub fn escape_html(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&#39;"),
+			_ => escaped.push(c),
+		}
+	}
+	escaped
+}
+
+/// At most `max` characters of `text`, cut at a word boundary where there is one and marked with an ellipsis
+pub fn truncate(text: &str, max: usize) -> String {
+	if text.chars().count() <= max {
+		return text.to_string();
+	}
+	let kept: String = text.chars().take(max.saturating_sub(1)).collect();
+	let cut = kept
+		.rfind(char::is_whitespace)
+		.and_then(|end| kept.get(..end))
+		.unwrap_or(&kept);
+	format!(
+		"{}…",
+		cut.trim_end_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation())
+	)
+}
@@ -0,0 +1,85 @@
+use crate::config::ScraperConfig;
+use crate::error::{AppError, Result};
+use crate::model::Article;
+use reqwest::Url;
+use scraper::{ElementRef, Html, Selector};
+use tracing::warn;
+
+/// Articles listed on a page, found with the scraper's CSS selectors.
+///
+/// Links are resolved against the page URL. Articles without a title or link are skipped; a page
+/// yielding none at all is an error, as it usually means the site's layout changed.
+///
+/// # Errors
+///
+/// `SelectorError` for an invalid selector, `LayoutChangedError` when the page yields no article
+pub fn scrape(scraper: &ScraperConfig, body: &str) -> Result<Vec<Article>> {
+	let source = scraper.name.as_str();
+	let selector = |selector: &str| {
+		Selector::parse(selector).map_err(|e| AppError::SelectorError {
+			source_name: source.into(),
+			selector: selector.into(),
+			message: e.to_string(),
+		})
+	};
+	let article_selector = selector(&scraper.article_selector)?;
+	let title_selector = selector(&scraper.title_selector)?;
+	let link_selector = scraper.link_selector.as_deref().map(selector).transpose()?;
+	let description_selector = scraper.description_selector.as_deref().map(selector).transpose()?;
+	let base = Url::parse(&scraper.url).map_err(|e| AppError::parse_error(source, e))?;
+
+	let document = Html::parse_document(body);
+	let mut articles = Vec::new();
+	let mut matched: usize = 0;
+	for element in document.select(&article_selector) {
+		matched = matched.saturating_add(1);
+		let Some(title_elem) = element.select(&title_selector).next() else {
+			continue;
+		};
+		let title = text(title_elem);
+		let link_elem = link_selector
+			.as_ref()
+			.map_or(Some(title_elem), |link_selector| element.select(link_selector).next());
+		let Some(url) = link_elem
+			.and_then(|link| link.value().attr("href"))
+			.filter(|href| !href.trim().is_empty())
+			.and_then(|href| base.join(href.trim()).ok())
+		else {
+			warn!(source, title, "Skipping article without a link");
+			continue;
+		};
+		if title.is_empty() {
+			warn!(source, url = %url, "Skipping article with empty title");
+			continue;
+		}
+
+		let article = Article::new(title, url.into(), source.to_string());
+		let description = description_selector
+			.as_ref()
+			.and_then(|selector| element.select(selector).next())
+			.map(text)
+			.filter(|description| !description.is_empty());
+		articles.push(match description {
+			Some(description) => article.with_description(description),
+			None => article,
+		});
+	}
+
+	if articles.is_empty() {
+		return Err(AppError::LayoutChangedError {
+			source_name: source.into(),
+			selector: scraper.article_selector.clone(),
+			matched,
+		});
+	}
+	Ok(articles)
+}
+
+/// Text of an element with whitespace runs collapsed
+fn text(element: ElementRef<'_>) -> String {
+	element
+		.text()
+		.flat_map(str::split_whitespace)
+		.collect::<Vec<_>>()
+		.join(" ")
+}
@@ -0,0 +1,141 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::ScriptingConfig;
+use crate::error::{AppError, Result};
+use crate::model::Article;
+use rhai::serde::{from_dynamic, to_dynamic};
+use rhai::{AST, CallFnOptions, Dynamic, Engine, Scope};
+use std::fs;
+use tracing::warn;
+
+/// Rhai hooks for rules too personal for static configuration.
+///
+/// Scripts are compiled once per run and may define any of these functions:
+/// - `filter(article)`: after fetching; returning `false` drops the article
+/// - `transform(article)`: after fetching; returns the article map with any fields changed
+/// - `adjust_score(item)`: after scoring; returns the new score, or `()` to drop the article
+/// - `decorate(item)`: after ranking; a returned string is attached to the article as its note
+///
+/// `article` is a map of the article's fields (`title`, `url`, `source`, `description`, `points`, ...);
+/// `item` holds the scored article with `article`, `relevance_score`, `matched_keywords`, `categories`
+/// and `entities`. Only functions are called, top-level statements are not run. A hook that fails
+/// logs a warning and leaves the article as it was.
+pub struct Scripts {
+	engine: Engine,
+	ast: AST,
+}
+
+impl Scripts {
+	/// # Errors
+	///
+	/// `ConfigError` when a script cannot be read or compiled
+	pub fn load(config: &ScriptingConfig) -> Result<Self> {
+		let mut engine = Engine::new();
+		engine.set_max_operations(config.max_operations);
+		let mut ast = AST::empty();
+		for path in &config.scripts {
+			let source = fs::read_to_string(path).map_err(|e| AppError::io_error(path, e))?;
+			let script = engine
+				.compile(source)
+				.map_err(|e| AppError::ConfigError(format!("script {path}: {e}")))?;
+			ast.combine(script);
+		}
+		Ok(Self { engine, ast })
+	}
+
+	/// Apply the `filter` and `transform` hooks to freshly fetched articles
+	pub fn on_fetched(&self, articles: Vec<Article>) -> Vec<Article> {
+		let filter = self.defines("filter");
+		let transform = self.defines("transform");
+		if !filter && !transform {
+			return articles;
+		}
+		articles
+			.into_iter()
+			.filter(|article| !filter || self.call("filter", article, article.url()).unwrap_or(true))
+			.map(|article| {
+				if !transform {
+					return article;
+				}
+				self.call::<Dynamic>("transform", &article, article.url())
+					.and_then(|result| {
+						from_dynamic(&result)
+							.map_err(|e| {
+								warn!(url = article.url(), error = %e, "Script transform returned an invalid article");
+							})
+							.ok()
+					})
+					.unwrap_or(article)
+			})
+			.collect()
+	}
+
+	/// Apply the `adjust_score` hook, dropping the articles it returns `()` for
+	pub fn adjust_scores(&self, scored: &mut Vec<ScoredArticle>) {
+		if !self.defines("adjust_score") {
+			return;
+		}
+		scored.retain_mut(|item| {
+			let url = item.article().url().to_string();
+			let Some(result) = self.call::<Dynamic>("adjust_score", item, &url) else {
+				return true;
+			};
+			if result.is_unit() {
+				return false;
+			}
+			let score = result.as_float().ok().or_else(|| {
+				let score = result.as_int().ok()?;
+				i32::try_from(score).ok().map(f64::from)
+			});
+			if let Some(score) = score {
+				item.set_relevance_score(score);
+			} else {
+				warn!(
+					url,
+					kind = result.type_name(),
+					"Script adjust_score returned neither a number nor ()"
+				);
+			}
+			true
+		});
+	}
+
+	/// Apply the `decorate` hook to the ranked articles
+	pub fn decorate(&self, scored: &mut [ScoredArticle]) {
+		if !self.defines("decorate") {
+			return;
+		}
+		for item in scored {
+			let url = item.article().url().to_string();
+			if let Some(note) = self
+				.call::<Dynamic>("decorate", item, &url)
+				.and_then(|result| result.into_string().ok())
+				.filter(|note| !note.is_empty())
+			{
+				item.set_note(note);
+			}
+		}
+	}
+
+	fn defines(&self, hook: &str) -> bool {
+		self.ast
+			.iter_functions()
+			.any(|function| function.name == hook && function.params.len() == 1)
+	}
+
+	/// Call `hook` with `value` converted to a script map, logging failures against `url`
+	fn call<T: Clone + Send + Sync + 'static>(
+		&self,
+		hook: &str,
+		value: &impl serde::Serialize,
+		url: &str,
+	) -> Option<T> {
+		let argument = to_dynamic(value)
+			.map_err(|e| warn!(hook, url, error = %e, "Failed to pass article to script"))
+			.ok()?;
+		let options = CallFnOptions::new().eval_ast(false);
+		self.engine
+			.call_fn_with_options(options, &mut Scope::new(), &self.ast, hook, (argument,))
+			.map_err(|e| warn!(hook, url, error = %e, "Script hook failed"))
+			.ok()
+	}
+}
@@ -0,0 +1,50 @@
+use crate::error::{AppError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Where a credential is read from; only the reference is kept in the configuration, never the value.
+///
+/// In TOML: `{ env = "OPENAI_API_KEY" }`, `{ file = "/run/secrets/openai" }` or
+/// `{ keyring = { service = "news_aggregator", user = "openai" } }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Secret {
+	/// Environment variable
+	Env(String),
+	/// File whose contents, without surrounding whitespace, are the secret
+	File(PathBuf),
+	/// Entry in the OS keyring (requires the `keyring` feature)
+	Keyring { service: String, user: String },
+}
+
+impl Secret {
+	/// # Errors
+	///
+	/// `ConfigError` when the variable or keyring entry is missing, `IoError` when the file cannot be read
+	pub fn resolve(&self) -> Result<String> {
+		match self {
+			Self::Env(name) => {
+				std::env::var(name).map_err(|e| AppError::ConfigError(format!("secret env var {name}: {e}")))
+			}
+			Self::File(path) => fs::read_to_string(path)
+				.map(|contents| contents.trim().to_string())
+				.map_err(|e| AppError::io_error(path, e)),
+			Self::Keyring { service, user } => read_keyring(service, user),
+		}
+	}
+}
+
+#[cfg(feature = "keyring")]
+fn read_keyring(service: &str, user: &str) -> Result<String> {
+	keyring::Entry::new(service, user)
+		.and_then(|entry| entry.get_password())
+		.map_err(|e| AppError::ConfigError(format!("keyring entry {service}/{user}: {e}")))
+}
+
+#[cfg(not(feature = "keyring"))]
+fn read_keyring(service: &str, user: &str) -> Result<String> {
+	Err(AppError::ConfigError(format!(
+		"keyring entry {service}/{user}: built without the `keyring` feature"
+	)))
+}
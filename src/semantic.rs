@@ -0,0 +1,271 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::SemanticConfig;
+use crate::error::{AppError, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "fastembed")]
+use std::path::PathBuf;
+use std::sync::Arc;
+#[cfg(feature = "fastembed")]
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+use tokio::time::timeout;
+use tracing::info;
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+	model: &'a str,
+	input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+	data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+	index: usize,
+	embedding: Vec<f32>,
+}
+
+/// Where the embeddings come from, see `semantic.backend`
+enum Embedder<'a> {
+	/// A locally served sentence-embedding model (Ollama, llama.cpp, text-embeddings-inference, ...)
+	/// speaking the OpenAI-compatible `/embeddings` API
+	Server {
+		client: &'a Client,
+		config: &'a SemanticConfig,
+	},
+	/// A model run in process
+	Local(Arc<LocalModel>),
+}
+
+impl Embedder<'_> {
+	async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+		match self {
+			Self::Server { client, config } => {
+				let mut embeddings = Vec::with_capacity(texts.len());
+				for batch in texts.chunks(config.batch_size.max(1)) {
+					embeddings.extend(embed_batch(client, config, batch).await?);
+				}
+				Ok(embeddings)
+			}
+			// Inference keeps a core busy; let the runtime's other tasks, such as the fetchers, go on meanwhile
+			Self::Local(model) => tokio::task::block_in_place(|| model.embed(texts)),
+		}
+	}
+}
+
+async fn embed_batch(client: &Client, config: &SemanticConfig, batch: &[String]) -> Result<Vec<Vec<f32>>> {
+	let url = format!("{}/embeddings", config.base_url.trim_end_matches('/'));
+	let request = EmbeddingRequest {
+		model: &config.model,
+		input: batch,
+	};
+
+	let response = timeout(
+		Duration::from_secs(config.timeout_secs),
+		client.post(&url).json(&request).send(),
+	)
+	.await
+	.map_err(|_| AppError::TimeoutError(url.clone()))?
+	.and_then(reqwest::Response::error_for_status)
+	.map_err(|e| AppError::http_error(&url, e))?
+	.json::<EmbeddingResponse>()
+	.await
+	.map_err(|e| AppError::parse_error("embedding response", e))?;
+
+	if response.data.len() != batch.len() {
+		return Err(AppError::parse_error(
+			"embedding response",
+			format!("expected {} embeddings, got {}", batch.len(), response.data.len()),
+		));
+	}
+
+	let mut data = response.data;
+	data.sort_by_key(|item| item.index);
+	Ok(data.into_iter().map(|item| item.embedding).collect())
+}
+
+/// The fastembed ONNX model of the `local` backend, loaded on first use and then kept, so that a daemon
+/// loads it once rather than every cycle
+pub struct LocalModel {
+	name: String,
+	#[cfg(feature = "fastembed")]
+	dir: PathBuf,
+	#[cfg(feature = "fastembed")]
+	batch_size: usize,
+	#[cfg(feature = "fastembed")]
+	loaded: Mutex<Option<fastembed::TextEmbedding>>,
+}
+
+impl LocalModel {
+	#[must_use]
+	pub fn new(config: &SemanticConfig) -> Self {
+		Self {
+			name: config.local_model.clone(),
+			#[cfg(feature = "fastembed")]
+			dir: PathBuf::from(&config.model_dir),
+			#[cfg(feature = "fastembed")]
+			batch_size: config.batch_size.max(1),
+			#[cfg(feature = "fastembed")]
+			loaded: Mutex::new(None),
+		}
+	}
+
+	#[cfg(feature = "fastembed")]
+	fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+		let mut loaded = self.loaded.lock().unwrap_or_else(PoisonError::into_inner);
+		let model = match loaded.take() {
+			Some(model) => model,
+			None => self.load()?,
+		};
+		loaded
+			.insert(model)
+			.embed(texts, Some(self.batch_size))
+			.map_err(|e| AppError::AnalyzerError(format!("Embedding model {}: {e:#}", self.name)))
+	}
+
+	#[cfg(not(feature = "fastembed"))]
+	fn embed(&self, _texts: &[String]) -> Result<Vec<Vec<f32>>> {
+		Err(AppError::ConfigError(format!(
+			"semantic.backend = \"local\" ({}): built without the `fastembed` feature",
+			self.name
+		)))
+	}
+
+	/// Load the ONNX Runtime library, then the model, downloading it into `model_dir` unless already there
+	#[cfg(feature = "fastembed")]
+	fn load(&self) -> Result<fastembed::TextEmbedding> {
+		use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+		use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+
+		let model = self
+			.name
+			.parse::<EmbeddingModel>()
+			.ok()
+			.or_else(|| {
+				TextEmbedding::list_supported_models()
+					.into_iter()
+					.find(|info| info.model_code.eq_ignore_ascii_case(&self.name))
+					.map(|info| info.model)
+			})
+			.ok_or_else(|| {
+				AppError::ConfigError(format!("semantic.local_model: {} is not a fastembed model", self.name))
+			})?;
+		// Where fastembed would look by itself, but failing with an error rather than a panic
+		let library = std::env::var("ORT_DYLIB_PATH")
+			.ok()
+			.filter(|path| !path.is_empty())
+			.unwrap_or_else(|| format!("{DLL_PREFIX}onnxruntime{DLL_SUFFIX}"));
+		ort::init_from(&library)
+			.map_err(|e| AppError::ConfigError(format!("ONNX Runtime library {library}: {e}")))?
+			.commit();
+
+		let start = std::time::Instant::now();
+		let loaded = TextEmbedding::try_new(
+			InitOptions::new(model)
+				.with_cache_dir(self.dir.clone())
+				.with_show_download_progress(false),
+		)
+		.map_err(|e| AppError::AnalyzerError(format!("Embedding model {}: {e:#}", self.name)))?;
+		info!(
+			model = %self.name,
+			elapsed_ms = crate::run::millis(start.elapsed()),
+			"Loaded the embedding model"
+		);
+		Ok(loaded)
+	}
+}
+
+/// Semantic scoring across the chunks of one run: the interests are embedded once, with the first chunk, and
+/// articles are embedded until `semantic.max_articles` is used up
+pub struct Session<'a> {
+	config: &'a SemanticConfig,
+	embedder: Embedder<'a>,
+	interests: Option<Vec<Vec<f32>>>,
+	/// Articles that may still be embedded this run
+	budget: usize,
+}
+
+impl<'a> Session<'a> {
+	/// Embeddings come from `local` when given, the model of the `local` backend, and from the server otherwise
+	#[must_use]
+	pub fn new(client: &'a Client, config: &'a SemanticConfig, local: Option<Arc<LocalModel>>) -> Self {
+		Self {
+			config,
+			embedder: local.map_or(Embedder::Server { client, config }, Embedder::Local),
+			interests: None,
+			budget: config.max_articles,
+		}
+	}
+
+	/// Blend each article's best cosine similarity to the configured interests into its score, for the
+	/// best keyword-scored articles of the chunk while the run's budget lasts
+	///
+	/// # Errors
+	///
+	/// When the embeddings server cannot be reached or answers with something else than embeddings, or the
+	/// local model cannot be loaded or run
+	pub async fn apply(&mut self, articles: &mut [ScoredArticle]) -> Result<()> {
+		let config = self.config;
+		if articles.is_empty() || config.interests.is_empty() || self.budget == 0 {
+			return Ok(());
+		}
+
+		let interests = match self.interests.take() {
+			Some(interests) => interests,
+			None => self.embedder.embed(&config.interests).await?,
+		};
+		let interests = &*self.interests.insert(interests);
+
+		let take = articles.len().min(self.budget);
+		if take < articles.len() {
+			// Best-ranked first
+			articles.select_nth_unstable_by(take, |a, b| b.cmp(a));
+		}
+		let (embedded, skipped) = articles.split_at_mut(take);
+		let texts: Vec<String> = embedded
+			.iter()
+			.map(|scored| scored.article().searchable_text().into_owned())
+			.collect();
+		let embeddings = self.embedder.embed(&texts).await?;
+		self.budget = self.budget.saturating_sub(take);
+
+		let mut boosted = 0usize;
+		for (scored, embedding) in embedded.iter_mut().zip(&embeddings) {
+			let similarity = interests
+				.iter()
+				.map(|interest| cosine_similarity(interest, embedding))
+				.fold(0.0, f64::max);
+			if similarity >= config.min_similarity {
+				boosted = boosted.saturating_add(1);
+			}
+			scored.blend_semantic(similarity, config.min_similarity, config.weight);
+		}
+
+		info!(
+			articles = embedded.len(),
+			boosted,
+			skipped = skipped.len(),
+			"Semantic scoring applied"
+		);
+		if self.budget == 0 {
+			info!(
+				max_articles = config.max_articles,
+				"Reached semantic.max_articles, the rest of the run keeps its keyword scores"
+			);
+		}
+		Ok(())
+	}
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+	let (dot, norm_a, norm_b) = a.iter().zip(b).fold((0.0, 0.0, 0.0), |(dot, na, nb), (&x, &y)| {
+		let (x, y) = (f64::from(x), f64::from(y));
+		(x.mul_add(y, dot), x.mul_add(x, na), y.mul_add(y, nb))
+	});
+	let norm = norm_a.sqrt() * norm_b.sqrt();
+	if norm > 0.0 { dot / norm } else { 0.0 }
+}
@@ -0,0 +1,561 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::{ApiScope, ApiToken, ClicksConfig, ConfigHandle, ServerConfig, UserConfig};
+use crate::daemon::{Handles, Health, HealthState, Ranking, Rankings, ReloadRequests, Trigger};
+use crate::error::{AppError, Result};
+use crate::fetcher;
+use crate::metrics::{Breaker, Metrics, SourceHealth};
+use crate::model::{self, Article};
+use crate::rate_limiter::ClientRateLimiter;
+use crate::secret::Secret;
+use crate::store::Store;
+use axum::extract::{ConnectInfo, Path, Query, Request, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt, stream};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::num::NonZeroU32;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
+
+/// Article ids a `/stream` client remembers as sent; past this, those no longer ranked are forgotten
+const MAX_STREAMED_IDS: usize = 10_000;
+
+/// `WWW-Authenticate` challenges of the users' API and of the token-guarded endpoints
+const BASIC_CHALLENGE: &str = "Basic realm=\"news_aggregator\"";
+const BEARER_CHALLENGE: &str = "Bearer realm=\"news_aggregator\"";
+
+#[derive(Clone)]
+struct AppState {
+	metrics: Metrics,
+	health: Health,
+	rankings: Rankings,
+	trigger: Trigger,
+	reload: ReloadRequests,
+	config: ConfigHandle,
+	cancel_token: CancellationToken,
+	/// Per-client limit of `server.requests_per_minute`, fixed when the server starts like `listen`
+	limiter: Option<Arc<ClientRateLimiter>>,
+	/// Store the `/r/<id>` redirect counts clicks in and the users' read state is kept in, when `[clicks]`
+	/// is enabled or `[users]` are configured
+	store: Option<Arc<Mutex<Store>>>,
+}
+
+#[derive(Serialize)]
+struct HealthResponse {
+	status: &'static str,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	reason: Option<String>,
+	#[serde(flatten)]
+	state: HealthState,
+}
+
+#[derive(Deserialize)]
+struct StreamQuery {
+	min_score: Option<f64>,
+}
+
+/// One `/stream` event
+#[derive(Serialize)]
+struct StreamedArticle<'a> {
+	run_id: &'a str,
+	#[serde(flatten)]
+	scored: &'a ScoredArticle,
+}
+
+/// One source of `/admin/sources`
+#[derive(Serialize)]
+struct SourceStatus<'a> {
+	source: &'a str,
+	/// `ok` when the last fetch succeeded, `failing` when it failed, `open` while its circuit breaker skips
+	/// it, `pending` before the first
+	status: &'static str,
+	next_fetch: Option<DateTime<Utc>>,
+	breaker: Breaker,
+	#[serde(flatten)]
+	health: SourceHealth,
+}
+
+#[derive(Serialize)]
+struct Reloaded {
+	changes: usize,
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+	error: String,
+}
+
+#[derive(Deserialize)]
+struct ArticlesQuery {
+	#[serde(default)]
+	unread: bool,
+}
+
+/// A user's `/api/articles`
+#[derive(Serialize)]
+struct UserArticles<'a> {
+	user: &'a str,
+	profile: Option<&'a str>,
+	run_id: &'a str,
+	articles: Vec<UserArticle<'a>>,
+}
+
+#[derive(Serialize)]
+struct UserArticle<'a> {
+	/// For `/api/articles/<id>/read`
+	id: String,
+	read: bool,
+	#[serde(flatten)]
+	scored: &'a ScoredArticle,
+}
+
+/// Bind the HTTP endpoint and serve it in the background until shutdown; with a `store` it also serves
+/// the click-counting redirect and the users' API
+///
+/// # Errors
+///
+/// `ConfigError` when the listen address cannot be bound
+pub async fn spawn(
+	config: &ServerConfig,
+	metrics: Metrics,
+	daemon: Handles,
+	handle: ConfigHandle,
+	cancel_token: CancellationToken,
+	store: Option<Store>,
+) -> Result<()> {
+	let listener = TcpListener::bind(&config.listen)
+		.await
+		.map_err(|e| AppError::ConfigError(format!("cannot listen on {}: {e}", config.listen)))?;
+	info!(listen = %config.listen, "HTTP server listening");
+
+	let state = AppState {
+		metrics,
+		health: daemon.health,
+		rankings: daemon.rankings,
+		trigger: daemon.trigger,
+		reload: daemon.reload,
+		config: handle,
+		cancel_token: cancel_token.clone(),
+		limiter: NonZeroU32::new(config.requests_per_minute)
+			.map(|requests| Arc::new(ClientRateLimiter::per_minute(requests))),
+		store: store.map(|store| Arc::new(Mutex::new(store))),
+	};
+	let open = Router::new()
+		.route("/healthz", get(healthz))
+		.route("/readyz", get(readyz))
+		.route("/r/{id}", get(redirect_handler))
+		.route("/api/articles", get(articles_handler))
+		.route("/api/articles/{id}/read", post(mark_read).delete(mark_unread));
+	let read = Router::new()
+		.route("/metrics", get(metrics_handler))
+		.route("/stream", get(stream_handler))
+		.route_layer(middleware::from_fn_with_state(state.clone(), require_read));
+	let refresh = Router::new()
+		.route("/api/refresh", post(refresh_handler))
+		.route_layer(middleware::from_fn_with_state(state.clone(), require_refresh));
+	let admin = Router::new()
+		.route("/admin/fetch", post(refresh_handler))
+		.route("/admin/reload", post(reload_handler))
+		.route("/admin/sources", get(sources_handler))
+		.route_layer(middleware::from_fn_with_state(state.clone(), require_admin));
+	let app = open
+		.merge(read)
+		.merge(refresh)
+		.merge(admin)
+		.layer(middleware::from_fn_with_state(state.clone(), rate_limit))
+		.with_state(state);
+	tokio::spawn(async move {
+		if let Err(e) = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+			.with_graceful_shutdown(cancel_token.cancelled_owned())
+			.await
+		{
+			error!(error = %e, "HTTP server failed");
+		}
+	});
+	Ok(())
+}
+
+/// Turn away a client over `server.requests_per_minute` with a 429 telling it when to retry. Clients are
+/// told apart by their token, or else by address, which behind a reverse proxy is the proxy's.
+async fn rate_limit(
+	State(state): State<AppState>,
+	ConnectInfo(address): ConnectInfo<SocketAddr>,
+	request: Request,
+	next: Next,
+) -> Response {
+	if let Some(limiter) = &state.limiter {
+		let client = token(&state.config.current().server.tokens, request.headers())
+			.map_or_else(|| address.ip().to_string(), |(name, _)| format!("token {name}"));
+		if let Err(wait) = limiter.check(&client) {
+			debug!(client, "Rate limited a request");
+			let retry_after = wait.as_secs().saturating_add(1).to_string();
+			return (StatusCode::TOO_MANY_REQUESTS, [(header::RETRY_AFTER, retry_after)]).into_response();
+		}
+	}
+	next.run(request).await
+}
+
+/// Let `/stream` and `/metrics` through with a token of any scope, and anyone while no tokens are configured
+async fn require_read(State(state): State<AppState>, request: Request, next: Next) -> Response {
+	let tokens = &state.config.current().server.tokens;
+	if tokens.is_empty() || token(tokens, request.headers()).is_some() {
+		next.run(request).await
+	} else {
+		unauthorized(BEARER_CHALLENGE)
+	}
+}
+
+/// Let only `refresh` and `admin` tokens through, so that without tokens fetches cannot be started over
+/// HTTP at all
+async fn require_refresh(State(state): State<AppState>, request: Request, next: Next) -> Response {
+	match refusal(&state, request.headers(), ApiScope::Refresh) {
+		None => next.run(request).await,
+		Some(refusal) => refusal,
+	}
+}
+
+/// Let only `admin` tokens through
+async fn require_admin(State(state): State<AppState>, request: Request, next: Next) -> Response {
+	match refusal(&state, request.headers(), ApiScope::Admin) {
+		None => next.run(request).await,
+		Some(refusal) => refusal,
+	}
+}
+
+/// The response turning away a request without a token of at least `scope`
+fn refusal(state: &AppState, headers: &HeaderMap, scope: ApiScope) -> Option<Response> {
+	match token(&state.config.current().server.tokens, headers) {
+		Some((_, granted)) if granted >= scope => None,
+		Some(_) => Some(StatusCode::FORBIDDEN.into_response()),
+		None => Some(unauthorized(BEARER_CHALLENGE)),
+	}
+}
+
+async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
+	(
+		[(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+		state.metrics.render_prometheus(),
+	)
+}
+
+/// Liveness: fails when a fetch cycle has been running longer than `daemon.stall_timeout_secs`
+async fn healthz(State(state): State<AppState>) -> impl IntoResponse {
+	let health = state.health.snapshot();
+	let stalled = health.stalled(state.config.current().daemon.stall_timeout_secs);
+	respond(stalled, health)
+}
+
+/// Readiness: fails until a cycle has succeeded, and when the last success is older than `daemon.ready_max_age_secs`
+async fn readyz(State(state): State<AppState>) -> impl IntoResponse {
+	let health = state.health.snapshot();
+	let max_age = state.config.current().daemon.ready_max_age_secs;
+	let reason = health.last_success.map_or_else(
+		|| Some("no successful fetch cycle yet".to_string()),
+		|success| {
+			let age = Utc::now().signed_duration_since(success).num_seconds();
+			(age > i64::try_from(max_age).unwrap_or(i64::MAX)).then(|| format!("last success {age}s ago"))
+		},
+	);
+	respond(reason, health)
+}
+
+/// Server-sent events of the articles of each finished cycle scoring at least `?min_score=` (by default
+/// `server.stream_min_score`), each sent once: the articles ranked when the client connected, and those
+/// sent before, are skipped. Streams end on shutdown so that the server can stop.
+async fn stream_handler(
+	State(state): State<AppState>,
+	Query(query): Query<StreamQuery>,
+) -> Sse<impl Stream<Item = std::result::Result<Event, Infallible>>> {
+	let min_score = query
+		.min_score
+		.unwrap_or_else(|| state.config.current().server.stream_min_score);
+	let receiver = state.rankings.subscribe();
+	let mut sent: HashSet<String> = receiver.borrow().as_deref().map(ranked_ids).unwrap_or_default();
+	let rankings = stream::unfold(receiver, |mut receiver| async move {
+		receiver.changed().await.ok()?;
+		let ranking = receiver.borrow_and_update().clone();
+		Some((ranking, receiver))
+	});
+	let events = rankings
+		.flat_map(move |ranking| stream::iter(new_articles(ranking, min_score, &mut sent)))
+		.map(Ok)
+		.take_until(state.cancel_token.cancelled_owned());
+	Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Start a fetch cycle now; asked during a cycle, one starts right after it
+async fn refresh_handler(State(state): State<AppState>) -> StatusCode {
+	state.trigger.fire();
+	StatusCode::ACCEPTED
+}
+
+/// Reload the configuration like SIGHUP does, answering with the number of changed settings, or with a 422
+/// and the reason when the configuration on disk is invalid and the running one stays
+async fn reload_handler(State(state): State<AppState>) -> Response {
+	match state.reload.reload().await {
+		Ok(changes) => Json(Reloaded { changes }).into_response(),
+		Err(AppError::ShutdownError) => StatusCode::SERVICE_UNAVAILABLE.into_response(),
+		Err(e) => (
+			StatusCode::UNPROCESSABLE_ENTITY,
+			Json(ErrorResponse { error: e.to_string() }),
+		)
+			.into_response(),
+	}
+}
+
+/// Every source of the current configuration with how its fetches have been going and when it is next due
+async fn sources_handler(State(state): State<AppState>) -> Response {
+	let config = state.config.current();
+	let health = state.health.snapshot();
+	let mut fetches = state.metrics.source_health();
+	let now = Utc::now();
+	let sources: Vec<SourceStatus> = fetcher::source_names(&config)
+		.map(|source| {
+			let source_health = fetches.remove(source).unwrap_or_default();
+			let breaker = source_health.breaker(&config.fetcher.circuit_breaker, now);
+			let status = if source_health.last_fetch.is_none() {
+				"pending"
+			} else if matches!(breaker, Breaker::Open { .. }) {
+				"open"
+			} else if source_health.consecutive_failures > 0 {
+				"failing"
+			} else {
+				"ok"
+			};
+			SourceStatus {
+				source,
+				status,
+				next_fetch: health.next_fetches.get(source).copied().or(health.next_cycle),
+				breaker,
+				health: source_health,
+			}
+		})
+		.collect();
+	Json(sources).into_response()
+}
+
+/// Count a click on a delivered link and send the reader on to the article, without its tracking parameters
+async fn redirect_handler(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+	let Some(store) = state.store else {
+		return StatusCode::NOT_FOUND.into_response();
+	};
+	let url = tokio::task::block_in_place(|| store.lock().unwrap_or_else(PoisonError::into_inner).record_click(&id));
+	match url {
+		Ok(Some(url)) => {
+			state.metrics.record_click();
+			(StatusCode::FOUND, [(header::LOCATION, model::strip_tracking(&url))]).into_response()
+		}
+		Ok(None) => StatusCode::NOT_FOUND.into_response(),
+		Err(e) => {
+			warn!(id, error = %e, "Failed to record a click");
+			StatusCode::INTERNAL_SERVER_ERROR.into_response()
+		}
+	}
+}
+
+/// The leading articles of the user's ranking, that of the user's profile or else the main one, each with
+/// whether the user has read it; only the unread ones with `?unread=true`
+async fn articles_handler(
+	State(state): State<AppState>,
+	headers: HeaderMap,
+	Query(query): Query<ArticlesQuery>,
+) -> Response {
+	let config = state.config.current();
+	let Some(user) = authenticate(&config.users, &headers) else {
+		return unauthorized(BASIC_CHALLENGE);
+	};
+	let Some(store) = state.store else {
+		return StatusCode::NOT_FOUND.into_response();
+	};
+	let profile = config.users.get(&user).and_then(|user| user.profile.as_deref());
+	let Some(ranking) = profile.map_or_else(|| state.rankings.latest(), |profile| state.rankings.profile(profile))
+	else {
+		return (StatusCode::SERVICE_UNAVAILABLE, "no ranking yet").into_response();
+	};
+	let read =
+		match tokio::task::block_in_place(|| store.lock().unwrap_or_else(PoisonError::into_inner).read_ids(&user)) {
+			Ok(read) => read,
+			Err(e) => {
+				warn!(user, error = %e, "Failed to load the read articles");
+				return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+			}
+		};
+	let articles = ranking
+		.articles
+		.iter()
+		.take(ranking.displayed)
+		.map(|scored| {
+			let id = scored.article().id();
+			UserArticle {
+				read: read.contains(&id),
+				id,
+				scored,
+			}
+		})
+		.filter(|article| !(query.unread && article.read))
+		.collect();
+	Json(UserArticles {
+		user: &user,
+		profile,
+		run_id: &ranking.run_id,
+		articles,
+	})
+	.into_response()
+}
+
+async fn mark_read(State(state): State<AppState>, headers: HeaderMap, Path(id): Path<String>) -> Response {
+	set_read(&state, &headers, &id, true)
+}
+
+async fn mark_unread(State(state): State<AppState>, headers: HeaderMap, Path(id): Path<String>) -> Response {
+	set_read(&state, &headers, &id, false)
+}
+
+/// Mark a stored article read or unread for the authenticated user
+fn set_read(state: &AppState, headers: &HeaderMap, id: &str, read: bool) -> Response {
+	let Some(user) = authenticate(&state.config.current().users, headers) else {
+		return unauthorized(BASIC_CHALLENGE);
+	};
+	let Some(store) = &state.store else {
+		return StatusCode::NOT_FOUND.into_response();
+	};
+	let result = tokio::task::block_in_place(|| {
+		store
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner)
+			.set_read(&user, id, read, Utc::now())
+	});
+	match result {
+		Ok(true) => StatusCode::NO_CONTENT.into_response(),
+		Ok(false) => StatusCode::NOT_FOUND.into_response(),
+		Err(e) => {
+			warn!(user, id, error = %e, "Failed to update the read state");
+			StatusCode::INTERNAL_SERVER_ERROR.into_response()
+		}
+	}
+}
+
+/// The user a request is from, by `Authorization: Bearer <token>` or by basic authentication with the
+/// user's name and password
+fn authenticate(users: &BTreeMap<String, UserConfig>, headers: &HeaderMap) -> Option<String> {
+	let (scheme, credentials) = authorization(headers)?;
+	if scheme.eq_ignore_ascii_case("bearer") {
+		users
+			.iter()
+			.find(|(_, user)| user.token.as_ref().is_some_and(|token| matches(token, credentials)))
+			.map(|(name, _)| name.clone())
+	} else if scheme.eq_ignore_ascii_case("basic") {
+		let decoded = String::from_utf8(BASE64.decode(credentials).ok()?).ok()?;
+		let (name, password) = decoded.split_once(':')?;
+		users
+			.get(name)?
+			.password
+			.as_ref()
+			.filter(|secret| matches(secret, password))
+			.map(|_| name.to_string())
+	} else {
+		None
+	}
+}
+
+/// The name and scope of the `[server.tokens]` entry whose token a request bears
+fn token(tokens: &BTreeMap<String, ApiToken>, headers: &HeaderMap) -> Option<(String, ApiScope)> {
+	let (scheme, credentials) = authorization(headers)?;
+	if !scheme.eq_ignore_ascii_case("bearer") {
+		return None;
+	}
+	tokens
+		.iter()
+		.find(|(_, token)| matches(&token.token, credentials))
+		.map(|(name, token)| (name.clone(), token.scope))
+}
+
+/// The scheme and credentials of the `Authorization` header
+fn authorization(headers: &HeaderMap) -> Option<(&str, &str)> {
+	let (scheme, credentials) = headers.get(header::AUTHORIZATION)?.to_str().ok()?.split_once(' ')?;
+	Some((scheme, credentials.trim()))
+}
+
+/// Whether `given` is the secret, compared in constant time so that the response time does not tell how
+/// much of it matched
+fn matches(secret: &Secret, given: &str) -> bool {
+	match secret.resolve() {
+		Ok(expected) => {
+			expected.len() == given.len()
+				&& expected
+					.bytes()
+					.zip(given.bytes())
+					.fold(0u8, |diff, (a, b)| diff | (a ^ b))
+					== 0
+		}
+		Err(e) => {
+			warn!(error = %e, "User secret unavailable");
+			false
+		}
+	}
+}
+
+fn unauthorized(challenge: &'static str) -> Response {
+	(StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, challenge)]).into_response()
+}
+
+/// The link sinks deliver for an article when `[clicks]` is enabled
+#[must_use]
+pub fn click_url(config: &ClicksConfig, article: &Article) -> String {
+	format!("{}/r/{}", config.base_url.trim_end_matches('/'), article.id())
+}
+
+fn ranked_ids(ranking: &Ranking) -> HashSet<String> {
+	ranking.articles.iter().map(|scored| scored.article().id()).collect()
+}
+
+/// Events for the articles of `ranking` scoring at least `min_score` that were not sent yet
+fn new_articles(ranking: Option<Arc<Ranking>>, min_score: f64, sent: &mut HashSet<String>) -> Vec<Event> {
+	let Some(ranking) = ranking else {
+		return Vec::new();
+	};
+	if sent.len() > MAX_STREAMED_IDS {
+		let ranked = ranked_ids(&ranking);
+		sent.retain(|id| ranked.contains(id));
+	}
+	ranking
+		.articles
+		.iter()
+		.filter(|scored| scored.relevance_score() >= min_score)
+		.filter_map(|scored| {
+			let id = scored.article().id();
+			if !sent.insert(id.clone()) {
+				return None;
+			}
+			Event::default()
+				.event("article")
+				.id(id)
+				.json_data(StreamedArticle {
+					run_id: &ranking.run_id,
+					scored,
+				})
+				.ok()
+		})
+		.collect()
+}
+
+const fn respond(reason: Option<String>, state: HealthState) -> (StatusCode, Json<HealthResponse>) {
+	let (code, status) = if reason.is_some() {
+		(StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+	} else {
+		(StatusCode::OK, "ok")
+	};
+	(code, Json(HealthResponse { status, reason, state }))
+}
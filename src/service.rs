@@ -0,0 +1,277 @@
+use crate::error::{AppError, Result};
+#[cfg(not(all(feature = "windows-service", windows)))]
+use crate::shutdown::Shutdown;
+use std::path::Path;
+#[cfg(not(all(feature = "windows-service", windows)))]
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+
+/// Services start in the system directory; look for `config.toml` and relative paths next to the executable instead
+///
+/// # Errors
+///
+/// `IoError` when the executable's directory cannot be found or entered
+pub fn enter_install_dir() -> Result<()> {
+	let exe = std::env::current_exe().map_err(|e| AppError::io_error("current executable", e))?;
+	let dir = exe.parent().unwrap_or_else(|| Path::new("."));
+	std::env::set_current_dir(dir).map_err(|e| AppError::io_error(dir, e))
+}
+
+#[cfg(all(feature = "windows-service", windows))]
+pub use windows::{Service, event_log, start};
+
+/// A daemon registered with the Windows service control manager
+#[cfg(not(all(feature = "windows-service", windows)))]
+pub struct Service;
+
+#[cfg(not(all(feature = "windows-service", windows)))]
+impl Service {
+	#[allow(clippy::unused_self)]
+	pub const fn stopped(self, _result: &Result<()>) {}
+}
+
+/// # Errors
+///
+/// `ConfigError` when built without the `windows-service` feature or not on Windows, or when the service cannot register
+#[cfg(not(all(feature = "windows-service", windows)))]
+pub fn start(_shutdown: &Shutdown) -> Result<Service> {
+	Err(AppError::ConfigError(
+		"daemon --service: built without the `windows-service` feature, or not on Windows".into(),
+	))
+}
+
+/// # Errors
+///
+/// `ConfigError` when built without the `windows-service` feature or not on Windows, or when the Event Log cannot be opened
+#[cfg(not(all(feature = "windows-service", windows)))]
+pub fn event_log() -> Result<BoxMakeWriter> {
+	Err(AppError::ConfigError(
+		"log.event_log: built without the `windows-service` feature, or not on Windows".into(),
+	))
+}
+
+#[cfg(all(feature = "windows-service", windows))]
+mod windows {
+	use crate::error::{AppError, Result};
+	use crate::shutdown::Shutdown;
+	use std::ffi::OsString;
+	use std::io::{self, Write};
+	use std::sync::{Mutex, OnceLock, mpsc};
+	use std::thread::JoinHandle;
+	use std::time::Duration;
+	use tracing::{Level, Metadata};
+	use tracing_subscriber::fmt::MakeWriter;
+	use tracing_subscriber::fmt::writer::BoxMakeWriter;
+	use windows_service::service::{
+		ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus, ServiceType,
+	};
+	use windows_service::service_control_handler::{self, ServiceControlHandlerResult, ServiceStatusHandle};
+	use windows_service::{define_windows_service, service_dispatcher};
+	use windows_sys::Win32::System::EventLog::{
+		EVENTLOG_ERROR_TYPE, EVENTLOG_INFORMATION_TYPE, EVENTLOG_WARNING_TYPE, REPORT_EVENT_TYPE, RegisterEventSourceW,
+		ReportEventW,
+	};
+
+	/// What the dispatcher thread, on which Windows calls `service_main`, shares with the daemon
+	struct Handover {
+		shutdown: Shutdown,
+		registered: mpsc::SyncSender<std::result::Result<ServiceStatusHandle, String>>,
+		/// Keeps `service_main`, and with it the dispatcher, running until the daemon is done
+		done: Mutex<mpsc::Receiver<()>>,
+	}
+
+	static HANDOVER: OnceLock<Handover> = OnceLock::new();
+
+	/// Name of the Windows service, and source name of its Event Log entries
+	const SERVICE_NAME: &str = "news_aggregator";
+
+	define_windows_service!(ffi_service_main, service_main);
+
+	fn service_main(_arguments: Vec<OsString>) {
+		let Some(handover) = HANDOVER.get() else {
+			return;
+		};
+		let shutdown = handover.shutdown.clone();
+		let registered = service_control_handler::register(SERVICE_NAME, move |control| match control {
+			ServiceControl::Stop | ServiceControl::Shutdown => {
+				shutdown.request();
+				ServiceControlHandlerResult::NoError
+			}
+			ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+			_ => ServiceControlHandlerResult::NotImplemented,
+		});
+		let failed = registered.is_err();
+		let _ = handover.registered.send(registered.map_err(|e| e.to_string()));
+		if failed {
+			return;
+		}
+		if let Ok(done) = handover.done.lock() {
+			let _ = done.recv();
+		}
+	}
+
+	/// The running service; report its end with [`Service::stopped`]
+	pub struct Service {
+		status: ServiceStatusHandle,
+		done: mpsc::Sender<()>,
+		dispatcher: JoinHandle<()>,
+	}
+
+	/// Connect to the service control manager and report the service as running.
+	/// A service stop or system shutdown then starts a graceful shutdown.
+	pub fn start(shutdown: &Shutdown) -> Result<Service> {
+		let (registered_tx, registered_rx) = mpsc::sync_channel(1);
+		let (done_tx, done_rx) = mpsc::channel();
+		let dispatcher_failed = registered_tx.clone();
+		HANDOVER
+			.set(Handover {
+				shutdown: shutdown.clone(),
+				registered: registered_tx,
+				done: Mutex::new(done_rx),
+			})
+			.map_err(|_| AppError::ConfigError("the service is already running".into()))?;
+
+		// Blocks until service_main returns; fails at once when not started by the service control manager
+		let dispatcher = std::thread::spawn(move || {
+			if let Err(e) = service_dispatcher::start(SERVICE_NAME, ffi_service_main) {
+				let _ = dispatcher_failed.send(Err(e.to_string()));
+			}
+		});
+		let status = tokio::task::block_in_place(|| registered_rx.recv())
+			.map_err(|e| e.to_string())
+			.and_then(|registered| registered)
+			.map_err(|e| AppError::ConfigError(format!("cannot run as a Windows service: {e}")))?;
+		set_state(status, ServiceState::Running, ServiceExitCode::NO_ERROR)?;
+		Ok(Service {
+			status,
+			done: done_tx,
+			dispatcher,
+		})
+	}
+
+	impl Service {
+		/// Report the service as stopped, with the exit status of a failed run as its service-specific exit code
+		pub fn stopped(self, result: &Result<()>) {
+			let exit_code = match result {
+				Ok(()) | Err(AppError::ShutdownError) => ServiceExitCode::NO_ERROR,
+				Err(e) => ServiceExitCode::ServiceSpecific(u32::from(e.exit_code())),
+			};
+			if let Err(e) = set_state(self.status, ServiceState::Stopped, exit_code) {
+				tracing::warn!(error = %e, "Failed to report the service as stopped");
+			}
+			let _ = self.done.send(());
+			let _ = self.dispatcher.join();
+		}
+	}
+
+	fn set_state(status: ServiceStatusHandle, state: ServiceState, exit_code: ServiceExitCode) -> Result<()> {
+		let controls_accepted = if state == ServiceState::Running {
+			ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN
+		} else {
+			ServiceControlAccept::empty()
+		};
+		status
+			.set_service_status(ServiceStatus {
+				service_type: ServiceType::OWN_PROCESS,
+				current_state: state,
+				controls_accepted,
+				exit_code,
+				checkpoint: 0,
+				wait_hint: Duration::default(),
+				process_id: None,
+			})
+			.map_err(|e| AppError::ConfigError(format!("cannot report service status: {e}")))
+	}
+
+	/// Log lines as Application log entries, with the event type following the level
+	pub fn event_log() -> Result<BoxMakeWriter> {
+		let source = wide(SERVICE_NAME);
+		// SAFETY: `source` is a NUL-terminated UTF-16 string that outlives the call
+		let handle = unsafe { RegisterEventSourceW(std::ptr::null(), source.as_ptr()) };
+		if handle.is_null() {
+			return Err(AppError::ConfigError(format!(
+				"log.event_log: cannot register event source: {}",
+				io::Error::last_os_error()
+			)));
+		}
+		// The handle stays registered for the life of the process
+		Ok(BoxMakeWriter::new(EventLog {
+			handle: handle as usize,
+		}))
+	}
+
+	struct EventLog {
+		handle: usize,
+	}
+
+	impl<'a> MakeWriter<'a> for EventLog {
+		type Writer = EventLogEntry;
+
+		fn make_writer(&'a self) -> Self::Writer {
+			self.entry(EVENTLOG_INFORMATION_TYPE)
+		}
+
+		fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+			let kind = match *meta.level() {
+				Level::ERROR => EVENTLOG_ERROR_TYPE,
+				Level::WARN => EVENTLOG_WARNING_TYPE,
+				_ => EVENTLOG_INFORMATION_TYPE,
+			};
+			self.entry(kind)
+		}
+	}
+
+	impl EventLog {
+		const fn entry(&self, kind: REPORT_EVENT_TYPE) -> EventLogEntry {
+			EventLogEntry {
+				handle: self.handle,
+				kind,
+				line: Vec::new(),
+			}
+		}
+	}
+
+	/// One log line, reported when the formatter drops it
+	struct EventLogEntry {
+		handle: usize,
+		kind: REPORT_EVENT_TYPE,
+		line: Vec<u8>,
+	}
+
+	impl Write for EventLogEntry {
+		fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+			self.line.extend_from_slice(buf);
+			Ok(buf.len())
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	impl Drop for EventLogEntry {
+		fn drop(&mut self) {
+			let text = String::from_utf8_lossy(&self.line);
+			let text = wide(text.trim_end());
+			let strings = [text.as_ptr()];
+			// SAFETY: the handle came from RegisterEventSourceW and `strings` holds one NUL-terminated
+			// UTF-16 string that outlives the call
+			unsafe {
+				ReportEventW(
+					self.handle as _,
+					self.kind,
+					0,
+					0,
+					std::ptr::null_mut(),
+					1,
+					0,
+					strings.as_ptr(),
+					std::ptr::null(),
+				);
+			}
+		}
+	}
+
+	fn wide(text: &str) -> Vec<u16> {
+		text.encode_utf16().chain(std::iter::once(0)).collect()
+	}
+}
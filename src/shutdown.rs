@@ -0,0 +1,175 @@
+use crate::error::{AppError, Result};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Staged shutdown, so that a SIGTERM from systemd or Kubernetes still ends with a digest.
+///
+/// The first SIGINT/SIGTERM marks shutdown as requested: the daemon starts no further cycles but the
+/// current run carries on. Once the grace period has passed, in-flight requests are aborted and whatever
+/// was fetched so far is scored and delivered. A second signal stops everything immediately.
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+	requested: CancellationToken,
+	abort: CancellationToken,
+	force: CancellationToken,
+	/// Shutdown requested by a service manager rather than a signal
+	stop: Arc<Notify>,
+}
+
+impl Shutdown {
+	#[must_use]
+	pub fn new() -> Self {
+		// Each stage implies the earlier ones
+		let force = CancellationToken::new();
+		let abort = force.child_token();
+		let requested = abort.child_token();
+		Self {
+			requested,
+			abort,
+			force,
+			stop: Arc::new(Notify::new()),
+		}
+	}
+
+	/// Start shutting down as if a first shutdown signal had arrived, e.g. on a Windows service stop
+	#[cfg_attr(not(all(feature = "windows-service", windows)), allow(dead_code))]
+	pub fn request(&self) {
+		self.stop.notify_one();
+	}
+
+	/// Cancelled by the first shutdown signal
+	#[must_use]
+	pub const fn requested(&self) -> &CancellationToken {
+		&self.requested
+	}
+
+	/// Cancelled when the grace period runs out; network work in flight is dropped
+	#[must_use]
+	pub const fn abort(&self) -> &CancellationToken {
+		&self.abort
+	}
+
+	/// Cancelled by a second shutdown signal; nothing more is scored or delivered
+	#[must_use]
+	pub const fn force(&self) -> &CancellationToken {
+		&self.force
+	}
+
+	/// Listen for shutdown signals in the background, advancing through the stages
+	///
+	/// # Errors
+	///
+	/// When the signal handlers cannot be installed
+	pub fn listen(&self, grace_period: Duration) -> Result<()> {
+		let mut signals = Signals::new()?;
+		let shutdown = self.clone();
+		tokio::spawn(async move {
+			let signal = tokio::select! {
+				signal = signals.recv() => signal,
+				() = shutdown.stop.notified() => "service stop",
+			};
+			info!(
+				signal,
+				grace_period_secs = grace_period.as_secs(),
+				"Shutdown signal received, finishing the current run"
+			);
+			shutdown.requested.cancel();
+
+			tokio::select! {
+				() = time::sleep(grace_period) => {
+					warn!("Shutdown grace period elapsed, aborting in-flight requests");
+					shutdown.abort.cancel();
+					signals.recv().await;
+				}
+				_ = signals.recv() => {}
+			}
+			warn!("Second shutdown signal received, stopping immediately");
+			shutdown.force.cancel();
+		});
+		Ok(())
+	}
+}
+
+impl Default for Shutdown {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(unix)]
+struct Signals {
+	interrupt: tokio::signal::unix::Signal,
+	terminate: tokio::signal::unix::Signal,
+}
+
+#[cfg(unix)]
+impl Signals {
+	fn new() -> Result<Self> {
+		use tokio::signal::unix::{SignalKind, signal};
+		let listen = |kind| {
+			signal(kind).map_err(|e| AppError::ConfigError(format!("failed to listen for shutdown signals: {e}")))
+		};
+		Ok(Self {
+			interrupt: listen(SignalKind::interrupt())?,
+			terminate: listen(SignalKind::terminate())?,
+		})
+	}
+
+	async fn recv(&mut self) -> &'static str {
+		tokio::select! {
+			Some(()) = self.interrupt.recv() => "SIGINT",
+			Some(()) = self.terminate.recv() => "SIGTERM",
+			else => std::future::pending().await,
+		}
+	}
+}
+
+/// Ctrl-C and Ctrl-Break in a Windows console
+#[cfg(windows)]
+struct Signals {
+	ctrl_c: tokio::signal::windows::CtrlC,
+	ctrl_break: tokio::signal::windows::CtrlBreak,
+}
+
+#[cfg(windows)]
+impl Signals {
+	fn new() -> Result<Self> {
+		use tokio::signal::windows::{ctrl_break, ctrl_c};
+		let error = |e| AppError::ConfigError(format!("failed to listen for shutdown signals: {e}"));
+		Ok(Self {
+			ctrl_c: ctrl_c().map_err(error)?,
+			ctrl_break: ctrl_break().map_err(error)?,
+		})
+	}
+
+	async fn recv(&mut self) -> &'static str {
+		tokio::select! {
+			Some(()) = self.ctrl_c.recv() => "Ctrl-C",
+			Some(()) = self.ctrl_break.recv() => "Ctrl-Break",
+			else => std::future::pending().await,
+		}
+	}
+}
+
+/// Only Ctrl-C is available elsewhere
+#[cfg(not(any(unix, windows)))]
+struct Signals;
+
+#[cfg(not(any(unix, windows)))]
+impl Signals {
+	#[allow(clippy::unnecessary_wraps)]
+	const fn new() -> Result<Self> {
+		Ok(Self)
+	}
+
+	async fn recv(&mut self) -> &'static str {
+		if tokio::signal::ctrl_c().await.is_err() {
+			std::future::pending::<()>().await;
+		}
+		"Ctrl-C"
+	}
+}
@@ -0,0 +1,235 @@
+#[cfg(feature = "epub")]
+mod epub;
+mod ics;
+mod jsonl;
+#[cfg(feature = "kafka")]
+mod kafka;
+mod matrix;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+#[cfg(feature = "nats")]
+mod nats;
+mod push;
+mod s3;
+
+use crate::analyzer::ScoredArticle;
+use crate::config::{Config, DeliveryConfig};
+use crate::error::Result;
+use crate::model::Article;
+use crate::run::RunContext;
+use crate::store::Store;
+use async_trait::async_trait;
+use chrono::TimeDelta;
+use reqwest::Client;
+use std::borrow::Cow;
+use tracing::{info, warn};
+
+#[cfg(feature = "epub")]
+pub use epub::EpubSink;
+pub use ics::IcsCalendarSink;
+pub use jsonl::JsonlArchiveSink;
+#[cfg(feature = "kafka")]
+pub use kafka::KafkaSink;
+pub use matrix::MatrixSink;
+#[cfg(feature = "mqtt")]
+pub use mqtt::MqttSink;
+#[cfg(feature = "nats")]
+pub use nats::NatsSink;
+pub use push::PushSink;
+pub use s3::S3Sink;
+
+/// A destination for the scored articles of a run
+#[async_trait]
+pub trait Sink: Send + Sync {
+	fn name(&self) -> &str;
+
+	/// Where the articles end up, e.g. a file path or URL, for the run report
+	fn destination(&self) -> String;
+
+	/// The articles of a batch this sink sends, e.g. those above a score threshold; all of them by
+	/// default. Only these are recorded as delivered.
+	fn select<'a>(&self, articles: &'a [ScoredArticle]) -> Cow<'a, [ScoredArticle]> {
+		Cow::Borrowed(articles)
+	}
+
+	/// Whether delivery is held back for now, e.g. during quiet hours; held-back articles stay undelivered
+	fn paused(&self) -> bool {
+		false
+	}
+
+	async fn deliver(&self, run: &RunContext, articles: &[ScoredArticle]) -> Result<()>;
+}
+
+/// Build every sink enabled in the configuration
+#[must_use]
+pub fn from_config(config: &Config, client: &Client) -> Vec<Box<dyn Sink>> {
+	let mut sinks: Vec<Box<dyn Sink>> = Vec::new();
+
+	if config.archive.enabled {
+		sinks.push(Box::new(JsonlArchiveSink::new(&config.archive)));
+	}
+	if config.calendar.enabled {
+		sinks.push(Box::new(IcsCalendarSink::new(&config.calendar)));
+	}
+	if config.s3.enabled {
+		sinks.push(Box::new(S3Sink::new(
+			client.clone(),
+			&config.s3,
+			config.profile.as_deref(),
+		)));
+	}
+	if config.epub.enabled {
+		#[cfg(feature = "epub")]
+		sinks.push(Box::new(EpubSink::new(
+			client.clone(),
+			&config.epub,
+			config.profile.as_deref(),
+		)));
+		#[cfg(not(feature = "epub"))]
+		warn!("epub.enabled is set but the binary was built without the `epub` feature");
+	}
+	if config.mqtt.enabled {
+		#[cfg(feature = "mqtt")]
+		sinks.push(Box::new(MqttSink::new(&config.mqtt, config.profile.as_deref())));
+		#[cfg(not(feature = "mqtt"))]
+		warn!("mqtt.enabled is set but the binary was built without the `mqtt` feature");
+	}
+	if config.nats.enabled {
+		#[cfg(feature = "nats")]
+		sinks.push(Box::new(NatsSink::new(&config.nats, config.profile.as_deref())));
+		#[cfg(not(feature = "nats"))]
+		warn!("nats.enabled is set but the binary was built without the `nats` feature");
+	}
+	if config.kafka.enabled {
+		#[cfg(feature = "kafka")]
+		sinks.push(Box::new(KafkaSink::new(&config.kafka, config.profile.as_deref())));
+		#[cfg(not(feature = "kafka"))]
+		warn!("kafka.enabled is set but the binary was built without the `kafka` feature");
+	}
+	if config.ntfy.enabled {
+		sinks.push(Box::new(PushSink::ntfy(client.clone(), &config.ntfy)));
+	}
+	if config.pushover.enabled {
+		sinks.push(Box::new(PushSink::pushover(client.clone(), &config.pushover)));
+	}
+	if config.gotify.enabled {
+		sinks.push(Box::new(PushSink::gotify(client.clone(), &config.gotify)));
+	}
+	if config.matrix.enabled {
+		sinks.push(Box::new(MatrixSink::new(client.clone(), &config.matrix)));
+	}
+
+	sinks
+}
+
+/// The store's record of which articles each sink received, per destination, for `delivery.dedupe`
+pub struct DeliveryLedger<'a> {
+	store: &'a mut Store,
+	config: &'a DeliveryConfig,
+}
+
+impl<'a> DeliveryLedger<'a> {
+	pub const fn new(store: &'a mut Store, config: &'a DeliveryConfig) -> Self {
+		Self { store, config }
+	}
+
+	/// The articles `sink` has not received within its re-notify window
+	fn undelivered(&self, sink: &dyn Sink, run: &RunContext, articles: &[ScoredArticle]) -> Result<Vec<ScoredArticle>> {
+		let since = self.config.renotify_after(sink.name()).map(|window| {
+			TimeDelta::from_std(window)
+				.ok()
+				.and_then(|window| run.started_at().checked_sub_signed(window))
+				.unwrap_or_default()
+		});
+		let delivered = self.store.delivered(sink.name(), &sink.destination(), since)?;
+		Ok(articles
+			.iter()
+			.filter(|item| !delivered.contains(&item.article().id()))
+			.cloned()
+			.collect())
+	}
+
+	fn record(&mut self, sink: &dyn Sink, run: &RunContext, articles: &[ScoredArticle]) -> Result<()> {
+		let articles: Vec<&Article> = articles.iter().map(ScoredArticle::article).collect();
+		self.store
+			.record_deliveries(sink.name(), &sink.destination(), &articles, run.started_at())
+	}
+}
+
+/// Log what each sink would be sent, sending nothing; for `delivery.dry_run`
+pub fn preview_all(sinks: &[Box<dyn Sink>], articles: &[ScoredArticle]) {
+	for sink in sinks {
+		let name = sink.name();
+		let selected = sink.select(articles);
+		info!(
+			sink = name,
+			destination = %sink.destination(),
+			count = selected.len(),
+			paused = sink.paused(),
+			"Dry run, not delivering"
+		);
+		for item in selected.iter() {
+			let article = item.article();
+			info!(
+				sink = name,
+				title = article.title(),
+				url = article.url(),
+				"Would deliver"
+			);
+		}
+	}
+}
+
+/// Deliver to all sinks; a failing sink is logged and does not stop the others.
+/// With a ledger, each sink only gets the articles it has not received yet, and nothing when all were.
+pub async fn deliver_all(
+	sinks: &[Box<dyn Sink>],
+	run: &RunContext,
+	articles: &[ScoredArticle],
+	mut ledger: Option<DeliveryLedger<'_>>,
+) {
+	for sink in sinks {
+		let name = sink.name();
+		if sink.paused() {
+			info!(sink = name, "Delivery paused, holding articles back");
+			continue;
+		}
+		let undelivered = ledger.as_ref().and_then(|ledger| {
+			tokio::task::block_in_place(|| ledger.undelivered(sink.as_ref(), run, articles))
+				.map_err(|e| warn!(sink = name, error = %e, "Delivery ledger unavailable, delivering every article"))
+				.ok()
+		});
+		let pending = undelivered.as_deref().unwrap_or(articles);
+		if pending.len() < articles.len() {
+			info!(
+				sink = name,
+				skipped = articles.len().saturating_sub(pending.len()),
+				"Skipped articles already delivered"
+			);
+		}
+		let selected = sink.select(pending);
+		let batch = selected.as_ref();
+		let result = if batch.is_empty() && !articles.is_empty() {
+			Ok(())
+		} else {
+			sink.deliver(run, batch).await
+		};
+		match &result {
+			Ok(()) => info!(sink = name, count = batch.len(), "Delivered articles"),
+			Err(e) => warn!(sink = name, error = %e, "Sink delivery failed"),
+		}
+		let recorded = match (&result, ledger.as_mut()) {
+			(Ok(()), Some(ledger)) => tokio::task::block_in_place(|| ledger.record(sink.as_ref(), run, batch)),
+			_ => Ok(()),
+		};
+		if let Err(e) = recorded {
+			warn!(sink = name, error = %e, "Failed to record deliveries");
+		}
+		run.record_output(
+			name,
+			sink.destination(),
+			batch.len(),
+			result.err().map(|e| e.to_string()),
+		);
+	}
+}
@@ -0,0 +1,346 @@
+use super::Sink;
+use crate::analyzer::ScoredArticle;
+use crate::config::EpubConfig;
+use crate::content::fetch_body;
+use crate::error::{AppError, Result};
+use crate::extract::Block;
+use crate::run::RunContext;
+use crate::sanitize::escape_html;
+use async_trait::async_trait;
+use futures::{StreamExt, stream};
+use reqwest::Client;
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::info;
+use zip::CompressionMethod;
+use zip::write::{SimpleFileOptions, ZipWriter};
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+const STYLE_CSS: &str = "body { font-family: serif; line-height: 1.4; }
+h1 { font-size: 1.4em; }
+.meta { color: #555; font-size: 0.9em; }
+.link { font-size: 0.8em; word-wrap: break-word; }
+blockquote { font-style: italic; margin-left: 1em; }
+pre { white-space: pre-wrap; font-size: 0.8em; }
+";
+
+/// One chapter of the book
+struct Chapter<'a> {
+	scored: &'a ScoredArticle,
+	body: Option<Vec<Block>>,
+}
+
+impl Chapter<'_> {
+	fn file_name(index: usize) -> String {
+		format!("article{}.xhtml", index.saturating_add(1))
+	}
+
+	/// Title line of the table of contents
+	fn toc_entry(&self) -> String {
+		format!(
+			"{} ({}, {:.1})",
+			self.scored.article().title(),
+			self.scored.article().source(),
+			self.scored.relevance_score()
+		)
+	}
+}
+
+/// Writes the top articles of each run as an EPUB, one chapter each and the table of contents ordered
+/// by score, and optionally renders it to PDF with an external command
+pub struct EpubSink {
+	client: Client,
+	config: EpubConfig,
+	profile: String,
+}
+
+impl EpubSink {
+	#[must_use]
+	pub fn new(client: Client, config: &EpubConfig, profile: Option<&str>) -> Self {
+		Self {
+			client,
+			config: config.clone(),
+			profile: profile.unwrap_or("default").to_string(),
+		}
+	}
+
+	fn path(&self, run: &RunContext) -> PathBuf {
+		PathBuf::from(
+			self.config
+				.path
+				.replace("{run_id}", run.id())
+				.replace("{profile}", &self.profile)
+				.replace("{date}", &run.started_at().format("%Y-%m-%d").to_string()),
+		)
+	}
+
+	/// Each article's full text where its page has a readable body, a few pages at a time
+	async fn bodies(&self, articles: &[&ScoredArticle]) -> Vec<Option<Vec<Block>>> {
+		if !self.config.full_content {
+			return articles.iter().map(|_| None).collect();
+		}
+		let urls: Vec<String> = articles
+			.iter()
+			.map(|scored| scored.article().url().to_string())
+			.collect();
+		let client = &self.client;
+		stream::iter(urls)
+			.map(|url| async move { fetch_body(client, &url).await })
+			.buffered(self.config.fetch_concurrency)
+			.collect()
+			.await
+	}
+
+	fn write(&self, path: &Path, run: &RunContext, chapters: &[Chapter<'_>]) -> Result<()> {
+		if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+			fs::create_dir_all(parent).map_err(|e| AppError::io_error(parent, e))?;
+		}
+		let partial = path.with_extension("epub.partial");
+		let file = File::create(&partial).map_err(|e| AppError::io_error(&partial, e))?;
+		let mut zip = ZipWriter::new(BufWriter::new(file));
+		let date = run.started_at().format("%Y-%m-%d").to_string();
+		let title = format!("{}, {date}", self.config.title);
+
+		// The mimetype comes first and uncompressed, so that readers can sniff the format
+		let mut entries = vec![
+			("mimetype", "application/epub+zip".to_string()),
+			("META-INF/container.xml", CONTAINER_XML.to_string()),
+			("OEBPS/content.opf", package(run, &title, chapters)),
+			("OEBPS/nav.xhtml", nav(&title, chapters)),
+			("OEBPS/toc.ncx", ncx(run, &title, chapters)),
+			("OEBPS/style.css", STYLE_CSS.to_string()),
+		];
+		let files: Vec<(String, String)> = chapters
+			.iter()
+			.enumerate()
+			.map(|(index, chapter)| (format!("OEBPS/{}", Chapter::file_name(index)), chapter_xhtml(chapter)))
+			.collect();
+		entries.extend(files.iter().map(|(name, content)| (name.as_str(), content.clone())));
+
+		for (name, content) in entries {
+			let method = if name == "mimetype" {
+				CompressionMethod::Stored
+			} else {
+				CompressionMethod::Deflated
+			};
+			zip.start_file(name, SimpleFileOptions::default().compression_method(method))
+				.map_err(|e| AppError::io_error(&partial, e))?;
+			zip.write_all(content.as_bytes())
+				.map_err(|e| AppError::io_error(&partial, e))?;
+		}
+		zip.finish()
+			.map_err(|e| AppError::io_error(&partial, e))?
+			.flush()
+			.map_err(|e| AppError::io_error(&partial, e))?;
+		fs::rename(&partial, path).map_err(|e| AppError::io_error(path, e))
+	}
+
+	/// Run `pdf_command` on the written EPUB
+	fn render_pdf(&self, epub: &Path) -> Result<()> {
+		let Some((program, args)) = self.config.pdf_command.split_first() else {
+			return Ok(());
+		};
+		let pdf = epub.with_extension("pdf");
+		let epub_arg = epub.display().to_string();
+		let pdf_arg = pdf.display().to_string();
+		let status = Command::new(program)
+			.args(
+				args.iter()
+					.map(|arg| arg.replace("{epub}", &epub_arg).replace("{pdf}", &pdf_arg)),
+			)
+			.status()
+			.map_err(|e| AppError::io_error(program, e))?;
+		if !status.success() {
+			return Err(AppError::delivery_error(
+				"epub",
+				format!("PDF renderer {program} exited with {status}"),
+			));
+		}
+		info!(path = %pdf.display(), "Rendered digest PDF");
+		Ok(())
+	}
+}
+
+/// Escaped text, without the control characters XML does not allow
+fn xml(text: &str) -> String {
+	escape_html(&text.replace(|c: char| c.is_control() && c != '\n' && c != '\t', ""))
+}
+
+fn chapter_xhtml(chapter: &Chapter<'_>) -> String {
+	let scored = chapter.scored;
+	let article = scored.article();
+	let mut meta = vec![article.source().to_string()];
+	meta.extend(article.author().map(str::to_string));
+	meta.extend(article.published_at().map(|at| at.format("%Y-%m-%d").to_string()));
+	meta.push(format!("score {:.1}", scored.relevance_score()));
+
+	let mut html = format!(
+		"<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE html>\n<html xmlns=\"http://www.w3.org/1999/xhtml\" \
+		 lang=\"{lang}\" xml:lang=\"{lang}\">\n<head>\n<title>{title}</title>\n<link rel=\"stylesheet\" \
+		 type=\"text/css\" href=\"style.css\"/>\n</head>\n<body>\n<h1>{title}</h1>\n<p class=\"meta\">{meta}</p>\n\
+		 <p class=\"link\"><a href=\"{link}\">{url}</a></p>\n",
+		lang = xml(article.language().unwrap_or("en")),
+		title = xml(article.title()),
+		meta = xml(&meta.join(" · ")),
+		link = xml(article.link()),
+		url = xml(article.url()),
+	);
+	match &chapter.body {
+		Some(blocks) => {
+			if let Some(summary) = scored.summary() {
+				let _ = writeln!(html, "<blockquote>{}</blockquote>", xml(summary));
+			}
+			for block in blocks {
+				let _ = match block {
+					Block::Heading(text) => writeln!(html, "<h2>{}</h2>", xml(text)),
+					Block::Paragraph(text) => writeln!(html, "<p>{}</p>", xml(text)),
+					Block::Preformatted(text) => writeln!(html, "<pre>{}</pre>", xml(text)),
+				};
+			}
+		}
+		None => {
+			if let Some(text) = scored.summary().or_else(|| article.description()) {
+				let _ = writeln!(html, "<p>{}</p>", xml(text));
+			}
+		}
+	}
+	html.push_str("</body>\n</html>\n");
+	html
+}
+
+/// The package document: metadata, every file and the reading order
+fn package(run: &RunContext, title: &str, chapters: &[Chapter<'_>]) -> String {
+	let language = chapters
+		.first()
+		.and_then(|chapter| chapter.scored.article().language())
+		.unwrap_or("en");
+	let mut manifest = String::new();
+	let mut spine = String::new();
+	for index in 0..chapters.len() {
+		let id = format!("article{}", index.saturating_add(1));
+		let _ = writeln!(
+			manifest,
+			"<item id=\"{id}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>",
+			Chapter::file_name(index)
+		);
+		let _ = writeln!(spine, "<itemref idref=\"{id}\"/>");
+	}
+	format!(
+		"<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" \
+		 unique-identifier=\"book-id\">\n<metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n<dc:identifier \
+		 id=\"book-id\">urn:news-aggregator:{run_id}</dc:identifier>\n<dc:title>{title}</dc:title>\n\
+		 <dc:language>{language}</dc:language>\n<dc:creator>news_aggregator</dc:creator>\n<dc:date>{date}</dc:date>\n\
+		 <meta property=\"dcterms:modified\">{modified}</meta>\n</metadata>\n<manifest>\n<item id=\"nav\" \
+		 href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n<item id=\"ncx\" \
+		 href=\"toc.ncx\" media-type=\"application/x-dtbncx+xml\"/>\n<item id=\"style\" href=\"style.css\" \
+		 media-type=\"text/css\"/>\n{manifest}</manifest>\n<spine toc=\"ncx\">\n<itemref idref=\"nav\"/>\n{spine}</spine>\n\
+		 </package>\n",
+		run_id = xml(run.id()),
+		title = xml(title),
+		language = xml(language),
+		date = run.started_at().format("%Y-%m-%d"),
+		modified = run.started_at().format("%Y-%m-%dT%H:%M:%SZ"),
+	)
+}
+
+/// The EPUB 3 table of contents, also the first page of the book
+fn nav(title: &str, chapters: &[Chapter<'_>]) -> String {
+	let mut entries = String::new();
+	for (index, chapter) in chapters.iter().enumerate() {
+		let _ = writeln!(
+			entries,
+			"<li><a href=\"{}\">{}</a></li>",
+			Chapter::file_name(index),
+			xml(&chapter.toc_entry())
+		);
+	}
+	format!(
+		"<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<!DOCTYPE html>\n<html xmlns=\"http://www.w3.org/1999/xhtml\" \
+		 xmlns:epub=\"http://www.idpf.org/2007/ops\">\n<head>\n<title>{title}</title>\n<link rel=\"stylesheet\" \
+		 type=\"text/css\" href=\"style.css\"/>\n</head>\n<body>\n<nav epub:type=\"toc\" id=\"toc\">\n<h1>{title}</h1>\n\
+		 <ol>\n{entries}</ol>\n</nav>\n</body>\n</html>\n",
+		title = xml(title),
+	)
+}
+
+/// The EPUB 2 table of contents, for older readers such as early Kindles
+fn ncx(run: &RunContext, title: &str, chapters: &[Chapter<'_>]) -> String {
+	let mut points = String::new();
+	for (index, chapter) in chapters.iter().enumerate() {
+		let order = index.saturating_add(1);
+		let _ = writeln!(
+			points,
+			"<navPoint id=\"article{order}\" playOrder=\"{order}\"><navLabel><text>{}</text></navLabel><content \
+			 src=\"{}\"/></navPoint>",
+			xml(&chapter.toc_entry()),
+			Chapter::file_name(index)
+		);
+	}
+	format!(
+		"<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<ncx xmlns=\"http://www.daisy.org/z3986/2005/ncx/\" \
+		 version=\"2005-1\">\n<head>\n<meta name=\"dtb:uid\" content=\"urn:news-aggregator:{run_id}\"/>\n</head>\n\
+		 <docTitle><text>{title}</text></docTitle>\n<navMap>\n{points}</navMap>\n</ncx>\n",
+		run_id = xml(run.id()),
+		title = xml(title),
+	)
+}
+
+#[async_trait]
+impl Sink for EpubSink {
+	fn name(&self) -> &'static str {
+		"epub"
+	}
+
+	fn destination(&self) -> String {
+		self.config.path.clone()
+	}
+
+	fn select<'a>(&self, articles: &'a [ScoredArticle]) -> Cow<'a, [ScoredArticle]> {
+		Cow::Owned(
+			articles
+				.iter()
+				.filter(|scored| scored.relevance_score() >= self.config.min_score)
+				.take(self.config.top_n)
+				.cloned()
+				.collect(),
+		)
+	}
+
+	async fn deliver(&self, run: &RunContext, articles: &[ScoredArticle]) -> Result<()> {
+		if articles.is_empty() {
+			return Ok(());
+		}
+		let mut ordered: Vec<&ScoredArticle> = articles.iter().collect();
+		ordered.sort_by(|a, b| b.cmp(a));
+		let bodies = self.bodies(&ordered).await;
+		let full = bodies.iter().filter(|body| body.is_some()).count();
+		let chapters: Vec<Chapter<'_>> = ordered
+			.into_iter()
+			.zip(bodies)
+			.map(|(scored, body)| Chapter { scored, body })
+			.collect();
+
+		let path = self.path(run);
+		tokio::task::block_in_place(|| {
+			self.write(&path, run, &chapters)?;
+			info!(
+				path = %path.display(),
+				articles = chapters.len(),
+				full_text = full,
+				"Wrote digest EPUB"
+			);
+			self.render_pdf(&path)
+		})
+	}
+}
@@ -0,0 +1,292 @@
+use super::Sink;
+use crate::analyzer::ScoredArticle;
+use crate::config::CalendarConfig;
+use crate::error::{AppError, Result};
+use crate::run::RunContext;
+use crate::sanitize::truncate;
+use async_trait::async_trait;
+use chrono::{Datelike, Days, NaiveDate, Utc};
+use regex::{Regex, RegexSet, RegexSetBuilder};
+use std::borrow::Cow;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// Month names and their abbreviations, as matched in article text
+const MONTHS: &str = "jan(?:uary)?|feb(?:ruary)?|mar(?:ch)?|apr(?:il)?|may|june?|july?|aug(?:ust)?|sep(?:t(?:ember)?)?|oct(?:ober)?|nov(?:ember)?|dec(?:ember)?";
+
+/// Longest description quoted in an event, in characters
+const MAX_DESCRIPTION_CHARS: usize = 500;
+
+/// Longest content line before folding, in octets (RFC 5545 section 3.1)
+const MAX_LINE_OCTETS: usize = 75;
+
+/// One all-day event, as the unfolded content lines between `BEGIN:VEVENT` and `END:VEVENT`
+struct Event {
+	uid: String,
+	start: NaiveDate,
+	lines: Vec<String>,
+}
+
+/// Keeps an iCalendar file of the upcoming releases, CFPs and events the articles announce. Each run adds
+/// or replaces the events of its articles and drops the events that started `keep_past_days` ago.
+pub struct IcsCalendarSink {
+	config: CalendarConfig,
+	path: PathBuf,
+	/// Event kinds with their patterns, by name
+	kinds: Vec<(String, RegexSet)>,
+	/// `2026-11-03`, `Nov 3, 2026`, `November 3-5` and `3 November 2026`, with named groups
+	dates: Vec<Regex>,
+}
+
+impl IcsCalendarSink {
+	#[must_use]
+	pub fn new(config: &CalendarConfig) -> Self {
+		let kinds = config
+			.patterns
+			.iter()
+			.filter_map(|(kind, patterns)| {
+				RegexSetBuilder::new(patterns)
+					.case_insensitive(true)
+					.build()
+					.ok()
+					.map(|set| (kind.clone(), set))
+			})
+			.collect();
+		let ordinal = "(?:st|nd|rd|th)?";
+		let dates = [
+			r"\b(?P<year>\d{4})-(?P<month>\d{2})-(?P<day>\d{2})\b".to_string(),
+			format!(
+				r"(?i)\b(?P<month>{MONTHS})\.?\s+(?P<day>\d{{1,2}}){ordinal}(?:\s*(?:-|–|to)\s*(?P<end>\d{{1,2}}){ordinal})?\b(?:,?\s+(?P<year>\d{{4}})\b)?"
+			),
+			format!(
+				r"(?i)\b(?P<day>\d{{1,2}}){ordinal}(?:\s*(?:-|–)\s*(?P<end>\d{{1,2}}){ordinal})?\s+(?:of\s+)?(?P<month>{MONTHS})\b\.?(?:,?\s+(?P<year>\d{{4}})\b)?"
+			),
+		]
+		.iter()
+		.filter_map(|pattern| Regex::new(pattern).ok())
+		.collect();
+		Self {
+			config: config.clone(),
+			path: PathBuf::from(&config.path),
+			kinds,
+			dates,
+		}
+	}
+
+	/// The event an article announces: its first matching kind, on the first date it mentions between
+	/// `today` and `calendar.horizon_days` ahead
+	fn event(&self, scored: &ScoredArticle, today: NaiveDate) -> Option<(&str, NaiveDate, NaiveDate)> {
+		let text = scored.article().searchable_text();
+		let (kind, _) = self.kinds.iter().find(|(_, set)| set.is_match(&text))?;
+		let horizon = today.checked_add_days(Days::new(self.config.horizon_days.into()))?;
+		let mut mentioned: Vec<(usize, NaiveDate, NaiveDate)> = self
+			.dates
+			.iter()
+			.flat_map(|pattern| pattern.captures_iter(&text))
+			.filter_map(|captures| {
+				let position = captures.get(0)?.start();
+				let (start, end) = date_range(&captures, today)?;
+				Some((position, start, end))
+			})
+			.filter(|(_, start, _)| (today..=horizon).contains(start))
+			.collect();
+		mentioned.sort_by_key(|(position, ..)| *position);
+		let (_, start, end) = mentioned.first()?;
+		Some((kind.as_str(), *start, *end))
+	}
+
+	/// The calendar entry of an article, all day from `start` to `end` inclusive
+	fn entry(run: &RunContext, scored: &ScoredArticle, kind: &str, start: NaiveDate, end: NaiveDate) -> Event {
+		let article = scored.article();
+		let uid = format!("{}@news-aggregator", article.id());
+		let mut description = format!(
+			"{} · score {:.1}\n{}",
+			article.source(),
+			scored.relevance_score(),
+			article.url()
+		);
+		if let Some(text) = scored.summary().or_else(|| article.description()) {
+			description.push_str("\n\n");
+			description.push_str(&truncate(text, MAX_DESCRIPTION_CHARS));
+		}
+		let lines = vec![
+			format!("UID:{uid}"),
+			format!("DTSTAMP:{}", run.started_at().format("%Y%m%dT%H%M%SZ")),
+			format!("DTSTART;VALUE=DATE:{}", start.format("%Y%m%d")),
+			format!("DTEND;VALUE=DATE:{}", end.succ_opt().unwrap_or(end).format("%Y%m%d")),
+			format!("SUMMARY:{}", escape_text(&format!("[{kind}] {}", article.title()))),
+			format!("CATEGORIES:{}", escape_text(kind)),
+			format!("URL:{}", article.link()),
+			format!("DESCRIPTION:{}", escape_text(&description)),
+			"TRANSP:TRANSPARENT".to_string(),
+		];
+		Event { uid, start, lines }
+	}
+
+	/// Events already in the file; none when it does not exist yet
+	fn existing(&self) -> Result<Vec<Event>> {
+		let contents = match fs::read_to_string(&self.path) {
+			Ok(contents) => contents,
+			Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+			Err(e) => return Err(AppError::io_error(&self.path, e)),
+		};
+		let unfolded = contents.replace("\r\n ", "").replace("\r\n\t", "");
+		Ok(unfolded
+			.split("BEGIN:VEVENT")
+			.skip(1)
+			.filter_map(|block| {
+				let lines: Vec<String> = block
+					.split("END:VEVENT")
+					.next()?
+					.lines()
+					.map(str::trim_end)
+					.filter(|line| !line.is_empty())
+					.map(str::to_string)
+					.collect();
+				let uid = lines.iter().find_map(|line| line.strip_prefix("UID:"))?.to_string();
+				let start = lines
+					.iter()
+					.find_map(|line| line.strip_prefix("DTSTART;VALUE=DATE:"))
+					.and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())?;
+				Some(Event { uid, start, lines })
+			})
+			.collect())
+	}
+
+	fn calendar(&self, events: &[Event]) -> String {
+		let mut lines = vec![
+			"BEGIN:VCALENDAR".to_string(),
+			"VERSION:2.0".to_string(),
+			"PRODID:-//news-aggregator//calendar//EN".to_string(),
+			"CALSCALE:GREGORIAN".to_string(),
+			"METHOD:PUBLISH".to_string(),
+			format!("X-WR-CALNAME:{}", escape_text(&self.config.name)),
+		];
+		for event in events {
+			lines.push("BEGIN:VEVENT".to_string());
+			lines.extend(event.lines.iter().cloned());
+			lines.push("END:VEVENT".to_string());
+		}
+		lines.push("END:VCALENDAR".to_string());
+		lines.iter().map(|line| fold(line)).collect()
+	}
+}
+
+/// Start and inclusive end of a matched date; a date without a year is its next occurrence from `today`
+fn date_range(captures: &regex::Captures<'_>, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+	let month = month_number(captures.name("month")?.as_str())?;
+	let day: u32 = captures.name("day")?.as_str().parse().ok()?;
+	let start = match captures.name("year") {
+		Some(year) => NaiveDate::from_ymd_opt(year.as_str().parse().ok()?, month, day)?,
+		None => NaiveDate::from_ymd_opt(today.year(), month, day)
+			.filter(|date| *date >= today)
+			.or_else(|| NaiveDate::from_ymd_opt(today.year().saturating_add(1), month, day))?,
+	};
+	let end = captures
+		.name("end")
+		.and_then(|end| end.as_str().parse().ok())
+		.filter(|end| *end > day)
+		.and_then(|end| NaiveDate::from_ymd_opt(start.year(), month, end))
+		.unwrap_or(start);
+	Some((start, end))
+}
+
+/// 1-12 from a month number or an English month name or abbreviation
+fn month_number(month: &str) -> Option<u32> {
+	if let Ok(number) = month.parse() {
+		return Some(number);
+	}
+	let prefix = month.get(..3)?.to_ascii_lowercase();
+	[
+		"jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+	]
+	.iter()
+	.position(|name| *name == prefix)
+	.and_then(|index| u32::try_from(index).ok())
+	.map(|index| index.saturating_add(1))
+}
+
+/// A TEXT value with backslashes, separators and line breaks escaped and other control characters dropped
+fn escape_text(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'\\' | ';' | ',' => {
+				escaped.push('\\');
+				escaped.push(c);
+			}
+			'\n' => escaped.push_str("\\n"),
+			c if c.is_control() => {}
+			c => escaped.push(c),
+		}
+	}
+	escaped
+}
+
+/// A content line ending in CRLF, split into continuation lines of at most 75 octets
+fn fold(line: &str) -> String {
+	let mut folded = String::with_capacity(line.len().saturating_add(8));
+	let mut octets: usize = 0;
+	for c in line.chars() {
+		if octets.saturating_add(c.len_utf8()) > MAX_LINE_OCTETS {
+			folded.push_str("\r\n ");
+			octets = 1;
+		}
+		folded.push(c);
+		octets = octets.saturating_add(c.len_utf8());
+	}
+	folded.push_str("\r\n");
+	folded
+}
+
+#[async_trait]
+impl Sink for IcsCalendarSink {
+	fn name(&self) -> &'static str {
+		"ics_calendar"
+	}
+
+	fn destination(&self) -> String {
+		self.path.display().to_string()
+	}
+
+	/// Only the articles announcing an upcoming event
+	fn select<'a>(&self, articles: &'a [ScoredArticle]) -> Cow<'a, [ScoredArticle]> {
+		let today = Utc::now().date_naive();
+		Cow::Owned(
+			articles
+				.iter()
+				.filter(|scored| self.event(scored, today).is_some())
+				.cloned()
+				.collect(),
+		)
+	}
+
+	async fn deliver(&self, run: &RunContext, articles: &[ScoredArticle]) -> Result<()> {
+		let today = run.started_at().date_naive();
+		let entries: Vec<Event> = articles
+			.iter()
+			.filter_map(|scored| {
+				let (kind, start, end) = self.event(scored, today)?;
+				Some(Self::entry(run, scored, kind, start, end))
+			})
+			.collect();
+		let cutoff = today
+			.checked_sub_days(Days::new(self.config.keep_past_days.into()))
+			.unwrap_or(today);
+		let mut events: Vec<Event> = self
+			.existing()?
+			.into_iter()
+			.filter(|event| event.start >= cutoff && !entries.iter().any(|entry| entry.uid == event.uid))
+			.collect();
+		events.extend(entries);
+		events.sort_by(|a, b| a.start.cmp(&b.start).then_with(|| a.uid.cmp(&b.uid)));
+
+		if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+			fs::create_dir_all(parent).map_err(|e| AppError::io_error(parent, e))?;
+		}
+		let partial = self.path.with_extension("ics.partial");
+		fs::write(&partial, self.calendar(&events)).map_err(|e| AppError::io_error(&partial, e))?;
+		fs::rename(&partial, &self.path).map_err(|e| AppError::io_error(&self.path, e))
+	}
+}
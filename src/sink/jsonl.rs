@@ -0,0 +1,116 @@
+use super::Sink;
+use crate::analyzer::ScoredArticle;
+use crate::config::ArchiveConfig;
+use crate::error::{AppError, Result};
+use crate::run::RunContext;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+/// One archived article, stamped with the run that scored it
+#[derive(Serialize)]
+pub(super) struct ArchiveRecord<'a> {
+	pub run_id: &'a str,
+	pub timestamp: DateTime<Utc>,
+	#[serde(flatten)]
+	pub scored: &'a ScoredArticle,
+}
+
+/// Appends every scored article of a run as one JSON line, rotating the file by size
+pub struct JsonlArchiveSink {
+	path: PathBuf,
+	max_bytes: u64,
+	max_files: usize,
+}
+
+impl JsonlArchiveSink {
+	#[must_use]
+	pub fn new(config: &ArchiveConfig) -> Self {
+		Self {
+			path: PathBuf::from(&config.path),
+			max_bytes: config.max_bytes,
+			max_files: config.max_files,
+		}
+	}
+
+	fn rotated_path(&self, index: usize) -> PathBuf {
+		let mut name = self.path.clone().into_os_string();
+		name.push(format!(".{index}"));
+		PathBuf::from(name)
+	}
+
+	/// Shift `archive.jsonl` -> `archive.jsonl.1` -> ... dropping the oldest beyond `max_files`
+	fn rotate_if_needed(&self) -> Result<()> {
+		let size = match fs::metadata(&self.path) {
+			Ok(meta) => meta.len(),
+			Err(_) => return Ok(()),
+		};
+		if self.max_bytes == 0 || size < self.max_bytes {
+			return Ok(());
+		}
+
+		if self.max_files == 0 {
+			return fs::remove_file(&self.path).map_err(|e| AppError::io_error(&self.path, e));
+		}
+
+		let oldest = self.rotated_path(self.max_files);
+		if oldest.exists() {
+			fs::remove_file(&oldest).map_err(|e| AppError::io_error(&oldest, e))?;
+		}
+		for index in (1..self.max_files).rev() {
+			let from = self.rotated_path(index);
+			if from.exists() {
+				let to = self.rotated_path(index.saturating_add(1));
+				fs::rename(&from, &to).map_err(|e| AppError::io_error(&from, e))?;
+			}
+		}
+		let first = self.rotated_path(1);
+		fs::rename(&self.path, &first).map_err(|e| AppError::io_error(&self.path, e))
+	}
+
+	fn append(&self, run: &RunContext, articles: &[ScoredArticle]) -> Result<()> {
+		if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+			fs::create_dir_all(parent).map_err(|e| AppError::io_error(parent, e))?;
+		}
+		self.rotate_if_needed()?;
+
+		let file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(&self.path)
+			.map_err(|e| AppError::io_error(&self.path, e))?;
+		let mut writer = BufWriter::new(file);
+		let timestamp = run.started_at();
+
+		for scored in articles {
+			let record = ArchiveRecord {
+				run_id: run.id(),
+				timestamp,
+				scored,
+			};
+			serde_json::to_writer(&mut writer, &record).map_err(|e| AppError::parse_error("JSONL archive", e))?;
+			writer.write_all(b"\n").map_err(|e| AppError::io_error(&self.path, e))?;
+		}
+
+		writer.flush().map_err(|e| AppError::io_error(&self.path, e))
+	}
+}
+
+#[async_trait]
+impl Sink for JsonlArchiveSink {
+	fn name(&self) -> &'static str {
+		"jsonl_archive"
+	}
+
+	fn destination(&self) -> String {
+		self.path.display().to_string()
+	}
+
+	async fn deliver(&self, run: &RunContext, articles: &[ScoredArticle]) -> Result<()> {
+		// Small appends; blocking file I/O here is cheaper than shipping the articles to another thread
+		tokio::task::block_in_place(|| self.append(run, articles))
+	}
+}
@@ -0,0 +1,100 @@
+use super::Sink;
+use super::jsonl::ArchiveRecord;
+use crate::analyzer::ScoredArticle;
+use crate::config::KafkaConfig;
+use crate::error::{AppError, Result};
+use crate::run::RunContext;
+use async_trait::async_trait;
+use futures::future::join_all;
+use rdkafka::ClientConfig;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+use std::time::Duration;
+
+/// Produces every scored article of a run as one JSON record to a Kafka topic, keyed by article id
+pub struct KafkaSink {
+	config: KafkaConfig,
+	topic: String,
+}
+
+impl KafkaSink {
+	#[must_use]
+	pub fn new(config: &KafkaConfig, active_profile: Option<&str>) -> Self {
+		let topic = config.topic.replace("{profile}", active_profile.unwrap_or("default"));
+		Self {
+			config: config.clone(),
+			topic,
+		}
+	}
+
+	/// A producer for this run; librdkafka keeps retrying within `message.timeout.ms`
+	fn producer(&self) -> Result<FutureProducer> {
+		let mut client = ClientConfig::new();
+		for (key, value) in &self.config.properties {
+			client.set(key, value);
+		}
+		client
+			.set("bootstrap.servers", &self.config.brokers)
+			.set("client.id", &self.config.client_id)
+			.set(
+				"message.timeout.ms",
+				self.config.timeout_secs.saturating_mul(1000).to_string(),
+			);
+		if let (Some(username), Some(password)) = (&self.config.sasl_username, &self.config.sasl_password) {
+			client
+				.set("sasl.username", username)
+				.set("sasl.password", password.resolve()?);
+			if !self.config.properties.contains_key("sasl.mechanism") {
+				client.set("sasl.mechanism", "PLAIN");
+			}
+			if !self.config.properties.contains_key("security.protocol") {
+				client.set("security.protocol", "SASL_SSL");
+			}
+		}
+		client
+			.create()
+			.map_err(|e| AppError::ConfigError(format!("kafka producer: {e}")))
+	}
+}
+
+#[async_trait]
+impl Sink for KafkaSink {
+	fn name(&self) -> &'static str {
+		"kafka"
+	}
+
+	fn destination(&self) -> String {
+		format!("kafka://{}/{}", self.config.brokers, self.topic)
+	}
+
+	/// Queue every record at once and wait for all delivery reports; the first failure is returned
+	async fn deliver(&self, run: &RunContext, articles: &[ScoredArticle]) -> Result<()> {
+		let producer = self.producer()?;
+		let timestamp = run.started_at();
+		let mut records = Vec::with_capacity(articles.len());
+		for scored in articles {
+			let record = ArchiveRecord {
+				run_id: run.id(),
+				timestamp,
+				scored,
+			};
+			let payload = serde_json::to_vec(&record).map_err(|e| AppError::parse_error("Kafka record", e))?;
+			records.push((scored.article().id(), payload));
+		}
+		let queue_timeout = Timeout::After(Duration::from_secs(self.config.timeout_secs));
+		let reports = join_all(records.iter().map(|(key, payload)| {
+			producer.send(
+				FutureRecord::to(&self.topic)
+					.key(key)
+					.payload(payload)
+					.timestamp(timestamp.timestamp_millis()),
+				queue_timeout,
+			)
+		}))
+		.await;
+		reports
+			.into_iter()
+			.find_map(std::result::Result::err)
+			.map_or(Ok(()), |(e, _)| Err(AppError::delivery_error("kafka", e)))
+	}
+}
@@ -0,0 +1,176 @@
+use super::Sink;
+use crate::analyzer::ScoredArticle;
+use crate::config::{MatrixConfig, MatrixMode};
+use crate::error::{AppError, Result};
+use crate::run::RunContext;
+use crate::sanitize::{escape_html, truncate};
+use async_trait::async_trait;
+use reqwest::{Client, Url};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::fmt::Write as _;
+
+/// Longest summary or description quoted in a message, in characters
+const MAX_SUMMARY_CHARS: usize = 400;
+
+/// An `m.room.message` event with an HTML body and its plain-text fallback
+#[derive(Serialize)]
+struct RoomMessage {
+	msgtype: &'static str,
+	body: String,
+	format: &'static str,
+	formatted_body: String,
+}
+
+/// Posts the articles of a run to a Matrix room, as one digest or one message each
+pub struct MatrixSink {
+	client: Client,
+	config: MatrixConfig,
+}
+
+impl MatrixSink {
+	#[must_use]
+	pub fn new(client: Client, config: &MatrixConfig) -> Self {
+		Self {
+			client,
+			config: config.clone(),
+		}
+	}
+
+	/// The URL sending an event with transaction id `txn_id`; the homeserver ignores a repeated one
+	fn send_url(&self, txn_id: &str) -> Result<Url> {
+		let mut url = Url::parse(&self.config.homeserver)
+			.map_err(|e| AppError::ConfigError(format!("matrix.homeserver {}: {e}", self.config.homeserver)))?;
+		url.path_segments_mut()
+			.map_err(|()| {
+				AppError::ConfigError(format!("matrix.homeserver {} cannot be a base", self.config.homeserver))
+			})?
+			.pop_if_empty()
+			.extend([
+				"_matrix",
+				"client",
+				"v3",
+				"rooms",
+				&self.config.room_id,
+				"send",
+				"m.room.message",
+				txn_id,
+			]);
+		Ok(url)
+	}
+
+	const fn message(&self, body: String, formatted_body: String) -> RoomMessage {
+		RoomMessage {
+			msgtype: if self.config.notice { "m.notice" } else { "m.text" },
+			body,
+			format: "org.matrix.custom.html",
+			formatted_body,
+		}
+	}
+
+	async fn send(&self, txn_id: &str, message: &RoomMessage) -> Result<()> {
+		let url = self.send_url(txn_id)?;
+		self.client
+			.put(url.clone())
+			.bearer_auth(self.config.access_token.resolve()?)
+			.json(message)
+			.send()
+			.await
+			.and_then(reqwest::Response::error_for_status)
+			.map_err(|e| AppError::http_error(url.as_str(), e))?;
+		Ok(())
+	}
+}
+
+/// Plain and HTML lines of one article: linked title, then source and score
+fn headline(scored: &ScoredArticle) -> (String, String) {
+	let article = scored.article();
+	let score = scored.relevance_score();
+	let plain = format!(
+		"{} ({}, {score:.1})\n{}",
+		article.title(),
+		article.source(),
+		article.link()
+	);
+	let html = format!(
+		"<a href=\"{}\">{}</a> — {} · {score:.1}",
+		escape_html(article.link()),
+		escape_html(article.title()),
+		escape_html(article.source())
+	);
+	(plain, html)
+}
+
+/// One message listing every article in ranking order
+fn digest(run: &RunContext, articles: &[ScoredArticle]) -> (String, String) {
+	let mut plain = format!("Top news, run {}\n", run.id());
+	let mut html = format!(
+		"<h4>Top news</h4>\n<p>Run <code>{}</code></p>\n<ol>\n",
+		escape_html(run.id())
+	);
+	for (index, scored) in articles.iter().enumerate() {
+		let (line, item) = headline(scored);
+		let _ = writeln!(plain, "{}. {line}", index.saturating_add(1));
+		let _ = writeln!(html, "<li>{item}</li>");
+	}
+	html.push_str("</ol>");
+	(plain, html)
+}
+
+/// One message about one article, quoting its summary or description
+fn item(scored: &ScoredArticle) -> (String, String) {
+	let (mut plain, mut html) = headline(scored);
+	if let Some(text) = scored.summary().or_else(|| scored.article().description()) {
+		let text = truncate(text, MAX_SUMMARY_CHARS);
+		let _ = write!(plain, "\n\n{text}");
+		let _ = write!(html, "<blockquote>{}</blockquote>", escape_html(&text));
+	}
+	(plain, html)
+}
+
+#[async_trait]
+impl Sink for MatrixSink {
+	fn name(&self) -> &'static str {
+		"matrix"
+	}
+
+	fn destination(&self) -> String {
+		format!(
+			"{}/{}",
+			self.config.homeserver.trim_end_matches('/'),
+			self.config.room_id
+		)
+	}
+
+	fn select<'a>(&self, articles: &'a [ScoredArticle]) -> Cow<'a, [ScoredArticle]> {
+		Cow::Owned(
+			articles
+				.iter()
+				.filter(|scored| scored.relevance_score() >= self.config.min_score)
+				.take(self.config.max_items)
+				.cloned()
+				.collect(),
+		)
+	}
+
+	async fn deliver(&self, run: &RunContext, articles: &[ScoredArticle]) -> Result<()> {
+		if articles.is_empty() {
+			return Ok(());
+		}
+		match self.config.mode {
+			MatrixMode::Digest => {
+				let (plain, html) = digest(run, articles);
+				self.send(&format!("{}-digest", run.id()), &self.message(plain, html))
+					.await
+			}
+			MatrixMode::Items => {
+				for (index, scored) in articles.iter().enumerate() {
+					let (plain, html) = item(scored);
+					self.send(&format!("{}-{index}", run.id()), &self.message(plain, html))
+						.await?;
+				}
+				Ok(())
+			}
+		}
+	}
+}
@@ -0,0 +1,185 @@
+use super::Sink;
+use super::jsonl::ArchiveRecord;
+use crate::analyzer::ScoredArticle;
+use crate::config::MqttConfig;
+use crate::error::{AppError, Result};
+use crate::run::RunContext;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rumqttc::{AsyncClient, Event, MqttOptions, Outgoing, Packet, QoS, TlsConfiguration, Transport};
+use serde::Serialize;
+use std::time::Duration;
+use tokio::time::timeout;
+
+/// Run summary published to `summary_topic`
+#[derive(Serialize)]
+struct Summary<'a> {
+	run_id: &'a str,
+	timestamp: DateTime<Utc>,
+	profile: &'a str,
+	articles: usize,
+	top: Vec<SummaryEntry<'a>>,
+}
+
+#[derive(Serialize)]
+struct SummaryEntry<'a> {
+	rank: usize,
+	title: &'a str,
+	url: &'a str,
+	source: &'a str,
+	score: f64,
+}
+
+/// Publishes the top articles of a run, one message each, and a run summary to an MQTT broker
+pub struct MqttSink {
+	config: MqttConfig,
+	profile: String,
+}
+
+impl MqttSink {
+	#[must_use]
+	pub fn new(config: &MqttConfig, profile: Option<&str>) -> Self {
+		Self {
+			config: config.clone(),
+			profile: profile.unwrap_or("default").to_string(),
+		}
+	}
+
+	const fn qos(&self) -> QoS {
+		match self.config.qos {
+			0 => QoS::AtMostOnce,
+			1 => QoS::AtLeastOnce,
+			_ => QoS::ExactlyOnce,
+		}
+	}
+
+	fn topic(&self, template: &str, position: usize) -> String {
+		template
+			.replace("{rank}", &position.to_string())
+			.replace("{profile}", &self.profile)
+	}
+
+	/// Topic and JSON payload of every message of the run, articles first
+	fn messages(&self, run: &RunContext, articles: &[ScoredArticle]) -> Result<Vec<(String, Vec<u8>)>> {
+		let timestamp = run.started_at();
+		let top = articles
+			.get(..self.config.top_n.min(articles.len()))
+			.unwrap_or_default();
+		let mut messages = Vec::with_capacity(top.len().saturating_add(1));
+		for (index, scored) in top.iter().enumerate() {
+			let record = ArchiveRecord {
+				run_id: run.id(),
+				timestamp,
+				scored,
+			};
+			let payload = serde_json::to_vec(&record).map_err(|e| AppError::parse_error("MQTT message", e))?;
+			messages.push((self.topic(&self.config.topic, index.saturating_add(1)), payload));
+		}
+		if let Some(summary_topic) = &self.config.summary_topic {
+			let summary = Summary {
+				run_id: run.id(),
+				timestamp,
+				profile: &self.profile,
+				articles: articles.len(),
+				top: top
+					.iter()
+					.enumerate()
+					.map(|(index, scored)| SummaryEntry {
+						rank: index.saturating_add(1),
+						title: scored.article().title(),
+						url: scored.article().url(),
+						source: scored.article().source(),
+						score: scored.relevance_score(),
+					})
+					.collect(),
+			};
+			let payload = serde_json::to_vec(&summary).map_err(|e| AppError::parse_error("MQTT summary", e))?;
+			messages.push((self.topic(summary_topic, 0), payload));
+		}
+		Ok(messages)
+	}
+
+	fn options(&self) -> Result<MqttOptions> {
+		let mut options = MqttOptions::new(&self.config.client_id, &self.config.host, self.config.port);
+		options.set_keep_alive(Duration::from_secs(30));
+		if let Some(username) = &self.config.username {
+			let password = self
+				.config
+				.password
+				.as_ref()
+				.map(crate::secret::Secret::resolve)
+				.transpose()?;
+			options.set_credentials(username, password.unwrap_or_default());
+		}
+		if self.config.tls {
+			options.set_transport(Transport::Tls(TlsConfiguration::Native));
+		}
+		Ok(options)
+	}
+
+	/// Connect, publish every message and wait until the broker has acknowledged them all (or, at
+	/// `QoS` 0, until they are written), then disconnect
+	async fn publish(&self, messages: Vec<(String, Vec<u8>)>) -> Result<()> {
+		let total = messages.len();
+		let (client, mut eventloop) = AsyncClient::new(self.options()?, total.saturating_add(2));
+		let qos = self.qos();
+		for (topic, payload) in messages {
+			client
+				.publish(topic, qos, self.config.retain, payload)
+				.await
+				.map_err(|e| AppError::delivery_error("mqtt", e))?;
+		}
+
+		let mut done = 0usize;
+		loop {
+			let event = eventloop
+				.poll()
+				.await
+				.map_err(|e| AppError::delivery_error("mqtt", e))?;
+			let acknowledged = match (qos, event) {
+				(_, Event::Outgoing(Outgoing::Disconnect)) => return Ok(()),
+				(QoS::AtMostOnce, Event::Outgoing(Outgoing::Publish(_)))
+				| (QoS::AtLeastOnce, Event::Incoming(Packet::PubAck(_)))
+				| (QoS::ExactlyOnce, Event::Incoming(Packet::PubComp(_))) => true,
+				_ => false,
+			};
+			if acknowledged {
+				done = done.saturating_add(1);
+				if done == total {
+					client
+						.disconnect()
+						.await
+						.map_err(|e| AppError::delivery_error("mqtt", e))?;
+				}
+			}
+		}
+	}
+}
+
+#[async_trait]
+impl Sink for MqttSink {
+	fn name(&self) -> &'static str {
+		"mqtt"
+	}
+
+	fn destination(&self) -> String {
+		format!(
+			"mqtt{}://{}:{}/{}",
+			if self.config.tls { "s" } else { "" },
+			self.config.host,
+			self.config.port,
+			self.config.topic
+		)
+	}
+
+	async fn deliver(&self, run: &RunContext, articles: &[ScoredArticle]) -> Result<()> {
+		let messages = self.messages(run, articles)?;
+		if messages.is_empty() {
+			return Ok(());
+		}
+		let destination = self.destination();
+		timeout(Duration::from_secs(self.config.timeout_secs), self.publish(messages))
+			.await
+			.map_err(|_| AppError::TimeoutError(destination))?
+	}
+}
@@ -0,0 +1,89 @@
+use super::Sink;
+use super::jsonl::ArchiveRecord;
+use crate::analyzer::ScoredArticle;
+use crate::config::NatsConfig;
+use crate::error::{AppError, Result};
+use crate::run::RunContext;
+use async_nats::{ConnectOptions, HeaderMap};
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::time::Duration;
+use tokio::time::timeout;
+
+/// Publishes every scored article of a run as one JSON message to a NATS subject
+pub struct NatsSink {
+	config: NatsConfig,
+	subject: String,
+}
+
+impl NatsSink {
+	#[must_use]
+	pub fn new(config: &NatsConfig, active_profile: Option<&str>) -> Self {
+		let subject = config.subject.replace("{profile}", active_profile.unwrap_or("default"));
+		Self {
+			config: config.clone(),
+			subject,
+		}
+	}
+
+	async fn options(&self) -> Result<ConnectOptions> {
+		let options = match (&self.config.token, &self.config.credentials_file) {
+			(Some(token), _) => ConnectOptions::with_token(token.resolve()?),
+			(None, Some(path)) => ConnectOptions::with_credentials_file(path)
+				.await
+				.map_err(|e| AppError::io_error(path, e))?,
+			(None, None) => ConnectOptions::new(),
+		};
+		Ok(options
+			.name("news-aggregator")
+			.connection_timeout(Duration::from_secs(self.config.timeout_secs)))
+	}
+
+	/// Connect, publish every article and flush, so that the server has them all before returning.
+	/// `Nats-Msg-Id` is the run and article id, for `JetStream` to drop duplicates.
+	async fn publish(&self, run: &RunContext, articles: &[ScoredArticle]) -> Result<()> {
+		let servers: Vec<&str> = self.config.url.split(',').map(str::trim).collect();
+		let client = self
+			.options()
+			.await?
+			.connect(servers)
+			.await
+			.map_err(|e| AppError::delivery_error("nats", e))?;
+		let timestamp = run.started_at();
+		for scored in articles {
+			let record = ArchiveRecord {
+				run_id: run.id(),
+				timestamp,
+				scored,
+			};
+			let payload = serde_json::to_vec(&record).map_err(|e| AppError::parse_error("NATS message", e))?;
+			let mut headers = HeaderMap::new();
+			headers.insert("Nats-Msg-Id", format!("{}-{}", run.id(), scored.article().id()));
+			client
+				.publish_with_headers(self.subject.clone(), headers, Bytes::from(payload))
+				.await
+				.map_err(|e| AppError::delivery_error("nats", e))?;
+		}
+		client.flush().await.map_err(|e| AppError::delivery_error("nats", e))
+	}
+}
+
+#[async_trait]
+impl Sink for NatsSink {
+	fn name(&self) -> &'static str {
+		"nats"
+	}
+
+	fn destination(&self) -> String {
+		format!("{}/{}", self.config.url, self.subject)
+	}
+
+	async fn deliver(&self, run: &RunContext, articles: &[ScoredArticle]) -> Result<()> {
+		timeout(
+			Duration::from_secs(self.config.timeout_secs),
+			self.publish(run, articles),
+		)
+		.await
+		.map_err(|_| AppError::TimeoutError(self.destination()))?
+	}
+}
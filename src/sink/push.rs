@@ -0,0 +1,196 @@
+use super::Sink;
+use crate::analyzer::ScoredArticle;
+use crate::config::{GotifyConfig, NtfyConfig, PushoverConfig, QuietHours};
+use crate::error::{AppError, Result};
+use crate::run::RunContext;
+use crate::sanitize::truncate;
+use async_trait::async_trait;
+use chrono::Local;
+use reqwest::{Client, RequestBuilder};
+use serde_json::json;
+use std::borrow::Cow;
+
+/// Longest notification body, in characters
+const MAX_MESSAGE_CHARS: usize = 300;
+
+const PUSHOVER_URL: &str = "https://api.pushover.net/1/messages.json";
+
+/// Which articles a push sink sends, and when
+struct Rules {
+	min_score: f64,
+	max_items: usize,
+	quiet_hours: Option<QuietHours>,
+}
+
+/// The push services, with their settings
+enum Service {
+	Ntfy(NtfyConfig),
+	Pushover(PushoverConfig),
+	Gotify(GotifyConfig),
+}
+
+/// Sends one phone notification per article scoring at least `min_score`, at most `max_items` per run
+/// and none during quiet hours
+pub struct PushSink {
+	client: Client,
+	service: Service,
+	rules: Rules,
+}
+
+impl PushSink {
+	#[must_use]
+	pub fn ntfy(client: Client, config: &NtfyConfig) -> Self {
+		Self {
+			client,
+			rules: Rules {
+				min_score: config.min_score,
+				max_items: config.max_items,
+				quiet_hours: config.quiet_hours,
+			},
+			service: Service::Ntfy(config.clone()),
+		}
+	}
+
+	#[must_use]
+	pub fn pushover(client: Client, config: &PushoverConfig) -> Self {
+		Self {
+			client,
+			rules: Rules {
+				min_score: config.min_score,
+				max_items: config.max_items,
+				quiet_hours: config.quiet_hours,
+			},
+			service: Service::Pushover(config.clone()),
+		}
+	}
+
+	#[must_use]
+	pub fn gotify(client: Client, config: &GotifyConfig) -> Self {
+		Self {
+			client,
+			rules: Rules {
+				min_score: config.min_score,
+				max_items: config.max_items,
+				quiet_hours: config.quiet_hours,
+			},
+			service: Service::Gotify(config.clone()),
+		}
+	}
+
+	/// The notification request of one article
+	fn request(&self, scored: &ScoredArticle) -> Result<RequestBuilder> {
+		let article = scored.article();
+		let title = article.title();
+		let url = article.link();
+		let message = message(scored);
+		let request = match &self.service {
+			Service::Ntfy(config) => {
+				let mut body = json!({
+					"topic": config.topic,
+					"title": title,
+					"message": message,
+					"click": url,
+					"priority": config.priority,
+				});
+				if let (Some(body), Some(image)) = (body.as_object_mut(), article.image_url()) {
+					body.insert("attach".into(), json!(image));
+				}
+				let request = self.client.post(config.server.trim_end_matches('/')).json(&body);
+				match &config.token {
+					Some(token) => request.bearer_auth(token.resolve()?),
+					None => request,
+				}
+			}
+			Service::Pushover(config) => {
+				let priority = config.priority.to_string();
+				let mut form = vec![
+					("token", config.app_token.resolve()?),
+					("user", config.user_key.resolve()?),
+					("title", title.to_string()),
+					("message", message),
+					("url", url.to_string()),
+					("priority", priority),
+				];
+				if let Some(device) = &config.device {
+					form.push(("device", device.clone()));
+				}
+				self.client.post(PUSHOVER_URL).form(&form)
+			}
+			Service::Gotify(config) => {
+				let mut notification = json!({ "click": { "url": url } });
+				if let (Some(notification), Some(image)) = (notification.as_object_mut(), article.image_url()) {
+					notification.insert("bigImageUrl".into(), json!(image));
+				}
+				self.client
+					.post(format!("{}/message", config.server.trim_end_matches('/')))
+					.header("X-Gotify-Key", config.token.resolve()?)
+					.json(&json!({
+						"title": title,
+						"message": message,
+						"priority": config.priority,
+						"extras": { "client::notification": notification },
+					}))
+			}
+		};
+		Ok(request)
+	}
+}
+
+/// Source and score, then the summary or description
+fn message(scored: &ScoredArticle) -> String {
+	let article = scored.article();
+	let header = format!("{} · score {:.1}", article.source(), scored.relevance_score());
+	match scored.summary().or_else(|| article.description()) {
+		Some(text) => truncate(&format!("{header}\n{text}"), MAX_MESSAGE_CHARS),
+		None => header,
+	}
+}
+
+#[async_trait]
+impl Sink for PushSink {
+	fn name(&self) -> &'static str {
+		match self.service {
+			Service::Ntfy(_) => "ntfy",
+			Service::Pushover(_) => "pushover",
+			Service::Gotify(_) => "gotify",
+		}
+	}
+
+	fn destination(&self) -> String {
+		match &self.service {
+			Service::Ntfy(config) => format!("{}/{}", config.server.trim_end_matches('/'), config.topic),
+			Service::Pushover(_) => PUSHOVER_URL.to_string(),
+			Service::Gotify(config) => config.server.clone(),
+		}
+	}
+
+	fn select<'a>(&self, articles: &'a [ScoredArticle]) -> Cow<'a, [ScoredArticle]> {
+		Cow::Owned(
+			articles
+				.iter()
+				.filter(|scored| scored.relevance_score() >= self.rules.min_score)
+				.take(self.rules.max_items)
+				.cloned()
+				.collect(),
+		)
+	}
+
+	fn paused(&self) -> bool {
+		self.rules
+			.quiet_hours
+			.is_some_and(|quiet| quiet.contains(Local::now().time()))
+	}
+
+	/// One notification per article, in ranking order; the first failure stops the rest
+	async fn deliver(&self, _run: &RunContext, articles: &[ScoredArticle]) -> Result<()> {
+		for scored in articles {
+			let destination = self.destination();
+			self.request(scored)?
+				.send()
+				.await
+				.and_then(reqwest::Response::error_for_status)
+				.map_err(|e| AppError::http_error(&destination, e))?;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,311 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::SiteConfig;
+use crate::error::{AppError, Result};
+use crate::model::Article;
+use crate::sanitize::{escape_html, plain_text, truncate};
+use crate::store::StoreContents;
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write as _;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Longest description quoted under an article, in characters
+const MAX_DESCRIPTION_CHARS: usize = 300;
+
+const STYLE: &str =
+	"body { font-family: sans-serif; max-width: 46em; margin: 2em auto; padding: 0 1em; line-height: 1.5; }
+header a, nav a { margin-right: 1em; }
+ol li { margin-bottom: 0.8em; overflow: auto; }
+.thumb { float: right; width: 8em; height: 5em; object-fit: cover; margin-left: 0.8em; }
+.meta { color: #666; font-size: 0.9em; }
+.tag { font-size: 0.85em; margin-right: 0.5em; }
+";
+
+/// One article as ranked on one day
+struct Entry<'a> {
+	article: &'a Article,
+	score: f64,
+	day: NaiveDate,
+}
+
+/// Published rankings by day, newest day first, each ranked by score
+type DayPages<'a> = Vec<(NaiveDate, Vec<Entry<'a>>)>;
+
+/// Published articles by tag slug: the tag as first spelled and its articles, newest first
+type TagPages<'a> = BTreeMap<String, (&'a str, Vec<&'a Entry<'a>>)>;
+
+/// Regenerate the site in `site.dir` from the rankings the store recorded for `profile`;
+/// returns the number of articles published
+///
+/// # Errors
+///
+/// `IoError` when a page cannot be written
+pub fn publish(config: &SiteConfig, contents: &StoreContents, profile: &str, now: DateTime<Utc>) -> Result<usize> {
+	let days = rankings_by_day(config, contents, profile, now);
+	let tags = tags(&days);
+	let dir = Path::new(&config.dir);
+	let mut written = HashSet::new();
+	write_page(dir, "style.css", STYLE, &mut written)?;
+	write_page(dir, ".nojekyll", "", &mut written)?;
+	write_page(dir, "index.html", &index(config, &days, &tags), &mut written)?;
+	for (day, entries) in &days {
+		let body = format!("<h2>{day}</h2>\n{}", entry_list(entries.iter(), Some("../"), false));
+		write_page(
+			dir,
+			&format!("days/{day}.html"),
+			&page(&config.title, &day.to_string(), &body, "../"),
+			&mut written,
+		)?;
+	}
+	for (slug, (tag, entries)) in &tags {
+		let body = format!(
+			"<h2>Tagged {}</h2>\n{}",
+			escape_html(tag),
+			entry_list(entries.iter().copied(), Some("../"), true)
+		);
+		write_page(
+			dir,
+			&format!("tags/{slug}.html"),
+			&page(&config.title, tag, &body, "../"),
+			&mut written,
+		)?;
+	}
+	remove_stale(&dir.join("days"), &written)?;
+	remove_stale(&dir.join("tags"), &written)?;
+	Ok(days.iter().map(|(_, entries)| entries.len()).sum())
+}
+
+/// The profile's recorded rankings within `site.days`, grouped by UTC day, newest first. An article ranked by
+/// several runs of a day keeps its best score; articles the store no longer holds are left out.
+fn rankings_by_day<'a>(
+	config: &SiteConfig,
+	contents: &'a StoreContents,
+	profile: &str,
+	now: DateTime<Utc>,
+) -> DayPages<'a> {
+	let articles: HashMap<&str, &Article> = contents
+		.articles
+		.iter()
+		.map(|stored| (stored.article.url(), &stored.article))
+		.collect();
+	let first_day = (config.days > 0)
+		.then(|| {
+			now.date_naive()
+				.checked_sub_days(Days::new(u64::from(config.days).saturating_sub(1)))
+		})
+		.flatten();
+	let mut by_day: BTreeMap<NaiveDate, HashMap<&str, Entry<'a>>> = BTreeMap::new();
+	for ranking in contents.rankings.iter().filter(|ranking| ranking.profile == profile) {
+		let day = ranking.ranked_at.date_naive();
+		if first_day.is_some_and(|first| day < first) {
+			continue;
+		}
+		let Some(article) = articles.get(ranking.url.as_str()) else {
+			continue;
+		};
+		let entry = by_day.entry(day).or_default().entry(article.url()).or_insert(Entry {
+			article,
+			score: ranking.score,
+			day,
+		});
+		entry.score = entry.score.max(ranking.score);
+	}
+	by_day
+		.into_iter()
+		.rev()
+		.map(|(day, entries)| {
+			let mut entries: Vec<Entry<'a>> = entries.into_values().collect();
+			entries.sort_by(|a, b| {
+				b.score
+					.total_cmp(&a.score)
+					.then_with(|| a.article.title().cmp(b.article.title()))
+			});
+			entries.truncate(config.top_n);
+			(day, entries)
+		})
+		.collect()
+}
+
+/// Every tag of the published articles; an article ranked on several days is listed under its latest
+fn tags<'a>(days: &'a [(NaiveDate, Vec<Entry<'a>>)]) -> TagPages<'a> {
+	let mut tags = TagPages::new();
+	let mut listed: HashSet<(String, &str)> = HashSet::new();
+	for entry in days.iter().flat_map(|(_, entries)| entries) {
+		for tag in entry.article.tags() {
+			let slug = slug(tag);
+			if slug.is_empty() || !listed.insert((slug.clone(), entry.article.url())) {
+				continue;
+			}
+			tags.entry(slug).or_insert((tag.as_str(), Vec::new())).1.push(entry);
+		}
+	}
+	tags
+}
+
+/// Lowercase ASCII letters and digits, with every other run of characters turned into one dash
+fn slug(tag: &str) -> String {
+	let mut slug = String::with_capacity(tag.len());
+	for c in tag.chars() {
+		if c.is_ascii_alphanumeric() {
+			slug.push(c.to_ascii_lowercase());
+		} else if !slug.is_empty() && !slug.ends_with('-') {
+			slug.push('-');
+		}
+	}
+	slug.trim_end_matches('-').to_string()
+}
+
+/// The latest day's ranking, then links to every day and tag page
+fn index(config: &SiteConfig, days: &[(NaiveDate, Vec<Entry<'_>>)], tags: &TagPages<'_>) -> String {
+	let mut body = String::new();
+	match days.first() {
+		Some((day, entries)) => {
+			let _ = writeln!(body, "<h2>{day}</h2>\n{}", entry_list(entries.iter(), Some(""), false));
+		}
+		None => body.push_str("<p>Nothing ranked yet.</p>\n"),
+	}
+	if !days.is_empty() {
+		body.push_str("<h2>Archive</h2>\n<ul>\n");
+		for (day, entries) in days {
+			let _ = writeln!(
+				body,
+				"<li><a href=\"days/{day}.html\">{day}</a> <span class=\"meta\">{} articles</span></li>",
+				entries.len()
+			);
+		}
+		body.push_str("</ul>\n");
+	}
+	if !tags.is_empty() {
+		body.push_str("<h2>Tags</h2>\n<p>\n");
+		for (slug, (tag, entries)) in tags {
+			let _ = writeln!(
+				body,
+				"<a class=\"tag\" href=\"tags/{slug}.html\">{}</a> ({})",
+				escape_html(tag),
+				entries.len()
+			);
+		}
+		body.push_str("</p>\n");
+	}
+	page(&config.title, &config.title, &body, "")
+}
+
+/// Ranked articles with their thumbnail, source, score and tags; `root` leads back to the site's top directory,
+/// and without one the tags and days are not linked. The store keeps articles as fetched, so titles and
+/// descriptions may still hold markup.
+fn entry_list<'e, 'a: 'e>(entries: impl Iterator<Item = &'e Entry<'a>>, root: Option<&str>, show_day: bool) -> String {
+	let mut html = String::from("<ol>\n");
+	for entry in entries {
+		let article = entry.article;
+		html.push_str("<li>");
+		if let Some(image) = article.image_url() {
+			let _ = write!(
+				html,
+				"<img class=\"thumb\" src=\"{}\" alt=\"\" loading=\"lazy\">",
+				escape_html(image)
+			);
+		}
+		let _ = write!(
+			html,
+			"<a href=\"{}\">{}</a><br><span class=\"meta\">{} · {:.1}",
+			escape_html(article.url()),
+			escape_html(&plain_text(article.title())),
+			escape_html(article.source()),
+			entry.score
+		);
+		if let Some(root) = root.filter(|_| show_day) {
+			let _ = write!(html, " · <a href=\"{root}days/{0}.html\">{0}</a>", entry.day);
+		}
+		html.push_str("</span>");
+		for tag in article.tags() {
+			let slug = slug(tag);
+			match root {
+				Some(root) if !slug.is_empty() => {
+					let _ = write!(
+						html,
+						" <a class=\"tag\" href=\"{root}tags/{slug}.html\">{}</a>",
+						escape_html(tag)
+					);
+				}
+				Some(_) => {}
+				None => {
+					let _ = write!(html, " <span class=\"tag\">{}</span>", escape_html(tag));
+				}
+			}
+		}
+		if let Some(description) = article.description() {
+			let _ = write!(
+				html,
+				"<br>{}",
+				escape_html(&truncate(&plain_text(description), MAX_DESCRIPTION_CHARS))
+			);
+		}
+		html.push_str("</li>\n");
+	}
+	html.push_str("</ol>\n");
+	html
+}
+
+/// One day's ranking as a page of its own, with the style inlined and no links into a site, for uploads such as
+/// the S3 `html` format
+#[must_use]
+pub fn ranking_page(title: &str, day: NaiveDate, ranked: &[ScoredArticle]) -> String {
+	let entries: Vec<Entry<'_>> = ranked
+		.iter()
+		.map(|scored| Entry {
+			article: scored.article(),
+			score: scored.relevance_score(),
+			day,
+		})
+		.collect();
+	format!(
+		"<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+		<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+		<title>{0}</title>\n<style>\n{STYLE}</style>\n</head>\n<body>\n\
+		<header><h1>{0}</h1></header>\n<main>\n<h2>{day}</h2>\n{1}</main>\n</body>\n</html>\n",
+		escape_html(title),
+		entry_list(entries.iter(), None, false)
+	)
+}
+
+fn page(site_title: &str, title: &str, body: &str, root: &str) -> String {
+	format!(
+		"<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+		<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+		<title>{}</title>\n<link rel=\"stylesheet\" href=\"{root}style.css\">\n</head>\n<body>\n\
+		<header><h1><a href=\"{root}index.html\">{}</a></h1></header>\n<main>\n{body}</main>\n</body>\n</html>\n",
+		escape_html(title),
+		escape_html(site_title)
+	)
+}
+
+/// Write one file under `dir` through a temporary file, so a published page is never half written
+fn write_page(dir: &Path, name: &str, contents: &str, written: &mut HashSet<PathBuf>) -> Result<()> {
+	let path = dir.join(name);
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent).map_err(|e| AppError::io_error(parent, e))?;
+	}
+	let partial = dir.join(format!("{name}.partial"));
+	fs::write(&partial, contents).map_err(|e| AppError::io_error(&partial, e))?;
+	fs::rename(&partial, &path).map_err(|e| AppError::io_error(&path, e))?;
+	written.insert(path);
+	Ok(())
+}
+
+/// Remove the pages in `dir` this run did not write, e.g. days past `site.days` or tags no article carries
+fn remove_stale(dir: &Path, written: &HashSet<PathBuf>) -> Result<()> {
+	let entries = match fs::read_dir(dir) {
+		Ok(entries) => entries,
+		Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+		Err(e) => return Err(AppError::io_error(dir, e)),
+	};
+	for entry in entries.flatten() {
+		let path = entry.path();
+		if path.extension().is_some_and(|extension| extension == "html") && !written.contains(&path) {
+			fs::remove_file(&path).map_err(|e| AppError::io_error(&path, e))?;
+		}
+	}
+	Ok(())
+}
@@ -0,0 +1,152 @@
+use crate::error::{AppError, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// A parsed sitemap document: either a list of pages or an index of further sitemaps
+#[derive(Debug)]
+pub enum Sitemap {
+	Pages(Vec<Entry>),
+	Index(Vec<Entry>),
+}
+
+/// One `<url>` or `<sitemap>` entry
+#[derive(Debug, Clone)]
+pub struct Entry {
+	pub url: String,
+	pub lastmod: Option<DateTime<Utc>>,
+}
+
+impl Sitemap {
+	/// Parse a `<urlset>` or `<sitemapindex>` document (sitemaps.org protocol)
+	///
+	/// # Errors
+	///
+	/// `ParseError` when the body is neither a `<urlset>` nor a `<sitemapindex>`
+	pub fn parse(origin: &str, body: &str) -> Result<Self> {
+		let document = roxmltree::Document::parse(body).map_err(|e| AppError::parse_error(origin, e))?;
+		let root = document.root_element();
+		let (entry_tag, index) = match root.tag_name().name() {
+			"urlset" => ("url", false),
+			"sitemapindex" => ("sitemap", true),
+			other => {
+				return Err(AppError::parse_error(
+					origin,
+					format!("unexpected root element <{other}>"),
+				));
+			}
+		};
+		let entries = root
+			.children()
+			.filter(|node| node.has_tag_name(entry_tag))
+			.filter_map(|node| {
+				let text = |tag: &str| {
+					node.children()
+						.find(|child| child.has_tag_name(tag))
+						.and_then(|child| child.text())
+						.map(str::trim)
+				};
+				Some(Entry {
+					url: text("loc").filter(|loc| !loc.is_empty())?.to_string(),
+					lastmod: text("lastmod").and_then(parse_lastmod),
+				})
+			})
+			.collect();
+		Ok(if index {
+			Self::Index(entries)
+		} else {
+			Self::Pages(entries)
+		})
+	}
+}
+
+/// W3C datetime as used by `<lastmod>`: a full RFC 3339 timestamp or a bare date
+fn parse_lastmod(text: &str) -> Option<DateTime<Utc>> {
+	DateTime::parse_from_rfc3339(text)
+		.map(|date| date.with_timezone(&Utc))
+		.ok()
+		.or_else(|| {
+			NaiveDate::parse_from_str(text, "%Y-%m-%d")
+				.ok()
+				.and_then(|date| date.and_hms_opt(0, 0, 0))
+				.map(|date| date.and_utc())
+		})
+}
+
+/// `Allow`/`Disallow` rules of the `User-agent: *` group of a robots.txt
+#[derive(Debug, Default)]
+pub struct Robots {
+	/// Path patterns with whether they allow access
+	rules: Vec<(String, bool)>,
+}
+
+impl Robots {
+	#[must_use]
+	pub fn parse(body: &str) -> Self {
+		let mut rules = Vec::new();
+		let mut in_group = false;
+		// Consecutive User-agent lines open one group
+		let mut reading_agents = false;
+		for line in body.lines() {
+			let line = line.split('#').next().unwrap_or_default().trim();
+			let Some((field, value)) = line.split_once(':') else {
+				continue;
+			};
+			let value = value.trim();
+			match field.trim().to_ascii_lowercase().as_str() {
+				"user-agent" => {
+					if !reading_agents {
+						in_group = false;
+					}
+					reading_agents = true;
+					in_group |= value == "*";
+				}
+				"allow" | "disallow" => {
+					reading_agents = false;
+					// An empty Disallow allows everything and needs no rule
+					if in_group && !value.is_empty() {
+						rules.push((value.to_string(), field.trim().eq_ignore_ascii_case("allow")));
+					}
+				}
+				_ => reading_agents = false,
+			}
+		}
+		Self { rules }
+	}
+
+	/// The most specific (longest) matching rule decides; paths matching no rule are allowed
+	#[must_use]
+	pub fn allows(&self, path: &str) -> bool {
+		self.rules
+			.iter()
+			.filter(|(pattern, _)| matches(pattern, path))
+			.max_by_key(|(pattern, allow)| (pattern.len(), *allow))
+			.is_none_or(|(_, allow)| *allow)
+	}
+}
+
+/// robots.txt path pattern: a prefix match where `*` matches any run of characters and a trailing `$` anchors the end
+fn matches(pattern: &str, path: &str) -> bool {
+	let (pattern, anchored) = pattern
+		.strip_suffix('$')
+		.map_or((pattern, false), |pattern| (pattern, true));
+	let parts: Vec<&str> = pattern.split('*').collect();
+	let Some((first, wildcards)) = parts.split_first() else {
+		return false;
+	};
+	let Some(mut rest) = path.strip_prefix(first) else {
+		return false;
+	};
+	let Some((last, middle)) = wildcards.split_last() else {
+		return !anchored || rest.is_empty();
+	};
+	for part in middle {
+		match rest.find(part) {
+			Some(position) => rest = rest.get(position.saturating_add(part.len())..).unwrap_or_default(),
+			None => return false,
+		}
+	}
+	if anchored {
+		rest.ends_with(last)
+	} else {
+		rest.contains(last)
+	}
+}
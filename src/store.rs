@@ -0,0 +1,256 @@
+mod bundle;
+mod sqlite;
+
+#[cfg(feature = "postgres")]
+mod postgres;
+
+use crate::config::{RetentionConfig, StoreBackend, StoreConfig};
+use crate::error::{AppError, Result};
+use crate::model::Article;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+pub use bundle::{read_bundle, write_bundle};
+pub use sqlite::SqliteStore;
+
+/// Rankings of earlier runs of one profile
+#[derive(Debug, Default)]
+pub struct RankingHistory {
+	/// 1-based positions in the most recent earlier run, by article id
+	pub previous: HashMap<String, usize>,
+	/// Ids of the articles that appeared in any earlier run
+	pub seen: HashSet<String>,
+}
+
+/// What pruning removed from the store
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Pruned {
+	pub articles: usize,
+	pub rankings: usize,
+	pub deliveries: usize,
+}
+
+impl Pruned {
+	#[must_use]
+	pub const fn is_empty(&self) -> bool {
+		self.articles == 0 && self.rankings == 0 && self.deliveries == 0
+	}
+}
+
+/// A stored article with when it was first and last fetched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredArticle {
+	pub article: Article,
+	pub first_seen: DateTime<Utc>,
+	pub last_seen: DateTime<Utc>,
+	#[serde(default)]
+	pub starred: bool,
+	/// Times its link was followed through the `/r/<id>` redirect, see `[clicks]`
+	#[serde(default)]
+	pub clicks: u64,
+}
+
+/// One article's place in a recorded run ranking
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredRanking {
+	pub profile: String,
+	pub run_id: String,
+	pub ranked_at: DateTime<Utc>,
+	pub url: String,
+	pub position: u64,
+	pub score: f64,
+}
+
+/// One entry of the delivery ledger
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredDelivery {
+	pub sink: String,
+	pub destination: String,
+	pub url: String,
+	pub delivered_at: DateTime<Utc>,
+}
+
+/// An article a `[users]` entry marked as read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredRead {
+	pub user: String,
+	pub url: String,
+	pub read_at: DateTime<Utc>,
+}
+
+/// Everything the store holds, for `export` and `import`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StoreContents {
+	pub articles: Vec<StoredArticle>,
+	pub rankings: Vec<StoredRanking>,
+	pub deliveries: Vec<StoredDelivery>,
+	#[serde(default)]
+	pub reads: Vec<StoredRead>,
+}
+
+/// Persistent store of every fetched article, the rankings shown by earlier runs and the per-sink delivery ledger.
+/// Calls block; from async code, make them inside `tokio::task::block_in_place`.
+pub trait Storage: Send {
+	/// Insert new articles and refresh already known ones; returns the number written
+	///
+	/// # Errors
+	///
+	/// `StorageError` when the database cannot be written
+	fn save_articles(&mut self, articles: &[Article], seen_at: DateTime<Utc>) -> Result<usize>;
+
+	/// Every stored article, most recently seen first
+	///
+	/// # Errors
+	///
+	/// `StorageError` when the database cannot be read
+	fn load_articles(&self) -> Result<Vec<Article>>;
+
+	/// Record the leading articles of a run's ranking, as `(article, score)` in ranking order
+	///
+	/// # Errors
+	///
+	/// `StorageError` when the database cannot be written
+	fn save_ranking(
+		&mut self,
+		profile: &str,
+		run_id: &str,
+		ranked_at: DateTime<Utc>,
+		ranking: &[(&Article, f64)],
+	) -> Result<usize>;
+
+	/// Rankings recorded for `profile` before the current run
+	///
+	/// # Errors
+	///
+	/// `StorageError` when the database cannot be read
+	fn ranking_history(&self, profile: &str) -> Result<RankingHistory>;
+
+	/// Ids of the articles delivered to `sink` at `destination` at or after `since`, or ever without it
+	///
+	/// # Errors
+	///
+	/// `StorageError` when the database cannot be read
+	fn delivered(&self, sink: &str, destination: &str, since: Option<DateTime<Utc>>) -> Result<HashSet<String>>;
+
+	/// Remember that these articles were delivered to `sink` at `destination`
+	///
+	/// # Errors
+	///
+	/// `StorageError` when the database cannot be written
+	fn record_deliveries(
+		&mut self,
+		sink: &str,
+		destination: &str,
+		articles: &[&Article],
+		delivered_at: DateTime<Utc>,
+	) -> Result<()>;
+
+	/// Star or unstar a stored article by its URL or any URL with the same canonical form;
+	/// returns whether the article is stored
+	///
+	/// # Errors
+	///
+	/// `StorageError` when the database cannot be written
+	fn set_starred(&mut self, url: &str, starred: bool) -> Result<bool>;
+
+	/// Mark a stored article as read or unread for `user`; returns whether an article has `id`
+	///
+	/// # Errors
+	///
+	/// `StorageError` when the database cannot be written
+	fn set_read(&mut self, user: &str, id: &str, read: bool, at: DateTime<Utc>) -> Result<bool>;
+
+	/// Ids of the articles `user` marked as read
+	///
+	/// # Errors
+	///
+	/// `StorageError` when the database cannot be read
+	fn read_ids(&self, user: &str) -> Result<HashSet<String>>;
+
+	/// Count one click on the link of a stored article; returns its URL, or `None` when no article has `id`
+	///
+	/// # Errors
+	///
+	/// `StorageError` when the database cannot be written
+	fn record_click(&mut self, id: &str) -> Result<Option<String>>;
+
+	/// Drop the articles and rankings `policy` no longer keeps, then the deliveries and read marks of articles
+	/// no longer stored
+	///
+	/// # Errors
+	///
+	/// `StorageError` when the database cannot be written
+	fn prune(&mut self, policy: &RetentionConfig, now: DateTime<Utc>) -> Result<Pruned>;
+
+	/// Every stored article, ranking, delivery and read mark
+	///
+	/// # Errors
+	///
+	/// `StorageError` when the database cannot be read
+	fn dump(&self) -> Result<StoreContents>;
+
+	/// Merge exported contents in: an article keeps its earliest first and latest last sighting, stays starred
+	/// if either side starred it and keeps the higher click count, rankings are replaced and a delivery or read mark
+	/// keeps its latest time
+	///
+	/// # Errors
+	///
+	/// `StorageError` when the database cannot be written; nothing is merged then
+	fn restore(&mut self, contents: &StoreContents) -> Result<()>;
+
+	/// Give space freed by pruning back to the file system; nothing to do where the database does that itself
+	///
+	/// # Errors
+	///
+	/// `StorageError` when the database cannot be compacted
+	fn compact(&mut self) -> Result<()> {
+		Ok(())
+	}
+}
+
+/// The configured store backend
+pub type Store = Box<dyn Storage>;
+
+/// Open the configured backend, applying pending schema migrations
+///
+/// # Errors
+///
+/// `ConfigError` when the backend is not built in, `StorageError` when it cannot be opened or migrated
+pub fn open(config: &StoreConfig) -> Result<Store> {
+	match config.backend {
+		StoreBackend::Sqlite => Ok(Box::new(SqliteStore::open(Path::new(&config.path))?)),
+		StoreBackend::Postgres => open_postgres(config),
+	}
+}
+
+/// Prune by the retention policy, compacting afterwards when anything was removed and `vacuum` is set
+///
+/// # Errors
+///
+/// As `Storage::prune` and `Storage::compact`
+pub fn apply_retention(store: &mut Store, policy: &RetentionConfig, now: DateTime<Utc>) -> Result<Pruned> {
+	let pruned = store.prune(policy, now)?;
+	if policy.vacuum && !pruned.is_empty() {
+		store.compact()?;
+	}
+	Ok(pruned)
+}
+
+#[cfg(feature = "postgres")]
+fn open_postgres(config: &StoreConfig) -> Result<Store> {
+	let url = config
+		.url
+		.as_ref()
+		.ok_or_else(|| AppError::ConfigError("store.url is required for the postgres backend".into()))?
+		.resolve()?;
+	Ok(Box::new(postgres::PostgresStore::connect(&url)?))
+}
+
+#[cfg(not(feature = "postgres"))]
+fn open_postgres(_config: &StoreConfig) -> Result<Store> {
+	Err(AppError::ConfigError(
+		"store.backend = \"postgres\": built without the `postgres` feature".into(),
+	))
+}
@@ -0,0 +1,157 @@
+use super::{StoreContents, StoredArticle, StoredDelivery, StoredRanking, StoredRead};
+use crate::error::{AppError, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Bumped when a bundle written by this version can no longer be read by older ones
+const FORMAT: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+	format: u32,
+	exported_at: DateTime<Utc>,
+}
+
+/// A `.json` bundle: the header fields next to the record lists
+#[derive(Serialize, Deserialize)]
+struct Document<C> {
+	#[serde(flatten)]
+	header: Header,
+	#[serde(flatten)]
+	contents: C,
+}
+
+/// A line of a JSONL bundle, as written by `Tagged`
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Line {
+	Header(Header),
+	Article(Box<StoredArticle>),
+	Ranking(StoredRanking),
+	Delivery(StoredDelivery),
+	Read(StoredRead),
+}
+
+#[derive(Serialize)]
+struct Tagged<'a, T> {
+	r#type: &'static str,
+	#[serde(flatten)]
+	record: &'a T,
+}
+
+/// Write the store contents to `path`: one JSON document for a `.json` path, JSON Lines otherwise,
+/// starting with a header line and then one line per article, ranking, delivery and read mark
+///
+/// # Errors
+///
+/// `IoError` when the file cannot be written
+pub fn write_bundle(path: &Path, contents: &StoreContents) -> Result<()> {
+	if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+		fs::create_dir_all(parent).map_err(|e| AppError::io_error(parent, e))?;
+	}
+	let file = File::create(path).map_err(|e| AppError::io_error(path, e))?;
+	let mut writer = BufWriter::new(file);
+	let header = Header {
+		format: FORMAT,
+		exported_at: Utc::now(),
+	};
+
+	if is_document(path) {
+		let document = Document { header, contents };
+		serde_json::to_writer_pretty(&mut writer, &document).map_err(|e| AppError::parse_error("store bundle", e))?;
+	} else {
+		write_line(&mut writer, path, "header", &header)?;
+		for article in &contents.articles {
+			write_line(&mut writer, path, "article", article)?;
+		}
+		for ranking in &contents.rankings {
+			write_line(&mut writer, path, "ranking", ranking)?;
+		}
+		for delivery in &contents.deliveries {
+			write_line(&mut writer, path, "delivery", delivery)?;
+		}
+		for read in &contents.reads {
+			write_line(&mut writer, path, "read", read)?;
+		}
+	}
+	writer.flush().map_err(|e| AppError::io_error(path, e))
+}
+
+fn write_line<T: Serialize>(writer: &mut impl Write, path: &Path, kind: &'static str, record: &T) -> Result<()> {
+	let line = Tagged { r#type: kind, record };
+	serde_json::to_writer(&mut *writer, &line).map_err(|e| AppError::parse_error("store bundle", e))?;
+	writer.write_all(b"\n").map_err(|e| AppError::io_error(path, e))
+}
+
+/// Read a bundle written by `write_bundle`, telling the two layouts apart by the file extension
+///
+/// # Errors
+///
+/// `IoError` when the file cannot be read, `ParseError` when it is not a bundle
+pub fn read_bundle(path: &Path) -> Result<StoreContents> {
+	let file = File::open(path).map_err(|e| AppError::io_error(path, e))?;
+	let reader = BufReader::new(file);
+	if is_document(path) {
+		let document: Document<StoreContents> =
+			serde_json::from_reader(reader).map_err(|e| AppError::parse_error(path.display().to_string(), e))?;
+		check_format(path, &document.header)?;
+		return Ok(document.contents);
+	}
+
+	let mut contents = StoreContents::default();
+	let mut header = None;
+	for (number, line) in (1usize..).zip(reader.lines()) {
+		let line = line.map_err(|e| AppError::io_error(path, e))?;
+		if line.trim().is_empty() {
+			continue;
+		}
+		let line: Line = serde_json::from_str(&line)
+			.map_err(|e| AppError::parse_error(format!("{}:{number}", path.display()), e))?;
+		match (line, &header) {
+			(Line::Header(found), None) => {
+				check_format(path, &found)?;
+				header = Some(found);
+			}
+			(_, None) => {
+				return Err(AppError::parse_error(
+					path.display().to_string(),
+					"not a store bundle: the first line must be its header",
+				));
+			}
+			(Line::Header(_), Some(_)) => {
+				return Err(AppError::parse_error(
+					format!("{}:{number}", path.display()),
+					"a second header",
+				));
+			}
+			(Line::Article(article), Some(_)) => contents.articles.push(*article),
+			(Line::Ranking(ranking), Some(_)) => contents.rankings.push(ranking),
+			(Line::Delivery(delivery), Some(_)) => contents.deliveries.push(delivery),
+			(Line::Read(read), Some(_)) => contents.reads.push(read),
+		}
+	}
+	if header.is_none() {
+		return Err(AppError::parse_error(path.display().to_string(), "empty store bundle"));
+	}
+	Ok(contents)
+}
+
+fn check_format(path: &Path, header: &Header) -> Result<()> {
+	if header.format > FORMAT {
+		return Err(AppError::parse_error(
+			path.display().to_string(),
+			format!(
+				"bundle format {} is newer than the supported format {FORMAT}",
+				header.format
+			),
+		));
+	}
+	Ok(())
+}
+
+fn is_document(path: &Path) -> bool {
+	path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
@@ -0,0 +1,684 @@
+use super::{Pruned, RankingHistory, Storage, StoreContents, StoredArticle, StoredDelivery, StoredRanking, StoredRead};
+use crate::config::RetentionConfig;
+use crate::error::{AppError, Result};
+use crate::model::{self, Article};
+use chrono::{DateTime, Utc};
+use postgres_native_tls::MakeTlsConnector;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use tokio::runtime::Handle;
+use tokio_postgres::{Client, GenericClient};
+use tracing::warn;
+
+/// Schema migrations, applied in order and tracked in `schema_version`
+const MIGRATIONS: &[&str] = &[
+	"CREATE TABLE articles (
+		url TEXT PRIMARY KEY,
+		source TEXT NOT NULL,
+		title TEXT NOT NULL,
+		data TEXT NOT NULL,
+		first_seen TIMESTAMPTZ NOT NULL,
+		last_seen TIMESTAMPTZ NOT NULL
+	);
+	CREATE INDEX articles_last_seen ON articles (last_seen);",
+	"CREATE TABLE rankings (
+		profile TEXT NOT NULL,
+		run_id TEXT NOT NULL,
+		ranked_at TIMESTAMPTZ NOT NULL,
+		url TEXT NOT NULL,
+		position BIGINT NOT NULL,
+		score DOUBLE PRECISION NOT NULL,
+		PRIMARY KEY (profile, run_id, url)
+	);
+	CREATE INDEX rankings_profile_ranked_at ON rankings (profile, ranked_at);",
+	"CREATE TABLE deliveries (
+		sink TEXT NOT NULL,
+		destination TEXT NOT NULL,
+		url TEXT NOT NULL,
+		delivered_at TIMESTAMPTZ NOT NULL,
+		PRIMARY KEY (sink, destination, url)
+	);",
+	"ALTER TABLE articles ADD COLUMN starred BOOLEAN NOT NULL DEFAULT FALSE;",
+	"DROP TABLE deliveries;
+	DROP TABLE rankings;
+	DROP TABLE articles;
+	CREATE TABLE articles (
+		id TEXT PRIMARY KEY,
+		url TEXT NOT NULL,
+		source TEXT NOT NULL,
+		title TEXT NOT NULL,
+		data TEXT NOT NULL,
+		first_seen TIMESTAMPTZ NOT NULL,
+		last_seen TIMESTAMPTZ NOT NULL,
+		starred BOOLEAN NOT NULL DEFAULT FALSE
+	);
+	CREATE INDEX articles_url ON articles (url);
+	CREATE INDEX articles_last_seen ON articles (last_seen);
+	CREATE TABLE rankings (
+		profile TEXT NOT NULL,
+		run_id TEXT NOT NULL,
+		ranked_at TIMESTAMPTZ NOT NULL,
+		id TEXT NOT NULL,
+		url TEXT NOT NULL,
+		position BIGINT NOT NULL,
+		score DOUBLE PRECISION NOT NULL,
+		PRIMARY KEY (profile, run_id, id)
+	);
+	CREATE INDEX rankings_profile_ranked_at ON rankings (profile, ranked_at);
+	CREATE TABLE deliveries (
+		sink TEXT NOT NULL,
+		destination TEXT NOT NULL,
+		id TEXT NOT NULL,
+		url TEXT NOT NULL,
+		delivered_at TIMESTAMPTZ NOT NULL,
+		PRIMARY KEY (sink, destination, id)
+	);",
+	"ALTER TABLE articles ADD COLUMN clicks BIGINT NOT NULL DEFAULT 0;",
+	"CREATE TABLE reads (
+		username TEXT NOT NULL,
+		id TEXT NOT NULL,
+		read_at TIMESTAMPTZ NOT NULL,
+		PRIMARY KEY (username, id)
+	);",
+];
+
+/// The migration re-keying every table by article id instead of URL; rows are carried over through
+/// `dump` and `restore`, so articles whose URLs differ only in tracking parameters and the like merge
+const REKEY_VERSION: i32 = 5;
+
+/// Advisory lock held while migrating, so instances starting together do not race
+const MIGRATION_LOCK: i64 = 0x6e65_7773;
+
+/// PostgreSQL store, shared by several instances on different machines
+pub struct PostgresStore {
+	client: Client,
+}
+
+impl PostgresStore {
+	/// Connect with TLS when the server offers it (`sslmode=prefer`, the default) or requires it
+	pub fn connect(url: &str) -> Result<Self> {
+		let connector = native_tls::TlsConnector::new().map_err(AppError::storage_error)?;
+		let (client, connection) = block_on(tokio_postgres::connect(url, MakeTlsConnector::new(connector)))
+			.map_err(AppError::storage_error)?;
+		tokio::spawn(async move {
+			if let Err(e) = connection.await {
+				warn!(error = %e, "PostgreSQL connection closed");
+			}
+		});
+		let mut store = Self { client };
+		store.migrate()?;
+		Ok(store)
+	}
+
+	fn migrate(&mut self) -> Result<()> {
+		block_on(async {
+			let tx = self.client.transaction().await.map_err(AppError::storage_error)?;
+			tx.execute("SELECT pg_advisory_xact_lock($1)", &[&MIGRATION_LOCK])
+				.await
+				.map_err(AppError::storage_error)?;
+			tx.batch_execute("CREATE TABLE IF NOT EXISTS schema_version (version INTEGER NOT NULL)")
+				.await
+				.map_err(AppError::storage_error)?;
+			let version = match tx
+				.query_opt("SELECT version FROM schema_version", &[])
+				.await
+				.map_err(AppError::storage_error)?
+			{
+				Some(row) => row.try_get::<_, i32>(0).map_err(AppError::storage_error)?,
+				None => 0,
+			};
+			for (version, migration) in (1i32..)
+				.zip(MIGRATIONS)
+				.skip(usize::try_from(version).unwrap_or(usize::MAX))
+			{
+				let carried = if version == REKEY_VERSION {
+					Some(dump(&tx).await?)
+				} else {
+					None
+				};
+				tx.batch_execute(migration).await.map_err(AppError::storage_error)?;
+				if let Some(contents) = carried {
+					restore(&tx, &contents).await?;
+				}
+				tx.execute("DELETE FROM schema_version", &[])
+					.await
+					.map_err(AppError::storage_error)?;
+				tx.execute("INSERT INTO schema_version (version) VALUES ($1)", &[&version])
+					.await
+					.map_err(AppError::storage_error)?;
+			}
+			tx.commit().await.map_err(AppError::storage_error)
+		})
+	}
+}
+
+impl Storage for PostgresStore {
+	fn save_articles(&mut self, articles: &[Article], seen_at: DateTime<Utc>) -> Result<usize> {
+		block_on(async {
+			let tx = self.client.transaction().await.map_err(AppError::storage_error)?;
+			let stmt = tx
+				.prepare(
+					"INSERT INTO articles (id, url, source, title, data, first_seen, last_seen)
+					VALUES ($1, $2, $3, $4, $5, $6, $6)
+					ON CONFLICT (id) DO UPDATE SET
+						url = excluded.url,
+						source = excluded.source,
+						title = excluded.title,
+						data = excluded.data,
+						last_seen = excluded.last_seen",
+				)
+				.await
+				.map_err(AppError::storage_error)?;
+			for article in articles {
+				let data = serde_json::to_string(article).map_err(|e| AppError::parse_error("stored article", e))?;
+				tx.execute(
+					&stmt,
+					&[
+						&article.id(),
+						&article.url(),
+						&article.source(),
+						&article.title(),
+						&data,
+						&seen_at,
+					],
+				)
+				.await
+				.map_err(AppError::storage_error)?;
+			}
+			tx.commit().await.map_err(AppError::storage_error)?;
+			Ok(articles.len())
+		})
+	}
+
+	fn load_articles(&self) -> Result<Vec<Article>> {
+		let rows = block_on(
+			self.client
+				.query("SELECT data FROM articles ORDER BY last_seen DESC", &[]),
+		)
+		.map_err(AppError::storage_error)?;
+		rows.iter()
+			.map(|row| {
+				let data: String = row.try_get(0).map_err(AppError::storage_error)?;
+				serde_json::from_str(&data).map_err(|e| AppError::parse_error("stored article", e))
+			})
+			.collect()
+	}
+
+	fn save_ranking(
+		&mut self,
+		profile: &str,
+		run_id: &str,
+		ranked_at: DateTime<Utc>,
+		ranking: &[(&Article, f64)],
+	) -> Result<usize> {
+		block_on(async {
+			let tx = self.client.transaction().await.map_err(AppError::storage_error)?;
+			let stmt = tx
+				.prepare(
+					"INSERT INTO rankings (profile, run_id, ranked_at, id, url, position, score)
+					VALUES ($1, $2, $3, $4, $5, $6, $7)
+					ON CONFLICT (profile, run_id, id) DO UPDATE SET
+						url = excluded.url,
+						ranked_at = excluded.ranked_at,
+						position = excluded.position,
+						score = excluded.score",
+				)
+				.await
+				.map_err(AppError::storage_error)?;
+			for (position, &(article, score)) in (1i64..).zip(ranking) {
+				tx.execute(
+					&stmt,
+					&[
+						&profile,
+						&run_id,
+						&ranked_at,
+						&article.id(),
+						&article.url(),
+						&position,
+						&score,
+					],
+				)
+				.await
+				.map_err(AppError::storage_error)?;
+			}
+			tx.commit().await.map_err(AppError::storage_error)?;
+			Ok(ranking.len())
+		})
+	}
+
+	fn ranking_history(&self, profile: &str) -> Result<RankingHistory> {
+		let mut history = RankingHistory::default();
+		let rows = block_on(self.client.query(
+			"SELECT id, position FROM rankings WHERE profile = $1 AND run_id = (
+				SELECT run_id FROM rankings WHERE profile = $1 ORDER BY ranked_at DESC LIMIT 1
+			)",
+			&[&profile],
+		))
+		.map_err(AppError::storage_error)?;
+		for row in rows {
+			let id: String = row.try_get(0).map_err(AppError::storage_error)?;
+			let position: i64 = row.try_get(1).map_err(AppError::storage_error)?;
+			history
+				.previous
+				.insert(id, usize::try_from(position).unwrap_or(usize::MAX));
+		}
+
+		let rows = block_on(
+			self.client
+				.query("SELECT DISTINCT id FROM rankings WHERE profile = $1", &[&profile]),
+		)
+		.map_err(AppError::storage_error)?;
+		for row in rows {
+			history.seen.insert(row.try_get(0).map_err(AppError::storage_error)?);
+		}
+		Ok(history)
+	}
+
+	fn delivered(&self, sink: &str, destination: &str, since: Option<DateTime<Utc>>) -> Result<HashSet<String>> {
+		let rows = block_on(self.client.query(
+			"SELECT id FROM deliveries
+			WHERE sink = $1 AND destination = $2 AND ($3::timestamptz IS NULL OR delivered_at >= $3)",
+			&[&sink, &destination, &since],
+		))
+		.map_err(AppError::storage_error)?;
+		rows.iter()
+			.map(|row| row.try_get(0).map_err(AppError::storage_error))
+			.collect()
+	}
+
+	fn record_deliveries(
+		&mut self,
+		sink: &str,
+		destination: &str,
+		articles: &[&Article],
+		delivered_at: DateTime<Utc>,
+	) -> Result<()> {
+		block_on(async {
+			let tx = self.client.transaction().await.map_err(AppError::storage_error)?;
+			let stmt = tx
+				.prepare(
+					"INSERT INTO deliveries (sink, destination, id, url, delivered_at) VALUES ($1, $2, $3, $4, $5)
+					ON CONFLICT (sink, destination, id) DO UPDATE SET
+						url = excluded.url,
+						delivered_at = excluded.delivered_at",
+				)
+				.await
+				.map_err(AppError::storage_error)?;
+			for article in articles {
+				tx.execute(
+					&stmt,
+					&[&sink, &destination, &article.id(), &article.url(), &delivered_at],
+				)
+				.await
+				.map_err(AppError::storage_error)?;
+			}
+			tx.commit().await.map_err(AppError::storage_error)
+		})
+	}
+
+	fn set_starred(&mut self, url: &str, starred: bool) -> Result<bool> {
+		let updated = block_on(self.client.execute(
+			"UPDATE articles SET starred = $3 WHERE url = $1 OR id = $2",
+			&[&url, &model::id_for_url(url), &starred],
+		))
+		.map_err(AppError::storage_error)?;
+		Ok(updated > 0)
+	}
+
+	fn set_read(&mut self, user: &str, id: &str, read: bool, at: DateTime<Utc>) -> Result<bool> {
+		block_on(async {
+			let stored: bool = self
+				.client
+				.query_one("SELECT EXISTS (SELECT 1 FROM articles WHERE id = $1)", &[&id])
+				.await
+				.and_then(|row| row.try_get(0))
+				.map_err(AppError::storage_error)?;
+			if !stored {
+				return Ok(false);
+			}
+			if read {
+				self.client
+					.execute(
+						"INSERT INTO reads (username, id, read_at) VALUES ($1, $2, $3)
+						ON CONFLICT (username, id) DO NOTHING",
+						&[&user, &id, &at],
+					)
+					.await
+			} else {
+				self.client
+					.execute("DELETE FROM reads WHERE username = $1 AND id = $2", &[&user, &id])
+					.await
+			}
+			.map_err(AppError::storage_error)?;
+			Ok(true)
+		})
+	}
+
+	fn read_ids(&self, user: &str) -> Result<HashSet<String>> {
+		let rows = block_on(self.client.query("SELECT id FROM reads WHERE username = $1", &[&user]))
+			.map_err(AppError::storage_error)?;
+		rows.iter()
+			.map(|row| row.try_get(0).map_err(AppError::storage_error))
+			.collect()
+	}
+
+	fn record_click(&mut self, id: &str) -> Result<Option<String>> {
+		let row = block_on(self.client.query_opt(
+			"UPDATE articles SET clicks = clicks + 1 WHERE id = $1 RETURNING url",
+			&[&id],
+		))
+		.map_err(AppError::storage_error)?;
+		row.map(|row| row.try_get(0).map_err(AppError::storage_error))
+			.transpose()
+	}
+
+	fn prune(&mut self, policy: &RetentionConfig, now: DateTime<Utc>) -> Result<Pruned> {
+		block_on(async {
+			let tx = self.client.transaction().await.map_err(AppError::storage_error)?;
+			let mut pruned = Pruned::default();
+			if let Some(cutoff) = policy.cutoff(now) {
+				pruned.articles = count(
+					tx.execute(
+						"DELETE FROM articles WHERE last_seen < $1 AND NOT ($2 AND starred)",
+						&[&cutoff, &policy.keep_starred],
+					)
+					.await,
+				)?;
+				pruned.rankings = count(
+					tx.execute("DELETE FROM rankings WHERE ranked_at < $1", &[&cutoff])
+						.await,
+				)?;
+			}
+			if let Some(max_articles) = policy.max_articles {
+				let dropped = count(
+					tx.execute(
+						"DELETE FROM articles WHERE NOT ($2 AND starred)
+						AND id NOT IN (SELECT id FROM articles ORDER BY last_seen DESC LIMIT $1)",
+						&[&i64::try_from(max_articles).unwrap_or(i64::MAX), &policy.keep_starred],
+					)
+					.await,
+				)?;
+				pruned.articles = pruned.articles.saturating_add(dropped);
+			}
+			let dropped = count(
+				tx.execute("DELETE FROM rankings WHERE id NOT IN (SELECT id FROM articles)", &[])
+					.await,
+			)?;
+			pruned.rankings = pruned.rankings.saturating_add(dropped);
+			pruned.deliveries = count(
+				tx.execute("DELETE FROM deliveries WHERE id NOT IN (SELECT id FROM articles)", &[])
+					.await,
+			)?;
+			tx.execute("DELETE FROM reads WHERE id NOT IN (SELECT id FROM articles)", &[])
+				.await
+				.map_err(AppError::storage_error)?;
+			tx.commit().await.map_err(AppError::storage_error)?;
+			Ok(pruned)
+		})
+	}
+
+	fn dump(&self) -> Result<StoreContents> {
+		block_on(async {
+			let mut contents = dump(&self.client).await?;
+			dump_clicks(&self.client, &mut contents).await?;
+			contents.reads = dump_reads(&self.client).await?;
+			Ok(contents)
+		})
+	}
+
+	fn restore(&mut self, contents: &StoreContents) -> Result<()> {
+		block_on(async {
+			let tx = self.client.transaction().await.map_err(AppError::storage_error)?;
+			restore(&tx, contents).await?;
+			restore_clicks(&tx, contents).await?;
+			restore_reads(&tx, contents).await?;
+			tx.commit().await.map_err(AppError::storage_error)
+		})
+	}
+}
+
+/// Every stored article, ranking and delivery
+async fn dump(client: &(impl GenericClient + Sync)) -> Result<StoreContents> {
+	let mut contents = StoreContents::default();
+	let rows = client
+		.query(
+			"SELECT data, first_seen, last_seen, starred FROM articles ORDER BY first_seen",
+			&[],
+		)
+		.await
+		.map_err(AppError::storage_error)?;
+	for row in rows {
+		let data: String = row.try_get(0).map_err(AppError::storage_error)?;
+		contents.articles.push(StoredArticle {
+			article: serde_json::from_str(&data).map_err(|e| AppError::parse_error("stored article", e))?,
+			first_seen: row.try_get(1).map_err(AppError::storage_error)?,
+			last_seen: row.try_get(2).map_err(AppError::storage_error)?,
+			starred: row.try_get(3).map_err(AppError::storage_error)?,
+			clicks: 0,
+		});
+	}
+
+	let rows = client
+		.query(
+			"SELECT profile, run_id, ranked_at, url, position, score FROM rankings ORDER BY ranked_at, position",
+			&[],
+		)
+		.await
+		.map_err(AppError::storage_error)?;
+	for row in rows {
+		let position: i64 = row.try_get(4).map_err(AppError::storage_error)?;
+		contents.rankings.push(StoredRanking {
+			profile: row.try_get(0).map_err(AppError::storage_error)?,
+			run_id: row.try_get(1).map_err(AppError::storage_error)?,
+			ranked_at: row.try_get(2).map_err(AppError::storage_error)?,
+			url: row.try_get(3).map_err(AppError::storage_error)?,
+			position: u64::try_from(position).unwrap_or_default(),
+			score: row.try_get(5).map_err(AppError::storage_error)?,
+		});
+	}
+
+	let rows = client
+		.query(
+			"SELECT sink, destination, url, delivered_at FROM deliveries ORDER BY delivered_at",
+			&[],
+		)
+		.await
+		.map_err(AppError::storage_error)?;
+	for row in rows {
+		contents.deliveries.push(StoredDelivery {
+			sink: row.try_get(0).map_err(AppError::storage_error)?,
+			destination: row.try_get(1).map_err(AppError::storage_error)?,
+			url: row.try_get(2).map_err(AppError::storage_error)?,
+			delivered_at: row.try_get(3).map_err(AppError::storage_error)?,
+		});
+	}
+	Ok(contents)
+}
+
+/// Merge exported contents in, see `Storage::restore`; rankings and deliveries take the id of the stored
+/// article with their URL
+async fn restore(client: &(impl GenericClient + Sync), contents: &StoreContents) -> Result<()> {
+	let stmt = client
+		.prepare(
+			"INSERT INTO articles AS a (id, url, source, title, data, first_seen, last_seen, starred)
+			VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+			ON CONFLICT (id) DO UPDATE SET
+				url = CASE WHEN excluded.last_seen >= a.last_seen THEN excluded.url ELSE a.url END,
+				source = CASE WHEN excluded.last_seen >= a.last_seen THEN excluded.source ELSE a.source END,
+				title = CASE WHEN excluded.last_seen >= a.last_seen THEN excluded.title ELSE a.title END,
+				data = CASE WHEN excluded.last_seen >= a.last_seen THEN excluded.data ELSE a.data END,
+				first_seen = LEAST(a.first_seen, excluded.first_seen),
+				last_seen = GREATEST(a.last_seen, excluded.last_seen),
+				starred = a.starred OR excluded.starred",
+		)
+		.await
+		.map_err(AppError::storage_error)?;
+	for stored in &contents.articles {
+		let article = &stored.article;
+		let data = serde_json::to_string(article).map_err(|e| AppError::parse_error("stored article", e))?;
+		client
+			.execute(
+				&stmt,
+				&[
+					&article.id(),
+					&article.url(),
+					&article.source(),
+					&article.title(),
+					&data,
+					&stored.first_seen,
+					&stored.last_seen,
+					&stored.starred,
+				],
+			)
+			.await
+			.map_err(AppError::storage_error)?;
+	}
+
+	let stmt = client
+		.prepare(
+			"INSERT INTO rankings (profile, run_id, ranked_at, id, url, position, score)
+			VALUES ($1, $2, $3, COALESCE((SELECT id FROM articles WHERE url = $4), $5), $4, $6, $7)
+			ON CONFLICT (profile, run_id, id) DO UPDATE SET
+				url = excluded.url,
+				ranked_at = excluded.ranked_at,
+				position = excluded.position,
+				score = excluded.score",
+		)
+		.await
+		.map_err(AppError::storage_error)?;
+	for ranking in &contents.rankings {
+		let position = i64::try_from(ranking.position).unwrap_or(i64::MAX);
+		client
+			.execute(
+				&stmt,
+				&[
+					&ranking.profile,
+					&ranking.run_id,
+					&ranking.ranked_at,
+					&ranking.url,
+					&model::id_for_url(&ranking.url),
+					&position,
+					&ranking.score,
+				],
+			)
+			.await
+			.map_err(AppError::storage_error)?;
+	}
+
+	let stmt = client
+		.prepare(
+			"INSERT INTO deliveries AS d (sink, destination, id, url, delivered_at)
+			VALUES ($1, $2, COALESCE((SELECT id FROM articles WHERE url = $3), $4), $3, $5)
+			ON CONFLICT (sink, destination, id) DO UPDATE SET
+				delivered_at = GREATEST(d.delivered_at, excluded.delivered_at)",
+		)
+		.await
+		.map_err(AppError::storage_error)?;
+	for delivery in &contents.deliveries {
+		client
+			.execute(
+				&stmt,
+				&[
+					&delivery.sink,
+					&delivery.destination,
+					&delivery.url,
+					&model::id_for_url(&delivery.url),
+					&delivery.delivered_at,
+				],
+			)
+			.await
+			.map_err(AppError::storage_error)?;
+	}
+	Ok(())
+}
+
+/// Fill in the click counts of the dumped articles. Kept apart from `dump`, which also carries rows through
+/// the re-keying migration, from before the column existed.
+async fn dump_clicks(client: &(impl GenericClient + Sync), contents: &mut StoreContents) -> Result<()> {
+	let rows = client
+		.query("SELECT id, clicks FROM articles WHERE clicks > 0", &[])
+		.await
+		.map_err(AppError::storage_error)?;
+	let mut clicks = HashMap::with_capacity(rows.len());
+	for row in rows {
+		let id: String = row.try_get(0).map_err(AppError::storage_error)?;
+		let count: i64 = row.try_get(1).map_err(AppError::storage_error)?;
+		clicks.insert(id, u64::try_from(count).unwrap_or_default());
+	}
+	for stored in &mut contents.articles {
+		if let Some(count) = clicks.get(&stored.article.id()) {
+			stored.clicks = *count;
+		}
+	}
+	Ok(())
+}
+
+/// Keep the higher click count of each restored article, see `dump_clicks`
+async fn restore_clicks(client: &(impl GenericClient + Sync), contents: &StoreContents) -> Result<()> {
+	let stmt = client
+		.prepare("UPDATE articles SET clicks = GREATEST(clicks, $2) WHERE id = $1")
+		.await
+		.map_err(AppError::storage_error)?;
+	for stored in contents.articles.iter().filter(|stored| stored.clicks > 0) {
+		let clicks = i64::try_from(stored.clicks).unwrap_or(i64::MAX);
+		client
+			.execute(&stmt, &[&stored.article.id(), &clicks])
+			.await
+			.map_err(AppError::storage_error)?;
+	}
+	Ok(())
+}
+
+/// Every read mark, by the URL of its article; apart from `dump` like `dump_clicks`
+async fn dump_reads(client: &(impl GenericClient + Sync)) -> Result<Vec<StoredRead>> {
+	let rows = client
+		.query(
+			"SELECT r.username, a.url, r.read_at FROM reads r JOIN articles a ON a.id = r.id ORDER BY r.read_at",
+			&[],
+		)
+		.await
+		.map_err(AppError::storage_error)?;
+	rows.iter()
+		.map(|row| {
+			Ok(StoredRead {
+				user: row.try_get(0).map_err(AppError::storage_error)?,
+				url: row.try_get(1).map_err(AppError::storage_error)?,
+				read_at: row.try_get(2).map_err(AppError::storage_error)?,
+			})
+		})
+		.collect()
+}
+
+/// Merge read marks in, each keeping its latest time, see `dump_reads`
+async fn restore_reads(client: &(impl GenericClient + Sync), contents: &StoreContents) -> Result<()> {
+	let stmt = client
+		.prepare(
+			"INSERT INTO reads AS r (username, id, read_at)
+			VALUES ($1, COALESCE((SELECT id FROM articles WHERE url = $2), $3), $4)
+			ON CONFLICT (username, id) DO UPDATE SET read_at = GREATEST(r.read_at, excluded.read_at)",
+		)
+		.await
+		.map_err(AppError::storage_error)?;
+	for read in &contents.reads {
+		client
+			.execute(
+				&stmt,
+				&[&read.user, &read.url, &model::id_for_url(&read.url), &read.read_at],
+			)
+			.await
+			.map_err(AppError::storage_error)?;
+	}
+	Ok(())
+}
+
+/// Rows affected by a statement
+fn count(result: std::result::Result<u64, tokio_postgres::Error>) -> Result<usize> {
+	result
+		.map(|rows| usize::try_from(rows).unwrap_or(usize::MAX))
+		.map_err(AppError::storage_error)
+}
+
+/// `Storage` blocks while tokio-postgres is async; wait for it on the current runtime without stalling other tasks
+fn block_on<F: Future>(future: F) -> F::Output {
+	tokio::task::block_in_place(|| Handle::current().block_on(future))
+}
@@ -0,0 +1,639 @@
+use super::{Pruned, RankingHistory, Storage, StoreContents, StoredArticle, StoredDelivery, StoredRanking, StoredRead};
+use crate::config::RetentionConfig;
+use crate::error::{AppError, Result};
+use crate::model::{self, Article};
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// Schema migrations, applied in order and tracked through `PRAGMA user_version`
+const MIGRATIONS: &[&str] = &[
+	"CREATE TABLE articles (
+		url TEXT PRIMARY KEY,
+		source TEXT NOT NULL,
+		title TEXT NOT NULL,
+		data TEXT NOT NULL,
+		first_seen TEXT NOT NULL,
+		last_seen TEXT NOT NULL
+	);
+	CREATE INDEX articles_last_seen ON articles (last_seen);",
+	"CREATE TABLE rankings (
+		profile TEXT NOT NULL,
+		run_id TEXT NOT NULL,
+		ranked_at TEXT NOT NULL,
+		url TEXT NOT NULL,
+		position INTEGER NOT NULL,
+		score REAL NOT NULL,
+		PRIMARY KEY (profile, run_id, url)
+	);
+	CREATE INDEX rankings_profile_ranked_at ON rankings (profile, ranked_at);",
+	"CREATE TABLE deliveries (
+		sink TEXT NOT NULL,
+		destination TEXT NOT NULL,
+		url TEXT NOT NULL,
+		delivered_at TEXT NOT NULL,
+		PRIMARY KEY (sink, destination, url)
+	);",
+	"ALTER TABLE articles ADD COLUMN starred INTEGER NOT NULL DEFAULT 0;",
+	"DROP TABLE deliveries;
+	DROP TABLE rankings;
+	DROP TABLE articles;
+	CREATE TABLE articles (
+		id TEXT PRIMARY KEY,
+		url TEXT NOT NULL,
+		source TEXT NOT NULL,
+		title TEXT NOT NULL,
+		data TEXT NOT NULL,
+		first_seen TEXT NOT NULL,
+		last_seen TEXT NOT NULL,
+		starred INTEGER NOT NULL DEFAULT 0
+	);
+	CREATE INDEX articles_url ON articles (url);
+	CREATE INDEX articles_last_seen ON articles (last_seen);
+	CREATE TABLE rankings (
+		profile TEXT NOT NULL,
+		run_id TEXT NOT NULL,
+		ranked_at TEXT NOT NULL,
+		id TEXT NOT NULL,
+		url TEXT NOT NULL,
+		position INTEGER NOT NULL,
+		score REAL NOT NULL,
+		PRIMARY KEY (profile, run_id, id)
+	);
+	CREATE INDEX rankings_profile_ranked_at ON rankings (profile, ranked_at);
+	CREATE TABLE deliveries (
+		sink TEXT NOT NULL,
+		destination TEXT NOT NULL,
+		id TEXT NOT NULL,
+		url TEXT NOT NULL,
+		delivered_at TEXT NOT NULL,
+		PRIMARY KEY (sink, destination, id)
+	);",
+	"ALTER TABLE articles ADD COLUMN clicks INTEGER NOT NULL DEFAULT 0;",
+	"CREATE TABLE reads (
+		username TEXT NOT NULL,
+		id TEXT NOT NULL,
+		read_at TEXT NOT NULL,
+		PRIMARY KEY (username, id)
+	);",
+];
+
+/// The migration re-keying every table by article id instead of URL; rows are carried over through
+/// `dump` and `restore`, so articles whose URLs differ only in tracking parameters and the like merge
+const REKEY_VERSION: u32 = 5;
+
+/// Single-file SQLite store, the default backend
+pub struct SqliteStore {
+	conn: Connection,
+}
+
+impl SqliteStore {
+	/// # Errors
+	///
+	/// `StorageError` when the database cannot be opened or migrated
+	pub fn open(path: &Path) -> Result<Self> {
+		if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+			fs::create_dir_all(parent).map_err(|e| AppError::io_error(parent, e))?;
+		}
+		let conn = Connection::open(path).map_err(AppError::storage_error)?;
+		let mut store = Self { conn };
+		store.migrate()?;
+		Ok(store)
+	}
+
+	fn migrate(&mut self) -> Result<()> {
+		let tx = self.conn.transaction().map_err(AppError::storage_error)?;
+		let version: u32 = tx
+			.query_row("PRAGMA user_version", [], |row| row.get(0))
+			.map_err(AppError::storage_error)?;
+		for (version, migration) in (1u32..)
+			.zip(MIGRATIONS)
+			.skip(usize::try_from(version).unwrap_or(usize::MAX))
+		{
+			let carried = if version == REKEY_VERSION {
+				Some(dump(&tx)?)
+			} else {
+				None
+			};
+			tx.execute_batch(migration).map_err(AppError::storage_error)?;
+			if let Some(contents) = carried {
+				restore(&tx, &contents)?;
+			}
+			tx.pragma_update(None, "user_version", version)
+				.map_err(AppError::storage_error)?;
+		}
+		tx.commit().map_err(AppError::storage_error)
+	}
+}
+
+impl Storage for SqliteStore {
+	fn save_articles(&mut self, articles: &[Article], seen_at: DateTime<Utc>) -> Result<usize> {
+		let tx = self.conn.transaction().map_err(AppError::storage_error)?;
+		let seen_at = seen_at.to_rfc3339();
+		{
+			let mut stmt = tx
+				.prepare_cached(
+					"INSERT INTO articles (id, url, source, title, data, first_seen, last_seen)
+					VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+					ON CONFLICT (id) DO UPDATE SET
+						url = excluded.url,
+						source = excluded.source,
+						title = excluded.title,
+						data = excluded.data,
+						last_seen = excluded.last_seen",
+				)
+				.map_err(AppError::storage_error)?;
+			for article in articles {
+				let data = serde_json::to_string(article).map_err(|e| AppError::parse_error("stored article", e))?;
+				stmt.execute(params![
+					article.id(),
+					article.url(),
+					article.source(),
+					article.title(),
+					data,
+					seen_at
+				])
+				.map_err(AppError::storage_error)?;
+			}
+		}
+		tx.commit().map_err(AppError::storage_error)?;
+		Ok(articles.len())
+	}
+
+	fn load_articles(&self) -> Result<Vec<Article>> {
+		let mut stmt = self
+			.conn
+			.prepare("SELECT data FROM articles ORDER BY last_seen DESC")
+			.map_err(AppError::storage_error)?;
+		let rows = stmt
+			.query_map([], |row| row.get::<_, String>(0))
+			.map_err(AppError::storage_error)?;
+
+		let mut articles = Vec::new();
+		for data in rows {
+			let data = data.map_err(AppError::storage_error)?;
+			articles.push(serde_json::from_str(&data).map_err(|e| AppError::parse_error("stored article", e))?);
+		}
+		Ok(articles)
+	}
+
+	fn save_ranking(
+		&mut self,
+		profile: &str,
+		run_id: &str,
+		ranked_at: DateTime<Utc>,
+		ranking: &[(&Article, f64)],
+	) -> Result<usize> {
+		let tx = self.conn.transaction().map_err(AppError::storage_error)?;
+		let ranked_at = ranked_at.to_rfc3339();
+		let mut saved = 0usize;
+		{
+			let mut stmt = tx
+				.prepare_cached(
+					"INSERT OR REPLACE INTO rankings (profile, run_id, ranked_at, id, url, position, score)
+					VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+				)
+				.map_err(AppError::storage_error)?;
+			for (position, &(article, score)) in (1i64..).zip(ranking) {
+				stmt.execute(params![
+					profile,
+					run_id,
+					ranked_at,
+					article.id(),
+					article.url(),
+					position,
+					score
+				])
+				.map_err(AppError::storage_error)?;
+				saved = saved.saturating_add(1);
+			}
+		}
+		tx.commit().map_err(AppError::storage_error)?;
+		Ok(saved)
+	}
+
+	fn ranking_history(&self, profile: &str) -> Result<RankingHistory> {
+		let mut history = RankingHistory::default();
+		let mut stmt = self
+			.conn
+			.prepare(
+				"SELECT id, position FROM rankings WHERE profile = ?1 AND run_id = (
+					SELECT run_id FROM rankings WHERE profile = ?1 ORDER BY ranked_at DESC LIMIT 1
+				)",
+			)
+			.map_err(AppError::storage_error)?;
+		let rows = stmt
+			.query_map([profile], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+			.map_err(AppError::storage_error)?;
+		for row in rows {
+			let (id, position) = row.map_err(AppError::storage_error)?;
+			history
+				.previous
+				.insert(id, usize::try_from(position).unwrap_or(usize::MAX));
+		}
+
+		let mut stmt = self
+			.conn
+			.prepare("SELECT DISTINCT id FROM rankings WHERE profile = ?1")
+			.map_err(AppError::storage_error)?;
+		let rows = stmt
+			.query_map([profile], |row| row.get::<_, String>(0))
+			.map_err(AppError::storage_error)?;
+		for id in rows {
+			history.seen.insert(id.map_err(AppError::storage_error)?);
+		}
+		Ok(history)
+	}
+
+	fn delivered(&self, sink: &str, destination: &str, since: Option<DateTime<Utc>>) -> Result<HashSet<String>> {
+		let since = since.map_or_else(String::new, |since| since.to_rfc3339());
+		let mut stmt = self
+			.conn
+			.prepare_cached("SELECT id FROM deliveries WHERE sink = ?1 AND destination = ?2 AND delivered_at >= ?3")
+			.map_err(AppError::storage_error)?;
+		let rows = stmt
+			.query_map(params![sink, destination, since], |row| row.get::<_, String>(0))
+			.map_err(AppError::storage_error)?;
+		rows.map(|id| id.map_err(AppError::storage_error)).collect()
+	}
+
+	fn record_deliveries(
+		&mut self,
+		sink: &str,
+		destination: &str,
+		articles: &[&Article],
+		delivered_at: DateTime<Utc>,
+	) -> Result<()> {
+		let tx = self.conn.transaction().map_err(AppError::storage_error)?;
+		let delivered_at = delivered_at.to_rfc3339();
+		{
+			let mut stmt = tx
+				.prepare_cached(
+					"INSERT INTO deliveries (sink, destination, id, url, delivered_at) VALUES (?1, ?2, ?3, ?4, ?5)
+					ON CONFLICT (sink, destination, id) DO UPDATE SET
+						url = excluded.url,
+						delivered_at = excluded.delivered_at",
+				)
+				.map_err(AppError::storage_error)?;
+			for article in articles {
+				stmt.execute(params![sink, destination, article.id(), article.url(), delivered_at])
+					.map_err(AppError::storage_error)?;
+			}
+		}
+		tx.commit().map_err(AppError::storage_error)
+	}
+
+	fn set_starred(&mut self, url: &str, starred: bool) -> Result<bool> {
+		let updated = self
+			.conn
+			.execute(
+				"UPDATE articles SET starred = ?3 WHERE url = ?1 OR id = ?2",
+				params![url, model::id_for_url(url), starred],
+			)
+			.map_err(AppError::storage_error)?;
+		Ok(updated > 0)
+	}
+
+	fn set_read(&mut self, user: &str, id: &str, read: bool, at: DateTime<Utc>) -> Result<bool> {
+		let stored: bool = self
+			.conn
+			.query_row("SELECT EXISTS (SELECT 1 FROM articles WHERE id = ?1)", [id], |row| {
+				row.get(0)
+			})
+			.map_err(AppError::storage_error)?;
+		if !stored {
+			return Ok(false);
+		}
+		if read {
+			self.conn.execute(
+				"INSERT INTO reads (username, id, read_at) VALUES (?1, ?2, ?3) ON CONFLICT (username, id) DO NOTHING",
+				params![user, id, at.to_rfc3339()],
+			)
+		} else {
+			self.conn
+				.execute("DELETE FROM reads WHERE username = ?1 AND id = ?2", params![user, id])
+		}
+		.map_err(AppError::storage_error)?;
+		Ok(true)
+	}
+
+	fn read_ids(&self, user: &str) -> Result<HashSet<String>> {
+		let mut stmt = self
+			.conn
+			.prepare_cached("SELECT id FROM reads WHERE username = ?1")
+			.map_err(AppError::storage_error)?;
+		stmt.query_map([user], |row| row.get(0))
+			.map_err(AppError::storage_error)?
+			.collect::<rusqlite::Result<_>>()
+			.map_err(AppError::storage_error)
+	}
+
+	fn record_click(&mut self, id: &str) -> Result<Option<String>> {
+		self.conn
+			.query_row(
+				"UPDATE articles SET clicks = clicks + 1 WHERE id = ?1 RETURNING url",
+				[id],
+				|row| row.get(0),
+			)
+			.optional()
+			.map_err(AppError::storage_error)
+	}
+
+	fn prune(&mut self, policy: &RetentionConfig, now: DateTime<Utc>) -> Result<Pruned> {
+		let tx = self.conn.transaction().map_err(AppError::storage_error)?;
+		let mut pruned = Pruned::default();
+		if let Some(cutoff) = policy.cutoff(now).map(|cutoff| cutoff.to_rfc3339()) {
+			pruned.articles = tx
+				.execute(
+					"DELETE FROM articles WHERE last_seen < ?1 AND NOT (?2 AND starred)",
+					params![cutoff, policy.keep_starred],
+				)
+				.map_err(AppError::storage_error)?;
+			pruned.rankings = tx
+				.execute("DELETE FROM rankings WHERE ranked_at < ?1", [cutoff])
+				.map_err(AppError::storage_error)?;
+		}
+		if let Some(max_articles) = policy.max_articles {
+			let dropped = tx
+				.execute(
+					"DELETE FROM articles WHERE NOT (?2 AND starred)
+					AND id NOT IN (SELECT id FROM articles ORDER BY last_seen DESC LIMIT ?1)",
+					params![i64::try_from(max_articles).unwrap_or(i64::MAX), policy.keep_starred],
+				)
+				.map_err(AppError::storage_error)?;
+			pruned.articles = pruned.articles.saturating_add(dropped);
+		}
+		let dropped = tx
+			.execute("DELETE FROM rankings WHERE id NOT IN (SELECT id FROM articles)", [])
+			.map_err(AppError::storage_error)?;
+		pruned.rankings = pruned.rankings.saturating_add(dropped);
+		pruned.deliveries = tx
+			.execute("DELETE FROM deliveries WHERE id NOT IN (SELECT id FROM articles)", [])
+			.map_err(AppError::storage_error)?;
+		tx.execute("DELETE FROM reads WHERE id NOT IN (SELECT id FROM articles)", [])
+			.map_err(AppError::storage_error)?;
+		tx.commit().map_err(AppError::storage_error)?;
+		Ok(pruned)
+	}
+
+	fn dump(&self) -> Result<StoreContents> {
+		let mut contents = dump(&self.conn)?;
+		dump_clicks(&self.conn, &mut contents)?;
+		contents.reads = dump_reads(&self.conn)?;
+		Ok(contents)
+	}
+
+	fn restore(&mut self, contents: &StoreContents) -> Result<()> {
+		let tx = self.conn.transaction().map_err(AppError::storage_error)?;
+		restore(&tx, contents)?;
+		restore_clicks(&tx, contents)?;
+		restore_reads(&tx, contents)?;
+		tx.commit().map_err(AppError::storage_error)
+	}
+
+	fn compact(&mut self) -> Result<()> {
+		self.conn.execute_batch("VACUUM").map_err(AppError::storage_error)
+	}
+}
+
+/// Every stored article, ranking and delivery
+fn dump(conn: &Connection) -> Result<StoreContents> {
+	let mut contents = StoreContents::default();
+	let mut stmt = conn
+		.prepare("SELECT data, first_seen, last_seen, starred FROM articles ORDER BY first_seen")
+		.map_err(AppError::storage_error)?;
+	let rows = stmt
+		.query_map([], |row| {
+			Ok((
+				row.get::<_, String>(0)?,
+				row.get::<_, String>(1)?,
+				row.get::<_, String>(2)?,
+				row.get::<_, bool>(3)?,
+			))
+		})
+		.map_err(AppError::storage_error)?;
+	for row in rows {
+		let (data, first_seen, last_seen, starred) = row.map_err(AppError::storage_error)?;
+		contents.articles.push(StoredArticle {
+			article: serde_json::from_str(&data).map_err(|e| AppError::parse_error("stored article", e))?,
+			first_seen: timestamp(&first_seen)?,
+			last_seen: timestamp(&last_seen)?,
+			starred,
+			clicks: 0,
+		});
+	}
+
+	let mut stmt = conn
+		.prepare("SELECT profile, run_id, ranked_at, url, position, score FROM rankings ORDER BY ranked_at, position")
+		.map_err(AppError::storage_error)?;
+	let rows = stmt
+		.query_map([], |row| {
+			Ok((
+				row.get::<_, String>(0)?,
+				row.get::<_, String>(1)?,
+				row.get::<_, String>(2)?,
+				row.get::<_, String>(3)?,
+				row.get::<_, i64>(4)?,
+				row.get::<_, f64>(5)?,
+			))
+		})
+		.map_err(AppError::storage_error)?;
+	for row in rows {
+		let (profile, run_id, ranked_at, url, position, score) = row.map_err(AppError::storage_error)?;
+		contents.rankings.push(StoredRanking {
+			profile,
+			run_id,
+			ranked_at: timestamp(&ranked_at)?,
+			url,
+			position: u64::try_from(position).unwrap_or_default(),
+			score,
+		});
+	}
+
+	let mut stmt = conn
+		.prepare("SELECT sink, destination, url, delivered_at FROM deliveries ORDER BY delivered_at")
+		.map_err(AppError::storage_error)?;
+	let rows = stmt
+		.query_map([], |row| {
+			Ok((
+				row.get::<_, String>(0)?,
+				row.get::<_, String>(1)?,
+				row.get::<_, String>(2)?,
+				row.get::<_, String>(3)?,
+			))
+		})
+		.map_err(AppError::storage_error)?;
+	for row in rows {
+		let (sink, destination, url, delivered_at) = row.map_err(AppError::storage_error)?;
+		contents.deliveries.push(StoredDelivery {
+			sink,
+			destination,
+			url,
+			delivered_at: timestamp(&delivered_at)?,
+		});
+	}
+	Ok(contents)
+}
+
+/// Merge exported contents in, see `Storage::restore`; rankings and deliveries take the id of the stored
+/// article with their URL
+fn restore(conn: &Connection, contents: &StoreContents) -> Result<()> {
+	let mut stmt = conn
+		.prepare(
+			"INSERT INTO articles (id, url, source, title, data, first_seen, last_seen, starred)
+			VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+			ON CONFLICT (id) DO UPDATE SET
+				url = CASE WHEN excluded.last_seen >= last_seen THEN excluded.url ELSE url END,
+				source = CASE WHEN excluded.last_seen >= last_seen THEN excluded.source ELSE source END,
+				title = CASE WHEN excluded.last_seen >= last_seen THEN excluded.title ELSE title END,
+				data = CASE WHEN excluded.last_seen >= last_seen THEN excluded.data ELSE data END,
+				first_seen = MIN(first_seen, excluded.first_seen),
+				last_seen = MAX(last_seen, excluded.last_seen),
+				starred = starred OR excluded.starred",
+		)
+		.map_err(AppError::storage_error)?;
+	for stored in &contents.articles {
+		let article = &stored.article;
+		let data = serde_json::to_string(article).map_err(|e| AppError::parse_error("stored article", e))?;
+		stmt.execute(params![
+			article.id(),
+			article.url(),
+			article.source(),
+			article.title(),
+			data,
+			stored.first_seen.to_rfc3339(),
+			stored.last_seen.to_rfc3339(),
+			stored.starred
+		])
+		.map_err(AppError::storage_error)?;
+	}
+
+	let mut stmt = conn
+		.prepare(
+			"INSERT OR REPLACE INTO rankings (profile, run_id, ranked_at, id, url, position, score)
+			VALUES (?1, ?2, ?3, COALESCE((SELECT id FROM articles WHERE url = ?4), ?5), ?4, ?6, ?7)",
+		)
+		.map_err(AppError::storage_error)?;
+	for ranking in &contents.rankings {
+		stmt.execute(params![
+			ranking.profile,
+			ranking.run_id,
+			ranking.ranked_at.to_rfc3339(),
+			ranking.url,
+			model::id_for_url(&ranking.url),
+			i64::try_from(ranking.position).unwrap_or(i64::MAX),
+			ranking.score
+		])
+		.map_err(AppError::storage_error)?;
+	}
+
+	let mut stmt = conn
+		.prepare(
+			"INSERT INTO deliveries (sink, destination, id, url, delivered_at)
+			VALUES (?1, ?2, COALESCE((SELECT id FROM articles WHERE url = ?3), ?4), ?3, ?5)
+			ON CONFLICT (sink, destination, id) DO UPDATE SET
+				delivered_at = MAX(delivered_at, excluded.delivered_at)",
+		)
+		.map_err(AppError::storage_error)?;
+	for delivery in &contents.deliveries {
+		stmt.execute(params![
+			delivery.sink,
+			delivery.destination,
+			delivery.url,
+			model::id_for_url(&delivery.url),
+			delivery.delivered_at.to_rfc3339()
+		])
+		.map_err(AppError::storage_error)?;
+	}
+	Ok(())
+}
+
+/// Fill in the click counts of the dumped articles. Kept apart from `dump`, which also carries rows through
+/// the re-keying migration, from before the column existed.
+fn dump_clicks(conn: &Connection, contents: &mut StoreContents) -> Result<()> {
+	let mut stmt = conn
+		.prepare("SELECT id, clicks FROM articles WHERE clicks > 0")
+		.map_err(AppError::storage_error)?;
+	let clicks: HashMap<String, i64> = stmt
+		.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+		.map_err(AppError::storage_error)?
+		.collect::<rusqlite::Result<_>>()
+		.map_err(AppError::storage_error)?;
+	for stored in &mut contents.articles {
+		if let Some(count) = clicks.get(&stored.article.id()) {
+			stored.clicks = u64::try_from(*count).unwrap_or_default();
+		}
+	}
+	Ok(())
+}
+
+/// Keep the higher click count of each restored article, see `dump_clicks`
+fn restore_clicks(conn: &Connection, contents: &StoreContents) -> Result<()> {
+	let mut stmt = conn
+		.prepare("UPDATE articles SET clicks = MAX(clicks, ?2) WHERE id = ?1")
+		.map_err(AppError::storage_error)?;
+	for stored in contents.articles.iter().filter(|stored| stored.clicks > 0) {
+		stmt.execute(params![
+			stored.article.id(),
+			i64::try_from(stored.clicks).unwrap_or(i64::MAX)
+		])
+		.map_err(AppError::storage_error)?;
+	}
+	Ok(())
+}
+
+/// Every read mark, by the URL of its article; apart from `dump` like `dump_clicks`
+fn dump_reads(conn: &Connection) -> Result<Vec<StoredRead>> {
+	let mut stmt = conn
+		.prepare("SELECT r.username, a.url, r.read_at FROM reads r JOIN articles a ON a.id = r.id ORDER BY r.read_at")
+		.map_err(AppError::storage_error)?;
+	let rows = stmt
+		.query_map([], |row| {
+			Ok((
+				row.get::<_, String>(0)?,
+				row.get::<_, String>(1)?,
+				row.get::<_, String>(2)?,
+			))
+		})
+		.map_err(AppError::storage_error)?;
+	let mut reads = Vec::new();
+	for row in rows {
+		let (user, url, read_at) = row.map_err(AppError::storage_error)?;
+		reads.push(StoredRead {
+			user,
+			url,
+			read_at: timestamp(&read_at)?,
+		});
+	}
+	Ok(reads)
+}
+
+/// Merge read marks in, each keeping its latest time, see `dump_reads`
+fn restore_reads(conn: &Connection, contents: &StoreContents) -> Result<()> {
+	let mut stmt = conn
+		.prepare(
+			"INSERT INTO reads (username, id, read_at)
+			VALUES (?1, COALESCE((SELECT id FROM articles WHERE url = ?2), ?3), ?4)
+			ON CONFLICT (username, id) DO UPDATE SET read_at = MAX(read_at, excluded.read_at)",
+		)
+		.map_err(AppError::storage_error)?;
+	for read in &contents.reads {
+		stmt.execute(params![
+			read.user,
+			read.url,
+			model::id_for_url(&read.url),
+			read.read_at.to_rfc3339()
+		])
+		.map_err(AppError::storage_error)?;
+	}
+	Ok(())
+}
+
+/// Timestamps are stored as RFC 3339 text
+fn timestamp(value: &str) -> Result<DateTime<Utc>> {
+	DateTime::parse_from_rfc3339(value)
+		.map(|time| time.with_timezone(&Utc))
+		.map_err(|e| AppError::parse_error("stored timestamp", e))
+}
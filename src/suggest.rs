@@ -0,0 +1,176 @@
+use crate::error::{AppError, Result};
+use crate::model::Article;
+use aho_corasick::AhoCorasick;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Words too common in English headlines to be worth suggesting
+const STOPWORDS: &[&str] = &[
+	"about", "after", "all", "also", "and", "any", "are", "because", "been", "before", "being", "but", "can", "could",
+	"did", "does", "for", "from", "get", "had", "has", "have", "her", "here", "his", "how", "into", "its", "just",
+	"like", "more", "most", "new", "not", "now", "off", "one", "only", "our", "out", "over", "own", "she", "should",
+	"some", "than", "that", "the", "their", "them", "then", "there", "these", "they", "this", "those", "through",
+	"too", "two", "under", "use", "using", "very", "was", "way", "were", "what", "when", "where", "which", "while",
+	"who", "why", "will", "with", "would", "you", "your",
+];
+
+/// A proposed keyword with the evidence for it
+#[derive(Debug)]
+pub struct Suggestion {
+	pub term: String,
+	/// Summed TF-IDF weight over the articles matching an existing keyword
+	pub score: f64,
+	/// Matching articles that contain the term
+	pub articles: usize,
+	/// Existing keywords the term appeared alongside, most frequent first
+	pub keywords: Vec<String>,
+	/// Titles of the matching articles where the term weighs most
+	pub examples: Vec<String>,
+}
+
+/// One article reduced to its term counts and the existing keywords it matches
+struct Document<'a> {
+	title: &'a str,
+	terms: HashMap<String, usize>,
+	length: usize,
+	matched: Vec<usize>,
+}
+
+/// Mine `articles` for terms that carry the most TF-IDF weight in articles matching one of `keywords`,
+/// ignoring terms already covered by a keyword and those found in fewer than `min_articles` matching articles
+///
+/// # Errors
+///
+/// `ConfigError` when `keywords` is empty, `AnalyzerError` when their automaton cannot be built
+pub fn suggest(
+	articles: &[Article],
+	keywords: &[String],
+	limit: usize,
+	min_articles: usize,
+	examples: usize,
+) -> Result<Vec<Suggestion>> {
+	if keywords.is_empty() {
+		return Err(AppError::ConfigError("no keywords configured".into()));
+	}
+	let ac = AhoCorasick::builder()
+		.ascii_case_insensitive(true)
+		.build(keywords)
+		.map_err(|e| AppError::AnalyzerError(format!("failed to build AC: {e}")))?;
+
+	let documents: Vec<Document<'_>> = articles
+		.par_iter()
+		.map(|article| {
+			let text = article.searchable_text();
+			let mut matched: Vec<usize> = ac.find_iter(text.as_ref()).map(|m| m.pattern().as_usize()).collect();
+			matched.sort_unstable();
+			matched.dedup();
+			let mut terms: HashMap<String, usize> = HashMap::new();
+			let mut length = 0usize;
+			for term in tokenize(&text) {
+				length = length.saturating_add(1);
+				let count = terms.entry(term).or_default();
+				*count = count.saturating_add(1);
+			}
+			Document {
+				title: article.title(),
+				terms,
+				length,
+				matched,
+			}
+		})
+		.collect();
+
+	let matching = documents.iter().filter(|doc| !doc.matched.is_empty()).count();
+	if matching == 0 {
+		return Err(AppError::AnalyzerError(
+			"no stored article matches the configured keywords".into(),
+		));
+	}
+
+	let mut frequency: HashMap<&str, usize> = HashMap::new();
+	for doc in &documents {
+		for term in doc.terms.keys() {
+			let df = frequency.entry(term.as_str()).or_default();
+			*df = df.saturating_add(1);
+		}
+	}
+	let covered: HashSet<String> = keywords.iter().flat_map(|keyword| tokenize(keyword)).collect();
+	let corpus = len_as_f64(documents.len());
+
+	let mut candidates: HashMap<&str, Candidate<'_>> = HashMap::new();
+	for doc in documents.iter().filter(|doc| !doc.matched.is_empty()) {
+		for (term, &count) in &doc.terms {
+			if covered.contains(term) {
+				continue;
+			}
+			let df = frequency.get(term.as_str()).copied().unwrap_or(1);
+			let weight = len_as_f64(count) / len_as_f64(doc.length.max(1)) * (corpus / len_as_f64(df)).ln();
+			let candidate = candidates.entry(term.as_str()).or_default();
+			candidate.score += weight;
+			candidate.articles = candidate.articles.saturating_add(1);
+			candidate.examples.push((weight, doc.title));
+			for &keyword in &doc.matched {
+				let together = candidate.keywords.entry(keyword).or_default();
+				*together = together.saturating_add(1);
+			}
+		}
+	}
+
+	let mut ranked: Vec<(&str, Candidate<'_>)> = candidates
+		.into_iter()
+		.filter(|(_, candidate)| candidate.articles >= min_articles && candidate.score > 0.0)
+		.collect();
+	ranked.sort_by(|a, b| b.1.score.total_cmp(&a.1.score).then_with(|| a.0.cmp(b.0)));
+	ranked.truncate(limit);
+
+	Ok(ranked
+		.into_iter()
+		.map(|(term, candidate)| candidate.into_suggestion(term, keywords, examples))
+		.collect())
+}
+
+#[derive(Default)]
+struct Candidate<'a> {
+	score: f64,
+	articles: usize,
+	/// Co-occurrence counts by keyword index
+	keywords: HashMap<usize, usize>,
+	examples: Vec<(f64, &'a str)>,
+}
+
+impl Candidate<'_> {
+	fn into_suggestion(mut self, term: &str, keywords: &[String], examples: usize) -> Suggestion {
+		let mut together: Vec<(usize, usize)> = self.keywords.into_iter().collect();
+		together.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+		self.examples.sort_by(|a, b| b.0.total_cmp(&a.0));
+		self.examples.dedup_by(|a, b| a.1 == b.1);
+		Suggestion {
+			term: term.to_string(),
+			score: self.score,
+			articles: self.articles,
+			keywords: together
+				.into_iter()
+				.filter_map(|(index, _)| keywords.get(index).cloned())
+				.collect(),
+			examples: self
+				.examples
+				.into_iter()
+				.take(examples)
+				.map(|(_, title)| title.to_string())
+				.collect(),
+		}
+	}
+}
+
+/// Lowercased words of three or more characters, without numbers and stopwords
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+	text.split(|c: char| !c.is_alphanumeric())
+		.filter(|word| word.chars().count() > 2 && !word.chars().all(|c| c.is_ascii_digit()))
+		.map(str::to_lowercase)
+		.filter(|word| !STOPWORDS.contains(&word.as_str()))
+}
+
+#[allow(clippy::cast_precision_loss)]
+const fn len_as_f64(len: usize) -> f64 {
+	len as f64
+}
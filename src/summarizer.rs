@@ -0,0 +1,130 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::SummarizerConfig;
+use rayon::prelude::*;
+use std::collections::HashSet;
+
+/// `PageRank` damping factor used by `TextRank`
+const DAMPING: f64 = 0.85;
+const ITERATIONS: usize = 30;
+/// Sentences shorter than this carry too little signal to rank
+const MIN_SENTENCE_WORDS: usize = 3;
+
+/// Attach extractive summaries to the top `top_n` articles of an already ranked list
+pub fn summarize_top(articles: &mut [ScoredArticle], config: &SummarizerConfig) {
+	let top_n = config.top_n.min(articles.len());
+	let Some(top) = articles.get_mut(..top_n) else {
+		return;
+	};
+	top.par_iter_mut().for_each(|scored| {
+		let summary = scored
+			.article()
+			.description()
+			.and_then(|text| summarize(text, config.sentences));
+		if let Some(summary) = summary {
+			scored.set_summary(summary);
+		}
+	});
+}
+
+/// TextRank-style extractive summary: rank sentences by word-overlap centrality and keep the
+/// best `max_sentences` in their original order
+#[must_use]
+pub fn summarize(text: &str, max_sentences: usize) -> Option<String> {
+	let sentences = split_sentences(text);
+	if sentences.is_empty() || max_sentences == 0 {
+		return None;
+	}
+	if sentences.len() <= max_sentences {
+		return Some(sentences.join(" "));
+	}
+
+	let tokens: Vec<HashSet<String>> = sentences.iter().map(|s| tokenize(s)).collect();
+	let similarity: Vec<Vec<f64>> = tokens
+		.iter()
+		.enumerate()
+		.map(|(i, a)| {
+			tokens
+				.iter()
+				.enumerate()
+				.map(|(j, b)| if i == j { 0.0 } else { sentence_similarity(a, b) })
+				.collect()
+		})
+		.collect();
+
+	let ranks = page_rank(&similarity);
+
+	let mut ranked: Vec<(usize, f64)> = ranks.into_iter().enumerate().collect();
+	ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+	ranked.truncate(max_sentences);
+	ranked.sort_by_key(|&(index, _)| index);
+
+	let summary: Vec<&str> = ranked
+		.into_iter()
+		.filter_map(|(index, _)| sentences.get(index).copied())
+		.collect();
+	Some(summary.join(" "))
+}
+
+fn split_sentences(text: &str) -> Vec<&str> {
+	let mut sentences = Vec::new();
+	let mut start = 0;
+	let mut chars = text.char_indices().peekable();
+	while let Some((index, c)) = chars.next() {
+		let at_boundary = matches!(c, '.' | '!' | '?') && chars.peek().is_none_or(|&(_, next)| next.is_whitespace());
+		if at_boundary {
+			let end = index.saturating_add(c.len_utf8());
+			if let Some(sentence) = text.get(start..end) {
+				sentences.push(sentence.trim());
+			}
+			start = end;
+		}
+	}
+	if let Some(rest) = text.get(start..) {
+		sentences.push(rest.trim());
+	}
+	sentences.retain(|s| s.split_whitespace().count() >= MIN_SENTENCE_WORDS);
+	sentences
+}
+
+fn tokenize(sentence: &str) -> HashSet<String> {
+	sentence
+		.split(|c: char| !c.is_alphanumeric())
+		.filter(|word| word.chars().count() > 2)
+		.map(str::to_lowercase)
+		.collect()
+}
+
+/// Overlap normalized by sentence length, as in the original `TextRank` paper
+fn sentence_similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+	let overlap = a.intersection(b).count();
+	let norm = len_as_f64(a.len()).ln() + len_as_f64(b.len()).ln();
+	if overlap == 0 || norm <= 0.0 {
+		return 0.0;
+	}
+	len_as_f64(overlap) / norm
+}
+
+fn page_rank(weights: &[Vec<f64>]) -> Vec<f64> {
+	let out_sums: Vec<f64> = weights.iter().map(|row| row.iter().sum()).collect();
+	let mut ranks = vec![1.0; weights.len()];
+
+	for _ in 0..ITERATIONS {
+		ranks = (0..weights.len())
+			.map(|i| {
+				let incoming: f64 = weights
+					.iter()
+					.zip(&out_sums)
+					.zip(&ranks)
+					.filter(|((_, out), _)| **out > 0.0)
+					.map(|((row, &out), &rank)| row.get(i).copied().unwrap_or(0.0) / out * rank)
+					.sum();
+				DAMPING.mul_add(incoming, 1.0 - DAMPING)
+			})
+			.collect();
+	}
+	ranks
+}
+
+fn len_as_f64(len: usize) -> f64 {
+	f64::from(u32::try_from(len).unwrap_or(u32::MAX))
+}
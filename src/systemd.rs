@@ -0,0 +1,96 @@
+use crate::config::ConfigHandle;
+use crate::daemon::{Health, HealthState};
+use tokio_util::sync::CancellationToken;
+
+/// Tell systemd the daemon is ready, keep a status line up to date, feed the watchdog while no fetch cycle
+/// has stalled past `daemon.stall_timeout_secs`, and report stopping on shutdown.
+///
+/// Does nothing when not started by systemd as a `Type=notify` unit.
+#[cfg(all(feature = "systemd", unix))]
+pub fn spawn(health: &Health, handle: &ConfigHandle, cancel_token: &CancellationToken) {
+	use sd_notify::NotifyState;
+	use std::time::Duration;
+	use tokio::time;
+	use tracing::{info, warn};
+
+	/// How often the status line is refreshed when the unit has no watchdog
+	const STATUS_INTERVAL: Duration = Duration::from_secs(10);
+
+	if std::env::var_os("NOTIFY_SOCKET").is_none() {
+		return;
+	}
+	let mut watchdog_usec = 0;
+	let watchdog = sd_notify::watchdog_enabled(false, &mut watchdog_usec);
+	// Ping at half the watchdog timeout, as sd_watchdog_enabled(3) recommends
+	let tick = if watchdog {
+		Duration::from_micros(watchdog_usec).mul_f64(0.5)
+	} else {
+		STATUS_INTERVAL
+	};
+	let mut last_status = describe(&health.snapshot());
+	notify(&[NotifyState::Ready, NotifyState::Status(&last_status)]);
+	info!(
+		watchdog_usec = watchdog.then_some(watchdog_usec),
+		"Notified systemd of readiness"
+	);
+
+	let (health, handle, cancel_token) = (health.clone(), handle.clone(), cancel_token.clone());
+	tokio::spawn(async move {
+		let mut was_stalled = false;
+		loop {
+			tokio::select! {
+				() = cancel_token.cancelled() => {
+					notify(&[NotifyState::Stopping, NotifyState::Status("Shutting down")]);
+					return;
+				}
+				() = time::sleep(tick) => {}
+			}
+			let state = health.snapshot();
+			let stalled = state.stalled(handle.current().daemon.stall_timeout_secs);
+			match &stalled {
+				Some(reason) if !was_stalled => {
+					warn!(reason, "Fetch cycle stalled, withholding systemd watchdog keepalives");
+				}
+				None if watchdog => notify(&[NotifyState::Watchdog]),
+				_ => {}
+			}
+			was_stalled = stalled.is_some();
+			let status = describe(&state);
+			if status != last_status {
+				notify(&[NotifyState::Status(&status)]);
+				last_status = status;
+			}
+		}
+	});
+}
+
+#[cfg(all(feature = "systemd", unix))]
+fn notify(state: &[sd_notify::NotifyState<'_>]) {
+	if let Err(e) = sd_notify::notify(false, state) {
+		tracing::warn!(error = %e, "Failed to notify systemd");
+	}
+}
+
+#[cfg(not(all(feature = "systemd", unix)))]
+pub const fn spawn(_health: &Health, _handle: &ConfigHandle, _cancel_token: &CancellationToken) {}
+
+/// The unit's status line, as shown by `systemctl status`
+#[cfg_attr(not(all(feature = "systemd", unix)), allow(dead_code))]
+fn describe(state: &HealthState) -> String {
+	let status = match (state.running_since, state.last_success) {
+		(Some(since), _) => format!("Cycle {} running since {}", state.cycles, since.format("%H:%M:%S")),
+		(None, Some(success)) => format!(
+			"{} cycles, last success at {}",
+			state.cycles,
+			success.format("%Y-%m-%d %H:%M:%S UTC")
+		),
+		(None, None) if state.cycles == 0 => "Waiting for the first fetch cycle".to_string(),
+		(None, None) => format!("{} cycles, none succeeded yet", state.cycles),
+	};
+	match (state.last_failure, &state.last_error) {
+		(Some(failure), Some(error)) if state.last_success.is_none_or(|success| failure > success) => {
+			format!("{status}; last failure: {error}")
+		}
+		_ => status,
+	}
+}
@@ -0,0 +1,40 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::ThumbnailsConfig;
+use crate::content::fetch_html;
+use crate::extract::page_image;
+use futures::stream::{self, StreamExt};
+use reqwest::Client;
+use tracing::info;
+
+/// Attach the preview image of each of the top `top_n` articles that has none from its feed, from the
+/// `og:image` or `twitter:image` of its page. Pages that fail to load or declare no image are skipped.
+pub async fn attach_thumbnails(client: &Client, config: &ThumbnailsConfig, scored: &mut [ScoredArticle]) {
+	let count = config.top_n.min(scored.len());
+	let Some(top) = scored.get_mut(..count) else {
+		return;
+	};
+	let missing: Vec<(usize, String)> = top
+		.iter()
+		.enumerate()
+		.filter(|(_, item)| item.article().image_url().is_none())
+		.map(|(index, item)| (index, item.article().url().to_string()))
+		.collect();
+	let looked_up = missing.len();
+	let images: Vec<(usize, Option<String>)> = stream::iter(missing)
+		.map(|(index, url)| async move {
+			let image = fetch_html(client, &url).await.and_then(|html| page_image(&html, &url));
+			(index, image)
+		})
+		.buffered(config.concurrency)
+		.collect()
+		.await;
+
+	let mut attached = 0usize;
+	for (index, image) in images {
+		if let (Some(item), Some(image)) = (top.get_mut(index), image) {
+			item.set_image_url(image);
+			attached = attached.saturating_add(1);
+		}
+	}
+	info!(articles = count, looked_up, attached, "Attached article thumbnails");
+}
@@ -0,0 +1,44 @@
+use crate::config::{TlsConfig, TlsVersion};
+use crate::error::{AppError, Result};
+use reqwest::ClientBuilder;
+use reqwest::tls::{Certificate, Identity, Version};
+use std::fs;
+
+/// `builder` trusting and presenting the certificates of `[http.tls]`, refusing TLS older than its
+/// `min_version`
+///
+/// # Errors
+///
+/// `IoError` when a file cannot be read, `ConfigError` when it holds no usable certificate or key
+pub fn configure(mut builder: ClientBuilder, config: &TlsConfig) -> Result<ClientBuilder> {
+	if let Some(path) = &config.ca_file {
+		let certificates = Certificate::from_pem_bundle(&read(path)?)
+			.map_err(|e| AppError::ConfigError(format!("http.tls.ca_file {path}: {e}")))?;
+		if certificates.is_empty() {
+			return Err(AppError::ConfigError(format!(
+				"http.tls.ca_file {path}: no certificate found"
+			)));
+		}
+		for certificate in certificates {
+			builder = builder.add_root_certificate(certificate);
+		}
+		builder = builder.tls_built_in_root_certs(!config.ca_only);
+	}
+	if let (Some(cert), Some(key)) = (&config.client_cert, &config.client_key) {
+		let identity = Identity::from_pkcs8_pem(&read(cert)?, &read(key)?)
+			.map_err(|e| AppError::ConfigError(format!("http.tls.client_cert {cert}: {e}")))?;
+		builder = builder.identity(identity);
+	}
+	if let Some(version) = config.min_version {
+		builder = builder.min_tls_version(match version {
+			TlsVersion::Tls10 => Version::TLS_1_0,
+			TlsVersion::Tls11 => Version::TLS_1_1,
+			TlsVersion::Tls12 => Version::TLS_1_2,
+		});
+	}
+	Ok(builder)
+}
+
+fn read(path: &str) -> Result<Vec<u8>> {
+	fs::read(path).map_err(|e| AppError::io_error(path, e))
+}
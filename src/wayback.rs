@@ -0,0 +1,107 @@
+use crate::analyzer::ScoredArticle;
+use crate::config::WaybackConfig;
+use crate::error::{AppError, Result};
+use futures::stream::{self, StreamExt};
+use reqwest::Client;
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::time::timeout;
+use tracing::{info, warn};
+
+/// Lookups in flight at once; the Wayback Machine throttles aggressive clients
+const MAX_CONCURRENT_LOOKUPS: usize = 4;
+
+#[derive(Deserialize)]
+struct Availability {
+	#[serde(default)]
+	archived_snapshots: Snapshots,
+}
+
+#[derive(Deserialize, Default)]
+struct Snapshots {
+	closest: Option<Snapshot>,
+}
+
+#[derive(Deserialize)]
+struct Snapshot {
+	available: bool,
+	url: String,
+}
+
+/// Attach the closest Wayback Machine snapshot to each of the top `top_n` articles.
+///
+/// With `save_missing`, articles without one are submitted to Save Page Now and get the new snapshot, unless
+/// `save_pages` is off, as in dry runs. Failed lookups are logged and leave the article without an archive link.
+pub async fn attach_snapshots(client: &Client, config: &WaybackConfig, save_pages: bool, scored: &mut [ScoredArticle]) {
+	let count = config.top_n.min(scored.len());
+	let Some(top) = scored.get_mut(..count) else {
+		return;
+	};
+	let snapshots: Vec<Option<String>> = stream::iter(top.iter())
+		.map(|item| snapshot(client, config, save_pages, item.article().url()))
+		.buffered(MAX_CONCURRENT_LOOKUPS)
+		.collect()
+		.await;
+
+	let mut attached = 0usize;
+	for (item, snapshot) in top.iter_mut().zip(snapshots) {
+		if let Some(snapshot) = snapshot {
+			item.set_archive_url(snapshot);
+			attached = attached.saturating_add(1);
+		}
+	}
+	info!(articles = count, attached, "Attached Wayback Machine snapshots");
+}
+
+async fn snapshot(client: &Client, config: &WaybackConfig, save_pages: bool, url: &str) -> Option<String> {
+	match closest(client, config, url).await {
+		Ok(Some(snapshot)) => return Some(snapshot),
+		Ok(None) => {}
+		Err(e) => {
+			warn!(url, error = %e, "Wayback Machine lookup failed");
+			return None;
+		}
+	}
+	if !(save_pages && config.save_missing) {
+		return None;
+	}
+	save(client, config, url)
+		.await
+		.map_err(|e| warn!(url, error = %e, "Wayback Machine save request failed"))
+		.ok()
+}
+
+/// The closest available snapshot, if the page was ever archived
+async fn closest(client: &Client, config: &WaybackConfig, url: &str) -> Result<Option<String>> {
+	let request = client.get(&config.api_url).query(&[("url", url)]).send();
+	let availability = timeout(Duration::from_secs(config.timeout_secs), request)
+		.await
+		.map_err(|_| AppError::TimeoutError(config.api_url.clone()))?
+		.and_then(reqwest::Response::error_for_status)
+		.map_err(|e| AppError::http_error(&config.api_url, e))?
+		.json::<Availability>()
+		.await
+		.map_err(|e| AppError::parse_error("Wayback Machine availability", e))?;
+	Ok(availability
+		.archived_snapshots
+		.closest
+		.filter(|snapshot| snapshot.available)
+		.map(|snapshot| https(&snapshot.url)))
+}
+
+/// Archive the page now; Save Page Now redirects to the new snapshot
+async fn save(client: &Client, config: &WaybackConfig, url: &str) -> Result<String> {
+	let save_url = format!("{}{url}", config.save_url);
+	let response = timeout(Duration::from_secs(config.timeout_secs), client.get(&save_url).send())
+		.await
+		.map_err(|_| AppError::TimeoutError(save_url.clone()))?
+		.and_then(reqwest::Response::error_for_status)
+		.map_err(|e| AppError::http_error(&save_url, e))?;
+	Ok(https(response.url().as_str()))
+}
+
+/// The availability API reports plain-HTTP snapshot links
+fn https(url: &str) -> String {
+	url.strip_prefix("http://web.archive.org/")
+		.map_or_else(|| url.to_string(), |path| format!("https://web.archive.org/{path}"))
+}
@@ -0,0 +1,283 @@
+use crate::error::{AppError, Result};
+use crate::model::{Article, Extra};
+use chrono::{DateTime, NaiveDate, Utc};
+use regex::Regex;
+use reqwest::Url;
+use scraper::{ElementRef, Html, Selector};
+use serde::Deserialize;
+
+/// Tag of every Current Events entry
+const CURRENT_EVENTS_TAG: &str = "current-events";
+/// Tag of every edit of a watched page
+const EDIT_TAG: &str = "wikipedia-edit";
+/// Titles the revisions API accepts in one request
+pub const MAX_TITLES_PER_REQUEST: usize = 50;
+
+#[derive(Debug, Deserialize)]
+struct ParseResponse {
+	parse: Option<Parsed>,
+	error: Option<ApiError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Parsed {
+	text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+	code: String,
+	info: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryResponse {
+	query: Option<Query>,
+	error: Option<ApiError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Query {
+	#[serde(default)]
+	pages: Vec<Page>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Page {
+	title: String,
+	#[serde(default)]
+	revisions: Vec<Revision>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Revision {
+	revid: u64,
+	#[serde(default)]
+	parentid: u64,
+	timestamp: DateTime<Utc>,
+	#[serde(default)]
+	user: Option<String>,
+	#[serde(default)]
+	comment: Option<String>,
+}
+
+/// Title of the Current Events page of `day`, e.g. `Portal:Current_events/2024_October_17`
+fn current_events_page(day: NaiveDate) -> String {
+	format!("Portal:Current_events/{}", day.format("%Y_%B_%-d"))
+}
+
+/// `parse` API request for the rendered Current Events page of `day`
+#[must_use]
+pub fn current_events_url(api: &Url, day: NaiveDate) -> Url {
+	let mut url = api.clone();
+	url.query_pairs_mut()
+		.append_pair("action", "parse")
+		.append_pair("page", &current_events_page(day))
+		.append_pair("prop", "text")
+		.append_pair("format", "json")
+		.append_pair("formatversion", "2");
+	url
+}
+
+/// `query` API request for the latest revision of each of `titles`
+#[must_use]
+pub fn revisions_url(api: &Url, titles: &[String]) -> Url {
+	let mut url = api.clone();
+	url.query_pairs_mut()
+		.append_pair("action", "query")
+		.append_pair("prop", "revisions")
+		.append_pair("titles", &titles.join("|"))
+		.append_pair("rvprop", "ids|timestamp|user|comment")
+		.append_pair("format", "json")
+		.append_pair("formatversion", "2");
+	url
+}
+
+/// The events listed on the Current Events page of `day`, one article each; none when the page does
+/// not exist (yet).
+///
+/// Each event links to the first news report it cites, the publication being kept in the `publisher`
+/// extra; events citing none are skipped. The title is the event's first sentence and the
+/// description its full text, after the topic it is filed under. Articles are tagged
+/// `current-events` plus their section, e.g. `science and technology`.
+///
+/// # Errors
+///
+/// `ParseError` when the body is not a parse API response
+pub fn parse_current_events(body: &[u8], url: &str, api: &Url, day: NaiveDate, source: &str) -> Result<Vec<Article>> {
+	let response: ParseResponse = serde_json::from_slice(body).map_err(|e| AppError::parse_error(url, e))?;
+	let parsed = match (response.parse, response.error) {
+		(_, Some(error)) if error.code == "missingtitle" => return Ok(Vec::new()),
+		(_, Some(error)) => return Err(AppError::parse_error(url, format!("{}: {}", error.code, error.info))),
+		(Some(parsed), None) => parsed,
+		(None, None) => return Err(AppError::parse_error(url, "no page in the response")),
+	};
+	let page = api
+		.join(&format!("/wiki/{}", current_events_page(day)))
+		.map_err(|e| AppError::parse_error(url, e))?;
+	let published_at = day.and_hms_opt(0, 0, 0).map(|midnight| midnight.and_utc());
+	Ok(events(&parsed.text, &page)
+		.into_iter()
+		.map(|event| {
+			let title = event
+				.text
+				.split_once(". ")
+				.map_or(event.text.as_str(), |(first, _)| first)
+				.trim_end_matches('.')
+				.to_string();
+			let description = match &event.topic {
+				Some(topic) => format!("{topic}: {}", event.text),
+				None => event.text.clone(),
+			};
+			let tags = std::iter::once(CURRENT_EVENTS_TAG.to_string())
+				.chain(event.section.map(|section| section.to_lowercase()))
+				.collect();
+			Article::new(title, event.link, source.to_string())
+				.with_description(description)
+				.with_published_at(published_at)
+				.with_tags(tags)
+				.with_extra("publisher", Extra::Text(event.publisher))
+		})
+		.collect())
+}
+
+/// One entry of a Current Events page
+struct Event {
+	section: Option<String>,
+	topic: Option<String>,
+	text: String,
+	link: String,
+	publisher: String,
+}
+
+/// Leaf list items of the page in order, under the last section heading seen. Older pages mark the
+/// sections as bold paragraphs, newer ones as `role="heading"` blocks.
+fn events(html: &str, page: &Url) -> Vec<Event> {
+	let (Ok(items), Ok(nested), Ok(citation)) = (
+		Selector::parse("p > b, [role=heading], li"),
+		Selector::parse("ul"),
+		Selector::parse("a.external"),
+	) else {
+		return Vec::new();
+	};
+	// Citations removed from the text leave empty parentheses behind
+	let empty_parens = Regex::new(r"\s*\(\s*\)").ok();
+	let fragment = Html::parse_fragment(html);
+	let mut section = None;
+	let mut events = Vec::new();
+	for element in fragment.select(&items) {
+		if element.value().name() != "li" {
+			section = Some(text(element)).filter(|heading| !heading.is_empty());
+			continue;
+		}
+		if element.select(&nested).next().is_some() {
+			continue;
+		}
+		let Some((link, publisher)) = element.select(&citation).find_map(|cited| {
+			let link = page.join(cited.value().attr("href")?.trim()).ok()?;
+			let publisher = text(cited).trim_matches(['(', ')']).to_string();
+			Some((link.to_string(), publisher))
+		}) else {
+			continue;
+		};
+		let own_text = element
+			.descendants()
+			.filter(|node| {
+				!node
+					.ancestors()
+					.filter_map(ElementRef::wrap)
+					.take_while(|ancestor| ancestor.id() != element.id())
+					.any(|ancestor| {
+						ancestor
+							.value()
+							.has_class("external", scraper::CaseSensitivity::AsciiCaseInsensitive)
+					})
+			})
+			.filter_map(|node| node.value().as_text().map(|text| &**text))
+			.collect::<String>();
+		let own_text = own_text.split_whitespace().collect::<Vec<_>>().join(" ");
+		let own_text = empty_parens.as_ref().map_or_else(
+			|| own_text.clone(),
+			|parens| parens.replace_all(&own_text, "").into_owned(),
+		);
+		if own_text.is_empty() {
+			continue;
+		}
+		// An entry nested under a story is filed under the story's link
+		let topic = element
+			.ancestors()
+			.filter_map(ElementRef::wrap)
+			.filter(|ancestor| ancestor.value().name() == "li")
+			.find_map(|story| {
+				story
+					.children()
+					.filter_map(ElementRef::wrap)
+					.find(|child| child.value().name() == "a")
+			})
+			.map(text);
+		events.push(Event {
+			section: section.clone(),
+			topic,
+			text: own_text,
+			link,
+			publisher,
+		});
+	}
+	events
+}
+
+/// The latest edit of each watched page made after `since`, one article each, linking to its diff
+///
+/// # Errors
+///
+/// `ParseError` when the body is not a revisions API response
+pub fn parse_revisions(body: &[u8], url: &str, api: &Url, since: DateTime<Utc>, source: &str) -> Result<Vec<Article>> {
+	let response: QueryResponse = serde_json::from_slice(body).map_err(|e| AppError::parse_error(url, e))?;
+	if let Some(error) = response.error {
+		return Err(AppError::parse_error(url, format!("{}: {}", error.code, error.info)));
+	}
+	let index = api.join("index.php").map_err(|e| AppError::parse_error(url, e))?;
+	Ok(response
+		.query
+		.map(|query| query.pages)
+		.unwrap_or_default()
+		.into_iter()
+		.filter_map(|page| {
+			let revision = page
+				.revisions
+				.into_iter()
+				.next()
+				.filter(|revision| revision.timestamp > since)?;
+			let mut diff = index.clone();
+			diff.query_pairs_mut()
+				.append_pair("diff", &revision.revid.to_string())
+				.append_pair("oldid", &revision.parentid.to_string());
+			let comment = revision
+				.comment
+				.as_deref()
+				.map(str::trim)
+				.filter(|comment| !comment.is_empty());
+			let title = match comment {
+				Some(comment) => format!("{} edited: {comment}", page.title),
+				None => format!("{} edited", page.title),
+			};
+			Some(
+				Article::new(title, diff.into(), source.to_string())
+					.with_published_at(Some(revision.timestamp))
+					.with_author(revision.user)
+					.with_tags(vec![EDIT_TAG.to_string()])
+					.with_guid(revision.revid.to_string())
+					.with_extra("wiki_page", Extra::Text(page.title)),
+			)
+		})
+		.collect())
+}
+
+/// Text of an element with whitespace runs collapsed
+fn text(element: ElementRef<'_>) -> String {
+	element
+		.text()
+		.flat_map(str::split_whitespace)
+		.collect::<Vec<_>>()
+		.join(" ")
+}
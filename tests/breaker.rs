@@ -0,0 +1,106 @@
+//! A source that keeps failing is skipped by its circuit breaker across runs sharing their metrics, like the
+//! daemon's cycles do
+use news_aggregator::config::{self, CircuitBreakerConfig, Config, ScraperConfig};
+use news_aggregator::error::{AppError, Result};
+use news_aggregator::fetcher::Fetcher;
+use news_aggregator::metrics::{Breaker, Metrics};
+use news_aggregator::run::RunContext;
+use reqwest::Client;
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+type TestResult = std::result::Result<(), Box<dyn std::error::Error>>;
+
+const SOURCE: &str = "Example Engineering";
+
+/// Answer every request with a 503 on a thread of its own, counting the requests
+fn serve_unavailable(requests: Arc<AtomicUsize>) -> std::io::Result<SocketAddr> {
+	let listener = TcpListener::bind("127.0.0.1:0")?;
+	let addr = listener.local_addr()?;
+	thread::spawn(move || {
+		for mut stream in listener.incoming().flatten() {
+			requests.fetch_add(1, Ordering::SeqCst);
+			let mut request = [0; 4096];
+			let _ = stream.read(&mut request);
+			let _ =
+				stream.write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+		}
+	});
+	Ok(addr)
+}
+
+/// The starter configuration reading only the failing feed at `addr`, without retries, its breaker opening
+/// after two failed fetches
+fn config(addr: SocketAddr) -> Result<Config> {
+	let mut config = config::starter()?;
+	config.http.retry_attempts = 1;
+	config.scrapers = vec![ScraperConfig {
+		name: SOURCE.into(),
+		url: format!("http://{addr}/"),
+		feed: Some(format!("http://{addr}/feed.xml")),
+		..ScraperConfig::default()
+	}];
+	config.fetcher.circuit_breaker = CircuitBreakerConfig {
+		enabled: true,
+		failure_threshold: 2,
+		cooldown_secs: 3600,
+	};
+	Ok(config)
+}
+
+/// Fetch the source once with a fetcher of its own, returning the error kind the run recorded for it
+async fn fetch(config: &Arc<Config>, metrics: &Metrics) -> Option<&'static str> {
+	let fetcher = Arc::new(
+		Fetcher::new(
+			Client::new(),
+			CancellationToken::new(),
+			metrics.clone(),
+			Arc::clone(config),
+		)
+		.only(HashSet::from([SOURCE.to_string()])),
+	);
+	let run = RunContext::new();
+	let (tx, _rx) = mpsc::channel(16);
+	let outcome = fetcher.fetch_into(&run, tx).await;
+	assert!(matches!(outcome, Err(AppError::NoArticlesError(_))), "{outcome:?}");
+	run.record()
+		.sources
+		.into_iter()
+		.find(|record| record.source == SOURCE)
+		.and_then(|record| record.error_kind)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_failing_source_is_skipped_once_its_breaker_opens() -> TestResult {
+	let requests = Arc::new(AtomicUsize::new(0));
+	let addr = serve_unavailable(Arc::clone(&requests))?;
+	let config = Arc::new(config(addr)?);
+	let metrics = Metrics::new();
+
+	for _ in 0..2 {
+		assert_ne!(fetch(&config, &metrics).await, Some("circuit_open"));
+	}
+	let served = requests.load(Ordering::SeqCst);
+	assert!(served > 0);
+	let breaker = metrics.source_breaker(SOURCE, &config.fetcher.circuit_breaker, chrono::Utc::now());
+	assert!(matches!(breaker, Breaker::Open { .. }), "{breaker:?}");
+
+	assert_eq!(fetch(&config, &metrics).await, Some("circuit_open"));
+	assert_eq!(
+		requests.load(Ordering::SeqCst),
+		served,
+		"an open breaker sends no request"
+	);
+	let failures = metrics
+		.source_health()
+		.get(SOURCE)
+		.map_or(0, |health| health.consecutive_failures);
+	assert_eq!(failures, 2, "a skipped fetch is not counted as a failure");
+	Ok(())
+}
@@ -0,0 +1,48 @@
+//! Helpers shared by the integration tests
+// Each test crate uses only some of them
+#![allow(dead_code)]
+
+use news_aggregator::error::Result;
+use news_aggregator::feed::parse_feed;
+use news_aggregator::model::Article;
+use std::path::{Path, PathBuf};
+use std::{fs, process};
+
+/// A directory of its own under the system temp directory, removed with everything in it when dropped
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+	pub fn new(name: &str) -> std::io::Result<Self> {
+		let path = std::env::temp_dir().join(format!("news_aggregator-{name}-{}", process::id()));
+		if path.exists() {
+			fs::remove_dir_all(&path)?;
+		}
+		fs::create_dir_all(&path)?;
+		Ok(Self(path))
+	}
+
+	pub fn path(&self) -> &Path {
+		&self.0
+	}
+}
+
+impl Drop for TempDir {
+	fn drop(&mut self) {
+		let _ = fs::remove_dir_all(&self.0);
+	}
+}
+
+/// The articles of the RSS fixture feed followed by those of the Atom one
+pub fn fixture_articles() -> Result<Vec<Article>> {
+	let mut articles = parse_feed(
+		include_str!("../fixtures/rss.xml"),
+		"https://blog.example/feed.xml",
+		"Example Engineering",
+	)?;
+	articles.extend(parse_feed(
+		include_str!("../fixtures/atom.xml"),
+		"https://news.example/atom.xml",
+		"Example News",
+	)?);
+	Ok(articles)
+}
@@ -0,0 +1,79 @@
+//! Decoding of compressed source responses and the size caps that stop decompression bombs
+use bytes::Bytes;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use news_aggregator::compression::{ContentEncoding, DEFAULT_MAX_DECODED_BYTES};
+use news_aggregator::error::AppError;
+use std::io::Write;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+const URL: &str = "https://feeds.example/rss.xml";
+
+/// `len` zero bytes, gzipped; zeros compress about a thousandfold, like a decompression bomb
+fn gzipped_zeros(len: usize) -> Result<Bytes, std::io::Error> {
+	let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+	encoder.write_all(&vec![0u8; len])?;
+	Ok(Bytes::from(encoder.finish()?))
+}
+
+#[test]
+fn gzip_body_within_the_limit_decodes() -> TestResult {
+	let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+	encoder.write_all(b"<rss><channel><title>News</title></channel></rss>")?;
+	let body = Bytes::from(encoder.finish()?);
+
+	let decoded = ContentEncoding::Gzip.decode(body, URL, Some(1024))?;
+
+	assert_eq!(decoded.as_ref(), b"<rss><channel><title>News</title></channel></rss>");
+	Ok(())
+}
+
+#[test]
+fn gzip_body_past_max_body_bytes_is_too_large() -> TestResult {
+	let body = gzipped_zeros(1024 * 1024)?;
+	assert!(body.len() < 4096, "the compressed body is small");
+
+	let result = ContentEncoding::Gzip.decode(body, URL, Some(64 * 1024));
+
+	assert!(
+		matches!(result, Err(AppError::BodyTooLarge { limit, .. }) if limit == 64 * 1024),
+		"{result:?}"
+	);
+	Ok(())
+}
+
+#[test]
+fn gzip_body_past_the_default_cap_is_too_large_without_max_body_bytes() -> TestResult {
+	let body = gzipped_zeros(DEFAULT_MAX_DECODED_BYTES.saturating_add(1))?;
+
+	let result = ContentEncoding::Gzip.decode(body, URL, None);
+
+	assert!(
+		matches!(result, Err(AppError::BodyTooLarge { limit, .. }) if limit == DEFAULT_MAX_DECODED_BYTES),
+		"{result:?}"
+	);
+	Ok(())
+}
+
+#[tokio::test]
+async fn decoding_on_the_blocking_pool_keeps_the_cap() -> TestResult {
+	let body = gzipped_zeros(1024 * 1024)?;
+
+	let result = ContentEncoding::Gzip.decode_blocking(body, URL, Some(1024)).await;
+
+	assert!(matches!(result, Err(AppError::BodyTooLarge { .. })), "{result:?}");
+	Ok(())
+}
+
+#[tokio::test]
+async fn identity_body_is_passed_through() -> TestResult {
+	let body = Bytes::from_static(b"plain");
+
+	let decoded = ContentEncoding::Identity
+		.decode_blocking(body.clone(), URL, None)
+		.await?;
+
+	assert_eq!(decoded, body);
+	Ok(())
+}
@@ -0,0 +1,66 @@
+//! Parsing of the RSS and Atom feeds under `tests/fixtures`
+use news_aggregator::error::AppError;
+use news_aggregator::feed::parse_feed;
+use news_aggregator::model::{Article, Extra};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+const RSS: &str = include_str!("fixtures/rss.xml");
+const ATOM: &str = include_str!("fixtures/atom.xml");
+
+#[test]
+fn rss_items_become_articles_in_feed_order() -> TestResult {
+	let articles = parse_feed(RSS, "https://blog.example/feed.xml", "Example Engineering")?;
+
+	let titles: Vec<&str> = articles.iter().map(Article::title).collect();
+	assert_eq!(titles, ["Rust 2024 in production", "Episode 12: Databases"]);
+
+	let post = articles.first().ok_or("no first article")?;
+	assert_eq!(post.url(), "https://blog.example/posts/rust-2024");
+	assert_eq!(post.source(), "Example Engineering");
+	assert_eq!(
+		post.description(),
+		Some("Migrating a large Rust service to the 2024 edition.")
+	);
+	assert_eq!(post.author(), Some("Jane Doe"));
+	assert_eq!(post.tags(), ["rust", "programming"]);
+	assert_eq!(post.published_at(), Some("2025-01-06T09:30:00Z".parse()?));
+	assert_eq!(post.image_url(), Some("https://blog.example/images/rust.png"));
+	Ok(())
+}
+
+#[test]
+fn podcast_enclosure_stands_in_for_a_missing_link() -> TestResult {
+	let articles = parse_feed(RSS, "https://blog.example/feed.xml", "Example Engineering")?;
+
+	let episode = articles.get(1).ok_or("no second article")?;
+	assert_eq!(episode.url(), "https://cdn.example/ep12.mp3");
+	assert_eq!(episode.extra("audio_type"), Some(&Extra::Text("audio/mpeg".into())));
+	assert_eq!(episode.extra("duration_secs"), Some(&Extra::Integer(3723)));
+	Ok(())
+}
+
+#[test]
+fn atom_entries_use_their_alternate_link() -> TestResult {
+	let articles = parse_feed(ATOM, "https://news.example/atom.xml", "Example News")?;
+
+	assert_eq!(articles.len(), 1, "the entry without a link is skipped");
+	let entry = articles.first().ok_or("no first article")?;
+	assert_eq!(entry.title(), "Kubernetes 1.32 released");
+	assert_eq!(entry.url(), "https://news.example/1");
+	assert_eq!(entry.author(), Some("John Roe"));
+	assert_eq!(entry.tags(), ["kubernetes"]);
+	assert_eq!(entry.published_at(), Some("2025-01-07T12:00:00Z".parse()?));
+	Ok(())
+}
+
+#[test]
+fn other_documents_are_parse_errors() {
+	let result = parse_feed(
+		"<html><body>Not a feed</body></html>",
+		"https://blog.example/",
+		"Example",
+	);
+
+	assert!(matches!(result, Err(AppError::ParseError { .. })), "{result:?}");
+}
@@ -0,0 +1,114 @@
+//! A run recorded through `[fixtures]` against a local feed server, then replayed with the server gone quiet
+mod common;
+
+use common::TempDir;
+use news_aggregator::config::{self, Config, FixtureMode, FixturesConfig, ScraperConfig};
+use news_aggregator::error::{AppError, Result};
+use news_aggregator::fetcher::Fetcher;
+use news_aggregator::metrics::Metrics;
+use news_aggregator::pipeline::{Pipeline, ScorerCache};
+use news_aggregator::run::RunContext;
+use reqwest::Client;
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{fs, thread};
+use tokio_util::sync::CancellationToken;
+
+type TestResult = std::result::Result<(), Box<dyn std::error::Error>>;
+
+const SOURCE: &str = "Example Engineering";
+
+/// Serve the RSS fixture to every request on a thread of its own, counting the requests
+fn serve_feed(requests: Arc<AtomicUsize>) -> std::io::Result<SocketAddr> {
+	let listener = TcpListener::bind("127.0.0.1:0")?;
+	let addr = listener.local_addr()?;
+	thread::spawn(move || {
+		for mut stream in listener.incoming().flatten() {
+			requests.fetch_add(1, Ordering::SeqCst);
+			let mut request = [0; 4096];
+			let _ = stream.read(&mut request);
+			let body = include_str!("fixtures/rss.xml");
+			let _ = write!(
+				stream,
+				"HTTP/1.1 200 OK\r\nContent-Type: application/rss+xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+				body.len()
+			);
+		}
+	});
+	Ok(addr)
+}
+
+/// The starter configuration reading only the fixture feed served at `addr`, recording or replaying in `dir`
+fn config(addr: SocketAddr, dir: &TempDir, mode: FixtureMode) -> Result<Config> {
+	let mut config = config::starter()?;
+	config.scrapers = vec![ScraperConfig {
+		name: SOURCE.into(),
+		url: format!("http://{addr}/"),
+		feed: Some(format!("http://{addr}/feed.xml")),
+		..ScraperConfig::default()
+	}];
+	config.fixtures = FixturesConfig {
+		mode: Some(mode),
+		dir: dir.path().display().to_string(),
+	};
+	Ok(config)
+}
+
+/// Fetch, score and rank the fixture source through the whole pipeline, returning each ranked URL and score
+async fn run(config: Config) -> Result<Vec<(String, f64)>> {
+	let config = Arc::new(config);
+	let client = Client::new();
+	let cancel_token = CancellationToken::new();
+	let metrics = Metrics::new();
+	let fetcher = Arc::new(
+		Fetcher::new(
+			client.clone(),
+			cancel_token.clone(),
+			metrics.clone(),
+			Arc::clone(&config),
+		)
+		.only(HashSet::from([SOURCE.to_string()])),
+	);
+	let pipeline = Pipeline::new(&config, &client, &cancel_token, &metrics, &ScorerCache::default())?;
+	let ranked = pipeline
+		.fetch_and_process(&fetcher, &RunContext::new(), |_| Ok(()))
+		.await?;
+	Ok(ranked
+		.iter()
+		.map(|scored| (scored.article().url().to_string(), scored.relevance_score()))
+		.collect())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_recorded_run_replays_offline_to_the_same_ranking() -> TestResult {
+	let dir = TempDir::new("record-replay")?;
+	let requests = Arc::new(AtomicUsize::new(0));
+	let addr = serve_feed(Arc::clone(&requests))?;
+
+	let recorded = run(config(addr, &dir, FixtureMode::Record)?).await?;
+	assert!(!recorded.is_empty(), "the recorded run ranks the fixture feed");
+	let served = requests.load(Ordering::SeqCst);
+	assert!(served > 0);
+	let index = fs::read_to_string(dir.path().join("index.json"))?;
+	assert!(index.contains(&format!("http://{addr}/feed.xml")), "{index}");
+
+	let replayed = run(config(addr, &dir, FixtureMode::Replay)?).await?;
+	assert_eq!(replayed, recorded);
+	assert_eq!(requests.load(Ordering::SeqCst), served, "replaying sends no request");
+	Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn replaying_without_fixtures_fails_without_fetching() -> TestResult {
+	let dir = TempDir::new("replay-missing")?;
+	let requests = Arc::new(AtomicUsize::new(0));
+	let addr = serve_feed(Arc::clone(&requests))?;
+
+	let replayed = run(config(addr, &dir, FixtureMode::Replay)?).await;
+	assert!(matches!(replayed, Err(AppError::NoArticlesError(_))), "{replayed:?}");
+	assert_eq!(requests.load(Ordering::SeqCst), 0);
+	Ok(())
+}
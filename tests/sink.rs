@@ -0,0 +1,82 @@
+//! What the JSONL archive sink writes for the scored articles of the fixture feeds
+mod common;
+
+use common::{TempDir, fixture_articles};
+use news_aggregator::analyzer::Analyzer;
+use news_aggregator::config::{self, ArchiveConfig};
+use news_aggregator::run::RunContext;
+use news_aggregator::sink::{JsonlArchiveSink, Sink};
+use serde_json::Value;
+use std::fs;
+use tokio_util::sync::CancellationToken;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn jsonl_archive_appends_one_line_per_article_of_each_run() -> TestResult {
+	let dir = TempDir::new("jsonl-archive")?;
+	let path = dir.path().join("archive/articles.jsonl");
+	let config = config::starter()?;
+	let scored = Analyzer::new(&config)?.score(fixture_articles()?, &config, &CancellationToken::new())?;
+	let sink = JsonlArchiveSink::new(&ArchiveConfig {
+		enabled: true,
+		path: path.display().to_string(),
+		..ArchiveConfig::default()
+	});
+
+	let first = RunContext::new();
+	sink.deliver(&first, &scored).await?;
+	let second = RunContext::new();
+	sink.deliver(&second, &scored).await?;
+
+	let records = fs::read_to_string(&path)?
+		.lines()
+		.map(serde_json::from_str::<Value>)
+		.collect::<Result<Vec<_>, _>>()?;
+	assert_eq!(records.len(), scored.len().saturating_mul(2));
+	for (record, scored) in records.iter().zip(scored.iter().chain(&scored)) {
+		assert_eq!(
+			record.pointer("/article/url").and_then(Value::as_str),
+			Some(scored.article().url())
+		);
+		assert_eq!(
+			record.pointer("/article/title").and_then(Value::as_str),
+			Some(scored.article().title())
+		);
+		assert!(record["relevance_score"].is_number(), "{record}");
+		assert!(record["timestamp"].is_string(), "{record}");
+	}
+	let run_ids: Vec<&Value> = records.iter().map(|record| &record["run_id"]).collect();
+	let (from_first, from_second) = run_ids.split_at(scored.len());
+	assert!(from_first.iter().all(|id| *id == first.id()), "{run_ids:?}");
+	assert!(from_second.iter().all(|id| *id == second.id()), "{run_ids:?}");
+	Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn jsonl_archive_rotates_past_max_bytes() -> TestResult {
+	let dir = TempDir::new("jsonl-rotation")?;
+	let path = dir.path().join("articles.jsonl");
+	let config = config::starter()?;
+	let scored = Analyzer::new(&config)?.score(fixture_articles()?, &config, &CancellationToken::new())?;
+	let sink = JsonlArchiveSink::new(&ArchiveConfig {
+		enabled: true,
+		path: path.display().to_string(),
+		max_bytes: 1,
+		max_files: 2,
+	});
+
+	for _ in 0..4 {
+		sink.deliver(&RunContext::new(), &scored).await?;
+	}
+
+	let lines = |name: &str| fs::read_to_string(dir.path().join(name)).map(|text| text.lines().count());
+	assert_eq!(lines("articles.jsonl")?, scored.len());
+	assert_eq!(lines("articles.jsonl.1")?, scored.len());
+	assert_eq!(lines("articles.jsonl.2")?, scored.len());
+	assert!(
+		!dir.path().join("articles.jsonl.3").exists(),
+		"the oldest file is dropped"
+	);
+	Ok(())
+}
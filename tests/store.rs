@@ -0,0 +1,87 @@
+//! Round trips of the fixture articles through the SQLite store and the export bundles
+mod common;
+
+use chrono::{DateTime, Utc};
+use common::{TempDir, fixture_articles};
+use news_aggregator::model::Article;
+use news_aggregator::store::{SqliteStore, Storage, StoreContents, read_bundle, write_bundle};
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+/// A store holding the fixture articles, one of them starred, ranked once and delivered once
+fn filled_store(dir: &TempDir, articles: &[Article], now: DateTime<Utc>) -> TestResult {
+	let mut store = SqliteStore::open(&dir.path().join("news.db"))?;
+	assert_eq!(store.save_articles(articles, now)?, articles.len());
+	let first = articles.first().ok_or("no fixture articles")?;
+	assert!(store.set_starred(first.url(), true)?);
+	let ranking: Vec<(&Article, f64)> = articles.iter().zip([3.0, 2.0, 1.0]).collect();
+	store.save_ranking("default", "run-1", now, &ranking)?;
+	store.record_deliveries("jsonl_archive", "archive.jsonl", &[first], now)?;
+	Ok(())
+}
+
+fn sorted_urls(contents: &StoreContents) -> Vec<&str> {
+	let mut urls: Vec<&str> = contents.articles.iter().map(|stored| stored.article.url()).collect();
+	urls.sort_unstable();
+	urls
+}
+
+#[test]
+fn sqlite_store_keeps_articles_across_reopening() -> TestResult {
+	let dir = TempDir::new("store-reopen")?;
+	let articles = fixture_articles()?;
+	let now = "2025-01-08T00:00:00Z".parse()?;
+	filled_store(&dir, &articles, now)?;
+
+	let store = SqliteStore::open(&dir.path().join("news.db"))?;
+	let mut loaded: Vec<String> = store
+		.load_articles()?
+		.iter()
+		.map(|article| article.url().to_string())
+		.collect();
+	loaded.sort_unstable();
+	let mut expected: Vec<String> = articles.iter().map(|article| article.url().to_string()).collect();
+	expected.sort_unstable();
+	assert_eq!(loaded, expected);
+
+	let history = store.ranking_history("default")?;
+	assert_eq!(history.seen.len(), articles.len());
+	let first = articles.first().ok_or("no fixture articles")?;
+	assert_eq!(history.previous.get(&first.id()), Some(&1));
+	assert!(
+		store
+			.delivered("jsonl_archive", "archive.jsonl", None)?
+			.contains(&first.id())
+	);
+	Ok(())
+}
+
+#[test]
+fn export_bundles_restore_into_an_empty_store() -> TestResult {
+	let dir = TempDir::new("store-bundle")?;
+	let articles = fixture_articles()?;
+	let now = "2025-01-08T00:00:00Z".parse()?;
+	filled_store(&dir, &articles, now)?;
+	let exported = SqliteStore::open(&dir.path().join("news.db"))?.dump()?;
+
+	for bundle in ["export.jsonl", "export.json"] {
+		let bundle = dir.path().join(bundle);
+		write_bundle(&bundle, &exported)?;
+		let mut restored = SqliteStore::open(&dir.path().join(bundle.with_extension("db")))?;
+		restored.restore(&read_bundle(&bundle)?)?;
+
+		let contents = restored.dump()?;
+		assert_eq!(sorted_urls(&contents), sorted_urls(&exported), "{}", bundle.display());
+		let starred: Vec<&str> = contents
+			.articles
+			.iter()
+			.filter(|stored| stored.starred)
+			.map(|stored| stored.article.url())
+			.collect();
+		assert_eq!(starred, [articles.first().ok_or("no fixture articles")?.url()]);
+		assert!(contents.articles.iter().all(|stored| stored.first_seen == now));
+		assert_eq!(contents.rankings.len(), exported.rankings.len());
+		assert_eq!(contents.deliveries.len(), 1);
+	}
+	Ok(())
+}